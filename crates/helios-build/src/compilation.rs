@@ -0,0 +1,418 @@
+//! An API for compiling a source file or project from other tools, without
+//! printing anything.
+
+use crate::imports::{self, Import};
+use crate::session::SessionOptions;
+use crate::{
+    import_cycle, module_name, parse_files, sarif, source_paths,
+    unresolved_import, MessageFormat, Result, SOURCE_FILE_EXTENSION,
+};
+use helios_diagnostics::files::SimpleFiles;
+use helios_diagnostics::{Diagnostic, DiagnosticSink, Severity};
+use helios_parser::Parse;
+use helios_query::{FileId, Profiler};
+use helios_vfs::Vfs;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// The options of a [`Compilation`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CompilationOptions {
+    /// The source file or project to compile, which is either a single source
+    /// file, a project's manifest or a directory containing a project's
+    /// manifest.
+    pub path: PathBuf,
+    /// Whether the phases of compiling each file are timed (see
+    /// [`Compilation::profiler`]).
+    pub profile: bool,
+    /// The options of the session (such as from the command line), which
+    /// take precedence over those of a project's manifest.
+    pub session: SessionOptions,
+}
+
+impl CompilationOptions {
+    /// The options to compile the given source file or project.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            ..Self::default()
+        }
+    }
+}
+
+/// A compilation of a source file or project (along with every file it
+/// imports).
+///
+/// ```no_run
+/// use helios_build::{Compilation, CompilationOptions};
+///
+/// let mut compilation = Compilation::new(CompilationOptions::new("hello"));
+/// let artifacts = compilation.check().expect("Failed to load the project");
+/// for module in &artifacts.modules {
+///     println!("{}", module.name);
+/// }
+///
+/// assert!(!artifacts.has_errors());
+/// ```
+#[derive(Debug)]
+pub struct Compilation {
+    vfs: Vfs,
+    options: CompilationOptions,
+    profiler: Option<Profiler>,
+    artifacts: Option<Artifacts>,
+}
+
+impl Compilation {
+    /// Constructs a compilation of the files on disk.
+    pub fn new(options: CompilationOptions) -> Self {
+        Self::with_vfs(Vfs::new(), options)
+    }
+
+    /// Constructs a compilation of the files in the given [`Vfs`] (such as
+    /// one with files held in memory).
+    pub fn with_vfs(vfs: Vfs, options: CompilationOptions) -> Self {
+        let profiler = Some(Profiler::default()).filter(|_| options.profile);
+        Self {
+            vfs,
+            options,
+            profiler,
+            artifacts: None,
+        }
+    }
+
+    /// The options of the compilation.
+    pub fn options(&self) -> &CompilationOptions {
+        &self.options
+    }
+
+    /// Checks the files for errors, returning the artifacts of the
+    /// compilation (which are also kept until the next check).
+    ///
+    /// This only fails if the files can't be loaded (such as when the
+    /// manifest is invalid): the errors in the files are reported as
+    /// diagnostics in the artifacts instead.
+    pub fn check(&mut self) -> Result<&Artifacts> {
+        let unused = Profiler::default();
+        let profiler = self.profiler.as_ref().unwrap_or(&unused);
+
+        let artifacts = compile(&self.vfs, &self.options, profiler)?;
+        Ok(self.artifacts.insert(artifacts))
+    }
+
+    /// The artifacts of the last check, if the files have been checked.
+    pub fn artifacts(&self) -> Option<&Artifacts> {
+        self.artifacts.as_ref()
+    }
+
+    /// The profiler timing the phases of compiling each file, if enabled in
+    /// the options.
+    pub fn profiler(&self) -> Option<&Profiler> {
+        self.profiler.as_ref()
+    }
+}
+
+/// A source file of a compilation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Module {
+    /// The name of the module (such as `Foo.Bar` for `src/Foo/Bar.he`).
+    pub name: String,
+    /// The path of the source file.
+    pub path: PathBuf,
+    /// The names of the modules imported by the module, in order.
+    pub imports: Vec<String>,
+    /// The syntax tree of the source file.
+    pub parse: Parse<usize>,
+}
+
+/// The results of checking a [`Compilation`].
+///
+/// There is no compiled output yet, as there is no code generation: the
+/// artifacts are the parsed modules and their diagnostics.
+#[derive(Clone, Debug)]
+pub struct Artifacts {
+    /// The modules compiled, in the order they were loaded (starting with the
+    /// entry point of a project). The file id of a diagnostic is the index
+    /// of its module.
+    pub modules: Vec<Module>,
+    /// The diagnostics of every module, sorted by file and location (with the
+    /// lint levels of the project applied to them).
+    pub diagnostics: Vec<Diagnostic<usize>>,
+    /// The options of the session, combining those of the project's manifest
+    /// with those of the compilation.
+    pub options: SessionOptions,
+    files: SimpleFiles<String, String>,
+}
+
+impl Artifacts {
+    /// Whether any diagnostic is an error (including the lints the project
+    /// denies), in which case the compilation failed.
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity >= Severity::Error)
+    }
+
+    /// The source files of the modules, for rendering the diagnostics.
+    pub fn files(&self) -> &SimpleFiles<String, String> {
+        &self.files
+    }
+
+    /// Writes the diagnostics in the given format.
+    pub fn emit_diagnostics(
+        &self,
+        f: &mut dyn Write,
+        format: MessageFormat,
+    ) -> helios_diagnostics::Result<()> {
+        match format {
+            MessageFormat::Human => {
+                let theme = helios_parser::highlight::theme();
+                for diagnostic in &self.diagnostics {
+                    helios_diagnostics::emit_with_theme(
+                        f,
+                        &self.files,
+                        diagnostic,
+                        &theme,
+                    )?;
+                }
+            }
+            MessageFormat::Sarif => {
+                let log = sarif::render(&self.files, &self.diagnostics)?;
+                write!(f, "{}", log)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Loads, parses and checks the file or project with the given options,
+/// timing the phases of compiling each file with the given profiler.
+pub(crate) fn compile(
+    vfs: &Vfs,
+    options: &CompilationOptions,
+    profiler: &Profiler,
+) -> Result<Artifacts> {
+    let mut files = SimpleFiles::new();
+
+    let sources = source_paths(vfs, &options.path)?;
+    let source = sources.source;
+    let mut paths = sources.paths;
+    let session = sources.options.merge(&options.session);
+    let parse_options = session.parse_options();
+    let lint_levels = sources.lint_levels;
+    let mut file_ids = HashMap::new();
+    let mut file_paths = Vec::new();
+    let mut parses = Vec::new();
+    let mut file_imports = Vec::<Vec<Import>>::new();
+    let mut diagnostics = DiagnosticSink::with_lint_levels(lint_levels);
+
+    // Every file imported by a file being compiled is loaded as well, even if
+    // it wasn't one of the files given. The files are loaded in waves: the
+    // files given, then the files they import, and so on
+    while !paths.is_empty() {
+        let mut loaded = Vec::new();
+        for path in paths.drain(..) {
+            if file_ids.contains_key(&path) {
+                continue;
+            }
+
+            let source_text = vfs.read(&path)?.to_string();
+            let file_id = files.add(path.display().to_string(), source_text);
+
+            file_ids.insert(path.clone(), file_id);
+            file_paths.push(path);
+            loaded.push(file_id);
+        }
+
+        let parses_loaded =
+            parse_files(&files, &loaded, parse_options, profiler);
+        for (file_id, parse) in parses_loaded {
+            // The import errors in the same region as an error of the parse
+            // are caused by it
+            for diagnostic in parse.diagnostics() {
+                diagnostics.push_syntax(diagnostic.clone());
+            }
+
+            let id = FileId(file_id as u32);
+            let imports = profiler
+                .time(id, "imports", || imports::imports(&parse.syntax()));
+            for import in &imports {
+                let imported = import.file_path(&source, SOURCE_FILE_EXTENSION);
+
+                if vfs.is_file(&imported) {
+                    paths.push(imported);
+                } else {
+                    let diagnostic =
+                        unresolved_import(file_id, import, &imported);
+                    diagnostics.push(diagnostic);
+                }
+            }
+
+            parses.push(parse);
+            file_imports.push(imports);
+        }
+    }
+
+    let edges = file_imports
+        .iter()
+        .map(|imports| {
+            imports
+                .iter()
+                .enumerate()
+                .filter_map(|(index, import)| {
+                    let path = import.file_path(&source, SOURCE_FILE_EXTENSION);
+                    file_ids.get(&path).map(|&file_id| (file_id, index))
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    for (cycle, (file_id, index)) in imports::cycles(&edges) {
+        let modules = cycle
+            .iter()
+            .map(|&file_id| module_name(&source, &file_paths[file_id]))
+            .collect();
+
+        let import = &file_imports[file_id][index];
+        diagnostics.push(import_cycle(file_id, import, modules));
+    }
+
+    let modules = file_paths
+        .into_iter()
+        .zip(parses)
+        .zip(file_imports)
+        .map(|((path, parse), imports)| Module {
+            name: module_name(&source, &path),
+            path,
+            imports: imports.iter().map(Import::module_name).collect(),
+            parse,
+        })
+        .collect();
+
+    let mut diagnostics = diagnostics.finish();
+    if session.deny_warnings {
+        for diagnostic in &mut diagnostics {
+            if diagnostic.severity == Severity::Warning {
+                diagnostic.severity = Severity::Error;
+            }
+        }
+    }
+
+    Ok(Artifacts {
+        modules,
+        diagnostics,
+        options: session,
+        files,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::{OptLevel, Target};
+    use crate::Error;
+    use std::path::Path;
+
+    fn project(files: &[(&str, &str)], profile: bool) -> Compilation {
+        let mut vfs = Vfs::in_memory();
+        vfs.set_file("hello/helios.toml", "[package]\nname = \"hello\"");
+        for (path, text) in files {
+            vfs.set_file(Path::new("hello/src").join(path), *text);
+        }
+
+        let options = CompilationOptions {
+            profile,
+            ..CompilationOptions::new("hello")
+        };
+        Compilation::with_vfs(vfs, options)
+    }
+
+    #[test]
+    fn test_check() {
+        let mut compilation = project(
+            &[
+                ("Main.he", "import Foo.Bar\nlet a = 1"),
+                ("Foo/Bar.he", "let b = 2"),
+            ],
+            false,
+        );
+        assert!(compilation.artifacts().is_none());
+
+        let artifacts = compilation.check().unwrap();
+        assert!(!artifacts.has_errors());
+        assert!(artifacts.diagnostics.is_empty());
+
+        let modules = artifacts
+            .modules
+            .iter()
+            .map(|module| (module.name.as_str(), module.imports.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            modules,
+            vec![("Main", vec!["Foo.Bar".to_string()]), ("Foo.Bar", vec![])]
+        );
+
+        let module = &artifacts.modules[1];
+        assert_eq!(module.path, PathBuf::from("hello/src/Foo/Bar.he"));
+        assert_eq!(module.parse.syntax().text().to_string(), "let b = 2");
+        assert!(compilation.artifacts().is_some());
+        assert!(compilation.profiler().is_none());
+    }
+
+    #[test]
+    fn test_check_with_errors() {
+        let files = [("Main.he", "import Missing\nimport Main")];
+        let mut compilation = project(&files, false);
+
+        let artifacts = compilation.check().unwrap();
+        assert!(artifacts.has_errors());
+
+        let messages = artifacts
+            .diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.title.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(messages, vec!["Unresolved import", "Import cycle"]);
+
+        let mut output = Vec::new();
+        artifacts
+            .emit_diagnostics(&mut output, MessageFormat::Sarif)
+            .unwrap();
+        let log = serde_json::from_slice::<serde_json::Value>(&output);
+        assert!(log.is_ok());
+
+        let mut compilation = project(&[("App.he", "let a = 1")], false);
+        let entry = PathBuf::from("hello/src/Main.he");
+        assert_eq!(compilation.check().err(), Some(Error::MissingEntry(entry)));
+        assert!(compilation.artifacts().is_none());
+    }
+
+    #[test]
+    fn test_check_with_options() {
+        let mut compilation =
+            project(&[("Main.he", "let a =\n  1\n  2\n")], false);
+        compilation.vfs.set_file(
+            "hello/helios.toml",
+            "[package]\nname = \"hello\"\n[build]\ntarget = \"vm\"\n\
+             [features]\nindentation-blocks = true\n",
+        );
+        compilation.options.session.opt_level = Some(OptLevel::O1);
+
+        // The options of the manifest are combined with those given, and
+        // blocks may be delimited by indentation
+        let artifacts = compilation.check().unwrap();
+        assert_eq!(artifacts.options.target, Some(Target::Vm));
+        assert_eq!(artifacts.options.opt_level, Some(OptLevel::O1));
+        let tree = artifacts.modules[0].parse.debug_tree();
+        assert!(tree.contains("Lay_Begin"));
+    }
+
+    #[test]
+    fn test_check_with_profile() {
+        let mut compilation = project(&[("Main.he", "let a = 1")], true);
+        compilation.check().unwrap();
+        let profiler = compilation.profiler().unwrap();
+        assert_eq!(profiler.timings().len(), 3);
+    }
+}
@@ -0,0 +1,165 @@
+//! Generating the documentation of a module from the doc comments of its
+//! public items.
+
+use helios_query::Item;
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// The formats documentation can be generated in.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DocFormat {
+    Markdown,
+    /// A standalone HTML page.
+    Html,
+}
+
+impl FromStr for DocFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "markdown" | "md" => Ok(Self::Markdown),
+            "html" => Ok(Self::Html),
+            _ => Err(format!(
+                "Unknown format `{}` (expected `markdown` or `html`)",
+                s
+            )),
+        }
+    }
+}
+
+/// Renders the documentation of the public items of the given module.
+pub(crate) fn render(
+    module: &str,
+    items: &[Item],
+    format: DocFormat,
+) -> String {
+    let items = items.iter().filter(|item| item.is_public);
+    match format {
+        DocFormat::Markdown => to_markdown(module, items),
+        DocFormat::Html => to_html(module, items),
+    }
+}
+
+fn to_markdown<'a>(
+    module: &str,
+    items: impl Iterator<Item = &'a Item>,
+) -> String {
+    let mut markdown = format!("# Module `{}`\n", module);
+
+    for item in items {
+        write!(
+            markdown,
+            "\n## `{}`\n\n```helios\n{}\n```\n",
+            item.name, item.signature
+        )
+        .unwrap();
+
+        if let Some(docs) = &item.docs {
+            writeln!(markdown, "\n{}", docs).unwrap();
+        }
+    }
+
+    markdown
+}
+
+fn to_html<'a>(module: &str, items: impl Iterator<Item = &'a Item>) -> String {
+    let module = escape_html(module);
+    let mut html = String::new();
+
+    writeln!(html, "<!DOCTYPE html>\n<html>\n<head>").unwrap();
+    writeln!(html, "<meta charset=\"utf-8\">").unwrap();
+    writeln!(html, "<title>{}</title>\n</head>\n<body>", module).unwrap();
+    writeln!(html, "<h1>Module <code>{}</code></h1>", module).unwrap();
+
+    for item in items {
+        let name = escape_html(&item.name);
+        writeln!(html, "<section id=\"{}\">", name).unwrap();
+        writeln!(html, "<h2><code>{}</code></h2>", name).unwrap();
+        writeln!(
+            html,
+            "<pre><code>{}</code></pre>",
+            escape_html(&item.signature)
+        )
+        .unwrap();
+
+        // Paragraphs are separated by blank lines, like in Markdown
+        for paragraph in item.docs.iter().flat_map(|docs| docs.split("\n\n")) {
+            let paragraph = paragraph.trim();
+            if !paragraph.is_empty() {
+                writeln!(html, "<p>{}</p>", escape_html(paragraph)).unwrap();
+            }
+        }
+
+        writeln!(html, "</section>").unwrap();
+    }
+
+    writeln!(html, "</body>\n</html>").unwrap();
+    html
+}
+
+pub(crate) fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items() -> Vec<Item> {
+        let source = "--| Adds one.\n--|\n--| Returns `x + 1`.\n\
+                      public let f = \\x -> x + 1\n\
+                      --| Not public.\nlet g = 2\n\
+                      public external function h(a: Int) -> Int\n";
+        let root = helios_parser::parse(0u8, source).syntax();
+        helios_query::items_of(&root)
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        assert_eq!(
+            render("Foo", &items(), DocFormat::Markdown),
+            "# Module `Foo`\n\
+             \n## `f`\n\n```helios\npublic let f\n```\n\
+             \nAdds one.\n\nReturns `x + 1`.\n\
+             \n## `h`\n\n```helios\n\
+             public external function h(a: Int) -> Int\n```\n"
+        );
+    }
+
+    #[test]
+    fn test_render_html() {
+        let html = render("Foo", &items(), DocFormat::Html);
+        assert!(html.starts_with("<!DOCTYPE html>\n"));
+        assert!(html.contains("<h1>Module <code>Foo</code></h1>\n"));
+        assert!(html.contains(
+            "<section id=\"f\">\n<h2><code>f</code></h2>\n\
+             <pre><code>public let f</code></pre>\n\
+             <p>Adds one.</p>\n<p>Returns `x + 1`.</p>\n</section>\n"
+        ));
+        assert!(html.contains(
+            "<pre><code>public external function h(a: Int) -&gt; Int\
+             </code></pre>\n"
+        ));
+        assert!(!html.contains("Not public"));
+    }
+
+    #[test]
+    fn test_doc_format_from_str() {
+        assert_eq!("markdown".parse(), Ok(DocFormat::Markdown));
+        assert_eq!("md".parse(), Ok(DocFormat::Markdown));
+        assert_eq!("html".parse(), Ok(DocFormat::Html));
+        assert!("pdf".parse::<DocFormat>().is_err());
+    }
+}
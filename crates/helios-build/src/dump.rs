@@ -0,0 +1,308 @@
+//! Printing the tokens and the syntax tree of a source file, for debugging the
+//! grammar or for use by other tools.
+
+use helios_parser::Token;
+use helios_syntax::{SyntaxElement, SyntaxNode};
+use serde_json::{json, Value};
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// The formats a syntax tree can be printed in.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TreeFormat {
+    /// The indented form used by the parser's tests.
+    Text,
+    /// A JSON object for every node and token, with its kind, range and
+    /// children (for nodes) or text (for tokens).
+    Json,
+    /// A Graphviz graph.
+    Dot,
+}
+
+impl FromStr for TreeFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "dot" => Ok(Self::Dot),
+            _ => Err(format!(
+                "Unknown format `{}` (expected `text`, `json` or `dot`)",
+                s
+            )),
+        }
+    }
+}
+
+/// Renders the given syntax tree in the given format.
+pub(crate) fn render(root: &SyntaxNode, format: TreeFormat) -> String {
+    match format {
+        TreeFormat::Text => format!("{:#?}", root),
+        TreeFormat::Json => {
+            let json = to_json(&SyntaxElement::Node(root.clone()));
+            serde_json::to_string_pretty(&json).unwrap() + "\n"
+        }
+        TreeFormat::Dot => to_dot(root),
+    }
+}
+
+fn to_json(element: &SyntaxElement) -> Value {
+    let range = element.text_range();
+    let range = [usize::from(range.start()), usize::from(range.end())];
+
+    match element {
+        SyntaxElement::Node(node) => json!({
+            "kind": format!("{:?}", node.kind()),
+            "range": range,
+            "children": node.children_with_tokens().map(|it| to_json(&it)).collect::<Vec<_>>(),
+        }),
+        SyntaxElement::Token(token) => json!({
+            "kind": format!("{:?}", token.kind()),
+            "range": range,
+            "text": token.text().to_string(),
+        }),
+    }
+}
+
+fn to_dot(root: &SyntaxNode) -> String {
+    fn visit(element: SyntaxElement, id: &mut usize, dot: &mut String) {
+        let this = *id;
+        *id += 1;
+
+        let label = match &element {
+            SyntaxElement::Node(node) => format!("{:?}", node),
+            SyntaxElement::Token(token) => format!("{:?}", token),
+        };
+
+        let shape = match element {
+            SyntaxElement::Node(_) => "box",
+            SyntaxElement::Token(_) => "ellipse",
+        };
+
+        writeln!(dot, "  n{} [label={:?}, shape={}];", this, label, shape)
+            .unwrap();
+
+        if let SyntaxElement::Node(node) = element {
+            for child in node.children_with_tokens() {
+                writeln!(dot, "  n{} -> n{};", this, *id).unwrap();
+                visit(child, id, dot);
+            }
+        }
+    }
+
+    let mut dot = String::from("digraph {\n");
+    visit(SyntaxElement::Node(root.clone()), &mut 0, &mut dot);
+    dot.push_str("}\n");
+    dot
+}
+
+/// A token that isn't trivia, along with the trivia attached to it.
+///
+/// Trivia following a token on the same line is attached to it as trailing
+/// trivia, and any other trivia is attached to the next token as leading
+/// trivia (or to the last token of the file, as trailing trivia).
+struct TokenWithTrivia<'a> {
+    token: &'a Token<'a>,
+    leading: Vec<&'a Token<'a>>,
+    trailing: Vec<&'a Token<'a>>,
+}
+
+fn attach_trivia<'a>(tokens: &'a [Token<'a>]) -> Vec<TokenWithTrivia<'a>> {
+    // A file with nothing but trivia has no token to attach it to
+    if tokens.iter().all(|token| token.kind.is_trivia()) {
+        return tokens
+            .iter()
+            .map(|token| TokenWithTrivia {
+                token,
+                leading: Vec::new(),
+                trailing: Vec::new(),
+            })
+            .collect();
+    }
+
+    let mut result: Vec<TokenWithTrivia> = Vec::new();
+    let mut leading = Vec::new();
+    let mut same_line = false;
+
+    for token in tokens {
+        if !token.kind.is_trivia() {
+            result.push(TokenWithTrivia {
+                token,
+                leading: std::mem::take(&mut leading),
+                trailing: Vec::new(),
+            });
+            same_line = true;
+        } else if same_line && !token.text.contains('\n') {
+            result.last_mut().unwrap().trailing.push(token);
+        } else {
+            same_line = false;
+            leading.push(token);
+        }
+    }
+
+    result.last_mut().unwrap().trailing.append(&mut leading);
+    result
+}
+
+/// Renders the given tokens as a table (or as JSON), with the trivia attached
+/// to each token.
+pub(crate) fn render_tokens(tokens: &[Token], json: bool) -> String {
+    let tokens = attach_trivia(tokens);
+
+    if json {
+        let json = tokens
+            .iter()
+            .map(|it| {
+                let mut json = token_to_json(it.token);
+                json["leading_trivia"] =
+                    it.leading.iter().map(|t| token_to_json(t)).collect();
+                json["trailing_trivia"] =
+                    it.trailing.iter().map(|t| token_to_json(t)).collect();
+                json
+            })
+            .collect::<Vec<_>>();
+
+        return serde_json::to_string_pretty(&json).unwrap() + "\n";
+    }
+
+    let describe = |trivia: &[&Token]| {
+        trivia
+            .iter()
+            .map(|token| format!("{:?} {:?}", token.kind, token.text))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let header = ["RANGE", "KIND", "TEXT", "LEADING TRIVIA", "TRAILING TRIVIA"]
+        .map(String::from);
+    let rows = tokens.iter().map(|it| {
+        let range = &it.token.range;
+        [
+            format!("{}..{}", range.start, range.end),
+            format!("{:?}", it.token.kind),
+            format!("{:?}", it.token.text),
+            describe(&it.leading),
+            describe(&it.trailing),
+        ]
+    });
+    let rows = std::iter::once(header).chain(rows).collect::<Vec<_>>();
+
+    let mut widths = [0; 5];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut table = String::new();
+    for row in &rows {
+        let line = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:<1$}", cell, width))
+            .collect::<Vec<_>>()
+            .join("  ");
+        table.push_str(line.trim_end());
+        table.push('\n');
+    }
+
+    table
+}
+
+fn token_to_json(token: &Token) -> Value {
+    json!({
+        "kind": format!("{:?}", token.kind),
+        "range": [token.range.start, token.range.end],
+        "text": token.text,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_tree() {
+        let root = helios_parser::parse(0u8, "a").syntax();
+
+        assert_eq!(
+            render(&root, TreeFormat::Json),
+            r#"{
+  "children": [
+    {
+      "children": [
+        {
+          "kind": "Identifier",
+          "range": [
+            0,
+            1
+          ],
+          "text": "a"
+        }
+      ],
+      "kind": "Exp_VariableRef",
+      "range": [
+        0,
+        1
+      ]
+    }
+  ],
+  "kind": "Root",
+  "range": [
+    0,
+    1
+  ]
+}
+"#
+        );
+
+        assert_eq!(
+            render(&root, TreeFormat::Dot),
+            "digraph {
+  n0 [label=\"Root@0..1\", shape=box];
+  n0 -> n1;
+  n1 [label=\"Exp_VariableRef@0..1\", shape=box];
+  n1 -> n2;
+  n2 [label=\"Identifier@0..1 \\\"a\\\"\", shape=ellipse];
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_render_tokens() {
+        let source = "let a -- c\n\nb ";
+        let (tokens, _) = helios_parser::tokenize(0u8, source);
+
+        assert_eq!(
+            render_tokens(&tokens, false),
+            r#"RANGE   KIND        TEXT   LEADING TRIVIA     TRAILING TRIVIA
+0..3    Kwd_Let     "let"                     Whitespace " "
+4..5    Identifier  "a"                       Whitespace " ", Comment "-- c"
+12..13  Identifier  "b"    Whitespace "\n\n"  Whitespace " "
+"#
+        );
+
+        let (tokens, _) = helios_parser::tokenize(0u8, "a ");
+        let json = render_tokens(&tokens, true);
+        assert_eq!(
+            serde_json::from_str::<Value>(&json).unwrap(),
+            json!([{
+                "kind": "Identifier",
+                "range": [0, 1],
+                "text": "a",
+                "leading_trivia": [],
+                "trailing_trivia": [
+                    { "kind": "Whitespace", "range": [1, 2], "text": " " },
+                ],
+            }])
+        );
+    }
+
+    #[test]
+    fn test_parse_tree_format() {
+        assert_eq!("dot".parse(), Ok(TreeFormat::Dot));
+        assert!("xml".parse::<TreeFormat>().is_err());
+    }
+}
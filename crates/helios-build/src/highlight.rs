@@ -0,0 +1,109 @@
+//! Rendering source files with syntax highlighting (see
+//! [`helios_parser::highlight`]), such as for code samples in documentation.
+
+use crate::doc::escape_html;
+use helios_parser::highlight::highlight;
+use helios_syntax::SyntaxKind;
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// The formats highlighted source can be rendered in.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HighlightFormat {
+    /// A `<pre>` element to embed in a page, where each token is in a `<span>`
+    /// with the classes of its tag and its kind (such as `keyword kwd-let`).
+    Html,
+}
+
+impl FromStr for HighlightFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "html" => Ok(Self::Html),
+            _ => Err(format!("Unknown format `{}` (expected `html`)", s)),
+        }
+    }
+}
+
+/// Renders the given source with syntax highlighting in the given format.
+pub fn render(source: &str, format: HighlightFormat) -> String {
+    match format {
+        HighlightFormat::Html => to_html(source),
+    }
+}
+
+/// Renders the given source as HTML, where the tokens that are highlighted
+/// are in `<span>` elements (and whitespace is left as it is).
+pub fn to_html(source: &str) -> String {
+    let mut html = String::from("<pre class=\"helios\"><code>");
+
+    for highlighted in highlight(source) {
+        let text = escape_html(highlighted.token.text);
+        match highlighted.tag {
+            Some(tag) => {
+                let kind = kind_class(highlighted.token.kind);
+                if kind == tag.name() {
+                    write!(html, "<span class=\"{}\">", kind).unwrap();
+                } else {
+                    write!(html, "<span class=\"{} {}\">", tag.name(), kind)
+                        .unwrap();
+                }
+                write!(html, "{}</span>", text).unwrap();
+            }
+            None => html.push_str(&text),
+        }
+    }
+
+    html.push_str("</code></pre>\n");
+    html
+}
+
+/// The CSS class of the given kind, which is its name in kebab case (such as
+/// `sym-pipe-gt` for `Sym_PipeGt`).
+fn kind_class(kind: SyntaxKind) -> String {
+    let mut class = String::new();
+    for c in format!("{:?}", kind).chars() {
+        if c == '_' {
+            class.push('-');
+        } else if c.is_ascii_uppercase() {
+            if !class.is_empty() && !class.ends_with('-') {
+                class.push('-');
+            }
+            class.push(c.to_ascii_lowercase());
+        } else {
+            class.push(c);
+        }
+    }
+
+    class
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kind_classes() {
+        assert_eq!(kind_class(SyntaxKind::Kwd_Let), "kwd-let");
+        assert_eq!(kind_class(SyntaxKind::Sym_PipeGt), "sym-pipe-gt");
+        assert_eq!(kind_class(SyntaxKind::DocComment), "doc-comment");
+        assert_eq!(kind_class(SyntaxKind::Identifier), "identifier");
+    }
+
+    #[test]
+    fn test_to_html() {
+        assert_eq!(
+            to_html("let a = x |> f <\n"),
+            "<pre class=\"helios\"><code>\
+             <span class=\"keyword kwd-let\">let</span> \
+             <span class=\"identifier\">a</span> \
+             <span class=\"operator sym-eq\">=</span> \
+             <span class=\"identifier\">x</span> \
+             <span class=\"operator sym-pipe-gt\">|&gt;</span> \
+             <span class=\"identifier\">f</span> \
+             <span class=\"operator sym-lt\">&lt;</span>\n\
+             </code></pre>\n"
+        );
+    }
+}
@@ -0,0 +1,174 @@
+//! Resolving the imports between the source files of a build.
+
+use helios_syntax::{SyntaxKind, SyntaxNode};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// An `import` declaration found in a source file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct Import {
+    /// The components of the imported module's path (`["Foo", "Bar"]` for
+    /// `import Foo.Bar`).
+    pub(crate) path: Vec<String>,
+    /// The range of the module path in the importing file.
+    pub(crate) range: Range<usize>,
+}
+
+impl Import {
+    /// The name of the imported module, as written in the source file.
+    pub(crate) fn module_name(&self) -> String {
+        self.path.join(".")
+    }
+
+    /// The path of the file defining the imported module, which is found by
+    /// treating every component but the last as a directory in the given
+    /// source directory (`import Foo.Bar` refers to `Foo/Bar.he`).
+    pub(crate) fn file_path(&self, source: &Path, extension: &str) -> PathBuf {
+        let mut path = source.to_path_buf();
+        path.extend(&self.path);
+        path.set_extension(extension);
+        path
+    }
+}
+
+/// Finds every `import` declaration at the top level of the given syntax tree.
+///
+/// Imports with an incomplete module path (which the parser has already
+/// reported) are skipped.
+pub(crate) fn imports(root: &SyntaxNode) -> Vec<Import> {
+    root.children()
+        .filter(|node| node.kind() == SyntaxKind::Dec_Import)
+        .filter_map(|node| {
+            node.children()
+                .find(|node| node.kind() == SyntaxKind::ModulePath)
+        })
+        .filter_map(|path| {
+            let tokens = path
+                .children_with_tokens()
+                .filter_map(|it| it.into_token())
+                .filter(|token| !token.kind().is_trivia())
+                .collect::<Vec<_>>();
+
+            // A complete path alternates between identifiers and dots,
+            // starting and ending with an identifier
+            let last = tokens.last()?;
+            if last.kind() != SyntaxKind::Identifier {
+                return None;
+            }
+
+            let components = tokens
+                .iter()
+                .filter(|token| token.kind() == SyntaxKind::Identifier)
+                .map(|token| token.text().to_string())
+                .collect();
+
+            let start = tokens[0].text_range().start();
+            let end = last.text_range().end();
+
+            Some(Import {
+                path: components,
+                range: start.into()..end.into(),
+            })
+        })
+        .collect()
+}
+
+/// Finds the import cycles in the graph with the given edges, where
+/// `edges[file]` lists the files imported by `file` along with the index of
+/// each import.
+///
+/// Every cycle is reported once, as the list of files in the cycle (starting
+/// and ending with the same file) along with the file and the index of the
+/// import that closes the cycle.
+pub(crate) fn cycles(
+    edges: &[Vec<(usize, usize)>],
+) -> Vec<(Vec<usize>, (usize, usize))> {
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    enum State {
+        Unvisited,
+        InProgress,
+        Done,
+    }
+
+    fn visit(
+        file: usize,
+        edges: &[Vec<(usize, usize)>],
+        states: &mut [State],
+        stack: &mut Vec<usize>,
+        cycles: &mut Vec<(Vec<usize>, (usize, usize))>,
+    ) {
+        states[file] = State::InProgress;
+        stack.push(file);
+
+        for &(imported, import) in &edges[file] {
+            match states[imported] {
+                State::Unvisited => {
+                    visit(imported, edges, states, stack, cycles)
+                }
+                State::InProgress => {
+                    let start = stack.iter().position(|&it| it == imported);
+                    let mut cycle = stack[start.unwrap()..].to_vec();
+                    cycle.push(imported);
+                    cycles.push((cycle, (file, import)));
+                }
+                State::Done => {}
+            }
+        }
+
+        stack.pop();
+        states[file] = State::Done;
+    }
+
+    let mut states = vec![State::Unvisited; edges.len()];
+    let mut cycles = Vec::new();
+
+    for file in 0..edges.len() {
+        if states[file] == State::Unvisited {
+            visit(file, edges, &mut states, &mut Vec::new(), &mut cycles);
+        }
+    }
+
+    cycles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_imports() {
+        let parse = helios_parser::parse(0u8, "import Foo.Bar \nimport A.\n");
+        let imports = imports(&parse.syntax());
+
+        assert_eq!(
+            imports,
+            vec![Import {
+                path: vec!["Foo".to_string(), "Bar".to_string()],
+                range: 7..14,
+            }]
+        );
+
+        assert_eq!(imports[0].module_name(), "Foo.Bar");
+        assert_eq!(
+            imports[0].file_path(Path::new("src"), "he"),
+            PathBuf::from("src/Foo/Bar.he")
+        );
+    }
+
+    #[test]
+    fn test_cycles() {
+        // 0 -> 1 -> 2 -> 0, 2 -> 2 and 3 -> 1
+        let edges = vec![
+            vec![(1, 0)],
+            vec![(2, 0)],
+            vec![(0, 0), (2, 1)],
+            vec![(1, 0)],
+        ];
+
+        assert_eq!(
+            cycles(&edges),
+            vec![(vec![0, 1, 2, 0], (2, 0)), (vec![2, 2], (2, 1))]
+        );
+        assert!(cycles(&[vec![(1, 0)], vec![]]).is_empty());
+    }
+}
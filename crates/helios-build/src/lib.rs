@@ -1,14 +1,52 @@
+mod compilation;
+mod doc;
+mod dump;
+pub mod highlight;
+mod imports;
+pub mod manifest;
+mod sarif;
+pub mod session;
+
+pub use self::compilation::{
+    Artifacts, Compilation, CompilationOptions, Module,
+};
+pub use self::doc::DocFormat;
+pub use self::dump::TreeFormat;
+pub use self::highlight::HighlightFormat;
+use self::imports::Import;
+use self::manifest::{Manifest, ManifestError, MANIFEST_FILE_NAME};
+use self::session::SessionOptions;
 use colored::*;
 use helios_diagnostics::files::SimpleFiles;
-use helios_diagnostics::{Diagnostic, Severity};
+pub use helios_diagnostics::ColorChoice;
+use helios_diagnostics::{Diagnostic, LintLevels, Location};
+use helios_formatting::FormattedString;
+use helios_parser::Parse;
+pub use helios_parser::ParseOptions;
+use helios_query::{FileId, Profiler};
+use helios_vfs::Vfs;
 use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+/// The extension of every Helios source file.
+const SOURCE_FILE_EXTENSION: &str = "he";
 
 type Result<T> = std::result::Result<T, Error>;
 
+/// An error stopping a build (or a search).
+#[allow(clippy::enum_variant_names)]
 #[derive(Clone, Debug, Eq, PartialEq)]
-enum Error {
+pub enum Error {
+    /// The build failed with the given number of diagnostics.
     BuildError(usize),
+    /// A file couldn't be read.
     IoError(String),
+    /// The manifest at the given path is invalid.
+    ManifestError(PathBuf, ManifestError),
+    /// The pattern of a search is invalid.
+    InvalidPattern(helios_ssr::PatternError),
+    /// The entry point of a project doesn't exist.
+    MissingEntry(PathBuf),
 }
 
 impl std::error::Error for Error {}
@@ -33,62 +71,605 @@ impl Display for Error {
             Self::IoError(error) => {
                 write!(f, "An IO error occurred: {}", error)
             }
+            Self::ManifestError(path, error) => {
+                write!(f, "Invalid manifest `{}`: {}", path.display(), error)
+            }
+            Self::InvalidPattern(error) => write!(f, "{}", error),
+            Self::MissingEntry(path) => {
+                write!(f, "The entry point `{}` does not exist", path.display())
+            }
         }
     }
 }
 
-fn __build(path: &str) -> Result<()> {
-    let source = std::fs::read_to_string(path)?;
-    let mut stdout = std::io::stdout();
-    let mut files = SimpleFiles::new();
+/// How the diagnostics of a build are printed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MessageFormat {
+    /// Formatted for people reading them in a terminal.
+    #[default]
+    Human,
+    /// As a [SARIF](https://sarifweb.azurewebsites.net) log, for code
+    /// scanning tools (where every lint is a rule).
+    Sarif,
+}
+
+impl std::str::FromStr for MessageFormat {
+    type Err = String;
 
-    let file_id = files.add(path, source);
-    let file = files.get(file_id).unwrap();
+    fn from_str(format: &str) -> std::result::Result<Self, Self::Err> {
+        match format {
+            "human" => Ok(Self::Human),
+            "sarif" => Ok(Self::Sarif),
+            _ => Err(format!(
+                "Unknown message format `{}` (expected `human` or `sarif`)",
+                format
+            )),
+        }
+    }
+}
 
-    let parse = helios_parser::parse(file_id, file.source());
-    println!("{}", parse.debug_tree().cyan());
+/// The source files to build, along with the settings of their project.
+#[derive(Debug)]
+struct Sources {
+    /// The directory imports are resolved from.
+    source: PathBuf,
+    /// The source files given, starting with the entry point of a project.
+    paths: Vec<PathBuf>,
+    /// The lint levels of the project (the default ones for a single source
+    /// file).
+    lint_levels: LintLevels,
+    /// The session options of the project's manifest (the default ones for a
+    /// single source file).
+    options: SessionOptions,
+}
 
-    let mut emitted_ranges = Vec::new();
-    let mut severities = Vec::new();
+/// Determines the source files to build from the given path, which is either
+/// a single source file, a project's manifest or a directory containing a
+/// project's manifest.
+fn source_paths(vfs: &Vfs, path: &Path) -> Result<Sources> {
+    if vfs.is_dir(path) {
+        project_source_paths(vfs, &path.join(MANIFEST_FILE_NAME))
+    } else if path.file_name() == Some(MANIFEST_FILE_NAME.as_ref()) {
+        project_source_paths(vfs, path)
+    } else {
+        let source = path.parent().unwrap_or_else(|| Path::new(""));
+        Ok(Sources {
+            source: source.to_path_buf(),
+            paths: vec![path.to_path_buf()],
+            lint_levels: LintLevels::new(),
+            options: SessionOptions::default(),
+        })
+    }
+}
 
-    for message in parse.messages() {
-        let diagnostic = Diagnostic::from(message);
-        severities.push(diagnostic.severity);
+/// Finds every source file of the project with the given manifest, starting
+/// with the entry point of the project.
+fn project_source_paths(vfs: &Vfs, manifest_path: &Path) -> Result<Sources> {
+    let text = vfs.read(manifest_path)?.to_string();
+    let manifest = Manifest::parse(&text).map_err(|error| {
+        Error::ManifestError(manifest_path.to_path_buf(), error)
+    })?;
 
-        if !(emitted_ranges.contains(&diagnostic.location)) {
-            emitted_ranges.push(diagnostic.location.clone());
-            helios_diagnostics::emit(&mut stdout, &files, &diagnostic)
-                .expect("Failed to print diagnostic");
+    let root = manifest_path.parent().unwrap_or_else(|| Path::new("."));
+    let source = root.join(&manifest.source);
+    let entry = source.join(&manifest.entry);
+
+    let mut paths = vfs
+        .files_in(&source)?
+        .into_iter()
+        .filter(|path| path.extension() == Some(SOURCE_FILE_EXTENSION.as_ref()))
+        .collect::<Vec<_>>();
+
+    match paths.iter().position(|path| *path == entry) {
+        Some(index) => {
+            let entry = paths.remove(index);
+            paths.insert(0, entry);
+            Ok(Sources {
+                source,
+                paths,
+                lint_levels: manifest.lints,
+                options: manifest.options,
+            })
         }
+        None => Err(Error::MissingEntry(entry)),
     }
+}
 
-    // An empty vector (i.e. no messages to report) or a vector of severities
-    // lower in importance than error is okay
-    let is_ok = {
-        severities.is_empty()
-            || severities
-                .iter()
-                .any(|severity| *severity < Severity::Error)
-    };
+/// The name of the module defined by the source file at the given path (such
+/// as `Foo.Bar` for `src/Foo/Bar.he`).
+fn module_name(source: &Path, path: &Path) -> String {
+    let path = path.strip_prefix(source).unwrap_or(path).with_extension("");
+    let components = path
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>();
 
-    let message_count = emitted_ranges.len();
+    components.join(".")
+}
 
-    if is_ok {
-        Ok(())
+fn unresolved_import(
+    file_id: usize,
+    import: &Import,
+    path: &Path,
+) -> Diagnostic<usize> {
+    let description = FormattedString::default()
+        .text("I couldn't find the module imported here:");
+
+    let message = FormattedString::default()
+        .text("There is no module named ")
+        .code(import.module_name())
+        .text(", which I expected to find at ")
+        .code(path.display().to_string())
+        .text(".");
+
+    Diagnostic::error("Unresolved import")
+        .location(Location::new(file_id, import.range.clone()))
+        .description(description)
+        .message(message)
+}
+
+fn import_cycle(
+    file_id: usize,
+    import: &Import,
+    modules: Vec<String>,
+) -> Diagnostic<usize> {
+    let description = FormattedString::default()
+        .text("I found a module that ends up importing itself:");
+
+    let message = FormattedString::default()
+        .text("The following modules import each other in a cycle:")
+        .list(
+            modules
+                .into_iter()
+                .map(|module| FormattedString::default().code(module))
+                .collect::<Vec<_>>(),
+        );
+
+    let hint = "Try moving the definitions shared by these modules into a \
+                separate module.";
+
+    Diagnostic::error("Import cycle")
+        .location(Location::new(file_id, import.range.clone()))
+        .description(description)
+        .message(message)
+        .hint(hint)
+}
+
+/// Parses the given files in parallel, returning the parsed files in the order
+/// they were given (so that any output is deterministic).
+fn parse_files(
+    files: &SimpleFiles<String, String>,
+    file_ids: &[usize],
+    options: ParseOptions,
+    profiler: &Profiler,
+) -> Vec<(usize, Parse<usize>)> {
+    if file_ids.is_empty() {
+        return Vec::new();
+    }
+
+    let threads = std::thread::available_parallelism().map_or(1, usize::from);
+    let chunk_size = file_ids.len().div_ceil(threads);
+
+    std::thread::scope(|scope| {
+        let handles = file_ids
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|&file_id| {
+                            let file = files.get(file_id).unwrap();
+                            let (parse, timings) =
+                                helios_parser::parse_with_timings(
+                                    file_id,
+                                    file.source(),
+                                    options,
+                                );
+
+                            let id = FileId(file_id as u32);
+                            profiler.record(id, "lexing", timings.lexing);
+                            profiler.record(id, "parsing", timings.parsing);
+                            (file_id, parse)
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("Failed to parse files"))
+            .collect()
+    })
+}
+
+/// Builds the given file or project, writing its diagnostics (and its syntax
+/// trees if `print_tree` is set) to the given output. If a profiler is given,
+/// the phases of building each file are timed with it and its report is
+/// printed to the standard error at the end.
+fn __build(
+    vfs: &Vfs,
+    options: &CompilationOptions,
+    out: &mut dyn std::io::Write,
+    print_tree: bool,
+    format: MessageFormat,
+    profiler: Option<&Profiler>,
+) -> Result<()> {
+    let unused = Profiler::default();
+    let report = profiler.is_some();
+    let profiler = profiler.unwrap_or(&unused);
+
+    let artifacts = compilation::compile(vfs, options, profiler)?;
+    if print_tree {
+        for module in &artifacts.modules {
+            writeln!(out, "{}", module.parse.debug_tree().cyan())?;
+        }
+    }
+
+    artifacts
+        .emit_diagnostics(out, format)
+        .expect("Failed to print diagnostics");
+
+    if report {
+        let report = profiler.report(|file_id| {
+            let module = &artifacts.modules[file_id.0 as usize];
+            module.path.display().to_string()
+        });
+        eprint!("\n{}", report);
+    }
+
+    // The build fails if any diagnostic is an error, including the lints the
+    // project denies
+    if artifacts.has_errors() {
+        Err(Error::BuildError(artifacts.diagnostics.len()))
     } else {
-        Err(Error::BuildError(message_count))
+        Ok(())
     }
 }
 
-/// Starts the build process with the given path to a file.
-pub fn build(path: &str) {
-    println!("\n{} {}\n", "Building".green().bold(), path.underline());
+/// Starts the build process of the file or project with the given options.
+///
+/// Only the diagnostics are printed to the standard output in a format other
+/// than [`MessageFormat::Human`] (so that it can be read by other tools). The
+/// time spent in each phase of building each file is printed to the standard
+/// error if the options enable profiling.
+pub fn build(options: &CompilationOptions, format: MessageFormat) {
+    let is_human = format == MessageFormat::Human;
+    if is_human {
+        let path = options.path.display().to_string();
+        println!("\n{} {}\n", "Building".green().bold(), path.underline());
+    }
 
-    if let Err(error) = __build(path) {
+    let profiler = Profiler::default();
+    let profiler = Some(&profiler).filter(|_| options.profile);
+    let vfs = Vfs::new();
+    let mut stdout = std::io::stdout();
+    let result =
+        __build(&vfs, options, &mut stdout, is_human, format, profiler);
+    if let Err(error) = result {
         let error = format!("{}", error).red().bold();
         eprintln!("{}", error);
         std::process::exit(1);
     }
 
-    println!("{}", "Finished building".green().bold());
+    if is_human {
+        println!("{}", "Finished building".green().bold());
+    }
+}
+
+/// Checks the given file or project for errors without building it.
+///
+/// Only the diagnostics are printed to the standard output in a format other
+/// than [`MessageFormat::Human`] (so that it can be read by other tools). The
+/// time spent in each phase of checking each file is printed to the standard
+/// error if the options enable profiling.
+pub fn check(options: &CompilationOptions, format: MessageFormat) {
+    let is_human = format == MessageFormat::Human;
+    if is_human {
+        let path = options.path.display().to_string();
+        println!("\n{} {}\n", "Checking".green().bold(), path.underline());
+    }
+
+    let profiler = Profiler::default();
+    let profiler = Some(&profiler).filter(|_| options.profile);
+    let vfs = Vfs::new();
+    let mut stdout = std::io::stdout();
+    let result = __build(&vfs, options, &mut stdout, false, format, profiler);
+    if let Err(error) = result {
+        let error = format!("{}", error).red().bold();
+        eprintln!("{}", error);
+        std::process::exit(1);
+    }
+
+    if is_human {
+        println!("{}", "Finished checking".green().bold());
+    }
+}
+
+/// Finds the code matching the given pattern in the given file or project,
+/// returning a line for each match with its location and its first line.
+fn __search(vfs: &Vfs, pattern: &str, path: &str) -> Result<Vec<String>> {
+    let pattern =
+        helios_ssr::Pattern::parse(pattern).map_err(Error::InvalidPattern)?;
+    let sources = source_paths(vfs, Path::new(path))?;
+
+    let mut lines = Vec::new();
+    for path in sources.paths {
+        let source = vfs.read(&path)?.to_string();
+        let parse = helios_parser::parse(0usize, &source);
+
+        for found in helios_ssr::search(&pattern, &parse.syntax()) {
+            let range = found.range();
+            let before = &source[..range.start];
+            let line = before.matches('\n').count() + 1;
+            let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+            let column = source[line_start..range.start].chars().count() + 1;
+            let text = source[range].lines().next().unwrap_or_default();
+
+            lines.push(format!(
+                "{}:{}:{}: {}",
+                path.display(),
+                line,
+                column,
+                text
+            ));
+        }
+    }
+
+    Ok(lines)
+}
+
+/// Prints the location of every piece of code in the given file or project
+/// matching the given pattern (see [`helios_ssr`]).
+pub fn search(pattern: &str, path: &str) {
+    match __search(&Vfs::new(), pattern, path) {
+        Ok(lines) => lines.iter().for_each(|line| println!("{}", line)),
+        Err(error) => {
+            let error = format!("{}", error).red().bold();
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Prints the syntax tree of the given source file in the given format.
+pub fn parse(path: &str, format: TreeFormat, options: ParseOptions) {
+    let vfs = Vfs::new();
+    let source = match vfs.read(Path::new(path)) {
+        Ok(source) => source.to_string(),
+        Err(error) => {
+            let error = format!("{}", Error::from(error)).red().bold();
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
+
+    let parse = helios_parser::parse_with_options(0usize, &source, options);
+    print!("{}", dump::render(&parse.syntax(), format));
+}
+
+/// Prints the documentation of the public items of the given source file in
+/// the given format.
+pub fn doc(path: &str, format: DocFormat) {
+    let path = Path::new(path);
+    let source = match Vfs::new().read(path) {
+        Ok(source) => source.to_string(),
+        Err(error) => {
+            let error = format!("{}", Error::from(error)).red().bold();
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
+
+    let module = path.file_stem().unwrap_or_default().to_string_lossy();
+    let parse = helios_parser::parse(0usize, &source);
+    let items = helios_query::items_of(&parse.syntax());
+    print!("{}", doc::render(&module, &items, format));
+}
+
+/// Prints the given source file with syntax highlighting in the given format
+/// (see [`highlight::render`]).
+pub fn highlight(path: &str, format: HighlightFormat) {
+    let source = match Vfs::new().read(Path::new(path)) {
+        Ok(source) => source.to_string(),
+        Err(error) => {
+            let error = format!("{}", Error::from(error)).red().bold();
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
+
+    print!("{}", highlight::render(&source, format));
+}
+
+/// Prints the tokens of the given source file (with the trivia attached to
+/// each of them) as a table, or as JSON.
+pub fn tokenize(path: &str, json: bool) {
+    let mut files = SimpleFiles::new();
+
+    let source = match Vfs::new().read(Path::new(path)) {
+        Ok(source) => source.to_string(),
+        Err(error) => {
+            let error = format!("{}", Error::from(error)).red().bold();
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
+
+    let file_id = files.add(path.to_string(), source);
+    let source = files.get(file_id).unwrap().source();
+    let (tokens, messages) = helios_parser::tokenize(file_id, source);
+    print!("{}", dump::render_tokens(&tokens, json));
+
+    // The standard output only holds the JSON document in JSON mode
+    let (mut stdout, mut stderr) = (std::io::stdout(), std::io::stderr());
+    let out: &mut dyn std::io::Write =
+        if json { &mut stderr } else { &mut stdout };
+
+    let theme = helios_parser::highlight::theme();
+    for message in messages {
+        let diagnostic = Diagnostic::from(message);
+        helios_diagnostics::emit_with_theme(out, &files, &diagnostic, &theme)
+            .expect("Failed to print diagnostic");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(files: &[(&str, &str)]) -> Vfs {
+        let mut vfs = Vfs::in_memory();
+        vfs.set_file("hello/helios.toml", "[package]\nname = \"hello\"");
+        for (path, text) in files {
+            vfs.set_file(Path::new("hello/src").join(path), *text);
+        }
+
+        vfs
+    }
+
+    /// Checks the given project, returning the result along with the
+    /// diagnostics it printed.
+    fn check(vfs: &Vfs, options: &CompilationOptions) -> (Result<()>, String) {
+        let mut out = Vec::new();
+        let format = MessageFormat::Human;
+        let result = __build(vfs, options, &mut out, false, format, None);
+        (result, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_check_project() {
+        let options = CompilationOptions::new("hello");
+        let vfs = project(&[
+            ("Main.he", "import Foo.Bar\nlet a = 1"),
+            ("Foo/Bar.he", "let b = 2"),
+        ]);
+        assert_eq!(check(&vfs, &options).0, Ok(()));
+
+        let sources = source_paths(&vfs, Path::new("hello")).unwrap();
+        assert_eq!(sources.source, PathBuf::from("hello/src"));
+        assert_eq!(
+            sources.paths,
+            vec![
+                PathBuf::from("hello/src/Main.he"),
+                PathBuf::from("hello/src/Foo/Bar.he"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_project_with_errors() {
+        let options = CompilationOptions::new("hello");
+        let vfs = project(&[("Main.he", "import Missing\nimport Main")]);
+        let (result, diagnostics) = check(&vfs, &options);
+        assert_eq!(result, Err(Error::BuildError(2)));
+        let headers = diagnostics
+            .lines()
+            .filter(|line| line.starts_with("-- ") || line.starts_with("-> "))
+            .map(|line| line.trim_end_matches('-').trim_end())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            headers,
+            vec![
+                "-- Error: Unresolved import",
+                "-> hello/src/Main.he:1:8",
+                "-- Error: Import cycle",
+                "-> hello/src/Main.he:2:8",
+            ]
+        );
+        assert!(diagnostics.contains("There is no module named `Missing`"));
+
+        let vfs = project(&[("App.he", "let a = 1")]);
+        let entry = PathBuf::from("hello/src/Main.he");
+        assert_eq!(check(&vfs, &options).0, Err(Error::MissingEntry(entry)));
+    }
+
+    #[test]
+    fn test_check_project_with_lints() {
+        let options = CompilationOptions::new("hello");
+        let mut vfs = project(&[("Main.he", "var a = 1")]);
+        assert_eq!(check(&vfs, &options).0, Ok(()));
+
+        let manifest = "[package]\nname = \"hello\"\n[lints]\n";
+        vfs.set_file(
+            "hello/helios.toml",
+            format!("{}never-mutated = \"deny\"", manifest),
+        );
+        assert_eq!(check(&vfs, &options).0, Err(Error::BuildError(1)));
+
+        vfs.set_file(
+            "hello/helios.toml",
+            format!("{}never-mutated = \"allow\"", manifest),
+        );
+        assert_eq!(check(&vfs, &options).0, Ok(()));
+    }
+
+    #[test]
+    fn test_check_project_with_options() {
+        let vfs = project(&[("Main.he", "var a = 1")]);
+        let mut options = CompilationOptions::new("hello");
+        options.session.deny_warnings = true;
+        assert_eq!(check(&vfs, &options).0, Err(Error::BuildError(1)));
+    }
+
+    #[test]
+    fn test_check_project_with_profile() {
+        let options = CompilationOptions::new("hello");
+        let vfs = project(&[
+            ("Main.he", "import Foo\nlet a = 1"),
+            ("Foo.he", "let b = 2"),
+        ]);
+        let profiler = Profiler::default();
+        let format = MessageFormat::Human;
+        assert_eq!(
+            __build(
+                &vfs,
+                &options,
+                &mut Vec::new(),
+                false,
+                format,
+                Some(&profiler)
+            ),
+            Ok(())
+        );
+
+        let phases = profiler
+            .timings()
+            .into_iter()
+            .map(|(file_id, phase, timing)| (file_id.0, phase, timing.count))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            phases,
+            vec![
+                (0, "imports", 1),
+                (0, "lexing", 1),
+                (0, "parsing", 1),
+                (1, "imports", 1),
+                (1, "lexing", 1),
+                (1, "parsing", 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_search() {
+        let vfs = project(&[
+            ("Main.he", "import Foo\nlet a = 1 + 1\n"),
+            ("Foo.he", "let b = 2\nlet c =\n  (b) + (b)\nlet d = b + c"),
+        ]);
+        assert_eq!(
+            __search(&vfs, "$x + $x", "hello"),
+            Ok(vec![
+                "hello/src/Main.he:2:9: 1 + 1".to_string(),
+                "hello/src/Foo.he:3:3: (b) + (b)".to_string(),
+            ])
+        );
+
+        assert_eq!(
+            __search(&vfs, "$ + 1", "hello"),
+            Err(Error::InvalidPattern(
+                helios_ssr::PatternError::MissingPlaceholderName { offset: 0 }
+            ))
+        );
+    }
 }
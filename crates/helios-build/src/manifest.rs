@@ -0,0 +1,422 @@
+//! Reading the manifest of a Helios project (`helios.toml`).
+//!
+//! Only the subset of TOML used by manifests is understood: a `[package]`
+//! table, an optional `[lints]` table (which sets the level of each lint
+//! named), an optional `[build]` table (with the [`SessionOptions`]) and an
+//! optional `[features]` table (which enables each experimental feature set
+//! to `true`), whose keys are assigned basic strings, booleans or integers,
+//! along with comments and blank lines. For example:
+//!
+//! ```toml
+//! [package]
+//! name = "hello"
+//! source = "src"   # The default
+//! entry = "Main.he" # The default
+//!
+//! [lints]
+//! never-mutated = "deny"
+//! confusable = "allow"
+//!
+//! [build]
+//! opt-level = 2
+//! target = "vm"
+//! deny-warnings = true
+//!
+//! [features]
+//! indentation-blocks = true
+//! ```
+
+use crate::session::SessionOptions;
+use helios_diagnostics::LintLevels;
+use helios_parser::lints;
+use std::fmt::Display;
+use std::path::PathBuf;
+
+/// The file name of the manifest at the root of every project.
+pub const MANIFEST_FILE_NAME: &str = "helios.toml";
+
+/// The contents of a project's `helios.toml`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Manifest {
+    /// The name of the package.
+    pub name: String,
+    /// The directory containing every source file, relative to the manifest.
+    pub source: PathBuf,
+    /// The file the program starts from, relative to the source directory.
+    pub entry: PathBuf,
+    /// The levels the diagnostics of the lints are reported at.
+    pub lints: LintLevels,
+    /// The options of every compilation of the project.
+    pub options: SessionOptions,
+}
+
+/// The value assigned to a key.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Value<'a> {
+    String(&'a str),
+    Boolean(bool),
+    Integer(u64),
+}
+
+impl<'a> Value<'a> {
+    fn string(self, key: &str) -> Result<&'a str, String> {
+        match self {
+            Self::String(value) => Ok(value),
+            _ => Err(format!("Expected a string for `{}`", key)),
+        }
+    }
+
+    fn boolean(self, key: &str) -> Result<bool, String> {
+        match self {
+            Self::Boolean(value) => Ok(value),
+            _ => Err(format!("Expected `true` or `false` for `{}`", key)),
+        }
+    }
+
+    fn integer(self, key: &str) -> Result<u64, String> {
+        match self {
+            Self::Integer(value) => Ok(value),
+            _ => Err(format!("Expected an integer for `{}`", key)),
+        }
+    }
+}
+
+/// An issue found while reading a manifest.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ManifestError {
+    /// The (one-based) line number the issue was found on, if any.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl ManifestError {
+    fn new(line: impl Into<Option<usize>>, message: impl Into<String>) -> Self {
+        Self {
+            line: line.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for ManifestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{} (line {})", self.message, line),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl Manifest {
+    /// Reads a manifest from the given text.
+    pub fn parse(text: &str) -> Result<Self, ManifestError> {
+        let mut table = None;
+        let mut name = None;
+        let mut source = None;
+        let mut entry = None;
+        let mut lint_levels = LintLevels::new();
+        let mut options = SessionOptions::default();
+        let mut build_keys = Vec::new();
+        let mut features = Vec::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = strip_comment(line).trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(header) = line.strip_prefix('[') {
+                let header = header.strip_suffix(']').ok_or_else(|| {
+                    ManifestError::new(line_number, "Unclosed table header")
+                })?;
+
+                table = Some(header.trim().to_string());
+                continue;
+            }
+
+            match table.as_deref() {
+                Some("package" | "lints" | "build" | "features") => {}
+                Some(table) => {
+                    let message = format!("Unknown table `{}`", table);
+                    return Err(ManifestError::new(line_number, message));
+                }
+                None => {
+                    let message = "Expected a `[package]` table";
+                    return Err(ManifestError::new(line_number, message));
+                }
+            }
+
+            let (key, value) = key_value(line).ok_or_else(|| {
+                ManifestError::new(line_number, "Expected `key = \"value\"`")
+            })?;
+            let error = |message| ManifestError::new(line_number, message);
+
+            if table.as_deref() == Some("build") {
+                if build_keys.contains(&key) {
+                    return Err(error(format!("Duplicate key `{}`", key)));
+                }
+
+                match key {
+                    "opt-level" => {
+                        let level = value.integer(key).map_err(error)?;
+                        let level = level.to_string().parse().map_err(error)?;
+                        options.opt_level = Some(level);
+                    }
+                    "target" => {
+                        let target = value.string(key).map_err(error)?;
+                        options.target = Some(target.parse().map_err(error)?);
+                    }
+                    "deny-warnings" => {
+                        options.deny_warnings =
+                            value.boolean(key).map_err(error)?;
+                    }
+                    _ => return Err(error(format!("Unknown key `{}`", key))),
+                }
+
+                build_keys.push(key);
+                continue;
+            }
+
+            if table.as_deref() == Some("features") {
+                let feature = key.parse().map_err(error)?;
+                if features.contains(&feature) {
+                    return Err(error(format!("Duplicate feature `{}`", key)));
+                }
+
+                if value.boolean(key).map_err(error)? {
+                    options.features.enable(feature);
+                }
+
+                features.push(feature);
+                continue;
+            }
+
+            if table.as_deref() == Some("lints") {
+                if !lints::ALL.contains(&key) {
+                    let message = format!("Unknown lint `{}`", key);
+                    return Err(ManifestError::new(line_number, message));
+                }
+
+                let level = value.string(key).map_err(error)?;
+                let level = level.parse().map_err(error)?;
+
+                if lint_levels.level(key).is_some() {
+                    let message = format!("Duplicate lint `{}`", key);
+                    return Err(ManifestError::new(line_number, message));
+                }
+
+                lint_levels.set(key, level);
+                continue;
+            }
+
+            let slot = match key {
+                "name" => &mut name,
+                "source" => &mut source,
+                "entry" => &mut entry,
+                _ => {
+                    let message = format!("Unknown key `{}`", key);
+                    return Err(ManifestError::new(line_number, message));
+                }
+            };
+
+            let value = value.string(key).map_err(error)?;
+            if slot.replace(value.to_string()).is_some() {
+                let message = format!("Duplicate key `{}`", key);
+                return Err(ManifestError::new(line_number, message));
+            }
+        }
+
+        let name = name.ok_or_else(|| {
+            ManifestError::new(None, "Missing key `name` in `[package]`")
+        })?;
+
+        Ok(Self {
+            name,
+            source: source.unwrap_or_else(|| "src".to_string()).into(),
+            entry: entry.unwrap_or_else(|| "Main.he".to_string()).into(),
+            lints: lint_levels,
+            options,
+        })
+    }
+}
+
+/// Removes a trailing comment from the given line (ignoring any `#` inside a
+/// string).
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (index, c) in line.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..index],
+            _ => {}
+        }
+    }
+
+    line
+}
+
+/// Splits a line of the form `key = value` into its key and value (a basic
+/// string, a boolean or an integer).
+fn key_value(line: &str) -> Option<(&str, Value<'_>)> {
+    let (key, value) = line.split_at(line.find('=')?);
+    let value = match value[1..].trim() {
+        "true" => Value::Boolean(true),
+        "false" => Value::Boolean(false),
+        value if value.starts_with('"') => {
+            let value = value.strip_prefix('"')?.strip_suffix('"')?;
+            if value.contains('"') {
+                return None;
+            }
+
+            Value::String(value)
+        }
+        value => Value::Integer(value.parse().ok()?),
+    };
+
+    let key = key.trim();
+    if key.is_empty() {
+        None
+    } else {
+        Some((key, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::{Feature, OptLevel, Target};
+    use helios_diagnostics::LintLevel;
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = Manifest::parse(
+            "# A project\n[package]\nname = \"hello\" # The name\n\n\
+             source = \"lib\"\nentry = \"App.he\"\n",
+        );
+
+        assert_eq!(
+            manifest,
+            Ok(Manifest {
+                name: "hello".to_string(),
+                source: "lib".into(),
+                entry: "App.he".into(),
+                lints: LintLevels::new(),
+                options: SessionOptions::default(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_manifest_options() {
+        let manifest = Manifest::parse(
+            "[package]\nname = \"hello\"\n\n\
+             [build]\nopt-level = 2\ntarget = \"wasm\"\ndeny-warnings = true\n\n\
+             [features]\nindentation-blocks = true\nbackward-pipelines = false\n",
+        )
+        .unwrap();
+
+        let options = manifest.options;
+        assert_eq!(options.opt_level, Some(OptLevel::O2));
+        assert_eq!(options.target, Some(Target::Wasm));
+        assert!(options.deny_warnings);
+        assert_eq!(
+            options.features.iter().collect::<Vec<_>>(),
+            vec![Feature::IndentationBlocks]
+        );
+    }
+
+    #[test]
+    fn test_parse_manifest_lints() {
+        let manifest = Manifest::parse(
+            "[lints]\nnever-mutated = \"deny\"\n\n\
+             [package]\nname = \"hello\"\n\n\
+             [lints]\nconfusable = \"allow\" # Too many false positives\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            manifest.lints.level("never-mutated"),
+            Some(LintLevel::Deny)
+        );
+        assert_eq!(manifest.lints.level("confusable"), Some(LintLevel::Allow));
+        assert_eq!(manifest.lints.level("mixed-script"), None);
+    }
+
+    #[test]
+    fn test_parse_manifest_defaults() {
+        let manifest = Manifest::parse("[package]\nname = \"hello\"").unwrap();
+        assert_eq!(manifest.source, PathBuf::from("src"));
+        assert_eq!(manifest.entry, PathBuf::from("Main.he"));
+    }
+
+    #[test]
+    fn test_parse_invalid_manifest() {
+        let check = |text: &str, line: Option<usize>, message: &str| {
+            assert_eq!(
+                Manifest::parse(text),
+                Err(ManifestError::new(line, message))
+            );
+        };
+
+        check("name = \"hello\"", Some(1), "Expected a `[package]` table");
+        check("[package\n", Some(1), "Unclosed table header");
+        check(
+            "[dependencies]\nfoo = \"1\"",
+            Some(2),
+            "Unknown table `dependencies`",
+        );
+        check(
+            "[package]\nname = hello",
+            Some(2),
+            "Expected `key = \"value\"`",
+        );
+        check(
+            "[package]\nversion = \"1\"",
+            Some(2),
+            "Unknown key `version`",
+        );
+        check(
+            "[package]\nname = \"a\"\nname = \"b\"",
+            Some(3),
+            "Duplicate key `name`",
+        );
+        check("[package]\n", None, "Missing key `name` in `[package]`");
+        check(
+            "[lints]\nunused = \"allow\"",
+            Some(2),
+            "Unknown lint `unused`",
+        );
+        check(
+            "[lints]\nconfusable = \"error\"",
+            Some(2),
+            "Unknown lint level `error` (expected `allow`, `warn` or `deny`)",
+        );
+        check(
+            "[lints]\nconfusable = \"warn\"\nconfusable = \"deny\"",
+            Some(3),
+            "Duplicate lint `confusable`",
+        );
+        check(
+            "[package]\nname = true",
+            Some(2),
+            "Expected a string for `name`",
+        );
+        check(
+            "[build]\nopt-level = 4",
+            Some(2),
+            "Unknown optimization level `4` (expected `0`, `1`, `2` or `3`)",
+        );
+        check(
+            "[build]\ndeny-warnings = \"yes\"",
+            Some(2),
+            "Expected `true` or `false` for `deny-warnings`",
+        );
+        check(
+            "[features]\nmacros = true",
+            Some(2),
+            "Unknown feature `macros`",
+        );
+    }
+}
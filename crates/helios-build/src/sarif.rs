@@ -0,0 +1,313 @@
+use helios_diagnostics::files::{Files, SimpleFiles};
+use helios_diagnostics::{Diagnostic, Location, Severity};
+use helios_parser::lints;
+use serde_json::{json, Value};
+use std::path::Path;
+
+const SARIF_VERSION: &str = "2.1.0";
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// The rule of the diagnostics that aren't reported by a lint (such as syntax
+/// errors), since every result needs one.
+const COMPILER_RULE: &str = "compiler";
+
+/// Renders the given diagnostics as a SARIF log with a single run, where
+/// every lint is a rule.
+pub(crate) fn render(
+    files: &SimpleFiles<String, String>,
+    diagnostics: &[Diagnostic<usize>],
+) -> helios_diagnostics::Result<String> {
+    let rules = std::iter::once(COMPILER_RULE)
+        .chain(lints::ALL.iter().copied())
+        .collect::<Vec<_>>();
+
+    let results = diagnostics
+        .iter()
+        .map(|diagnostic| result(files, &rules, diagnostic))
+        .collect::<helios_diagnostics::Result<Vec<_>>>()?;
+
+    let log = json!({
+        "$schema": SARIF_SCHEMA,
+        "version": SARIF_VERSION,
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "helios",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/helios-lang/helios",
+                    "rules": rules.iter().map(|&rule| rule_json(rule)).collect::<Vec<_>>(),
+                }
+            },
+            "columnKind": "unicodeCodePoints",
+            "results": results,
+        }]
+    });
+
+    Ok(serde_json::to_string_pretty(&log).unwrap() + "\n")
+}
+
+fn rule_json(rule: &str) -> Value {
+    let (description, level) = match lints::description(rule) {
+        Some(description) => (description, "warning"),
+        None => (
+            "An error found by the compiler, which can't be allowed.",
+            "error",
+        ),
+    };
+
+    let mut rule_json = json!({
+        "id": rule,
+        "shortDescription": { "text": description },
+        "defaultConfiguration": { "level": level },
+    });
+
+    if let Some(url) = lints::documentation_url(rule) {
+        rule_json["helpUri"] = json!(url);
+    }
+
+    rule_json
+}
+
+fn result(
+    files: &SimpleFiles<String, String>,
+    rules: &[&str],
+    diagnostic: &Diagnostic<usize>,
+) -> helios_diagnostics::Result<Value> {
+    let rule = diagnostic.code.unwrap_or(COMPILER_RULE);
+    let rule_index = rules.iter().position(|&it| it == rule);
+
+    let level = match diagnostic.severity {
+        Severity::Bug | Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Note => "note",
+    };
+
+    let message = format!(
+        "{}: {}",
+        diagnostic.title,
+        diagnostic.message.plain().to_string().trim_end()
+    );
+
+    let related_locations = diagnostic
+        .labels
+        .iter()
+        .enumerate()
+        .map(|(index, label)| {
+            let mut location = location_json(files, &label.location)?;
+            location["id"] = json!(index);
+            location["message"] = json!({
+                "text": label.message.plain().to_string().trim_end(),
+            });
+            Ok(location)
+        })
+        .collect::<helios_diagnostics::Result<Vec<_>>>()?;
+
+    let fixes = diagnostic
+        .suggestions
+        .iter()
+        .map(|suggestion| {
+            let location = &suggestion.location;
+            let description = if suggestion.replacement.is_empty() {
+                "Remove this".to_string()
+            } else if location.range.is_empty() {
+                format!("Insert `{}`", suggestion.replacement)
+            } else {
+                format!("Replace with `{}`", suggestion.replacement)
+            };
+
+            Ok(json!({
+                "description": { "text": description },
+                "artifactChanges": [{
+                    "artifactLocation": artifact_location(files, location.file_id)?,
+                    "replacements": [{
+                        "deletedRegion": region(files, location)?,
+                        "insertedContent": { "text": suggestion.replacement },
+                    }],
+                }],
+            }))
+        })
+        .collect::<helios_diagnostics::Result<Vec<_>>>()?;
+
+    let mut result = json!({
+        "ruleId": rule,
+        "level": level,
+        "message": { "text": message },
+        "locations": [location_json(files, &diagnostic.location)?],
+    });
+
+    if let Some(index) = rule_index {
+        result["ruleIndex"] = json!(index);
+    }
+
+    if !related_locations.is_empty() {
+        result["relatedLocations"] = json!(related_locations);
+    }
+
+    if !fixes.is_empty() {
+        result["fixes"] = json!(fixes);
+    }
+
+    Ok(result)
+}
+
+fn location_json(
+    files: &SimpleFiles<String, String>,
+    location: &Location<usize>,
+) -> helios_diagnostics::Result<Value> {
+    Ok(json!({
+        "physicalLocation": {
+            "artifactLocation": artifact_location(files, location.file_id)?,
+            "region": region(files, location)?,
+        }
+    }))
+}
+
+/// The location of the given file, as a relative URI if its path is
+/// relative (or a `file` URI otherwise).
+fn artifact_location(
+    files: &SimpleFiles<String, String>,
+    file_id: usize,
+) -> helios_diagnostics::Result<Value> {
+    let name = files.name(file_id)?;
+    let path = name.replace('\\', "/");
+    let uri = if Path::new(&name).is_absolute() {
+        // Windows paths (such as `C:/Foo.he`) don't start with a slash
+        format!("file:///{}", path.trim_start_matches('/'))
+    } else {
+        path.strip_prefix("./").unwrap_or(&path).to_string()
+    };
+
+    Ok(json!({ "uri": uri }))
+}
+
+/// The lines and columns of the given location (indexed by 1, where the end
+/// column is the one after the location).
+fn region(
+    files: &SimpleFiles<String, String>,
+    location: &Location<usize>,
+) -> helios_diagnostics::Result<Value> {
+    let file_id = location.file_id;
+    let position = |offset| {
+        let line_index = files.line_index(file_id, offset)?;
+        let line = files.line_number(file_id, line_index)?;
+        let column = files.column_number(file_id, line_index, offset)?;
+        Ok::<_, helios_diagnostics::Error>((line, column))
+    };
+
+    let (start_line, start_column) = position(location.range.start)?;
+    let (end_line, end_column) = position(location.range.end)?;
+
+    Ok(json!({
+        "startLine": start_line,
+        "startColumn": start_column,
+        "endLine": end_line,
+        "endColumn": end_column,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use helios_diagnostics::Applicability;
+
+    #[test]
+    fn test_render() {
+        let mut files = SimpleFiles::new();
+        let file_id =
+            files.add("./src/Main.he".to_string(), "var a = 1\n".into());
+        let absolute = files.add("/tmp/Foo.he".to_string(), String::new());
+        let diagnostics = vec![
+            Diagnostic::warning("Binding is never mutated")
+                .code(lints::NEVER_MUTATED)
+                .location(Location::new(file_id, 4..5))
+                .message("`a` is never assigned.")
+                .suggestion(
+                    Location::new(file_id, 0..3),
+                    "let",
+                    Applicability::MachineApplicable,
+                ),
+            Diagnostic::error("Unexpected token")
+                .location(Location::new(file_id, 9..10))
+                .message("I expected an expression.")
+                .label(Location::new(file_id, 6..7), "After this"),
+        ];
+
+        let log = render(&files, &diagnostics).unwrap();
+        let log = serde_json::from_str::<Value>(&log).unwrap();
+        assert_eq!(log["version"], "2.1.0");
+
+        let run = &log["runs"][0];
+        let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+        assert_eq!(rules.len(), lints::ALL.len() + 1);
+        assert!(rules
+            .iter()
+            .all(|rule| rule["shortDescription"]["text"].is_string()));
+        assert_eq!(
+            rules[3]["helpUri"],
+            "https://github.com/helios-lang/helios/blob/master/docs/lints.md\
+             #never-mutated"
+        );
+
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(
+            results[0],
+            json!({
+                "ruleId": "never-mutated",
+                "ruleIndex": 3,
+                "level": "warning",
+                "message": {
+                    "text": "Binding is never mutated: `a` is never assigned.",
+                },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": "src/Main.he" },
+                        "region": {
+                            "startLine": 1,
+                            "startColumn": 5,
+                            "endLine": 1,
+                            "endColumn": 6,
+                        },
+                    }
+                }],
+                "fixes": [{
+                    "description": { "text": "Replace with `let`" },
+                    "artifactChanges": [{
+                        "artifactLocation": { "uri": "src/Main.he" },
+                        "replacements": [{
+                            "deletedRegion": {
+                                "startLine": 1,
+                                "startColumn": 1,
+                                "endLine": 1,
+                                "endColumn": 4,
+                            },
+                            "insertedContent": { "text": "let" },
+                        }],
+                    }],
+                }],
+            })
+        );
+
+        assert_eq!(results[1]["ruleId"], "compiler");
+        assert_eq!(
+            artifact_location(&files, absolute).unwrap(),
+            json!({ "uri": "file:///tmp/Foo.he" })
+        );
+        assert_eq!(results[1]["level"], "error");
+
+        // The newline ends the first line, so the location ends on the next
+        let region = &results[1]["locations"][0]["physicalLocation"]["region"];
+        assert_eq!(
+            region,
+            &json!({
+                "startLine": 1,
+                "startColumn": 10,
+                "endLine": 2,
+                "endColumn": 1,
+            })
+        );
+        assert_eq!(
+            results[1]["relatedLocations"][0]["message"]["text"],
+            "After this"
+        );
+    }
+}
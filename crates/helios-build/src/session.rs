@@ -0,0 +1,184 @@
+//! The options of a compilation session, which are given by the `[build]` and
+//! `[features]` tables of a project's manifest and by the command line (which
+//! takes precedence).
+
+use helios_parser::ParseOptions;
+pub use helios_parser::{Feature, Features};
+use std::str::FromStr;
+
+/// How much the compiled output is optimized, from `0` (not at all) to `3`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OptLevel {
+    #[default]
+    O0,
+    O1,
+    O2,
+    O3,
+}
+
+impl FromStr for OptLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "0" => Ok(Self::O0),
+            "1" => Ok(Self::O1),
+            "2" => Ok(Self::O2),
+            "3" => Ok(Self::O3),
+            _ => Err(format!(
+                "Unknown optimization level `{}` (expected `0`, `1`, `2` or \
+                 `3`)",
+                s
+            )),
+        }
+    }
+}
+
+/// What the program is compiled for.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Target {
+    /// Run directly from the syntax tree.
+    #[default]
+    Interpreter,
+    /// Run as bytecode on a virtual machine.
+    Vm,
+    /// Compiled to an executable for the host.
+    Native,
+    /// Compiled to a WebAssembly module.
+    Wasm,
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "interpreter" => Ok(Self::Interpreter),
+            "vm" => Ok(Self::Vm),
+            "native" => Ok(Self::Native),
+            "wasm" => Ok(Self::Wasm),
+            _ => Err(format!(
+                "Unknown target `{}` (expected `interpreter`, `vm`, `native` \
+                 or `wasm`)",
+                s
+            )),
+        }
+    }
+}
+
+/// The options shared by every file of a compilation.
+///
+/// The optimization level and target are only set if they were given, so
+/// that the options of the command line can be applied on top of those of
+/// the manifest (see [`SessionOptions::merge`]).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SessionOptions {
+    pub opt_level: Option<OptLevel>,
+    pub target: Option<Target>,
+    pub features: Features,
+    /// Whether every warning is reported as an error (so that it fails the
+    /// build).
+    pub deny_warnings: bool,
+}
+
+impl SessionOptions {
+    /// Combines these options with the given ones, which take precedence
+    /// (the features enabled by either are enabled).
+    pub fn merge(&self, other: &SessionOptions) -> SessionOptions {
+        let mut features = self.features;
+        other
+            .features
+            .iter()
+            .for_each(|feature| features.enable(feature));
+
+        SessionOptions {
+            opt_level: other.opt_level.or(self.opt_level),
+            target: other.target.or(self.target),
+            features,
+            deny_warnings: self.deny_warnings || other.deny_warnings,
+        }
+    }
+
+    /// The options of the parser with the features of the session.
+    pub fn parse_options(&self) -> ParseOptions {
+        ParseOptions {
+            features: self.features,
+            ..ParseOptions::default()
+        }
+    }
+
+    /// Reads the given command-line flag (`--opt-level=<level>`,
+    /// `--target=<target>`, `--feature=<feature>` or `--deny-warnings`),
+    /// returning `false` if it isn't one of the session's.
+    pub fn parse_flag(&mut self, flag: &str) -> Result<bool, String> {
+        if flag == "--deny-warnings" {
+            self.deny_warnings = true;
+        } else if let Some(level) = flag.strip_prefix("--opt-level=") {
+            self.opt_level = Some(level.parse()?);
+        } else if let Some(target) = flag.strip_prefix("--target=") {
+            self.target = Some(target.parse()?);
+        } else if let Some(feature) = flag.strip_prefix("--feature=") {
+            self.features.enable(feature.parse()?);
+        } else {
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_flags() {
+        let mut options = SessionOptions::default();
+        for flag in ["--opt-level=2", "--feature=indentation-blocks"] {
+            assert_eq!(options.parse_flag(flag), Ok(true));
+        }
+        assert_eq!(options.parse_flag("--profile"), Ok(false));
+        assert_eq!(
+            options.parse_flag("--target=jvm"),
+            Err("Unknown target `jvm` (expected `interpreter`, `vm`, \
+                 `native` or `wasm`)"
+                .to_string())
+        );
+        assert_eq!(
+            options.parse_flag("--feature=macros"),
+            Err("Unknown feature `macros`".to_string())
+        );
+
+        assert_eq!(options.opt_level, Some(OptLevel::O2));
+        assert_eq!(options.target, None);
+        assert!(!options.deny_warnings);
+        let features = options.parse_options().features;
+        assert!(features.is_enabled(Feature::IndentationBlocks));
+    }
+
+    #[test]
+    fn test_merge_options() {
+        let mut manifest = SessionOptions {
+            opt_level: Some(OptLevel::O3),
+            target: Some(Target::Vm),
+            ..SessionOptions::default()
+        };
+        manifest.features.enable(Feature::BackwardPipelines);
+
+        let mut flags = SessionOptions {
+            target: Some(Target::Wasm),
+            deny_warnings: true,
+            ..SessionOptions::default()
+        };
+        flags.features.enable(Feature::IndentationBlocks);
+
+        let options = manifest.merge(&flags);
+        assert_eq!(options.opt_level, Some(OptLevel::O3));
+        assert_eq!(options.target, Some(Target::Wasm));
+        assert_eq!(
+            options.features.iter().collect::<Vec<_>>(),
+            vec![Feature::IndentationBlocks, Feature::BackwardPipelines]
+        );
+        assert!(options.deny_warnings);
+    }
+}
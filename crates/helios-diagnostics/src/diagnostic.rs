@@ -16,18 +16,69 @@ impl<FileId> Location<FileId> {
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd)]
 #[repr(u8)]
 pub enum Severity {
     Bug = 3,
     Error = 2,
     Warning = 1,
+    #[default]
     Note = 0,
 }
 
-impl Default for Severity {
-    fn default() -> Self {
-        Self::Note
+/// A secondary location attached to a [`Diagnostic`], such as the location of
+/// a previous definition.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Label<FileId> {
+    pub location: Location<FileId>,
+    pub message: FormattedString,
+}
+
+impl<FileId> Label<FileId> {
+    pub fn new(
+        location: Location<FileId>,
+        message: impl Into<FormattedString>,
+    ) -> Self {
+        Self {
+            location,
+            message: message.into(),
+        }
+    }
+}
+
+/// How likely a [`Suggestion`] is to be what the user meant.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Applicability {
+    /// The suggestion is certainly right, so tools may apply it without
+    /// asking the user.
+    MachineApplicable,
+    /// The suggestion may not be what the user meant, so they should look at
+    /// it before applying it.
+    #[default]
+    MaybeIncorrect,
+}
+
+/// A change to the source code fixing the issue reported by a [`Diagnostic`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Suggestion<FileId> {
+    pub location: Location<FileId>,
+    /// The text replacing the source code at the location (which is inserted
+    /// if the location is empty).
+    pub replacement: String,
+    pub applicability: Applicability,
+}
+
+impl<FileId> Suggestion<FileId> {
+    pub fn new(
+        location: Location<FileId>,
+        replacement: impl Into<String>,
+        applicability: Applicability,
+    ) -> Self {
+        Self {
+            location,
+            replacement: replacement.into(),
+            applicability,
+        }
     }
 }
 
@@ -37,10 +88,17 @@ impl Default for Severity {
 pub struct Diagnostic<FileId> {
     pub location: Location<FileId>,
     pub severity: Severity,
+    /// The name of the lint reporting the diagnostic (such as
+    /// `never-mutated`), if its level can be configured (see [`LintLevels`]).
+    ///
+    /// [`LintLevels`]: crate::LintLevels
+    pub code: Option<&'static str>,
     pub title: String,
     pub description: Option<FormattedString>,
     pub message: FormattedString,
     pub hint: Option<FormattedString>,
+    pub labels: Vec<Label<FileId>>,
+    pub suggestions: Vec<Suggestion<FileId>>,
 }
 
 impl<FileId> Diagnostic<FileId>
@@ -58,10 +116,13 @@ where
         Self {
             location,
             severity,
+            code: None,
             title: title.into(),
             description: description.into(),
             message: message.into(),
             hint: hint.into(),
+            labels: Vec::new(),
+            suggestions: Vec::new(),
         }
     }
 
@@ -102,6 +163,11 @@ where
         self
     }
 
+    pub fn code(mut self, code: &'static str) -> Self {
+        self.code = Some(code);
+        self
+    }
+
     pub fn title(mut self, title: impl Into<String>) -> Self {
         self.title = title.into();
         self
@@ -129,6 +195,28 @@ where
         self.hint = Some(hint.into());
         self
     }
+
+    /// Adds a secondary [`Label`] pointing at the given location.
+    pub fn label(
+        mut self,
+        location: Location<FileId>,
+        message: impl Into<FormattedString>,
+    ) -> Self {
+        self.labels.push(Label::new(location, message));
+        self
+    }
+
+    /// Adds a [`Suggestion`] replacing the source code at the given location.
+    pub fn suggestion(
+        mut self,
+        location: Location<FileId>,
+        replacement: impl Into<String>,
+        applicability: Applicability,
+    ) -> Self {
+        let suggestion = Suggestion::new(location, replacement, applicability);
+        self.suggestions.push(suggestion);
+        self
+    }
 }
 
 #[cfg(test)]
@@ -1,24 +1,8 @@
 use crate::{Error, Result};
+use helios_text::{column_index, LineIndex};
 use std::fmt::Display;
 use std::ops::Range;
 
-fn line_indexes<'a>(source: &'a str) -> impl 'a + Iterator<Item = usize> {
-    std::iter::once(0).chain(source.match_indices('\n').map(|(i, _)| i + 1))
-}
-
-fn column_index(
-    source: &str,
-    line_range: Range<usize>,
-    byte_index: usize,
-) -> usize {
-    use std::cmp::min;
-    let end_index = min(byte_index, min(line_range.end, source.len()));
-
-    (line_range.start..end_index)
-        .filter(|index| source.is_char_boundary(index + 1))
-        .count()
-}
-
 pub trait Files<'a> {
     type FileId: Copy + PartialEq;
     type Name: 'a + Display;
@@ -78,7 +62,7 @@ pub trait Files<'a> {
 pub struct SimpleFile<Name, Source> {
     name: Name,
     source: Source,
-    line_indexes: Vec<usize>,
+    line_index: LineIndex,
 }
 
 impl<Name, Source> SimpleFile<Name, Source>
@@ -87,12 +71,12 @@ where
     Source: AsRef<str>,
 {
     pub fn new(name: Name, source: Source) -> Self {
-        let line_indexes = line_indexes(source.as_ref()).collect();
+        let line_index = LineIndex::new(source.as_ref());
 
         Self {
             name,
             source,
-            line_indexes,
+            line_index,
         }
     }
 
@@ -105,16 +89,11 @@ where
     }
 
     fn line_start(&self, line_index: usize) -> Result<usize> {
-        if line_index == self.line_indexes.len() {
-            return Ok(self.source.as_ref().len());
-        }
-
-        self.line_indexes
-            .get(line_index)
-            .cloned()
+        self.line_index
+            .line_start(line_index)
             .ok_or(Error::OutOfBounds {
                 given: line_index,
-                max: self.line_indexes.len() - 1,
+                max: self.line_index.line_count() - 1,
             })
     }
 }
@@ -141,10 +120,7 @@ where
         _: Self::FileId,
         byte_index: usize,
     ) -> Result<usize> {
-        Ok(self
-            .line_indexes
-            .binary_search(&byte_index)
-            .unwrap_or_else(|expected| expected.checked_sub(1).unwrap_or(0)))
+        Ok(self.line_index.line(byte_index))
     }
 
     fn line_range(
@@ -159,14 +135,25 @@ where
     }
 }
 
+#[derive(Clone, Debug)]
 pub struct SimpleFiles<Name, Source> {
     files: Vec<SimpleFile<Name, Source>>,
 }
 
-impl<'a, Name, Source> SimpleFiles<Name, Source>
+impl<Name, Source> Default for SimpleFiles<Name, Source>
 where
-    Name: 'a + std::fmt::Display + Clone,
-    Source: 'a + AsRef<str>,
+    Name: std::fmt::Display + Clone,
+    Source: AsRef<str>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Name, Source> SimpleFiles<Name, Source>
+where
+    Name: std::fmt::Display + Clone,
+    Source: AsRef<str>,
 {
     pub fn new() -> Self {
         Self { files: Vec::new() }
@@ -227,7 +214,7 @@ mod tests {
         let file = SimpleFile::new("Foo.he", source);
 
         assert_eq!(
-            file.line_indexes,
+            file.line_index.line_starts(),
             [
                 0,  // "let a = 0\n"
                 10, // "let b = 1\r\n"
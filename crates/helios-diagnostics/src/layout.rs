@@ -0,0 +1,162 @@
+use colored::*;
+use std::ops::Range;
+
+/// The text replacing the parts of a source line that are left out.
+const ELISION: &str = "...";
+
+/// The narrowest that a source line is ever truncated to, so that some of it
+/// is still shown in very narrow terminals.
+const MIN_LINE_WIDTH: usize = 20;
+
+/// Wraps the given text to the given width.
+pub(crate) fn wrap(text: &str, width: usize) -> String {
+    textwrap::fill(text, textwrap::Options::new(width))
+}
+
+/// Wraps the given text after a label (such as `Hint:`), with the lines after
+/// the first indented under the start of the text.
+pub(crate) fn wrap_labelled(label: &str, text: &str, width: usize) -> String {
+    let indent = " ".repeat(label.chars().count() + 2);
+    let options = textwrap::Options::new(width).subsequent_indent(&indent);
+    let text = format!("{}: {}", label.underline(), text);
+    textwrap::fill(&text, options)
+}
+
+/// Expands the tabs of the given line of source code to spaces, up to the next
+/// multiple of the given tab width.
+///
+/// Returns the expanded line along with the columns of the given span of
+/// columns (indexed by 0) in it.
+pub(crate) fn expand_tabs(
+    line: &str,
+    span: Range<usize>,
+    tab_width: usize,
+) -> (String, Range<usize>) {
+    let tab_width = std::cmp::max(tab_width, 1);
+    let mut expanded = String::with_capacity(line.len());
+    let mut columns = Vec::with_capacity(line.len() + 1);
+
+    // `columns[i]` is the column of the `i`th character once expanded
+    let mut width = 0;
+    for c in line.chars() {
+        columns.push(width);
+        if c == '\t' {
+            let spaces = tab_width - width % tab_width;
+            expanded.extend(std::iter::repeat_n(' ', spaces));
+            width += spaces;
+        } else {
+            expanded.push(c);
+            width += 1;
+        }
+    }
+
+    // Columns past the end of the line (such as the line feed) are one
+    // character wide
+    let column = |index: usize| match columns.get(index) {
+        Some(&column) => column,
+        None => width + index - columns.len(),
+    };
+
+    (expanded, column(span.start)..column(span.end))
+}
+
+/// Truncates the given line of source code to the given width, leaving out
+/// the parts furthest from the given span of columns (indexed by 0).
+///
+/// Returns the truncated line along with the columns of the span in it (the
+/// end of the span may be cut off).
+pub(crate) fn truncate_line(
+    line: &str,
+    span: Range<usize>,
+    width: usize,
+) -> (String, Range<usize>) {
+    let width = std::cmp::max(width, MIN_LINE_WIDTH);
+    let chars = line.chars().collect::<Vec<_>>();
+    let len = chars.len();
+    if len <= width {
+        return (line.to_string(), span);
+    }
+
+    let text = |range: Range<usize>| chars[range].iter().collect::<String>();
+    let shifted = |from: usize, to: usize| {
+        let shift = if from > 0 { ELISION.len() } else { 0 };
+        let start = span.start - from + shift;
+        let end = std::cmp::min(span.end, to) - from + shift;
+        start..std::cmp::max(start, end)
+    };
+
+    // The span starts a third of the way in, so that some of the code before
+    // it is shown too
+    let from = span.start.saturating_sub(width / 3);
+    if from == 0 {
+        let to = width - ELISION.len();
+        let line = format!("{}{}", text(0..to), ELISION);
+        (line, shifted(0, to))
+    } else if from + width - ELISION.len() >= len {
+        let from = len - (width - ELISION.len());
+        let line = format!("{}{}", ELISION, text(from..len));
+        (line, shifted(from, len))
+    } else {
+        let to = from + width - 2 * ELISION.len();
+        let line = format!("{}{}{}", ELISION, text(from..to), ELISION);
+        (line, shifted(from, to))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_labelled() {
+        colored::control::set_override(false);
+
+        let text = wrap_labelled("Hint", "Try defining it with `let`.", 20);
+        assert_eq!(text, "Hint: Try defining\n      it with `let`.");
+    }
+
+    #[test]
+    fn test_expand_tabs() {
+        let line = "\tlet a =\tb";
+        assert_eq!(
+            expand_tabs(line, 1..4, 4),
+            ("    let a = b".to_string(), 4..7)
+        );
+        assert_eq!(
+            expand_tabs(line, 9..10, 4),
+            ("    let a = b".to_string(), 12..13)
+        );
+        assert_eq!(expand_tabs(line, 8..9, 8).1, 15..16);
+
+        // Spans past the end of the line
+        assert_eq!(expand_tabs(line, 10..12, 4).1, 13..15);
+    }
+
+    #[test]
+    fn test_truncate_line() {
+        let line = "let a = 1 + 2 + 3 + 4 + 5 + 6 + 7 + 8 + 9 + 10 + 11 + 12";
+        assert_eq!(truncate_line(line, 4..5, 80), (line.to_string(), 4..5));
+
+        assert_eq!(
+            truncate_line(line, 4..5, 20),
+            ("let a = 1 + 2 + 3...".to_string(), 4..5)
+        );
+        assert_eq!(
+            truncate_line(line, 28..33, 20),
+            ("...+ 5 + 6 + 7 + ...".to_string(), 9..14)
+        );
+        assert_eq!(
+            truncate_line(line, 54..56, 20),
+            ("... 9 + 10 + 11 + 12".to_string(), 18..20)
+        );
+
+        // The span is cut off where the line is
+        assert_eq!(
+            truncate_line(line, 8..40, 20),
+            ("...t a = 1 + 2 + ...".to_string(), 9..17)
+        );
+
+        // The line is never narrower than `MIN_LINE_WIDTH`
+        assert_eq!(truncate_line(line, 4..5, 4), truncate_line(line, 4..5, 20));
+    }
+}
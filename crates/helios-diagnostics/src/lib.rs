@@ -1,9 +1,17 @@
 pub mod diagnostic;
 pub mod files;
+mod layout;
+pub mod lint;
+pub mod sink;
+pub mod theme;
 
 pub use crate::diagnostic::*;
+pub use crate::lint::{LintLevel, LintLevels};
+pub use crate::sink::DiagnosticSink;
+pub use crate::theme::{ColorChoice, Highlighter, Marker, SyntaxColors, Theme};
 use colored::*;
 use files::Files;
+use std::ops::Range;
 use std::{fmt::Display, io::Write};
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -49,99 +57,325 @@ impl From<std::fmt::Error> for Error {
     }
 }
 
+/// The width of the terminal, in columns.
+#[cfg(feature = "terminal")]
+fn termwidth() -> usize {
+    textwrap::termwidth()
+}
+
+/// The width of the terminal, in columns (which is fixed without the
+/// `terminal` feature, although the feature is still on if any crate of the
+/// build enables it).
+#[cfg(not(feature = "terminal"))]
+fn termwidth() -> usize {
+    80
+}
+
+/// Prints the given diagnostic with the default [`Theme`].
 pub fn emit<'files, F: Files<'files>>(
     f: &mut dyn Write,
     files: &'files F,
     diagnostic: &Diagnostic<F::FileId>,
+) -> Result<()> {
+    emit_with_theme(f, files, diagnostic, &Theme::default())
+}
+
+/// Prints the given diagnostic with the colors and characters of the given
+/// theme.
+pub fn emit_with_theme<'files, F: Files<'files>>(
+    f: &mut dyn Write,
+    files: &'files F,
+    diagnostic: &Diagnostic<F::FileId>,
+    theme: &Theme,
 ) -> Result<()> {
     let file_id = diagnostic.location.file_id;
+    let severity = diagnostic.severity;
+
+    let line_index =
+        files.line_index(file_id, diagnostic.location.range.start)?;
+    let line_number = files.line_number(file_id, line_index)?;
+    let column_start = files.column_number(
+        file_id,
+        line_index,
+        diagnostic.location.range.start,
+    )?;
+
+    let width = termwidth();
+    let marker = theme.severity(severity);
+    let header = {
+        let name = match severity {
+            Severity::Bug => "Bug",
+            Severity::Error => "Error",
+            Severity::Warning => "Warning",
+            Severity::Note => "Note",
+        };
+
+        let fill = theme.header_fill;
+        let msg = format!("{}{} {}: {} ", fill, fill, name, diagnostic.title);
+        let remaining_len = width.saturating_sub(msg.chars().count());
+        let fill = fill.to_string().repeat(remaining_len);
+        format!("{}{}", msg, fill)
+    };
+
+    let location_str = format!(
+        "-> {}:{}:{}",
+        files.name(file_id)?,
+        line_number,
+        column_start
+    );
+    writeln!(f, "{}", header.color(marker.color))?;
+    writeln!(f, "{}\n", location_str.color(marker.color))?;
+
+    if let Some(description) = &diagnostic.description {
+        let description = description.styled(theme.code).to_string();
+        writeln!(f, "{}\n", layout::wrap(&description, width))?;
+    }
+
+    emit_snippet(f, files, &diagnostic.location, theme, marker)?;
+    let message = diagnostic.message.styled(theme.code).to_string();
+    writeln!(f, "{}\n", layout::wrap(&message, width).trim_end())?;
+
+    for label in &diagnostic.labels {
+        emit_snippet(f, files, &label.location, theme, theme.label)?;
+        let message = label.message.styled(theme.code).to_string();
+        writeln!(f, "{}\n", layout::wrap(&message, width).trim_end())?;
+    }
+
+    for suggestion in &diagnostic.suggestions {
+        let location = &suggestion.location;
+        emit_snippet(f, files, location, theme, theme.suggestion)?;
+
+        let replacement = suggestion.replacement.color(theme.code);
+        let help = if suggestion.replacement.is_empty() {
+            "remove this".to_string()
+        } else if suggestion.location.range.is_empty() {
+            format!("insert {} here", quoted(replacement))
+        } else {
+            format!("replace with {}", quoted(replacement))
+        };
+
+        writeln!(f, "{}\n", layout::wrap_labelled("Help", &help, width))?;
+    }
+
+    if let Some(hint) = &diagnostic.hint {
+        let hint = hint.styled(theme.code).to_string();
+        writeln!(f, "{}\n", layout::wrap_labelled("Hint", &hint, width))?;
+    }
+
+    Ok(())
+}
+
+/// Quotes the given code like a [`FormattedString`] does: with backticks when
+/// it isn't colorized.
+///
+/// [`FormattedString`]: helios_formatting::FormattedString
+fn quoted(code: ColoredString) -> String {
+    if colored::control::SHOULD_COLORIZE.should_colorize() {
+        code.to_string()
+    } else {
+        format!("`{}`", code)
+    }
+}
+
+/// Prints the line of source code containing the given location, with the
+/// location underlined.
+///
+/// Lines too long for the terminal are truncated around the location.
+fn emit_snippet<'files, F: Files<'files>>(
+    f: &mut dyn Write,
+    files: &'files F,
+    location: &Location<F::FileId>,
+    theme: &Theme,
+    marker: Marker,
+) -> Result<()> {
+    let file_id = location.file_id;
     let source = files.source(file_id)?;
 
-    let severity = diagnostic.severity;
-    let error_range = diagnostic.location.range.clone();
-    let error_start = error_range.start;
-    let error_end = error_range.end;
+    let start = location.range.start;
+    let end = location.range.end;
 
-    let line_index = files.line_index(file_id, error_range.start)?;
+    let line_index = files.line_index(file_id, start)?;
     let line_range = files.line_range(file_id, line_index)?;
 
     let line_number = files.line_number(file_id, line_index)?;
-    let column_start = files.column_number(file_id, line_index, error_start)?;
-    let column_end = files.column_number(file_id, line_index, error_end)?;
-
-    let (color, header, underline) = {
-        let make_header = |msg: String| {
-            let remaining_len = textwrap::termwidth()
-                .checked_sub(msg.len())
-                .unwrap_or_default();
-            format!("{}{}", msg, "-".repeat(remaining_len))
-        };
+    let column_start = files.column_number(file_id, line_index, start)?;
+    let column_end = files.column_number(file_id, line_index, end)?;
 
-        match severity {
-            Severity::Bug => {
-                let msg = format!("-- Bug: {} ", diagnostic.title);
-                let header = make_header(msg);
-                (Color::Magenta, header, "^")
-            }
-            Severity::Error => {
-                let msg = format!("-- Error: {} ", diagnostic.title);
-                let header = make_header(msg);
-                (Color::Red, header, "^")
-            }
-            Severity::Warning => {
-                let msg = format!("-- Warning: {} ", diagnostic.title);
-                let header = make_header(msg);
-                (Color::Yellow, header, "~")
-            }
-            Severity::Note => {
-                let msg = format!("-- Note: {} ", diagnostic.title);
-                let header = make_header(msg);
-                (Color::Blue, header, "-")
+    let gutter = format!("{:>4} {} ", line_number, theme.gutter_separator);
+    let gutter_width = gutter.chars().count();
+    let line = &source.as_ref()[line_range].trim_end(); // remove trailing LF
+
+    // `column_start` and `column_end` are indexed by 1
+    let (line, columns) = layout::expand_tabs(
+        line,
+        column_start - 1..column_end - 1,
+        theme.tab_width,
+    );
+    let (line, columns) = layout::truncate_line(
+        &line,
+        columns,
+        termwidth().saturating_sub(gutter_width),
+    );
+
+    let line = match theme.highlighter {
+        Some(highlighter) => colorize(&line, highlighter(&line, &theme.syntax)),
+        None => line,
+    };
+
+    let styled_gutter = match theme.gutter {
+        Some(color) => gutter.color(color),
+        None => gutter.dimmed(),
+    };
+    writeln!(f, "{}{}", styled_gutter, line)?;
+
+    let offset = " ".repeat(gutter_width + columns.start);
+    // The width of the columns, or 1, whichever is larger
+    let underline_count = std::cmp::max(1, columns.end - columns.start);
+    // Underline string repeated `underline_count` times
+    let underline = marker.underline.to_string().repeat(underline_count);
+    writeln!(f, "{}{}", offset, underline.color(marker.color))?;
+
+    Ok(())
+}
+
+/// Colors the given ranges of a line, which are in order and don't overlap
+/// (ignoring those that don't fit in the line).
+fn colorize(line: &str, ranges: Vec<(Range<usize>, Color)>) -> String {
+    let mut colorized = String::with_capacity(line.len());
+    let mut end = 0;
+    for (range, color) in ranges {
+        match line.get(range.clone()) {
+            Some(text) if range.start >= end => {
+                colorized.push_str(&line[end..range.start]);
+                colorized.push_str(&text.color(color).to_string());
+                end = range.end;
             }
+            _ => {}
         }
-    };
+    }
 
-    macro_rules! wrap {
-        ($formatting:literal, $( $args:expr ),* $(,)?) => {
-            textwrap::fill(
-                &format!($formatting, $( $args ),*),
-                textwrap::Options::with_termwidth(),
+    colorized.push_str(&line[end..]);
+    colorized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use files::SimpleFiles;
+
+    #[test]
+    fn test_emit_suggestions() {
+        colored::control::set_override(false);
+
+        let mut files = SimpleFiles::new();
+        let file_id = files.add("Foo.he", "var a = 1\n");
+        let diagnostic = Diagnostic::warning("Binding is never mutated")
+            .location(Location::new(file_id, 4..5))
+            .suggestion(
+                Location::new(file_id, 0..3),
+                "let",
+                Applicability::MachineApplicable,
             )
-        };
-        ($item:expr) => {
-            textwrap::fill(
-                &format!("{}", $item),
-                textwrap::Options::with_termwidth(),
+            .suggestion(
+                Location::new(file_id, 9..9),
+                ";",
+                Applicability::MaybeIncorrect,
             )
+            .suggestion(
+                Location::new(file_id, 5..7),
+                "",
+                Applicability::MaybeIncorrect,
+            );
+
+        let mut output = Vec::new();
+        emit(&mut output, &files, &diagnostic).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.contains("   1 | var a = 1\n       +++\n"));
+        assert!(output.contains("Help: replace with `let`\n"));
+        assert!(output.contains("Help: insert `;` here\n"));
+        assert!(output.contains("Help: remove this\n"));
+    }
+
+    #[test]
+    fn test_emit_with_theme() {
+        colored::control::set_override(false);
+
+        let mut files = SimpleFiles::new();
+        let file_id = files.add("Foo.he", "let a = b\n");
+        let diagnostic = Diagnostic::error("Unknown name")
+            .location(Location::new(file_id, 8..9))
+            .label(Location::new(file_id, 4..5), "Defined here");
+
+        let theme = Theme {
+            error: Marker::new(Color::Red, '='),
+            label: Marker::new(Color::Blue, '.'),
+            gutter_separator: ':',
+            header_fill: '=',
+            ..Theme::default()
         };
-    };
 
-    let location_str =
-        format!("-> src/Foo.he:{}:{}", line_number, column_start);
-    writeln!(f, "{}", header.color(color))?;
-    writeln!(f, "{}\n", location_str.color(color))?;
+        let mut output = Vec::new();
+        emit_with_theme(&mut output, &files, &diagnostic, &theme).unwrap();
+        let output = String::from_utf8(output).unwrap();
 
-    if let Some(description) = &diagnostic.description {
-        writeln!(f, "{}\n", wrap!(description))?;
+        assert!(output.starts_with("== Error: Unknown name ==="));
+        assert!(output.contains("   1 : let a = b\n               =\n"));
+        assert!(output.contains("   1 : let a = b\n           .\n"));
     }
 
-    let gutter = format!("{:>4} | ", line_number);
-    let line = &source.as_ref()[line_range].trim_end(); // remove trailing LF
-    writeln!(f, "{}{}", gutter.dimmed(), line)?;
+    #[test]
+    fn test_emit_with_highlighter() {
+        colored::control::set_override(false);
 
-    // `column_start` is indexed by 1
-    let offset = " ".repeat(gutter.len() + column_start - 1);
-    // The difference of the column positions, or 1, whichever is larger
-    let underline_count = std::cmp::max(1, column_end - column_start);
-    // Underline string repeated `underline_count` times
-    let underline = underline.repeat(underline_count).color(color);
-    writeln!(f, "{}{}", offset, underline)?;
+        let mut files = SimpleFiles::new();
+        let file_id = files.add("Foo.he", "\tlet a = b\n");
+        let diagnostic = Diagnostic::error("Unknown name")
+            .location(Location::new(file_id, 9..10));
 
-    writeln!(f, "{}\n", wrap!(diagnostic.message).trim_end())?;
+        // The highlighter sees the line as it's shown (with its tabs
+        // expanded), and ranges outside of it are ignored
+        fn highlighter(
+            line: &str,
+            colors: &SyntaxColors,
+        ) -> Vec<(Range<usize>, Color)> {
+            assert_eq!(line, "    let a = b");
+            let keyword = colors.keyword.unwrap();
+            vec![(4..7, keyword), (5..6, keyword), (12..20, keyword)]
+        }
 
-    if let Some(hint) = &diagnostic.hint {
-        writeln!(f, "{}\n", wrap!("{}: {}", "Hint".underline(), hint))?;
+        let theme = Theme {
+            highlighter: Some(highlighter),
+            ..Theme::default()
+        };
+
+        let mut output = Vec::new();
+        emit_with_theme(&mut output, &files, &diagnostic, &theme).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("   1 |     let a = b\n                   ^\n"));
     }
 
-    Ok(())
+    #[test]
+    fn test_emit_tabs_and_crlf() {
+        colored::control::set_override(false);
+
+        let mut files = SimpleFiles::new();
+        let file_id = files.add("Foo.he", "let a = 1\r\n\tlet b =\tc\r\n");
+        let diagnostic = Diagnostic::error("Unknown name")
+            .location(Location::new(file_id, 20..21));
+
+        let theme = Theme {
+            tab_width: 4,
+            ..Theme::default()
+        };
+
+        let mut output = Vec::new();
+        emit_with_theme(&mut output, &files, &diagnostic, &theme).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        // The column counts the tabs as single characters, but the snippet
+        // expands them
+        assert!(output.contains("-> Foo.he:2:10\n"));
+        assert!(output.contains("   2 |     let b = c\n                   ^\n"));
+    }
 }
@@ -0,0 +1,125 @@
+use crate::{Diagnostic, Severity};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// How the diagnostics of a lint are reported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LintLevel {
+    /// The diagnostics aren't reported at all.
+    Allow,
+    /// The diagnostics are reported as warnings.
+    Warn,
+    /// The diagnostics are reported as errors (so that they fail a build).
+    Deny,
+}
+
+impl FromStr for LintLevel {
+    type Err = String;
+
+    fn from_str(level: &str) -> Result<Self, Self::Err> {
+        match level {
+            "allow" => Ok(Self::Allow),
+            "warn" => Ok(Self::Warn),
+            "deny" => Ok(Self::Deny),
+            _ => Err(format!(
+                "Unknown lint level `{}` (expected `allow`, `warn` or `deny`)",
+                level
+            )),
+        }
+    }
+}
+
+impl Display for LintLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let level = match self {
+            Self::Allow => "allow",
+            Self::Warn => "warn",
+            Self::Deny => "deny",
+        };
+
+        write!(f, "{}", level)
+    }
+}
+
+/// The levels chosen by the user for some lints, by their names (the
+/// [`code`] of their diagnostics). The other lints are reported as they are.
+///
+/// [`code`]: Diagnostic::code
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LintLevels {
+    levels: BTreeMap<String, LintLevel>,
+}
+
+impl LintLevels {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the level of the lint with the given name, replacing any previous
+    /// level.
+    pub fn set(&mut self, lint: impl Into<String>, level: LintLevel) {
+        self.levels.insert(lint.into(), level);
+    }
+
+    /// The level of the lint with the given name, if one was set.
+    pub fn level(&self, lint: &str) -> Option<LintLevel> {
+        self.levels.get(lint).copied()
+    }
+
+    /// Reports the given diagnostic at the level of its lint, returning
+    /// `None` if the lint is allowed.
+    pub fn apply<FileId>(
+        &self,
+        mut diagnostic: Diagnostic<FileId>,
+    ) -> Option<Diagnostic<FileId>> {
+        match diagnostic.code.and_then(|code| self.level(code)) {
+            Some(LintLevel::Allow) => return None,
+            Some(LintLevel::Warn) => diagnostic.severity = Severity::Warning,
+            Some(LintLevel::Deny) => diagnostic.severity = Severity::Error,
+            None => {}
+        }
+
+        Some(diagnostic)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_lint_levels() {
+        let mut levels = LintLevels::new();
+        levels.set("a", LintLevel::Allow);
+        levels.set("b", LintLevel::Warn);
+        levels.set("c", LintLevel::Allow);
+        levels.set("c", LintLevel::Deny);
+
+        let apply = |diagnostic: Diagnostic<u8>| {
+            levels.apply(diagnostic).map(|it| it.severity)
+        };
+
+        assert_eq!(apply(Diagnostic::warning("A").code("a")), None);
+        assert_eq!(
+            apply(Diagnostic::error("B").code("b")),
+            Some(Severity::Warning)
+        );
+        assert_eq!(
+            apply(Diagnostic::warning("C").code("c")),
+            Some(Severity::Error)
+        );
+        assert_eq!(
+            apply(Diagnostic::warning("D").code("d")),
+            Some(Severity::Warning)
+        );
+        assert_eq!(apply(Diagnostic::error("E")), Some(Severity::Error));
+    }
+
+    #[test]
+    fn test_parse_lint_level() {
+        assert_eq!("deny".parse(), Ok(LintLevel::Deny));
+        assert_eq!(LintLevel::Allow.to_string(), "allow");
+        assert!("error".parse::<LintLevel>().is_err());
+    }
+}
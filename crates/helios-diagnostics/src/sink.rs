@@ -0,0 +1,209 @@
+use crate::{Diagnostic, LintLevels, Location, Severity};
+
+/// Collects the diagnostics of every phase of the compiler into the list that
+/// is finally reported.
+///
+/// A syntax error usually causes more errors in the later phases (which see a
+/// tree the parser had to recover), so the errors of the later phases that are
+/// in the same region as a syntax error are left out: only the syntax error is
+/// worth reporting there. The diagnostics of the syntactic phases (the lexer
+/// and the parser) are collected with [`push_syntax`], and the others with
+/// [`push`].
+///
+/// The diagnostics of the lints are reported at the levels chosen by the user
+/// (see [`with_lint_levels`]).
+///
+/// [`with_lint_levels`]: DiagnosticSink::with_lint_levels
+/// [`push_syntax`]: DiagnosticSink::push_syntax
+/// [`push`]: DiagnosticSink::push
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DiagnosticSink<FileId> {
+    diagnostics: Vec<Diagnostic<FileId>>,
+    /// The locations of the syntax errors collected so far.
+    syntax_errors: Vec<Location<FileId>>,
+    lint_levels: LintLevels,
+}
+
+impl<FileId> DiagnosticSink<FileId>
+where
+    FileId: Clone + Ord,
+{
+    pub fn new() -> Self {
+        Self::with_lint_levels(LintLevels::default())
+    }
+
+    pub fn with_lint_levels(lint_levels: LintLevels) -> Self {
+        Self {
+            diagnostics: Vec::new(),
+            syntax_errors: Vec::new(),
+            lint_levels,
+        }
+    }
+
+    /// Collects a diagnostic of the lexer or the parser.
+    pub fn push_syntax(&mut self, diagnostic: Diagnostic<FileId>) {
+        let diagnostic = match self.lint_levels.apply(diagnostic) {
+            Some(diagnostic) => diagnostic,
+            None => return,
+        };
+
+        if diagnostic.severity >= Severity::Error {
+            self.syntax_errors.push(diagnostic.location.clone());
+        }
+
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// Collects a diagnostic of a phase after parsing, unless it is an error
+    /// in the same region as a syntax error (which touches or overlaps its
+    /// location).
+    pub fn push(&mut self, diagnostic: Diagnostic<FileId>) {
+        let diagnostic = match self.lint_levels.apply(diagnostic) {
+            Some(diagnostic) => diagnostic,
+            None => return,
+        };
+
+        let location = &diagnostic.location;
+        let is_cascaded = diagnostic.severity >= Severity::Error
+            && self.syntax_errors.iter().any(|error| {
+                error.file_id == location.file_id
+                    && error.range.start <= location.range.end
+                    && location.range.start <= error.range.end
+            });
+
+        if !is_cascaded {
+            self.diagnostics.push(diagnostic);
+        }
+    }
+
+    /// Determines if any of the diagnostics collected is an error (or a bug).
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity >= Severity::Error)
+    }
+
+    /// The diagnostics collected, sorted by file and location (those at the
+    /// same location stay in the order they were collected), without
+    /// duplicates.
+    pub fn finish(mut self) -> Vec<Diagnostic<FileId>> {
+        self.diagnostics.sort_by_key(|diagnostic| {
+            let location = &diagnostic.location;
+            (
+                location.file_id.clone(),
+                location.range.start,
+                location.range.end,
+            )
+        });
+
+        // Identical diagnostics have the same location, so they are now
+        // within the same run of diagnostics with that location
+        let mut diagnostics: Vec<Diagnostic<FileId>> = Vec::new();
+        for diagnostic in self.diagnostics {
+            let is_duplicate = diagnostics
+                .iter()
+                .rev()
+                .take_while(|other| other.location == diagnostic.location)
+                .any(|other| *other == diagnostic);
+
+            if !is_duplicate {
+                diagnostics.push(diagnostic);
+            }
+        }
+
+        diagnostics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LintLevel;
+
+    fn error(file_id: u8, range: std::ops::Range<usize>) -> Diagnostic<u8> {
+        Diagnostic::error("Error").location(Location::new(file_id, range))
+    }
+
+    #[test]
+    fn test_sort_and_deduplicate() {
+        let mut sink = DiagnosticSink::new();
+        sink.push(error(1, 0..1));
+        sink.push(error(0, 5..6));
+        sink.push(error(0, 2..4).title("First"));
+        sink.push(error(0, 2..3));
+        sink.push(error(0, 2..4).title("Second"));
+        sink.push(error(0, 2..4).title("First"));
+
+        let diagnostics = sink.finish();
+        let titles = diagnostics
+            .iter()
+            .map(|it| (it.location.file_id, it.title.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            titles,
+            vec![
+                (0, "Error"),
+                (0, "First"),
+                (0, "Second"),
+                (0, "Error"),
+                (1, "Error"),
+            ]
+        );
+        assert_eq!(diagnostics[0].location.range, 2..3);
+    }
+
+    #[test]
+    fn test_cascaded_errors() {
+        let mut sink = DiagnosticSink::new();
+        sink.push_syntax(error(0, 4..5));
+        sink.push_syntax(error(0, 4..5).severity(Severity::Warning));
+
+        sink.push(error(0, 0..4));
+        sink.push(error(0, 5..5));
+        sink.push(error(0, 6..7));
+        sink.push(error(1, 4..5));
+        sink.push(error(0, 4..5).severity(Severity::Warning));
+        assert!(sink.has_errors());
+
+        let locations = sink
+            .finish()
+            .into_iter()
+            .map(|it| (it.location.file_id, it.location.range, it.severity))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            locations,
+            vec![
+                (0, 4..5, Severity::Error),
+                (0, 4..5, Severity::Warning),
+                (0, 6..7, Severity::Error),
+                (1, 4..5, Severity::Error),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_levels() {
+        let mut lint_levels = LintLevels::new();
+        lint_levels.set("a", LintLevel::Allow);
+        lint_levels.set("b", LintLevel::Deny);
+
+        let mut sink = DiagnosticSink::with_lint_levels(lint_levels);
+        let warning = |code| {
+            Diagnostic::warning("Warning")
+                .code(code)
+                .location(Location::new(0u8, 0..1))
+        };
+
+        sink.push(warning("a"));
+        assert!(!sink.has_errors());
+        sink.push(warning("b"));
+        assert!(sink.has_errors());
+
+        let codes = sink
+            .finish()
+            .into_iter()
+            .map(|it| (it.code, it.severity))
+            .collect::<Vec<_>>();
+        assert_eq!(codes, vec![(Some("b"), Severity::Error)]);
+    }
+}
@@ -0,0 +1,194 @@
+use crate::Severity;
+use colored::Color;
+use std::fmt::Display;
+use std::io::IsTerminal;
+use std::ops::Range;
+use std::str::FromStr;
+
+/// How a kind of location is marked in a snippet of source code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Marker {
+    pub color: Color,
+    /// The character repeated under the code at the location.
+    pub underline: char,
+}
+
+impl Marker {
+    pub const fn new(color: Color, underline: char) -> Self {
+        Self { color, underline }
+    }
+}
+
+/// The colors of the kinds of tokens in highlighted source code, where `None`
+/// leaves a kind of token uncolored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SyntaxColors {
+    pub keyword: Option<Color>,
+    pub operator: Option<Color>,
+    pub punctuation: Option<Color>,
+    pub number: Option<Color>,
+    pub string: Option<Color>,
+    pub comment: Option<Color>,
+    pub identifier: Option<Color>,
+    /// The color of characters that aren't part of the language.
+    pub error: Option<Color>,
+}
+
+impl Default for SyntaxColors {
+    fn default() -> Self {
+        Self {
+            keyword: Some(Color::Magenta),
+            operator: None,
+            punctuation: None,
+            number: Some(Color::Cyan),
+            string: Some(Color::Green),
+            comment: Some(Color::BrightBlack),
+            identifier: None,
+            error: Some(Color::Red),
+        }
+    }
+}
+
+/// A function finding the ranges of a line of source code to color with the
+/// given colors, in order (see [`Theme::highlighter`]).
+///
+/// Diagnostics can't tokenize source code themselves, since the lexer reports
+/// its errors as diagnostics.
+pub type Highlighter = fn(&str, &SyntaxColors) -> Vec<(Range<usize>, Color)>;
+
+/// The colors and characters that diagnostics are printed with (see
+/// [`emit_with_theme`]).
+///
+/// The characters of the default theme are all ASCII, so that diagnostics
+/// read the same in any console. The colors are left out when colorizing is
+/// turned off (see [`ColorChoice`]).
+///
+/// [`emit_with_theme`]: crate::emit_with_theme
+#[derive(Clone, Debug)]
+pub struct Theme {
+    pub bug: Marker,
+    pub error: Marker,
+    pub warning: Marker,
+    pub note: Marker,
+    /// The marker of the secondary locations of diagnostics.
+    pub label: Marker,
+    /// The marker of the code that a suggestion replaces.
+    pub suggestion: Marker,
+    /// The color of the code quoted in messages.
+    pub code: Color,
+    /// The color of the line numbers before snippets, or `None` for dimmed
+    /// line numbers.
+    pub gutter: Option<Color>,
+    /// The character separating the line numbers from the source code.
+    pub gutter_separator: char,
+    /// The character filling the headers up to the width of the terminal.
+    pub header_fill: char,
+    /// The number of columns between tab stops, which the tabs of snippets
+    /// are expanded to (so that the underlines line up with the code).
+    pub tab_width: usize,
+    pub syntax: SyntaxColors,
+    /// The function the code of snippets is highlighted with, if any.
+    pub highlighter: Option<Highlighter>,
+}
+
+impl Theme {
+    /// The marker of the primary location of a diagnostic with the given
+    /// severity.
+    pub fn severity(&self, severity: Severity) -> Marker {
+        match severity {
+            Severity::Bug => self.bug,
+            Severity::Error => self.error,
+            Severity::Warning => self.warning,
+            Severity::Note => self.note,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            bug: Marker::new(Color::Magenta, '^'),
+            error: Marker::new(Color::Red, '^'),
+            warning: Marker::new(Color::Yellow, '~'),
+            note: Marker::new(Color::Blue, '-'),
+            label: Marker::new(Color::Blue, '-'),
+            suggestion: Marker::new(Color::Green, '+'),
+            code: helios_formatting::DEFAULT_CODE_COLOR,
+            gutter: None,
+            gutter_separator: '|',
+            header_fill: '-',
+            tab_width: 4,
+            syntax: SyntaxColors::default(),
+            highlighter: None,
+        }
+    }
+}
+
+/// When diagnostics (and other output) are colorized.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Colorizes the output if the standard output is a terminal (unless the
+    /// `NO_COLOR` or `CLICOLOR` environment variables turn colors off).
+    #[default]
+    Auto,
+    Always,
+    /// Prints plain text, without any ANSI escape codes (for logs, or for
+    /// consoles that don't support them).
+    Never,
+}
+
+impl ColorChoice {
+    /// Turns colorizing on or off for the rest of the process.
+    pub fn apply(self) {
+        match self {
+            Self::Auto => {
+                if !std::io::stdout().is_terminal() {
+                    colored::control::set_override(false);
+                }
+            }
+            Self::Always => colored::control::set_override(true),
+            Self::Never => colored::control::set_override(false),
+        }
+    }
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(choice: &str) -> Result<Self, Self::Err> {
+        match choice {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            _ => Err(format!(
+                "Unknown color choice `{}` (expected `auto`, `always` or \
+                 `never`)",
+                choice
+            )),
+        }
+    }
+}
+
+impl Display for ColorChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let choice = match self {
+            Self::Auto => "auto",
+            Self::Always => "always",
+            Self::Never => "never",
+        };
+
+        write!(f, "{}", choice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_color_choice() {
+        assert_eq!("never".parse(), Ok(ColorChoice::Never));
+        assert_eq!(ColorChoice::Always.to_string(), "always");
+        assert!("sometimes".parse::<ColorChoice>().is_err());
+    }
+}
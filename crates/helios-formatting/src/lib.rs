@@ -1,3 +1,5 @@
+pub mod pretty;
+
 use colored::*;
 use std::fmt::{self, Display};
 
@@ -28,30 +30,34 @@ impl FormattedStringSegment {
     }
 }
 
-impl Display for FormattedStringSegment {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let colorize = colored::control::SHOULD_COLORIZE.should_colorize();
+/// The color of the code in a [`FormattedString`], unless it is displayed
+/// with [`FormattedString::styled`].
+pub const DEFAULT_CODE_COLOR: Color = Color::Yellow;
 
+impl FormattedStringSegment {
+    fn fmt_styled(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        code_color: Option<Color>,
+    ) -> fmt::Result {
         match self {
             Self::LineBreak => write!(f, "\n\n"),
             Self::Text(text) => write!(f, "{}", text),
-            Self::Code(code) => {
-                if colorize {
-                    write!(f, "{}", code.yellow())
-                } else {
-                    write!(f, "`{}`", code)
-                }
-            }
-            Self::CodeBlock(block) => {
-                if colorize {
-                    write!(f, "    {}", block.yellow())
-                } else {
-                    write!(f, "    {}", block)
-                }
-            }
+            Self::Code(code) => match code_color {
+                Some(color) => write!(f, "{}", code.color(color)),
+                None => write!(f, "`{}`", code),
+            },
+            Self::CodeBlock(block) => match code_color {
+                Some(color) => write!(f, "    {}", block.color(color)),
+                None => write!(f, "    {}", block),
+            },
             Self::List(lines) => {
                 for line in lines {
-                    write!(f, "    {}\n", line)?;
+                    let line = Styled {
+                        string: line,
+                        code_color,
+                    };
+                    writeln!(f, "    {}", line)?;
                 }
 
                 Ok(())
@@ -60,6 +66,13 @@ impl Display for FormattedStringSegment {
     }
 }
 
+impl Display for FormattedStringSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let colorize = colored::control::SHOULD_COLORIZE.should_colorize();
+        self.fmt_styled(f, Some(DEFAULT_CODE_COLOR).filter(|_| colorize))
+    }
+}
+
 impl From<String> for FormattedStringSegment {
     fn from(string: String) -> Self {
         FormattedStringSegment::Text(string)
@@ -118,12 +131,46 @@ impl FormattedString {
     pub fn finish(self) -> String {
         self.to_string().trim_end().to_string()
     }
+
+    /// Displays the string with its code in the given color (if colorizing
+    /// is turned on).
+    pub fn styled(&self, code_color: Color) -> Styled<'_> {
+        let colorize = colored::control::SHOULD_COLORIZE.should_colorize();
+        Styled {
+            string: self,
+            code_color: Some(code_color).filter(|_| colorize),
+        }
+    }
+
+    /// Displays the string without colors, even if colorizing is turned on
+    /// (for output read by other tools).
+    pub fn plain(&self) -> Styled<'_> {
+        Styled {
+            string: self,
+            code_color: None,
+        }
+    }
 }
 
 impl Display for FormattedString {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for segment in &self.segments {
-            write!(f, "{}", segment)?;
+        write!(f, "{}", self.styled(DEFAULT_CODE_COLOR))
+    }
+}
+
+/// A [`FormattedString`] displayed with its code in a given color (see
+/// [`FormattedString::styled`]).
+#[derive(Clone, Copy, Debug)]
+pub struct Styled<'a> {
+    string: &'a FormattedString,
+    /// The color of the code, or `None` for code quoted in backticks.
+    code_color: Option<Color>,
+}
+
+impl Display for Styled<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for segment in &self.string.segments {
+            segment.fmt_styled(f, self.code_color)?;
         }
 
         Ok(())
@@ -168,4 +215,23 @@ mod tests {
         println!("{}", text);
         println!();
     }
+
+    #[test]
+    fn test_styled() {
+        let text = FormattedString::new()
+            .text("Use ")
+            .code("let")
+            .list(vec![FormattedString::new().code("a")]);
+
+        // Colorizing is turned on for the whole process, so everything that
+        // depends on it is displayed before it is turned back off
+        colored::control::set_override(true);
+        let styled = text.styled(Color::Cyan).to_string();
+        let plain = text.plain().to_string();
+        let cyan = format!("Use {}\n\n    {}\n\n\n", "let".cyan(), "a".cyan());
+        colored::control::unset_override();
+
+        assert_eq!(styled, cyan);
+        assert_eq!(plain, "Use `let`\n\n    `a`\n\n\n");
+    }
 }
@@ -0,0 +1,361 @@
+//! A pretty printer for formatting code to a maximum line width.
+//!
+//! The code to format is described by a [`Doc`]: text along with the places
+//! where lines may be broken, grouped by how they are broken. Every line in a
+//! [`Doc::group`] is broken if the group doesn't fit on the rest of the line
+//! (and none of them are otherwise), so the outer groups of nested code are
+//! broken before the inner ones. This is the algorithm described in Wadler's
+//! _A prettier printer_, which decides how to print a group by only looking
+//! at the rest of the current line.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use helios_formatting::pretty::{Doc, PrettyOptions};
+//!
+//! let args = ["first", "second", "third"].iter().map(|&it| Doc::text(it));
+//! let call = Doc::text("f(")
+//!     .append(
+//!         Doc::softline()
+//!             .append(Doc::join(args, Doc::text(",").append(Doc::line())))
+//!             .append(Doc::trailing_comma())
+//!             .indent(),
+//!     )
+//!     .append(Doc::softline())
+//!     .append(Doc::text(")"))
+//!     .group();
+//!
+//! let options = PrettyOptions { max_width: 16, ..PrettyOptions::default() };
+//! assert_eq!(call.render(&PrettyOptions::default()), "f(first, second, third)");
+//! assert_eq!(call.render(&options), "f(\n    first,\n    second,\n    third,\n)");
+//! ```
+
+/// The options of the pretty printer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PrettyOptions {
+    /// The width that lines are broken to fit in (where possible).
+    pub max_width: usize,
+    /// The number of spaces used for each level of indentation.
+    pub indent_width: usize,
+    /// Whether a [`Doc::trailing_comma`] is printed when the lines of its
+    /// group are broken.
+    pub trailing_commas: bool,
+}
+
+impl Default for PrettyOptions {
+    fn default() -> Self {
+        Self {
+            max_width: 80,
+            indent_width: 4,
+            trailing_commas: true,
+        }
+    }
+}
+
+/// A document to pretty print (see the [module documentation](self)).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Doc {
+    Nil,
+    /// Text without line breaks.
+    Text(String),
+    /// A line break, which is printed as a space (or nothing, if it is soft)
+    /// when its group isn't broken.
+    Line {
+        soft: bool,
+    },
+    /// A line break that is always printed, which breaks every group around
+    /// it.
+    HardLine,
+    Concat(Vec<Doc>),
+    /// Indents the lines broken in the given document by one more level.
+    Indent(Box<Doc>),
+    /// Breaks either every line of the given document, or none of them.
+    Group(Box<Doc>),
+    /// Prints the first document when its group is broken, or the second one
+    /// otherwise.
+    IfBreak(Box<Doc>, Box<Doc>),
+    /// A comma printed when its group is broken (if trailing commas are
+    /// enabled).
+    TrailingComma,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+impl Doc {
+    pub fn text(text: impl Into<String>) -> Self {
+        let text = text.into();
+        debug_assert!(!text.contains('\n'), "Text must not contain line feeds");
+        Self::Text(text)
+    }
+
+    /// A line break printed as a space when its group isn't broken.
+    pub fn line() -> Self {
+        Self::Line { soft: false }
+    }
+
+    /// A line break printed as nothing when its group isn't broken.
+    pub fn softline() -> Self {
+        Self::Line { soft: true }
+    }
+
+    pub fn hardline() -> Self {
+        Self::HardLine
+    }
+
+    pub fn trailing_comma() -> Self {
+        Self::TrailingComma
+    }
+
+    pub fn if_break(broken: Doc, flat: Doc) -> Self {
+        Self::IfBreak(Box::new(broken), Box::new(flat))
+    }
+
+    pub fn concat(docs: impl IntoIterator<Item = Doc>) -> Self {
+        Self::Concat(docs.into_iter().collect())
+    }
+
+    /// The given documents with the given separator between each of them.
+    pub fn join(docs: impl IntoIterator<Item = Doc>, separator: Doc) -> Self {
+        let mut joined = Vec::new();
+        for doc in docs {
+            if !joined.is_empty() {
+                joined.push(separator.clone());
+            }
+            joined.push(doc);
+        }
+
+        Self::Concat(joined)
+    }
+
+    pub fn append(self, other: Doc) -> Self {
+        match self {
+            Self::Nil => other,
+            Self::Concat(mut docs) => {
+                docs.push(other);
+                Self::Concat(docs)
+            }
+            doc => Self::Concat(vec![doc, other]),
+        }
+    }
+
+    pub fn indent(self) -> Self {
+        Self::Indent(Box::new(self))
+    }
+
+    pub fn group(self) -> Self {
+        Self::Group(Box::new(self))
+    }
+
+    /// Prints the document with the given options.
+    pub fn render(&self, options: &PrettyOptions) -> String {
+        let mut output = String::new();
+        let mut column = 0;
+        let mut stack = vec![(0, Mode::Break, self)];
+
+        while let Some((indent, mode, doc)) = stack.pop() {
+            match doc {
+                Self::Nil => {}
+                Self::Text(text) => {
+                    output.push_str(text);
+                    column += text.chars().count();
+                }
+                Self::Line { soft } if mode == Mode::Flat => {
+                    if !soft {
+                        output.push(' ');
+                        column += 1;
+                    }
+                }
+                Self::Line { .. } | Self::HardLine => {
+                    // The indentation of empty lines is left out
+                    let trimmed = output.trim_end_matches(' ').len();
+                    output.truncate(trimmed);
+                    output.push('\n');
+                    output.push_str(&" ".repeat(indent));
+                    column = indent;
+                }
+                Self::Concat(docs) => {
+                    stack.extend(docs.iter().rev().map(|it| (indent, mode, it)))
+                }
+                Self::Indent(doc) => {
+                    stack.push((indent + options.indent_width, mode, doc))
+                }
+                Self::Group(doc) => {
+                    let remaining =
+                        options.max_width as isize - column as isize;
+                    let mode = if mode == Mode::Flat
+                        || fits(remaining, (indent, Mode::Flat, doc), &stack)
+                    {
+                        Mode::Flat
+                    } else {
+                        Mode::Break
+                    };
+                    stack.push((indent, mode, doc));
+                }
+                Self::IfBreak(broken, flat) => {
+                    let doc = if mode == Mode::Break { broken } else { flat };
+                    stack.push((indent, mode, doc));
+                }
+                Self::TrailingComma => {
+                    if mode == Mode::Break && options.trailing_commas {
+                        output.push(',');
+                        column += 1;
+                    }
+                }
+            }
+        }
+
+        output
+    }
+}
+
+/// Determines if the given document fits in the given width when it isn't
+/// broken, along with the documents after it up to the next line break.
+fn fits(
+    mut remaining: isize,
+    next: (usize, Mode, &Doc),
+    rest: &[(usize, Mode, &Doc)],
+) -> bool {
+    let mut rest = rest.iter().rev();
+    let mut stack = vec![next];
+
+    while remaining >= 0 {
+        let (indent, mode, doc) = match stack.pop() {
+            Some(item) => item,
+            None => match rest.next() {
+                Some(&item) => item,
+                None => return true,
+            },
+        };
+
+        match doc {
+            Doc::Nil => {}
+            Doc::Text(text) => remaining -= text.chars().count() as isize,
+            Doc::Line { soft } if mode == Mode::Flat => {
+                if !soft {
+                    remaining -= 1;
+                }
+            }
+            // A group with a hard line break can't be printed on one line
+            Doc::HardLine if mode == Mode::Flat => return false,
+            Doc::Line { .. } | Doc::HardLine => return true,
+            Doc::Concat(docs) => {
+                stack.extend(docs.iter().rev().map(|it| (indent, mode, it)))
+            }
+            Doc::Indent(doc) | Doc::Group(doc) => {
+                stack.push((indent, mode, doc))
+            }
+            Doc::IfBreak(broken, flat) => {
+                let doc = if mode == Mode::Break { broken } else { flat };
+                stack.push((indent, mode, doc));
+            }
+            Doc::TrailingComma => {}
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_width(max_width: usize) -> PrettyOptions {
+        PrettyOptions {
+            max_width,
+            ..PrettyOptions::default()
+        }
+    }
+
+    /// `head |> f |> g |> ...`, broken before every `|>` at once.
+    fn pipeline(head: Doc, functions: &[&str]) -> Doc {
+        let functions = functions
+            .iter()
+            .map(|&it| Doc::line().append(Doc::text(format!("|> {}", it))));
+        head.append(Doc::concat(functions).indent()).group()
+    }
+
+    fn list(items: Vec<Doc>) -> Doc {
+        let separator = Doc::text(",").append(Doc::line());
+        Doc::text("[")
+            .append(
+                Doc::softline()
+                    .append(Doc::join(items, separator))
+                    .append(Doc::trailing_comma())
+                    .indent(),
+            )
+            .append(Doc::softline())
+            .append(Doc::text("]"))
+            .group()
+    }
+
+    #[test]
+    fn test_render_pipeline() {
+        let doc = pipeline(Doc::text("numbers"), &["filter odd", "map square"]);
+        assert_eq!(
+            doc.render(&with_width(40)),
+            "numbers |> filter odd |> map square"
+        );
+        assert_eq!(
+            doc.render(&with_width(20)),
+            "numbers\n    |> filter odd\n    |> map square"
+        );
+    }
+
+    #[test]
+    fn test_render_nested_groups() {
+        let inner = || list(vec![Doc::text("1"), Doc::text("2")]);
+        let doc = list(vec![inner(), inner(), inner()]);
+        assert_eq!(doc.render(&with_width(30)), "[[1, 2], [1, 2], [1, 2]]");
+
+        // The outer group is broken first, so the inner ones still fit
+        assert_eq!(
+            doc.render(&with_width(12)),
+            "[\n    [1, 2],\n    [1, 2],\n    [1, 2],\n]"
+        );
+
+        let options = PrettyOptions {
+            trailing_commas: false,
+            indent_width: 2,
+            ..with_width(6)
+        };
+        assert_eq!(
+            doc.render(&options),
+            "[\n  [\n    1,\n    2\n  ],\n  [\n    1,\n    2\n  ],\n  [\n    1,\n    2\n  ]\n]"
+        );
+    }
+
+    #[test]
+    fn test_render_hard_lines() {
+        let body = Doc::text("let a = 1")
+            .append(Doc::hardline())
+            .append(Doc::hardline())
+            .append(Doc::text("a"));
+        let doc = Doc::text("begin")
+            .append(Doc::line().append(body).indent())
+            .append(Doc::line())
+            .append(Doc::text("end"))
+            .group();
+
+        // The hard lines break the group even though it is short, and the
+        // empty line isn't indented
+        assert_eq!(
+            doc.render(&PrettyOptions::default()),
+            "begin\n    let a = 1\n\n    a\nend"
+        );
+    }
+
+    #[test]
+    fn test_render_if_break() {
+        let doc = Doc::text("x")
+            .append(Doc::if_break(Doc::text(" -- broken"), Doc::Nil))
+            .append(Doc::line())
+            .append(Doc::text("y"))
+            .group();
+        assert_eq!(doc.render(&with_width(10)), "x y");
+        assert_eq!(doc.render(&with_width(2)), "x -- broken\ny");
+    }
+}
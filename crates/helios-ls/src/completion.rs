@@ -0,0 +1,380 @@
+//! Telling what is being typed at the cursor from the syntax tree, so that
+//! completion only offers what fits there.
+//!
+//! Completions are ranked by their [`sort_text`] (which editors sort by
+//! instead of their labels): the names closest to the cursor come first,
+//! followed by keywords, literals and [`snippets`].
+//!
+//! [`sort_text`]: lsp_types::CompletionItem::sort_text
+
+use helios_parser::editor::missing_expression_at;
+use helios_parser::normalize_identifier;
+use helios_query::{Item, ItemKind};
+use helios_syntax::{SyntaxKind, SyntaxNode, SyntaxToken};
+use lsp_types::{CompletionItem, CompletionItemKind, InsertTextFormat};
+
+/// What is being typed at the cursor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum CompletionContext {
+    /// The name of a new binding, after `let` or `var`.
+    Name,
+    /// An expression starting at the given offset, either right where one is
+    /// expected or as a name being typed there.
+    Expression(usize),
+    /// A member of the given item (in the form identifiers are compared in),
+    /// after a `.` following its name.
+    Member(String),
+    /// A declaration, at the start of a line (or as a name being typed
+    /// there).
+    Declaration,
+    /// Anything else (such as the name of a type).
+    Unknown,
+}
+
+/// The groups completions are ranked in, in order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Rank {
+    Local,
+    Item,
+    Keyword,
+    Literal,
+    Snippet,
+}
+
+/// The text that ranks a completion in the given group, where it's in the
+/// given position.
+pub(crate) fn sort_text(rank: Rank, position: usize) -> String {
+    format!("{}{:05}", rank as u8, position)
+}
+
+/// The context of the given offset of the tree.
+pub(crate) fn completion_context(
+    root: &SyntaxNode,
+    offset: usize,
+) -> CompletionContext {
+    let tokens = root
+        .descendants_with_tokens()
+        .filter_map(|it| it.into_token())
+        .filter(|token| !token.kind().is_trivia() && !token.text().is_empty())
+        .take_while(|token| usize::from(token.text_range().start()) < offset)
+        .collect::<Vec<_>>();
+
+    // The name being typed at the cursor, if any, which completion replaces
+    let (typed, before) = match tokens.split_last() {
+        Some((last, before))
+            if last.kind() == SyntaxKind::Identifier
+                && offset <= usize::from(last.text_range().end()) =>
+        {
+            (Some(last), before)
+        }
+        _ => (None, &tokens[..]),
+    };
+
+    match before.last().map(SyntaxToken::kind) {
+        Some(SyntaxKind::Kwd_Let) | Some(SyntaxKind::Kwd_Var) => {
+            return CompletionContext::Name
+        }
+        Some(SyntaxKind::Sym_Dot) => {
+            return match before.iter().rev().nth(1) {
+                Some(name) if name.kind() == SyntaxKind::Identifier => {
+                    let name = normalize_identifier(name.text());
+                    CompletionContext::Member(name.into_owned())
+                }
+                _ => CompletionContext::Unknown,
+            };
+        }
+        _ => {}
+    }
+
+    // A missing expression may be on a line of its own (after `let a =`)
+    if let Some(missing) = missing_expression_at(root, offset) {
+        return CompletionContext::Expression(missing.start);
+    }
+
+    let start = typed.map_or(offset, |token| token.text_range().start().into());
+    if is_line_start(&root.to_string(), start) {
+        return CompletionContext::Declaration;
+    }
+
+    let reference = typed
+        .filter(|token| token.parent().kind() == SyntaxKind::Exp_VariableRef);
+    if let Some(token) = reference {
+        CompletionContext::Expression(token.text_range().start().into())
+    } else {
+        CompletionContext::Unknown
+    }
+}
+
+/// Determines if the given offset of the text is at the start of a line.
+fn is_line_start(text: &str, offset: usize) -> bool {
+    offset == 0 || text[..offset].ends_with('\n')
+}
+
+/// The snippets of the constructs that start an expression, as their label,
+/// their description and their body.
+pub(crate) const EXPRESSION_SNIPPETS: &[(&str, &str, &str)] = &[
+    (
+        "if",
+        "if … then … else …",
+        "if ${1:condition} then\n\t${2:()}\nelse\n\t${0:()}",
+    ),
+    (
+        "match",
+        "match … with | … -> …",
+        "match ${1:value} with\n\t| ${2:pattern} -> ${3:()}\n\
+         \t| ${4:_} -> ${0:()}",
+    ),
+];
+
+/// The snippets of the declarations (see [`EXPRESSION_SNIPPETS`]).
+pub(crate) const DECLARATION_SNIPPETS: &[(&str, &str, &str)] = &[
+    ("let", "let … = …", "let ${1:name} = ${0:()}"),
+    (
+        "function",
+        "let … = \\… -> …",
+        // The backslash is escaped, or it would escape the `$`
+        "let ${1:name} = \\\\${2:x} -> ${0:()}",
+    ),
+];
+
+/// The completions of the given snippets, whose bodies are in the syntax of
+/// LSP snippets: `$1`, `$2`, ... are the tab stops the cursor goes through
+/// (showing their placeholder, such as `${1:name}`) and `$0` is where it
+/// ends up.
+///
+/// They are only offered to the clients that support snippets.
+pub(crate) fn snippets(snippets: &[(&str, &str, &str)]) -> Vec<CompletionItem> {
+    snippets
+        .iter()
+        .enumerate()
+        .map(|(position, (label, detail, body))| CompletionItem {
+            label: label.to_string(),
+            kind: Some(CompletionItemKind::Snippet),
+            detail: Some(detail.to_string()),
+            insert_text: Some(body.to_string()),
+            insert_text_format: Some(InsertTextFormat::Snippet),
+            sort_text: Some(sort_text(Rank::Snippet, position)),
+            ..CompletionItem::default()
+        })
+        .collect()
+}
+
+/// A placeholder for the name of a new binding, since there is nothing to
+/// complete it with (which is a snippet as well).
+pub(crate) fn name_placeholder() -> CompletionItem {
+    CompletionItem {
+        label: "name".to_string(),
+        kind: Some(CompletionItemKind::Snippet),
+        insert_text: Some("${1:name}".to_string()),
+        insert_text_format: Some(InsertTextFormat::Snippet),
+        ..CompletionItem::default()
+    }
+}
+
+/// The names of the parameters of the lambdas around the given offset of the
+/// tree, from the innermost lambda to the outermost one.
+pub(crate) fn local_names(root: &SyntaxNode, offset: usize) -> Vec<String> {
+    let mut lambdas = root
+        .descendants()
+        .filter(|node| node.kind() == SyntaxKind::Exp_Lambda)
+        .filter(|node| {
+            let range = node.text_range();
+            usize::from(range.start()) < offset
+                && offset <= usize::from(range.end())
+        })
+        .collect::<Vec<_>>();
+    lambdas.reverse();
+
+    let mut names = Vec::new();
+    for params in lambdas.iter().filter_map(|lambda| {
+        lambda
+            .children()
+            .find(|node| node.kind() == SyntaxKind::ParamList)
+    }) {
+        for token in params
+            .descendants_with_tokens()
+            .filter_map(|it| it.into_token())
+            .filter(|token| token.kind() == SyntaxKind::Identifier)
+        {
+            let name = normalize_identifier(token.text()).into_owned();
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+
+    names
+}
+
+/// The completions of the given local names, in order.
+pub(crate) fn locals(names: &[String]) -> Vec<CompletionItem> {
+    names
+        .iter()
+        .enumerate()
+        .map(|(position, name)| CompletionItem {
+            label: name.clone(),
+            kind: Some(CompletionItemKind::Variable),
+            detail: Some("parameter".to_string()),
+            sort_text: Some(sort_text(Rank::Local, position)),
+            ..CompletionItem::default()
+        })
+        .collect()
+}
+
+/// The values among the given items, closest first to the given offset of
+/// their file.
+///
+/// The binding whose declaration contains the offset comes last, since a
+/// binding only refers to itself in a function calling itself.
+pub(crate) fn values_by_proximity(items: &[Item], offset: usize) -> Vec<&Item> {
+    let mut values = items
+        .iter()
+        .filter(|item| {
+            matches!(item.kind, ItemKind::Binding | ItemKind::External)
+        })
+        .collect::<Vec<_>>();
+
+    values.sort_by_key(|item| {
+        if item.range.contains(&offset) || item.range.end == offset {
+            usize::MAX
+        } else if item.range.end < offset {
+            offset - item.range.end
+        } else {
+            item.range.start - offset
+        }
+    });
+    values
+}
+
+/// The keywords and literals that start an expression.
+pub(crate) fn keywords_and_literals() -> Vec<CompletionItem> {
+    let keywords = ["if", "match", "let"]
+        .iter()
+        .map(|keyword| (*keyword, CompletionItemKind::Keyword, Rank::Keyword));
+    let literals = ["()", "\"\""]
+        .iter()
+        .map(|literal| (*literal, CompletionItemKind::Value, Rank::Literal));
+
+    keywords
+        .chain(literals)
+        .enumerate()
+        .map(|(position, (label, kind, rank))| CompletionItem {
+            label: label.to_string(),
+            kind: Some(kind),
+            sort_text: Some(sort_text(rank, position)),
+            ..CompletionItem::default()
+        })
+        .collect()
+}
+
+/// The completions of the members of the item with the given name in the
+/// tree, which are the variants of a type and the methods of an interface.
+pub(crate) fn members(root: &SyntaxNode, name: &str) -> Vec<CompletionItem> {
+    let declaration = root.children().find(|node| {
+        matches!(
+            node.kind(),
+            SyntaxKind::Dec_TypeEnum | SyntaxKind::Dec_Interface
+        ) && first_identifier(node).as_deref() == Some(name)
+    });
+    let declaration = match declaration {
+        Some(declaration) => declaration,
+        None => return Vec::new(),
+    };
+
+    declaration
+        .descendants()
+        .filter_map(|node| {
+            let kind = match node.kind() {
+                SyntaxKind::EnumVariant => CompletionItemKind::EnumMember,
+                SyntaxKind::MethodSignature => CompletionItemKind::Method,
+                _ => return None,
+            };
+            Some((first_identifier(&node)?, kind))
+        })
+        .enumerate()
+        .map(|(position, (label, kind))| CompletionItem {
+            label,
+            kind: Some(kind),
+            sort_text: Some(sort_text(Rank::Item, position)),
+            ..CompletionItem::default()
+        })
+        .collect()
+}
+
+/// The first identifier among the tokens of the given node (but not of its
+/// children), which names it.
+fn first_identifier(node: &SyntaxNode) -> Option<String> {
+    node.children_with_tokens()
+        .filter_map(|it| it.into_token())
+        .find(|token| token.kind() == SyntaxKind::Identifier)
+        .map(|token| normalize_identifier(token.text()).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(source: &str) -> CompletionContext {
+        let offset = source.find('|').unwrap();
+        let source = source.replacen('|', "", 1);
+        let root = helios_parser::parse((), &source).syntax();
+        completion_context(&root, offset)
+    }
+
+    #[test]
+    fn test_completion_context() {
+        use CompletionContext::*;
+
+        assert_eq!(context("let |"), Name);
+        assert_eq!(context("var ab|"), Name);
+        assert_eq!(context("let a = |"), Expression(8));
+        assert_eq!(context("let a = b +  |\nlet c = 1"), Expression(11));
+        assert_eq!(context("let a = bc|"), Expression(8));
+        assert_eq!(context("let a = T.|"), Member("T".to_string()));
+        assert_eq!(context("let a = T.A|"), Member("T".to_string()));
+        assert_eq!(context("let a = 1\n|"), Declaration);
+        assert_eq!(context("let a =\n|"), Expression(7));
+        assert_eq!(context("let a =\n|\nlet b = 1"), Expression(7));
+        assert_eq!(context("let a = 1\nle|"), Declaration);
+        assert_eq!(context("|"), Declaration);
+        assert_eq!(context("let a| = 1"), Name);
+        assert_eq!(context("let a |= 1"), Unknown);
+        assert_eq!(context("type |"), Unknown);
+    }
+
+    #[test]
+    fn test_local_names() {
+        let source = "let f = \\x -> \\y x -> x + 1\nlet g = 1";
+        let root = helios_parser::parse((), source).syntax();
+        assert_eq!(local_names(&root, 22), vec!["y", "x"]);
+        assert_eq!(local_names(&root, 11), vec!["x"]);
+        assert!(local_names(&root, 8).is_empty());
+        assert!(local_names(&root, 37).is_empty());
+    }
+
+    #[test]
+    fn test_members() {
+        let source = "type T =\n  | A\n  | B(Int)\n\
+                      interface S = {\n  function show(a)\n}\n";
+        let root = helios_parser::parse((), source).syntax();
+        let labels = |name| {
+            members(&root, name)
+                .into_iter()
+                .map(|item| (item.label, item.kind.unwrap()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            labels("T"),
+            vec![
+                ("A".to_string(), CompletionItemKind::EnumMember),
+                ("B".to_string(), CompletionItemKind::EnumMember),
+            ]
+        );
+        assert_eq!(
+            labels("S"),
+            vec![("show".to_string(), CompletionItemKind::Method)]
+        );
+        assert!(labels("U").is_empty());
+    }
+}
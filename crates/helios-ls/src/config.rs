@@ -0,0 +1,242 @@
+//! Configuration options of the language server.
+//!
+//! The client may provide these options when it first initializes the server
+//! (through the `initializationOptions` field of the `initialize` request) and
+//! whenever the user changes their settings (through the
+//! `workspace/didChangeConfiguration` notification). Every field is optional;
+//! any missing field takes its default value.
+
+use helios_diagnostics::{LintLevel, LintLevels, Severity};
+use helios_parser::{Feature, Features};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// The section of the client's settings that holds the server's options.
+const CONFIG_SECTION: &str = "helios";
+
+/// The options that control the behaviour of the language server.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Config {
+    pub diagnostics: DiagnosticsConfig,
+    /// The names of the experimental features the documents are parsed with
+    /// (such as `"indentation-blocks"`).
+    pub features: Vec<String>,
+    pub log: LogConfig,
+}
+
+impl Config {
+    /// Updates the configuration with the given JSON value.
+    ///
+    /// The options may either be given directly or nested under a `"helios"`
+    /// key (which is how most clients send their settings). If the value
+    /// cannot be deserialized, the current configuration is left untouched
+    /// and a warning is logged instead.
+    pub fn update(&mut self, value: &serde_json::Value) {
+        let value = value.get(CONFIG_SECTION).unwrap_or(value);
+
+        if value.is_null() {
+            return;
+        }
+
+        match serde_json::from_value(value.clone()) {
+            Ok(config) => *self = config,
+            Err(error) => {
+                log::warn!("Failed to deserialize configuration: {}", error)
+            }
+        }
+    }
+
+    /// The experimental features enabled, leaving out (and logging) the
+    /// names that aren't features.
+    pub fn features(&self) -> Features {
+        let mut features = Features::new();
+        for name in &self.features {
+            match name.parse::<Feature>() {
+                Ok(feature) => features.enable(feature),
+                Err(error) => log::warn!("{} in configuration", error),
+            }
+        }
+
+        features
+    }
+}
+
+/// Options related to the diagnostics published by the server.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DiagnosticsConfig {
+    /// Whether diagnostics should be published at all.
+    pub enable: bool,
+    /// The least severe diagnostic that will be published.
+    pub severity_threshold: SeverityThreshold,
+    /// The levels of some lints by their names (such as `"never-mutated":
+    /// "deny"`), which are applied before the threshold.
+    pub lints: BTreeMap<String, LintLevelConfig>,
+    /// How long to wait (in milliseconds) after the last change to a
+    /// document before publishing its diagnostics, so that typing doesn't
+    /// trigger an analysis per keystroke.
+    pub delay: u64,
+}
+
+impl DiagnosticsConfig {
+    /// Determines if a diagnostic with the given [`Severity`] should be
+    /// published to the client.
+    pub fn allows(&self, severity: Severity) -> bool {
+        self.enable && severity >= self.severity_threshold.into()
+    }
+
+    /// The delay before publishing the diagnostics of a changed document.
+    pub fn delay_duration(&self) -> Duration {
+        Duration::from_millis(self.delay)
+    }
+
+    /// The levels the diagnostics of the lints are reported at.
+    pub fn lint_levels(&self) -> LintLevels {
+        let mut levels = LintLevels::new();
+        for (lint, level) in &self.lints {
+            levels.set(lint.clone(), (*level).into());
+        }
+
+        levels
+    }
+}
+
+impl Default for DiagnosticsConfig {
+    fn default() -> Self {
+        Self {
+            enable: true,
+            severity_threshold: SeverityThreshold::default(),
+            lints: BTreeMap::new(),
+            delay: 200,
+        }
+    }
+}
+
+/// The user-facing equivalent of [`Severity`].
+///
+/// Bugs in the compiler are always reported, so there is no threshold above
+/// [`SeverityThreshold::Error`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SeverityThreshold {
+    Error,
+    Warning,
+    #[default]
+    Note,
+}
+
+impl From<SeverityThreshold> for Severity {
+    fn from(threshold: SeverityThreshold) -> Self {
+        match threshold {
+            SeverityThreshold::Error => Severity::Error,
+            SeverityThreshold::Warning => Severity::Warning,
+            SeverityThreshold::Note => Severity::Note,
+        }
+    }
+}
+
+/// The user-facing equivalent of [`LintLevel`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LintLevelConfig {
+    Allow,
+    Warn,
+    Deny,
+}
+
+impl From<LintLevelConfig> for LintLevel {
+    fn from(level: LintLevelConfig) -> Self {
+        match level {
+            LintLevelConfig::Allow => LintLevel::Allow,
+            LintLevelConfig::Warn => LintLevel::Warn,
+            LintLevelConfig::Deny => LintLevel::Deny,
+        }
+    }
+}
+
+/// Options related to the server's own log.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct LogConfig {
+    /// The filters choosing which records are logged, in the syntax of
+    /// `RUST_LOG` (such as `warn,helios_ls::server=trace`). If `None`, the
+    /// filters the server was started with are used.
+    pub filter: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_config_update() {
+        let mut config = Config::default();
+        assert!(config.diagnostics.allows(Severity::Note));
+
+        // Nested under the `helios` section, with missing fields
+        config.update(&json!({
+            "helios": {
+                "diagnostics": { "severityThreshold": "warning" },
+            }
+        }));
+
+        assert!(config.diagnostics.enable);
+        assert!(!config.diagnostics.allows(Severity::Note));
+        assert!(config.diagnostics.allows(Severity::Warning));
+        assert!(config.diagnostics.allows(Severity::Bug));
+        assert_eq!(config.log, LogConfig::default());
+        assert_eq!(
+            config.diagnostics.delay_duration(),
+            Duration::from_millis(200)
+        );
+
+        // Given directly
+        config.update(&json!({
+            "diagnostics": { "enable": false, "delay": 50 },
+        }));
+
+        assert!(!config.diagnostics.allows(Severity::Error));
+        assert_eq!(
+            config.diagnostics.severity_threshold,
+            SeverityThreshold::default()
+        );
+        assert_eq!(
+            config.diagnostics.delay_duration(),
+            Duration::from_millis(50)
+        );
+        assert_eq!(config.log.filter, None);
+
+        config.update(&json!({ "log": { "filter": "helios_ls=debug" } }));
+        assert_eq!(config.log.filter.as_deref(), Some("helios_ls=debug"));
+
+        config.update(&json!({
+            "diagnostics": { "lints": { "never-mutated": "deny" } }
+        }));
+        let lint_levels = config.diagnostics.lint_levels();
+        assert_eq!(lint_levels.level("never-mutated"), Some(LintLevel::Deny));
+        assert_eq!(lint_levels.level("confusable"), None);
+        assert_eq!(config.features(), Features::new());
+
+        config.update(&json!({
+            "features": ["backward-pipelines", "macros"]
+        }));
+        assert_eq!(
+            config.features(),
+            Features::new().with(Feature::BackwardPipelines)
+        );
+    }
+
+    #[test]
+    fn test_config_update_with_invalid_value() {
+        let mut config = Config::default();
+        config.update(&json!({ "diagnostics": { "delay": 50 } }));
+
+        let expected = config.clone();
+        config.update(&json!({ "diagnostics": { "delay": "soon" } }));
+        config.update(&serde_json::Value::Null);
+        assert_eq!(config, expected);
+    }
+}
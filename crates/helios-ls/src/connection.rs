@@ -1,8 +1,42 @@
 use crate::protocol::Message;
+use crate::trace::{Direction, Tracer};
 use flume::{Receiver, Sender};
-use std::io;
+use std::io::{self, BufReader, Read, Write};
+use std::net::{TcpListener, ToSocketAddrs};
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::thread;
 
+/// The means of communication between the client and the server.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Transport {
+    /// Communicate through standard input and output.
+    #[default]
+    Stdio,
+    /// Listen for a client on the given TCP port on the local machine.
+    Tcp(u16),
+    /// Connect to the named pipe (on Windows) or Unix domain socket (on other
+    /// platforms) at the given path.
+    Pipe(PathBuf),
+}
+
+impl Transport {
+    /// Establishes a connection to the client with this transport.
+    ///
+    /// Every message exchanged through the connection is passed to the given
+    /// `tracer`.
+    pub fn connect(
+        &self,
+        tracer: Arc<Tracer>,
+    ) -> io::Result<(Connection, IoThreads)> {
+        match self {
+            Transport::Stdio => Ok(stdio(tracer)),
+            Transport::Tcp(port) => tcp(("127.0.0.1", *port), tracer),
+            Transport::Pipe(path) => pipe(path.clone(), tracer),
+        }
+    }
+}
+
 pub struct Connection {
     pub sender: Sender<Message>,
     pub receiver: Receiver<Message>,
@@ -25,7 +59,7 @@ impl IoThreads {
             Ok(result) => result?,
             Err(error) => {
                 eprintln!("writer thread failed to join");
-                panic!(error)
+                std::panic::panic_any(error)
             }
         };
 
@@ -33,7 +67,7 @@ impl IoThreads {
             Ok(result) => result?,
             Err(error) => {
                 eprintln!("reader thread failed to join");
-                panic!(error)
+                std::panic::panic_any(error)
             }
         };
 
@@ -41,25 +75,97 @@ impl IoThreads {
     }
 }
 
-pub fn stdio() -> (Connection, IoThreads) {
+/// Creates a connection over standard input and output.
+pub fn stdio(tracer: Arc<Tracer>) -> (Connection, IoThreads) {
+    io_connection(io::stdin(), io::stdout(), tracer)
+}
+
+/// Creates a connection over TCP.
+///
+/// This function will listen on the given address and block until the first
+/// client connects to it. Any subsequent clients are ignored.
+pub fn tcp(
+    addr: impl ToSocketAddrs,
+    tracer: Arc<Tracer>,
+) -> io::Result<(Connection, IoThreads)> {
+    let listener = TcpListener::bind(addr)?;
+    log::info!("Listening on {}...", listener.local_addr()?);
+
+    let (stream, peer_addr) = listener.accept()?;
+    log::info!("Accepted connection from {}", peer_addr);
+
+    Ok(io_connection(stream.try_clone()?, stream, tracer))
+}
+
+/// Creates a connection over a Unix domain socket.
+///
+/// Unlike [`tcp`], the server is the one that connects to the socket, which is
+/// expected to have been created by the client beforehand.
+#[cfg(unix)]
+pub fn pipe(
+    path: PathBuf,
+    tracer: Arc<Tracer>,
+) -> io::Result<(Connection, IoThreads)> {
+    let stream = std::os::unix::net::UnixStream::connect(&path)?;
+    log::info!("Connected to socket at {}", path.display());
+
+    Ok(io_connection(stream.try_clone()?, stream, tracer))
+}
+
+/// Creates a connection over a named pipe.
+///
+/// Unlike [`tcp`], the server is the one that connects to the pipe, which is
+/// expected to have been created by the client beforehand.
+#[cfg(not(unix))]
+pub fn pipe(
+    path: PathBuf,
+    tracer: Arc<Tracer>,
+) -> io::Result<(Connection, IoThreads)> {
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&path)?;
+    log::info!("Connected to pipe at {}", path.display());
+
+    Ok(io_connection(file.try_clone()?, file, tracer))
+}
+
+/// Spawns the threads responsible for reading messages from the given `input`
+/// and writing messages to the given `output`.
+fn io_connection(
+    input: impl Read + Send + 'static,
+    mut output: impl Write + Send + 'static,
+    tracer: Arc<Tracer>,
+) -> (Connection, IoThreads) {
+    let writer_tracer = Arc::clone(&tracer);
     let (writer_tx, writer_rx) = flume::bounded::<Message>(0);
     let writer = thread::spawn(move || {
-        let stdout = std::io::stdout();
-        let mut stdout = stdout.lock();
-
-        writer_rx
-            .into_iter()
-            .try_for_each(|msg| msg.write(&mut stdout))?;
+        writer_rx.into_iter().try_for_each(|msg| {
+            let content = msg.to_json()?;
+            writer_tracer.trace(Direction::Outgoing, &msg, &content);
+            Message::write_raw(&mut output, &content)
+        })?;
 
         Ok(())
     });
 
     let (reader_tx, reader_rx) = flume::bounded::<Message>(0);
     let reader = thread::spawn(move || {
-        let stdin = std::io::stdin();
-        let mut stdin = stdin.lock();
+        let mut input = BufReader::new(input);
+
+        while let Some(content) = Message::read_raw(&mut input)? {
+            // A message with malformed content is still correctly framed, so
+            // we can safely skip it and carry on with the next one
+            let msg = match Message::from_json(&content) {
+                Ok(msg) => msg,
+                Err(error) => {
+                    log::error!("Malformed message ({}): {}", error, content);
+                    tracer.trace_malformed(Direction::Incoming, &content);
+                    continue;
+                }
+            };
 
-        while let Some(msg) = Message::read(&mut stdin)? {
+            tracer.trace(Direction::Incoming, &msg, &content);
             let exit = matches!(&msg, Message::Notification(n) if n.is_exit());
             reader_tx.send(msg).expect("Failed to send to reader");
 
@@ -75,3 +181,54 @@ pub fn stdio() -> (Connection, IoThreads) {
     let threads = IoThreads { writer, reader };
     (connection, threads)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{Notification, Request};
+    use std::net::TcpStream;
+
+    #[test]
+    fn test_tcp_connection() {
+        // Find a free port to listen on
+        let port = TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .unwrap()
+            .port();
+
+        let client = thread::spawn(move || {
+            let mut stream = loop {
+                match TcpStream::connect(("127.0.0.1", port)) {
+                    Ok(stream) => break stream,
+                    Err(_) => thread::yield_now(),
+                }
+            };
+
+            Message::from(Request::new_without_params(0, "shutdown"))
+                .write(&mut stream)
+                .unwrap();
+            Message::from(Notification::new("exit", ()))
+                .write(&mut stream)
+                .unwrap();
+
+            let mut reader = BufReader::new(stream);
+            Message::read(&mut reader).unwrap()
+        });
+
+        let tracer = Arc::new(Tracer::disabled());
+        let (connection, threads) =
+            Transport::Tcp(port).connect(tracer).unwrap();
+
+        let message = connection.receiver.recv().unwrap();
+        assert_eq!(message, Request::new_without_params(0, "shutdown").into());
+        let message = connection.receiver.recv().unwrap();
+        assert!(matches!(message, Message::Notification(n) if n.is_exit()));
+
+        let response = Notification::new("window/logMessage", ());
+        connection.sender.send(response.clone().into()).unwrap();
+        assert_eq!(client.join().unwrap(), Some(response.into()));
+
+        drop(connection);
+        threads.join().unwrap();
+    }
+}
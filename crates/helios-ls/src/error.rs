@@ -1,3 +1,5 @@
+use crate::protocol::ErrorCode;
+use helios_query::cancel::Cancelled;
 use std::error::Error;
 use std::fmt::{self, Display};
 
@@ -11,3 +13,94 @@ impl Display for ProtocolError {
         Display::fmt(&self.0, f)
     }
 }
+
+/// An error that is reported back to the client as the response of a request.
+///
+/// Handlers may return this error to control the [`ErrorCode`] that is sent to
+/// the client. Any other error returned by a handler is converted with the
+/// [`LspError::from_error`] function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LspError {
+    pub(crate) code: ErrorCode,
+    pub(crate) message: String,
+}
+
+impl LspError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    /// The requested method is not supported by the server.
+    pub fn method_not_found(method: &str) -> Self {
+        let message = format!("unsupported method: {:?}", method);
+        Self::new(ErrorCode::MethodNotFound, message)
+    }
+
+    /// The parameters of the request could not be deserialized.
+    pub fn invalid_params(error: impl Display) -> Self {
+        let message = format!("invalid params: {}", error);
+        Self::new(ErrorCode::InvalidParams, message)
+    }
+
+    /// The server received a request before the `initialize` request.
+    pub fn server_not_initialized() -> Self {
+        let message = "the server has not been initialized yet";
+        Self::new(ErrorCode::ServerNotInitialized, message)
+    }
+
+    /// The document changed while the request was being processed, so the
+    /// result is no longer valid.
+    pub fn content_modified() -> Self {
+        let message = "the content was modified during the request";
+        Self::new(ErrorCode::ContentModified, message)
+    }
+
+    /// Converts an error returned by a handler to an [`LspError`].
+    ///
+    /// Cancelled queries are reported as [`ErrorCode::ContentModified`] (a
+    /// query is only ever cancelled because its inputs have changed) and any
+    /// unknown error is reported as [`ErrorCode::InternalError`].
+    ///
+    /// Requests are handled on the main thread for now, which is the one
+    /// changing the inputs, so their queries aren't cancelled in practice
+    /// (only the revalidation in the background is).
+    pub fn from_error(error: crate::Error) -> Self {
+        if let Some(error) = error.downcast_ref::<LspError>() {
+            error.clone()
+        } else if error.is::<Cancelled>() {
+            Self::content_modified()
+        } else {
+            Self::new(ErrorCode::InternalError, error.to_string())
+        }
+    }
+}
+
+impl Error for LspError {}
+
+impl Display for LspError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (error code {})", self.message, self.code as i32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lsp_error_from_error() {
+        let error = LspError::invalid_params("missing field `uri`");
+        let converted = LspError::from_error(error.clone().into());
+        assert_eq!(converted, error);
+
+        let converted = LspError::from_error(Cancelled.into());
+        assert_eq!(converted.code, ErrorCode::ContentModified);
+
+        let converted =
+            LspError::from_error(ProtocolError("oh no".into()).into());
+        assert_eq!(converted, LspError::new(ErrorCode::InternalError, "oh no"));
+    }
+}
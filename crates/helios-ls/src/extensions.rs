@@ -0,0 +1,57 @@
+//! Requests that extend the LSP with features specific to Helios, which
+//! clients have to opt into.
+//!
+//! Their methods are prefixed with `helios/` so that they can't clash with
+//! the methods of the LSP.
+
+use lsp_types::request::Request;
+use lsp_types::{Range, TextDocumentIdentifier};
+use serde::{Deserialize, Serialize};
+
+/// The `helios/syntaxTree` request, which returns the syntax tree of a
+/// document for debugging the parser.
+///
+/// The tree is always that of the latest contents of the document, so
+/// clients showing it (such as in a virtual document) can keep it up to date
+/// by sending the request again whenever the document changes.
+pub(crate) enum SyntaxTree {}
+
+impl Request for SyntaxTree {
+    type Params = SyntaxTreeParams;
+    type Result = String;
+    const METHOD: &'static str = "helios/syntaxTree";
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SyntaxTreeParams {
+    pub(crate) text_document: TextDocumentIdentifier,
+    /// Only show the smallest node covering this range (instead of the whole
+    /// tree).
+    pub(crate) range: Option<Range>,
+}
+
+/// The `helios/memoryUsage` request, which returns the number of entries
+/// stored by each query of the database, the sizes of the interners and the
+/// approximate memory used by syntax trees, as a table of text.
+///
+/// It is meant for finding which queries keep too many results alive in
+/// long editing sessions.
+pub(crate) enum MemoryUsage {}
+
+impl Request for MemoryUsage {
+    type Params = ();
+    type Result = String;
+    const METHOD: &'static str = "helios/memoryUsage";
+}
+
+/// The `helios/profile` request, which returns the time the queries of the
+/// database have spent in each phase (such as lexing or parsing) of compiling
+/// each file since the server started, as a table of text.
+pub(crate) enum Profile {}
+
+impl Request for Profile {
+    type Params = ();
+    type Result = String;
+    const METHOD: &'static str = "helios/profile";
+}
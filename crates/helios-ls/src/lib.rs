@@ -1,27 +1,44 @@
+mod completion;
+mod config;
 mod connection;
 mod error;
+mod extensions;
+mod logger;
+mod proposed;
 mod protocol;
+mod scheduler;
 mod server;
 mod state;
+#[cfg(test)]
+mod test_server;
+mod trace;
 
+pub use connection::Transport;
+pub use logger::{init_logger, LogOptions, LogOutput};
 use server::Server;
 use state::State;
+use std::sync::Arc;
+use trace::Tracer;
+pub use trace::{TraceOptions, TraceOutput};
 
 pub type Error = Box<dyn std::error::Error + Send + Sync>;
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
-pub fn start() {
-    if let Err(error) = __start() {
+pub fn start(transport: Transport, trace: TraceOptions) {
+    if let Err(error) = __start(transport, trace) {
         eprintln!("Error: {}", error);
         std::process::exit(1);
     }
 }
 
-pub fn __start() -> Result<()> {
-    let (connection, threads) = connection::stdio();
+pub fn __start(transport: Transport, trace: TraceOptions) -> Result<()> {
+    let tracer = Arc::new(Tracer::new(trace)?);
+    let (connection, threads) = transport.connect(Arc::clone(&tracer))?;
 
-    let mut state = State::new(connection.sender);
-    Server::new(connection.receiver, &mut state).initialize()?.run()?;
+    let mut state = State::new(connection.sender, tracer);
+    Server::new(connection.receiver, &mut state)
+        .initialize()?
+        .run()?;
 
     threads.join()?;
     log::info!("Connection to client has closed");
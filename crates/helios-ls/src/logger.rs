@@ -0,0 +1,319 @@
+//! Logging of what the server does (as opposed to [tracing] the messages it
+//! exchanges with the client).
+//!
+//! Every record logged through the [`log`] macros is written on a single line
+//! as `[<timestamp>] <LEVEL> <target>: <message>`, either to standard error or
+//! to a file (with the `--log-file` command line option). The records written
+//! are chosen by filters in the same syntax as `RUST_LOG` (for example
+//! `warn,helios_ls::server=trace`), which are read from the `RUST_LOG`
+//! environment variable at startup and may be changed by the client with the
+//! `log.filter` option.
+//!
+//! Requests and notifications are timed as they are handled, and those that
+//! take longer than [`SLOW_MESSAGE`] are logged as warnings along with their
+//! duration.
+//!
+//! [tracing]: crate::trace
+
+use crate::trace::timestamp;
+use log::{LevelFilter, Log, Metadata, Record};
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+/// How long handling a message may take before it is reported as slow.
+pub(crate) const SLOW_MESSAGE: Duration = Duration::from_millis(100);
+
+/// Where the log is written to.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum LogOutput {
+    #[default]
+    Stderr,
+    /// Write the log to the file at the given path, which is truncated
+    /// beforehand.
+    File(PathBuf),
+}
+
+/// The options the logger is started with.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LogOptions {
+    pub output: LogOutput,
+    /// The initial filters (in the syntax of `RUST_LOG`), which only let
+    /// errors through if `None`.
+    pub filter: Option<String>,
+}
+
+/// The maximum level of the records to log, for each module.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Filters {
+    default: LevelFilter,
+    /// The levels of specific modules (and their submodules), from the least
+    /// to the most specific.
+    modules: Vec<(String, LevelFilter)>,
+}
+
+impl Filters {
+    /// Parses filters such as `warn,helios_ls::server=trace`.
+    ///
+    /// Each comma-separated directive is either a level (which applies to
+    /// every module) or a module path, optionally followed by `=` and a level
+    /// (without which every record of the module is logged). Invalid
+    /// directives are ignored.
+    fn parse(spec: &str) -> Self {
+        let mut filters = Self {
+            default: LevelFilter::Error,
+            modules: Vec::new(),
+        };
+
+        for directive in spec.split(',').map(str::trim) {
+            if directive.is_empty() {
+                continue;
+            }
+
+            let (module, level) = match directive.split_once('=') {
+                Some((module, level)) => match level.trim().parse() {
+                    Ok(level) => (Some(module.trim()), level),
+                    Err(_) => continue,
+                },
+                None => match directive.parse() {
+                    Ok(level) => (None, level),
+                    Err(_) => (Some(directive), LevelFilter::Trace),
+                },
+            };
+
+            match module {
+                Some(module) => {
+                    filters.modules.push((module.to_string(), level))
+                }
+                None => filters.default = level,
+            }
+        }
+
+        filters.modules.sort_by_key(|(module, _)| module.len());
+        filters
+    }
+
+    /// The maximum level of the records of the given target.
+    fn level(&self, target: &str) -> LevelFilter {
+        self.modules
+            .iter()
+            .rev()
+            .find(|(module, _)| {
+                target == module
+                    || target
+                        .strip_prefix(module.as_str())
+                        .is_some_and(|rest| rest.starts_with("::"))
+            })
+            .map_or(self.default, |(_, level)| *level)
+    }
+
+    /// The maximum level of any record.
+    fn max_level(&self) -> LevelFilter {
+        self.modules
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, std::cmp::max)
+    }
+}
+
+/// Writes the records logged by the server.
+///
+/// The filters can change while the server is running, hence the interior
+/// mutability.
+struct Logger {
+    initial_filters: Filters,
+    filters: RwLock<Filters>,
+    output: Mutex<Box<dyn Write + Send + Sync>>,
+}
+
+impl Logger {
+    fn new(filter: Option<&str>, output: Box<dyn Write + Send + Sync>) -> Self {
+        let filters = Filters::parse(filter.unwrap_or_default());
+        Self {
+            initial_filters: filters.clone(),
+            filters: RwLock::new(filters),
+            output: Mutex::new(output),
+        }
+    }
+
+    fn set_filter(&self, filter: Option<&str>) {
+        let filters = match filter {
+            Some(filter) => Filters::parse(filter),
+            None => self.initial_filters.clone(),
+        };
+
+        log::set_max_level(filters.max_level());
+        match self.filters.write() {
+            Ok(mut it) => *it = filters,
+            Err(poisoned) => *poisoned.into_inner() = filters,
+        }
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        let filters = match self.filters.read() {
+            Ok(filters) => filters,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        metadata.level() <= filters.level(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let mut output = match self.output.lock() {
+            Ok(output) => output,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        // There's nowhere left to report the error to
+        let _ = writeln!(
+            output,
+            "[{}] {:<5} {}: {}",
+            timestamp(),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+    }
+
+    fn flush(&self) {
+        if let Ok(mut output) = self.output.lock() {
+            let _ = output.flush();
+        }
+    }
+}
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+/// Starts logging with the given options.
+///
+/// This may only be called once, before the server is started.
+pub fn init_logger(options: LogOptions) -> io::Result<()> {
+    let output: Box<dyn Write + Send + Sync> = match &options.output {
+        LogOutput::Stderr => Box::new(io::stderr()),
+        LogOutput::File(path) => Box::new(File::create(path)?),
+    };
+
+    let logger = Logger::new(options.filter.as_deref(), output);
+    let max_level = logger.filters.read().unwrap().max_level();
+
+    let logger = LOGGER.get_or_init(|| logger);
+    log::set_logger(logger)
+        .map_err(|error| io::Error::other(error.to_string()))?;
+    log::set_max_level(max_level);
+    Ok(())
+}
+
+/// Replaces the filters of the logger (if it has been started), or restores
+/// the ones it was started with if `None`.
+pub(crate) fn set_filter(filter: Option<&str>) {
+    if let Some(logger) = LOGGER.get() {
+        logger.set_filter(filter);
+    }
+}
+
+/// Logs how long it took to handle the message with the given method since
+/// `start`, as a warning if it was slow.
+pub(crate) fn log_duration(method: &str, start: Instant) {
+    let duration = start.elapsed();
+    if duration >= SLOW_MESSAGE {
+        log::warn!("slow message: method={} duration={:?}", method, duration);
+    } else {
+        log::debug!(
+            "handled message: method={} duration={:?}",
+            method,
+            duration
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::Level;
+    use std::sync::Arc;
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_filters() {
+        let filters =
+            Filters::parse("warn, helios_ls::server=trace,helios_ls=info,,x=?");
+        assert_eq!(filters.default, LevelFilter::Warn);
+        assert_eq!(filters.level("salsa"), LevelFilter::Warn);
+        assert_eq!(filters.level("helios_ls"), LevelFilter::Info);
+        assert_eq!(filters.level("helios_ls::state"), LevelFilter::Info);
+        assert_eq!(filters.level("helios_ls::server"), LevelFilter::Trace);
+        assert_eq!(
+            filters.level("helios_ls::server::dispatcher"),
+            LevelFilter::Trace
+        );
+        assert_eq!(filters.level("helios_ls_other"), LevelFilter::Warn);
+        assert_eq!(filters.max_level(), LevelFilter::Trace);
+
+        let filters = Filters::parse("helios_query");
+        assert_eq!(filters.default, LevelFilter::Error);
+        assert_eq!(filters.level("helios_query::items"), LevelFilter::Trace);
+
+        assert_eq!(Filters::parse("").max_level(), LevelFilter::Error);
+    }
+
+    #[test]
+    fn test_logger() {
+        let buffer = SharedBuffer::default();
+        let logger = Logger::new(Some("info"), Box::new(buffer.clone()));
+
+        let log = |level, target: &str| {
+            logger.log(
+                &Record::builder()
+                    .level(level)
+                    .target(target)
+                    .args(format_args!("message"))
+                    .build(),
+            )
+        };
+
+        log(Level::Info, "helios_ls::server");
+        log(Level::Debug, "helios_ls::server");
+
+        logger.set_filter(Some("helios_ls::server=debug"));
+        log(Level::Debug, "helios_ls::server");
+        log(Level::Info, "helios_ls::state");
+
+        logger.set_filter(None);
+        log(Level::Debug, "helios_ls::server");
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone());
+        let lines = output
+            .unwrap()
+            .lines()
+            .map(|line| line.split_once("] ").unwrap().1.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            lines,
+            vec![
+                "INFO  helios_ls::server: message",
+                "DEBUG helios_ls::server: message",
+            ]
+        );
+    }
+}
@@ -1,8 +1,21 @@
+use helios_ls::{LogOptions, LogOutput, TraceOptions, TraceOutput, Transport};
+
 /// Prints the usage information of the Helios executable.
 fn print_usage() {
     println!("{}", include_str!("../usage.txt"));
 }
 
+/// The exit code of an invalid command line.
+const USAGE_ERROR: i32 = 2;
+
+/// Prints the given error message about the command line, followed by the
+/// usage information, and exits.
+fn usage_error(message: impl Into<String>) -> ! {
+    eprintln!("ERROR: {}", message.into());
+    print_usage();
+    std::process::exit(USAGE_ERROR)
+}
+
 /// Prints the current version number of the Helios executable.
 ///
 /// This function will print the version number found in the `Cargo.toml`
@@ -21,23 +34,68 @@ fn print_version() {
     }
 }
 
+/// Starts the language server with the given transport.
+fn start(transport: Transport, trace: TraceOptions, log: LogOptions) {
+    if let Err(error) = helios_ls::init_logger(log) {
+        eprintln!("ERROR: Failed to start logging: {}", error);
+        std::process::exit(1);
+    }
+
+    log::trace!("Starting Helios-LS...");
+    helios_ls::start(transport, trace)
+}
+
 fn main() {
-    env_logger::init();
     let mut args = std::env::args();
     args.next(); // Skip path to executable
 
-    match (args.next(), args.next()) {
-        (Some(arg), param) => match (&*arg, param) {
-            ("-h", _) | ("--help", _) => print_usage(),
-            ("-V", _) | ("--version", _) => print_version(),
-            _ => {
-                eprintln!("ERROR: Unrecognised option `{}`", arg);
-                print_usage()
+    let mut transport = Transport::default();
+    let mut trace = TraceOptions::default();
+    let mut log = LogOptions {
+        filter: std::env::var("RUST_LOG").ok(),
+        ..LogOptions::default()
+    };
+
+    while let Some(arg) = args.next() {
+        let mut param = |name: &str| match args.next() {
+            Some(param) => param,
+            None => {
+                usage_error(format!("Missing argument for option `{}`", name))
+            }
+        };
+
+        match &*arg {
+            "-h" | "--help" => return print_usage(),
+            "-V" | "--version" => return print_version(),
+            "--stdio" => transport = Transport::Stdio,
+            "--port" => {
+                let port = param(&arg);
+                match port.parse() {
+                    Ok(port) => transport = Transport::Tcp(port),
+                    Err(_) => {
+                        usage_error(format!("Invalid port number `{}`", port))
+                    }
+                }
             }
-        },
-        _ => {
-            log::trace!("Starting Helios-LS...");
-            helios_ls::start()
+            "--pipe" => transport = Transport::Pipe(param(&arg).into()),
+            "--log-file" => log.output = LogOutput::File(param(&arg).into()),
+            "--trace" => trace.enabled = true,
+            "--trace-file" => {
+                trace.enabled = true;
+                trace.output = TraceOutput::File(param(&arg).into());
+            }
+            "--trace-max-len" => {
+                let len = param(&arg);
+                match len.parse() {
+                    Ok(len) => trace.max_payload_len = Some(len),
+                    Err(_) => {
+                        usage_error(format!("Invalid payload length `{}`", len))
+                    }
+                }
+            }
+            _ => usage_error(format!("Unrecognised option `{}`", arg)),
         }
     }
+
+    start(transport, trace, log)
 }
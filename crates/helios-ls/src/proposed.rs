@@ -0,0 +1,98 @@
+//! Parts of version 3.17 of the LSP that `lsp-types` doesn't have yet (the
+//! pull model of diagnostics), which can be replaced by their `lsp-types`
+//! counterparts once it does.
+
+use lsp_types::request::Request;
+use lsp_types::{Diagnostic, InitializeParams, ServerInfo};
+use lsp_types::{TextDocumentIdentifier, WorkDoneProgressParams};
+use serde::{Deserialize, Serialize};
+
+/// The `initialize` request, whose result includes the server capabilities
+/// of version 3.17.
+pub(crate) enum Initialize {}
+
+impl Request for Initialize {
+    type Params = InitializeParams;
+    type Result = InitializeResult;
+    const METHOD: &'static str = "initialize";
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct InitializeResult {
+    pub(crate) capabilities: ServerCapabilities,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) server_info: Option<ServerInfo>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ServerCapabilities {
+    #[serde(flatten)]
+    pub(crate) base: lsp_types::ServerCapabilities,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) diagnostic_provider: Option<DiagnosticOptions>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DiagnosticOptions {
+    /// The name of the diagnostics, which clients may show along them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) identifier: Option<String>,
+    /// Whether the diagnostics of a document can change when other documents
+    /// change.
+    pub(crate) inter_file_dependencies: bool,
+    /// Whether the diagnostics of the whole workspace can be pulled at once.
+    pub(crate) workspace_diagnostics: bool,
+}
+
+/// The `textDocument/diagnostic` request, which is sent from the client to
+/// the server to pull the diagnostics of a document.
+pub(crate) enum DocumentDiagnosticRequest {}
+
+impl Request for DocumentDiagnosticRequest {
+    type Params = DocumentDiagnosticParams;
+    type Result = DocumentDiagnosticReport;
+    const METHOD: &'static str = "textDocument/diagnostic";
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DocumentDiagnosticParams {
+    pub(crate) text_document: TextDocumentIdentifier,
+    /// The identifier given in the [`DiagnosticOptions`].
+    pub(crate) identifier: Option<String>,
+    /// The result ID of the last report the client received for the
+    /// document.
+    pub(crate) previous_result_id: Option<String>,
+    #[serde(flatten)]
+    pub(crate) work_done_progress_params: WorkDoneProgressParams,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub(crate) enum DocumentDiagnosticReport {
+    /// Every diagnostic of the document.
+    #[serde(rename_all = "camelCase")]
+    Full {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        result_id: Option<String>,
+        items: Vec<Diagnostic>,
+    },
+    /// The diagnostics of the document haven't changed since the report with
+    /// the given result ID.
+    #[serde(rename_all = "camelCase")]
+    Unchanged { result_id: String },
+}
+
+/// The `workspace/diagnostic/refresh` request, which is sent from the server
+/// to the client to ask it to pull the diagnostics of every open document
+/// again.
+pub(crate) enum WorkspaceDiagnosticRefresh {}
+
+impl Request for WorkspaceDiagnosticRefresh {
+    type Params = ();
+    type Result = ();
+    const METHOD: &'static str = "workspace/diagnostic/refresh";
+}
@@ -1,3 +1,4 @@
+use crate::error::LspError;
 use serde::{Deserialize, Serialize};
 use std::fmt::{self, Debug, Display};
 use std::io::{self, BufRead, Write};
@@ -131,6 +132,10 @@ impl Response {
             error: Some(error),
         }
     }
+
+    pub fn new_lsp_error(id: impl Into<RequestId>, error: LspError) -> Self {
+        Self::new_error(id, error.code, error.message)
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -145,8 +150,8 @@ pub struct ResponseError {
     pub(crate) data: Option<serde_json::Value>,
 }
 
-#[derive(Clone, Copy, Debug)]
-#[allow(unused)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(unused, clippy::enum_variant_names)]
 pub enum ErrorCode {
     // Defined by JSON RPC
     ParseError = -32700,
@@ -154,6 +159,12 @@ pub enum ErrorCode {
     MethodNotFound = -32601,
     InvalidParams = -32602,
     InternalError = -32603,
+
+    // Defined by the LSP
+    ServerNotInitialized = -32002,
+    UnknownErrorCode = -32001,
+    RequestCancelled = -32800,
+    ContentModified = -32801,
 }
 
 /// A notification message.
@@ -195,29 +206,55 @@ pub enum Message {
 }
 
 impl Message {
+    /// Reads and deserializes the next message.
+    ///
+    /// An error is returned if the message is not correctly framed or if its
+    /// content is not a valid JSON-RPC message. Use [`Message::read_raw`] and
+    /// [`Message::from_json`] to distinguish between the two cases.
     pub fn read(reader: &mut impl BufRead) -> io::Result<Option<Self>> {
-        if let Some(input) = read_message(reader)? {
-            let input = serde_json::from_str(&input)?;
-            Ok(Some(input))
+        if let Some(input) = Self::read_raw(reader)? {
+            Ok(Some(Self::from_json(&input)?))
         } else {
             Ok(None)
         }
     }
 
+    /// Reads the content of the next message without deserializing it.
+    ///
+    /// An error is returned if the header of the message is malformed, after
+    /// which the rest of the input cannot be trusted to be correctly framed.
+    pub fn read_raw(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+        read_message(reader)
+    }
+
+    /// Deserializes the content of a message.
+    pub fn from_json(input: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(input)
+    }
+
     pub fn write(self, writer: &mut impl Write) -> io::Result<()> {
+        let content = self.to_json()?;
+        Self::write_raw(writer, &content)
+    }
+
+    /// Writes the given content as a message.
+    pub fn write_raw(writer: &mut impl Write, content: &str) -> io::Result<()> {
+        write_message(writer, content)
+    }
+
+    /// Serializes the message to be sent with [`Message::write_raw`].
+    pub fn to_json(&self) -> serde_json::Result<String> {
         #[derive(Serialize)]
-        struct JsonRpc {
+        struct JsonRpc<'a> {
             jsonrpc: &'static str,
             #[serde(flatten)]
-            pub(crate) message: Message,
+            pub(crate) message: &'a Message,
         }
 
-        let content = serde_json::to_string(&JsonRpc {
+        serde_json::to_string(&JsonRpc {
             jsonrpc: "2.0",
             message: self,
-        })?;
-
-        write_message(writer, &content)
+        })
     }
 }
 
@@ -359,26 +396,34 @@ mod tests {
         assert!(Message::read(&mut CONTENT.as_bytes()).is_err());
 
         // Missing `Content-Length` value
-        let header = format!("Content-Length: \r\n\r\n");
+        let header = "Content-Length: \r\n\r\n".to_string();
         let request = header + CONTENT;
         assert!(Message::read(&mut request.as_bytes()).is_err());
 
         // Missing header fields
-        let header = format!("\r\n\r\n");
+        let header = "\r\n\r\n".to_string();
         let request = header + CONTENT;
         assert!(Message::read(&mut request.as_bytes()).is_err());
 
         // Invalid header fields
-        let header = format!("Foo: abc\r\nBar: def\r\n");
+        let header = "Foo: abc\r\nBar: def\r\n".to_string();
         let request = header + CONTENT;
         assert!(Message::read(&mut request.as_bytes()).is_err());
 
         // Malformed header
-        let header = format!("abcdef\r\n");
+        let header = "abcdef\r\n".to_string();
         let request = header + CONTENT;
         assert!(Message::read(&mut request.as_bytes()).is_err());
 
         // Malformed input
         assert!(Message::read(&mut "<INVALID-INPUT>".as_bytes()).is_err());
+
+        // Malformed content with a valid header
+        let content = r#"{"jsonrpc":"2.0","id":0,"#;
+        let header = format!("Content-Length: {}\r\n\r\n", content.len());
+        let request = header + content;
+        let raw = Message::read_raw(&mut request.as_bytes()).unwrap();
+        assert_eq!(raw.as_deref(), Some(content));
+        assert!(Message::from_json(content).is_err());
     }
 }
@@ -0,0 +1,122 @@
+//! Coalescing the analyses of documents that change in quick succession.
+//!
+//! Every keystroke in the editor sends a `textDocument/didChange`
+//! notification. Its changes are applied to the document right away (so that
+//! requests such as completions are always answered against the latest
+//! text), but its diagnostics are only published once the document has been
+//! left alone for a while (see [`DiagnosticsConfig::delay`]), so that typing
+//! quickly doesn't trigger a full analysis per character.
+//!
+//! [`DiagnosticsConfig::delay`]: crate::config::DiagnosticsConfig::delay
+
+use helios_query::FileId;
+use lsp_types::Url;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// A document whose diagnostics are waiting to be published.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Pending {
+    pub(crate) uri: Url,
+    /// The version of the last change to the document.
+    pub(crate) version: Option<i32>,
+    /// When the diagnostics are due to be published.
+    pub(crate) deadline: Instant,
+}
+
+/// The documents whose diagnostics are waiting to be published, with at most
+/// one entry per document.
+#[derive(Debug, Default)]
+pub(crate) struct Scheduler {
+    pending: HashMap<FileId, Pending>,
+}
+
+impl Scheduler {
+    /// Schedules the publication of the diagnostics of the given document
+    /// after the given delay. If it's already scheduled, it's pushed back to
+    /// the new deadline (with the new version) instead.
+    pub(crate) fn schedule(
+        &mut self,
+        file_id: FileId,
+        uri: Url,
+        version: Option<i32>,
+        delay: Duration,
+    ) {
+        let deadline = Instant::now() + delay;
+        let pending = Pending {
+            uri,
+            version,
+            deadline,
+        };
+
+        self.pending.insert(file_id, pending);
+    }
+
+    /// Forgets the pending publication of the given document, if any (such as
+    /// when its diagnostics are published right away).
+    pub(crate) fn cancel(&mut self, file_id: FileId) {
+        self.pending.remove(&file_id);
+    }
+
+    /// The earliest deadline of the documents waiting, if any.
+    pub(crate) fn deadline(&self) -> Option<Instant> {
+        self.pending.values().map(|pending| pending.deadline).min()
+    }
+
+    /// Removes the documents whose deadline has passed at the given instant,
+    /// returning them in the order they're due.
+    pub(crate) fn take_due(&mut self, now: Instant) -> Vec<(FileId, Pending)> {
+        let due = self
+            .pending
+            .iter()
+            .filter(|(_, pending)| pending.deadline <= now)
+            .map(|(&file_id, _)| file_id)
+            .collect::<Vec<_>>();
+
+        let mut due = due
+            .into_iter()
+            .filter_map(|file_id| {
+                let pending = self.pending.remove(&file_id)?;
+                Some((file_id, pending))
+            })
+            .collect::<Vec<_>>();
+
+        due.sort_by_key(|(file_id, pending)| (pending.deadline, file_id.0));
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schedule_coalesces_changes() {
+        let foo = Url::parse("file:///Foo.he").unwrap();
+        let bar = Url::parse("file:///Bar.he").unwrap();
+        let delay = Duration::from_secs(60);
+
+        let mut scheduler = Scheduler::default();
+        assert_eq!(scheduler.deadline(), None);
+
+        scheduler.schedule(FileId(0), foo.clone(), Some(1), delay);
+        let first_deadline = scheduler.deadline().unwrap();
+        scheduler.schedule(FileId(1), bar, Some(1), delay);
+        scheduler.schedule(FileId(0), foo.clone(), Some(2), delay);
+
+        // Nothing is due before the delay, and each document is only
+        // published once, at its latest version
+        assert!(scheduler.take_due(Instant::now()).is_empty());
+        let due = scheduler.take_due(first_deadline + 2 * delay);
+        let due = due
+            .into_iter()
+            .map(|(file_id, pending)| (file_id, pending.version))
+            .collect::<Vec<_>>();
+        assert_eq!(due, vec![(FileId(1), Some(1)), (FileId(0), Some(2))]);
+        assert_eq!(scheduler.deadline(), None);
+
+        scheduler.schedule(FileId(0), foo, Some(3), delay);
+        scheduler.cancel(FileId(0));
+        assert_eq!(scheduler.deadline(), None);
+    }
+}
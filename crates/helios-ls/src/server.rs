@@ -1,12 +1,13 @@
+mod crash;
 mod dispatcher;
 mod handlers;
 
 use self::dispatcher::{NotificationDispatcher, RequestDispatcher};
-use crate::error::ProtocolError;
-use crate::protocol::{Message, Notification, Request};
+use crate::error::{LspError, ProtocolError};
+use crate::protocol::{ErrorCode, Message, Notification, Request, Response};
 use crate::state::State;
 use crate::Result;
-use flume::Receiver;
+use flume::{Receiver, RecvTimeoutError};
 
 pub struct Server<'a> {
     did_initialize: bool,
@@ -23,19 +24,81 @@ impl<'a> Server<'a> {
         }
     }
 
+    /// Waits for the `initialize` request and handles it.
+    ///
+    /// Any request received before it is responded to with an error and any
+    /// notification received before it is dropped (as required by the LSP).
     pub fn initialize(mut self) -> Result<Self> {
-        match self.receiver.recv()? {
-            Message::Request(request) if request.is_initialize() => {
-                self.handle_request(request)?;
-                self.did_initialize = true;
-            }
-            message => {
-                let message = format!(
-                    "expected initialize request, but found {:?}",
-                    message
-                );
+        loop {
+            match self.receiver.recv()? {
+                Message::Request(request) if request.is_initialize() => {
+                    if let Some(options) =
+                        request.params.get("initializationOptions")
+                    {
+                        self.state.update_config(options);
+                    }
+
+                    let watch_files = "/capabilities/workspace/\
+                                       didChangeWatchedFiles/dynamicRegistration";
+                    self.state.can_watch_files = request
+                        .params
+                        .pointer(watch_files)
+                        .and_then(|it| it.as_bool())
+                        .unwrap_or(false);
+
+                    // The pull model of diagnostics is newer than `lsp-types`
+                    // (see `proposed`)
+                    let pull_diagnostics =
+                        "/capabilities/textDocument/diagnostic";
+                    self.state.can_pull_diagnostics =
+                        request.params.pointer(pull_diagnostics).is_some();
 
-                return Err(ProtocolError(message).into());
+                    let refresh_diagnostics = "/capabilities/workspace/\
+                                               diagnostics/refreshSupport";
+                    self.state.can_refresh_diagnostics = request
+                        .params
+                        .pointer(refresh_diagnostics)
+                        .and_then(|it| it.as_bool())
+                        .unwrap_or(false);
+
+                    let snippets = "/capabilities/textDocument/completion/\
+                                    completionItem/snippetSupport";
+                    self.state.can_complete_snippets = request
+                        .params
+                        .pointer(snippets)
+                        .and_then(|it| it.as_bool())
+                        .unwrap_or(false);
+
+                    if let Some(trace) = request.params.get("trace") {
+                        match serde_json::from_value(trace.clone()) {
+                            Ok(Some(level)) => {
+                                self.state.tracer.set_initial_level(level)
+                            }
+                            Ok(None) => {}
+                            Err(error) => {
+                                log::warn!("Invalid trace: {}", error)
+                            }
+                        }
+                    }
+
+                    self.handle_request(request);
+                    break;
+                }
+                Message::Request(request) => {
+                    let error = LspError::server_not_initialized();
+                    log::warn!("Rejecting request {}: {}", request.id, error);
+                    self.state.send(Response::new_lsp_error(request.id, error));
+                }
+                Message::Notification(notification)
+                    if notification.is_exit() =>
+                {
+                    let message = "received exit notification before \
+                                   initialize request";
+                    return Err(ProtocolError(message.to_string()).into());
+                }
+                message => {
+                    log::warn!("Dropping uninitialized message: {:?}", message)
+                }
             }
         }
 
@@ -45,8 +108,28 @@ impl<'a> Server<'a> {
         })
     }
 
+    /// Handles the messages of the client until it exits.
+    ///
+    /// While the diagnostics of some changed documents are waiting to be
+    /// published, the server only waits for the next message until the first
+    /// of them is due.
     pub fn run(mut self) -> Result<()> {
-        while let Ok(message) = self.receiver.recv() {
+        loop {
+            let message = match self.state.scheduler.deadline() {
+                Some(deadline) => match self.receiver.recv_deadline(deadline) {
+                    Ok(message) => message,
+                    Err(RecvTimeoutError::Timeout) => {
+                        handlers::publish_due_diagnostics(self.state);
+                        continue;
+                    }
+                    Err(RecvTimeoutError::Disconnected) => break,
+                },
+                None => match self.receiver.recv() {
+                    Ok(message) => message,
+                    Err(_) => break,
+                },
+            };
+
             if !self.did_initialize {
                 log::warn!(
                     "Cannot process received message because the connection to \
@@ -57,40 +140,119 @@ impl<'a> Server<'a> {
             }
 
             match message {
-                Message::Request(r) => self.handle_request(r)?,
+                Message::Request(r) if r.is_initialize() => {
+                    let error = LspError::new(
+                        ErrorCode::InvalidRequest,
+                        "the server has already been initialized",
+                    );
+                    self.state.send(Response::new_lsp_error(r.id, error));
+                }
+                Message::Request(r) => self.handle_request(r),
                 Message::Notification(n) if n.is_exit() => {
                     log::trace!("Exiting...");
                     break;
                 }
-                Message::Notification(n) => self.handle_notification(n)?,
+                Message::Notification(n) => self.handle_notification(n),
                 _ => log::info!("Unhandled message: {:?}", message),
             }
+
+            // Without a delay, the diagnostics of a change are due right away
+            handlers::publish_due_diagnostics(self.state);
         }
 
         Ok(())
     }
 
-    fn handle_request(&mut self, req: Request) -> Result<()> {
+    fn handle_request(&mut self, req: Request) {
+        use crate::extensions::{MemoryUsage, Profile, SyntaxTree};
+        use crate::proposed::{DocumentDiagnosticRequest, Initialize};
         use lsp_types::request::*;
         RequestDispatcher::new(req, self.state)
-            .on::<Initialize>(handlers::initialize)?
-            .on::<Shutdown>(handlers::shutdown)?
-            .on::<Completion>(handlers::completion)?
-            .on::<HoverRequest>(handlers::hover)?
+            .on::<Initialize>(handlers::initialize)
+            .on::<Shutdown>(handlers::shutdown)
+            .on::<Completion>(handlers::completion)
+            .on::<HoverRequest>(handlers::hover)
+            .on::<DocumentHighlightRequest>(handlers::document_highlight)
+            .on::<CallHierarchyPrepare>(handlers::prepare_call_hierarchy)
+            .on::<CallHierarchyIncomingCalls>(handlers::incoming_calls)
+            .on::<CallHierarchyOutgoingCalls>(handlers::outgoing_calls)
+            .on::<CodeLensRequest>(handlers::code_lens)
+            .on::<CodeLensResolve>(handlers::code_lens_resolve)
+            .on::<CodeActionRequest>(handlers::code_action)
+            .on::<DocumentDiagnosticRequest>(handlers::document_diagnostic)
+            .on_mut::<ExecuteCommand>(handlers::execute_command)
+            .on::<SyntaxTree>(handlers::syntax_tree)
+            .on::<MemoryUsage>(handlers::memory_usage)
+            .on::<Profile>(handlers::profile)
             .finish();
-
-        Ok(())
     }
 
-    fn handle_notification(&mut self, not: Notification) -> Result<()> {
+    fn handle_notification(&mut self, not: Notification) {
+        use crate::trace::SetTrace;
         use lsp_types::notification::*;
         NotificationDispatcher::new(not, self.state)
+            .on::<Cancel>(handlers::cancel_request)
+            .on::<SetTrace>(handlers::set_trace)
             .on::<Initialized>(handlers::initialized)
+            .on::<DidChangeConfiguration>(handlers::did_change_configuration)
             .on::<DidOpenTextDocument>(handlers::did_open_text_document)
             .on::<DidChangeTextDocument>(handlers::did_change_text_document)
+            .on::<DidCloseTextDocument>(handlers::did_close_text_document)
+            .on::<DidChangeWatchedFiles>(handlers::did_change_watched_files)
             .on::<DidSaveTextDocument>(handlers::did_save_text_document)
             .finish();
+    }
+}
 
-        Ok(())
+#[cfg(test)]
+mod tests {
+    use crate::protocol::ErrorCode;
+    use crate::test_server::TestServer;
+    use serde_json::json;
+
+    fn error_code(server: &mut TestServer, method: &str) -> Option<i32> {
+        let params = json!({
+            "textDocument": { "uri": "file:///Foo.he" },
+            "position": { "line": 0, "character": 0 },
+        });
+
+        let response = server.send_request(method, params);
+        response.error.map(|error| error.code)
+    }
+
+    #[test]
+    fn test_request_before_initialize() {
+        let mut server = TestServer::uninitialized();
+        let code = error_code(&mut server, "textDocument/hover");
+        assert_eq!(code, Some(ErrorCode::ServerNotInitialized as i32));
+
+        server.initialize(None);
+        assert_eq!(error_code(&mut server, "textDocument/hover"), None);
+
+        let response = server.send_request("initialize", json!({}));
+        let code = response.error.map(|error| error.code);
+        assert_eq!(code, Some(ErrorCode::InvalidRequest as i32));
+    }
+
+    #[test]
+    fn test_unknown_method() {
+        let mut server = TestServer::new();
+        let code = error_code(&mut server, "textDocument/unknown");
+        assert_eq!(code, Some(ErrorCode::MethodNotFound as i32));
+
+        // The server should still be running after an error
+        assert_eq!(error_code(&mut server, "textDocument/hover"), None);
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        let mut server = TestServer::new();
+        let response = server.send_request("textDocument/hover", json!(42));
+        let code = response.error.map(|error| error.code);
+        assert_eq!(code, Some(ErrorCode::InvalidParams as i32));
+
+        // Invalid notifications must be ignored without a response
+        server.send_notification("textDocument/didOpen", json!(null));
+        assert_eq!(error_code(&mut server, "textDocument/hover"), None);
     }
 }
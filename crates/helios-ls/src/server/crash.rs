@@ -0,0 +1,155 @@
+//! Recovering from panics while handling messages.
+//!
+//! A bug in the compiler (or in a handler) shouldn't bring down the whole
+//! server, so every message is handled with [`catch`]. If handling it panics,
+//! a crash report is logged with what caused the panic (its message, where it
+//! happened and for which document) so that the bug can be reproduced, and
+//! the server carries on with the next message.
+
+use crate::protocol::Notification;
+use crate::state::State;
+use helios_query::Input;
+use lsp_types::notification::{Notification as _, ShowMessage};
+use lsp_types::{MessageType, ShowMessageParams, Url};
+use std::backtrace::Backtrace;
+use std::cell::{Cell, RefCell};
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::{self, Display};
+use std::hash::{Hash, Hasher};
+use std::panic::{self, AssertUnwindSafe, PanicHookInfo};
+use std::sync::Once;
+
+thread_local! {
+    /// Whether the current thread is running [`catch`].
+    static CATCHING: Cell<bool> = const { Cell::new(false) };
+    /// The last panic caught on the current thread.
+    static PANIC: RefCell<Option<Panic>> = const { RefCell::new(None) };
+}
+
+/// A panic caught while handling a message.
+#[derive(Debug)]
+pub(crate) struct Panic {
+    pub(crate) message: String,
+    /// Where the panic happened, such as `src/lib.rs:12:5`.
+    pub(crate) location: Option<String>,
+    pub(crate) backtrace: String,
+}
+
+impl Panic {
+    fn new(info: &PanicHookInfo) -> Self {
+        let payload = info.payload();
+        let message = match payload.downcast_ref::<&str>() {
+            Some(message) => message.to_string(),
+            None => match payload.downcast_ref::<String>() {
+                Some(message) => message.clone(),
+                None => "<unknown panic>".to_string(),
+            },
+        };
+
+        Self {
+            message,
+            location: info.location().map(|location| location.to_string()),
+            backtrace: Backtrace::force_capture().to_string(),
+        }
+    }
+}
+
+impl Display for Panic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if let Some(location) = &self.location {
+            write!(f, " (at {})", location)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Runs the given function, catching the panic it may raise.
+///
+/// Panics caught this way aren't printed by the default panic hook (they are
+/// meant to be [`report`]ed instead), but any other panic still is.
+pub(crate) fn catch<T>(f: impl FnOnce() -> T) -> Result<T, Panic> {
+    static INSTALL_HOOK: Once = Once::new();
+    INSTALL_HOOK.call_once(|| {
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if CATCHING.with(Cell::get) {
+                PANIC
+                    .with(|panic| *panic.borrow_mut() = Some(Panic::new(info)));
+            } else {
+                default_hook(info);
+            }
+        }));
+    });
+
+    let was_catching = CATCHING.with(|catching| catching.replace(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    CATCHING.with(|catching| catching.set(was_catching));
+
+    result.map_err(|_| {
+        PANIC
+            .with(|panic| panic.borrow_mut().take())
+            .unwrap_or(Panic {
+                message: "<unknown panic>".to_string(),
+                location: None,
+                backtrace: String::new(),
+            })
+    })
+}
+
+/// The document the params of a message are about, if any.
+pub(crate) fn document_of(params: &serde_json::Value) -> Option<Url> {
+    let uri = params
+        .pointer("/textDocument/uri")
+        .or_else(|| params.pointer("/item/uri"))?;
+
+    serde_json::from_value(uri.clone()).ok()
+}
+
+/// Logs a crash report for the given panic, which happened while handling the
+/// message with the given method (about the given document, if any), and
+/// tells the user about it.
+pub(crate) fn report(
+    state: &mut State,
+    method: &str,
+    document: Option<&Url>,
+    panic: &Panic,
+) {
+    let mut report =
+        format!("Crash report\n  method: {}\n  panic: {}\n", method, panic);
+
+    if let Some(uri) = document {
+        report.push_str(&format!("  document: {}\n", uri));
+
+        // The hash tells if a report is about the same contents of the
+        // document as another (without including the contents themselves)
+        let path = super::handlers::notifications::document_path(uri);
+        let file_id = state.host.file_id(path);
+        if state.loaded_files.contains(&file_id) {
+            let mut hasher = DefaultHasher::new();
+            state.host.raw_database().source(file_id).hash(&mut hasher);
+            report.push_str(&format!(
+                "  source hash: {:016x}\n",
+                hasher.finish()
+            ));
+        }
+    }
+
+    report.push_str(&format!("  backtrace:\n{}", panic.backtrace));
+    log::error!("{}", report);
+
+    let message = format!(
+        "Helios-LS crashed while handling `{}`: {}. Please report this bug \
+         along with the server's log.",
+        method, panic.message
+    );
+
+    state.send(Notification::new(
+        ShowMessage::METHOD,
+        ShowMessageParams {
+            typ: MessageType::Error,
+            message,
+        },
+    ));
+}
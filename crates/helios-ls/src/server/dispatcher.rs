@@ -1,7 +1,12 @@
-use crate::protocol::{Notification, Request, RequestId, Response};
+use super::crash::{self, Panic};
+use crate::error::LspError;
+use crate::logger;
+use crate::protocol::{ErrorCode, Notification, Request, RequestId, Response};
 use crate::state::{State, StateSnapshot};
 use crate::Result;
+use lsp_types::Url;
 use serde::{de::DeserializeOwned, Serialize};
+use std::time::Instant;
 
 pub struct RequestDispatcher<'a> {
     request: Option<Request>,
@@ -19,49 +24,121 @@ impl<'a> RequestDispatcher<'a> {
         }
     }
 
+    /// Invokes the given handler if the request is of type `R`.
+    ///
+    /// The result of the handler is sent back to the client. If the handler
+    /// fails (or if the request's params are invalid), an error response is
+    /// sent instead.
+    ///
+    /// The handler runs on the current thread, before any other message is
+    /// handled, so no change is applied to the snapshot's database while it
+    /// reads it.
     pub fn on<R>(
         &mut self,
         handler: fn(StateSnapshot, R::Params) -> Result<R::Result>,
-    ) -> Result<&mut Self>
+    ) -> &mut Self
     where
         R: lsp_types::request::Request + 'static,
         R::Params: DeserializeOwned + Send + 'static,
         R::Result: Serialize + std::fmt::Debug + 'static,
     {
-        let (id, params) = match self.parse_request::<R>() {
+        let (id, document, params) = match self.parse_request::<R>() {
             Some(it) => it,
-            _ => return Ok(self),
+            _ => return self,
         };
 
-        // let snapshot = self.state.snapshot();
-        // let handle_response: std::thread::JoinHandle<Result<Response>> =
-        //     std::thread::spawn(move || {
-        //         log::trace!("Invoking response handler in separate thread...");
-        //         let result = handler(snapshot, params)?;
-        //         let response = Response::new_ok(id, result);
-
-        //         Ok(response)
-        //     });
-
-        // let response = handle_response.join().expect("Failed to join")?;
-        // log::trace!("Sending response: {:?}", response);
-        // self.state.send(response);
+        let params = match params {
+            Ok(params) => params,
+            Err(error) => {
+                self.respond_with_error(id, error);
+                return self;
+            }
+        };
 
+        let start = Instant::now();
         let snapshot = self.state.snapshot();
-        let result = handler(snapshot, params)?;
-        let response = Response::new_ok(id, result);
-        self.state.send(response);
+        let result = crash::catch(|| handler(snapshot, params));
+        logger::log_duration(R::METHOD, start);
+        self.respond(id, R::METHOD, document, result);
+        self
+    }
+
+    /// Invokes the given handler if the request is of type `R`, like
+    /// [`on`](Self::on), but with mutable access to the state.
+    ///
+    /// This is only meant for the requests that change the state of the
+    /// server (such as the commands executed on behalf of the client).
+    pub fn on_mut<R>(
+        &mut self,
+        handler: fn(&mut State, R::Params) -> Result<R::Result>,
+    ) -> &mut Self
+    where
+        R: lsp_types::request::Request + 'static,
+        R::Params: DeserializeOwned + Send + 'static,
+        R::Result: Serialize + std::fmt::Debug + 'static,
+    {
+        let (id, document, params) = match self.parse_request::<R>() {
+            Some((id, document, Ok(params))) => (id, document, params),
+            Some((id, _, Err(error))) => {
+                self.respond_with_error(id, error);
+                return self;
+            }
+            None => return self,
+        };
 
-        Ok(self)
+        let start = Instant::now();
+        let result = crash::catch(|| handler(self.state, params));
+        logger::log_duration(R::METHOD, start);
+        self.respond(id, R::METHOD, document, result);
+        self
     }
 
+    /// Responds with a [`LspError::method_not_found`] error if the request
+    /// has not been handled by any of the handlers.
     pub fn finish(&mut self) {
         if let Some(request) = self.request.take() {
             log::warn!("Unhandled request: {:?}", request);
+            let error = LspError::method_not_found(&request.method);
+            self.respond_with_error(request.id, error);
+        }
+    }
+
+    /// Responds with the result of the request with the given id, method and
+    /// document, reporting the panic that happened while handling it (if
+    /// any).
+    fn respond<T>(
+        &mut self,
+        id: RequestId,
+        method: &str,
+        document: Option<Url>,
+        result: Result<Result<T>, Panic>,
+    ) where
+        T: Serialize + std::fmt::Debug,
+    {
+        match result {
+            Ok(Ok(result)) => self.state.send(Response::new_ok(id, result)),
+            Ok(Err(error)) => {
+                let error = LspError::from_error(error);
+                self.respond_with_error(id, error);
+            }
+            Err(panic) => {
+                crash::report(self.state, method, document.as_ref(), &panic);
+                let message = format!("the server panicked: {}", panic.message);
+                let error = LspError::new(ErrorCode::InternalError, message);
+                self.respond_with_error(id, error);
+            }
         }
     }
 
-    fn parse_request<R>(&mut self) -> Option<(RequestId, R::Params)>
+    fn respond_with_error(&mut self, id: RequestId, error: LspError) {
+        log::error!("Failed to handle request {}: {}", id, error);
+        self.state.send(Response::new_lsp_error(id, error));
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn parse_request<R>(
+        &mut self,
+    ) -> Option<(RequestId, Option<Url>, Result<R::Params, LspError>)>
     where
         R: lsp_types::request::Request,
     {
@@ -72,8 +149,10 @@ impl<'a> RequestDispatcher<'a> {
             _ => return None,
         };
 
-        let params = serde_json::from_value(request.params).unwrap();
-        Some((request.id, params))
+        let document = crash::document_of(&request.params);
+        let params = serde_json::from_value(request.params)
+            .map_err(LspError::invalid_params);
+        Some((request.id, document, params))
     }
 }
 
@@ -93,17 +172,31 @@ impl<'a> NotificationDispatcher<'a> {
         }
     }
 
+    /// Invokes the given handler if the notification is of type `N`.
+    ///
+    /// Notifications cannot be responded to, so notifications with invalid
+    /// params are logged and ignored.
     pub fn on<N>(&mut self, handler: fn(&mut State, N::Params)) -> &mut Self
     where
         N: lsp_types::notification::Notification + 'static,
         N::Params: DeserializeOwned + Send + 'static,
     {
-        let params = match self.parse_notification::<N>() {
-            Some(it) => it,
-            _ => return self,
+        let (document, params) = match self.parse_notification::<N>() {
+            Some((document, Ok(params))) => (document, params),
+            Some((_, Err(error))) => {
+                log::error!("Failed to handle {:?}: {}", N::METHOD, error);
+                return self;
+            }
+            None => return self,
         };
 
-        handler(self.state, params);
+        let start = Instant::now();
+        let result = crash::catch(|| handler(self.state, params));
+        logger::log_duration(N::METHOD, start);
+
+        if let Err(panic) = result {
+            crash::report(self.state, N::METHOD, document.as_ref(), &panic);
+        }
 
         self
     }
@@ -114,7 +207,10 @@ impl<'a> NotificationDispatcher<'a> {
         }
     }
 
-    fn parse_notification<N>(&mut self) -> Option<N::Params>
+    #[allow(clippy::type_complexity)]
+    fn parse_notification<N>(
+        &mut self,
+    ) -> Option<(Option<Url>, Result<N::Params, LspError>)>
     where
         N: lsp_types::notification::Notification,
     {
@@ -125,7 +221,56 @@ impl<'a> NotificationDispatcher<'a> {
             _ => return None,
         };
 
-        let params = serde_json::from_value(notification.params).unwrap();
-        Some(params)
+        let document = crash::document_of(&notification.params);
+        let params = serde_json::from_value(notification.params)
+            .map_err(LspError::invalid_params);
+        Some((document, params))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::Message;
+    use crate::trace::Tracer;
+    use lsp_types::request::HoverRequest;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_panicking_handler() {
+        let (sender, receiver) = flume::unbounded();
+        let mut state = State::new(sender, Arc::new(Tracer::disabled()));
+
+        let params = json!({
+            "textDocument": { "uri": "file:///Foo.he" },
+            "position": { "line": 0, "character": 0 },
+        });
+
+        for id in 0..2 {
+            let request = Request::new(id, "textDocument/hover", &params);
+            RequestDispatcher::new(request, &mut state)
+                .on::<HoverRequest>(|_, _| panic!("oh no"))
+                .finish();
+
+            // The user is told about the crash...
+            let message = match receiver.try_recv() {
+                Ok(Message::Notification(notification)) => notification,
+                message => panic!("Unexpected message: {:?}", message),
+            };
+
+            assert_eq!(message.method, "window/showMessage");
+            let text = message.params["message"].as_str().unwrap();
+            assert!(text.contains("`textDocument/hover`: oh no"));
+
+            // ...and the request is still responded to
+            let error = match receiver.try_recv() {
+                Ok(Message::Response(response)) => response.error.unwrap(),
+                message => panic!("Unexpected message: {:?}", message),
+            };
+
+            assert_eq!(error.code, ErrorCode::InternalError as i32);
+            assert_eq!(error.message, "the server panicked: oh no");
+        }
     }
 }
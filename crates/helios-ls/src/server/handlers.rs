@@ -1,8 +1,10 @@
 use crate::Result;
 use lsp_types::*;
 
+pub mod commands;
 pub mod notifications;
 pub mod requests;
 
+pub use commands::execute_command;
 pub use notifications::*;
 pub use requests::*;
@@ -0,0 +1,223 @@
+//! The commands the server executes on behalf of the client (with the
+//! `workspace/executeCommand` request), which editors can show as actions.
+
+use super::*;
+use crate::error::LspError;
+use crate::protocol::ErrorCode;
+use crate::state::{State, StateSnapshot};
+use helios_diagnostics::Severity;
+use helios_query::*;
+use serde_json::{json, Value};
+
+/// Checks that the document given as argument can be run and returns the
+/// path of the program to run.
+pub(crate) const RUN: &str = "helios.run";
+
+/// Returns the syntax tree of the document given as argument.
+pub(crate) const SHOW_SYNTAX_TREE: &str = "helios.showSyntaxTree";
+
+/// Reads the files that aren't open in the editor from the disk again.
+pub(crate) const RELOAD_WORKSPACE: &str = "helios.reloadWorkspace";
+
+/// Every command the server can execute, as advertised in its capabilities.
+pub(crate) const COMMANDS: &[&str] = &[RUN, SHOW_SYNTAX_TREE, RELOAD_WORKSPACE];
+
+/// The workspace/executeCommand request is sent from the client to the server
+/// to trigger command execution on the server.
+pub fn execute_command(
+    state: &mut State,
+    params: ExecuteCommandParams,
+) -> Result<Option<Value>> {
+    log::trace!("Executing {:?}", params.command);
+    match params.command.as_str() {
+        RUN => run(state.snapshot(), &params.arguments),
+        SHOW_SYNTAX_TREE => {
+            show_syntax_tree(state.snapshot(), &params.arguments)
+        }
+        RELOAD_WORKSPACE => reload_workspace(state),
+        command => {
+            let message = format!("unknown command: {:?}", command);
+            Err(LspError::new(ErrorCode::InvalidParams, message).into())
+        }
+    }
+}
+
+/// The loaded document whose URI is the first of the given arguments.
+fn document_argument(
+    snapshot: &StateSnapshot,
+    command: &str,
+    arguments: &[Value],
+) -> Result<(Url, FileId)> {
+    let invalid_params =
+        |message: String| LspError::new(ErrorCode::InvalidParams, message);
+
+    let uri = arguments
+        .first()
+        .and_then(|uri| serde_json::from_value::<Url>(uri.clone()).ok())
+        .ok_or_else(|| {
+            invalid_params(format!("`{}` expects a document URI", command))
+        })?;
+
+    let path = notifications::document_path(&uri);
+    let file_id = snapshot.analysis.file_id(path);
+    if !snapshot.loaded_files.contains(&file_id) {
+        let message = format!("unknown document: {}", uri);
+        return Err(invalid_params(message).into());
+    }
+
+    Ok((uri, file_id))
+}
+
+/// The language server can't run programs itself (it has no terminal to run
+/// them in), so running a document only checks that it can be run: it must
+/// define a `main` binding and have no errors. The path of the program to run
+/// is then sent back for the client to launch it.
+fn run(snapshot: StateSnapshot, arguments: &[Value]) -> Result<Option<Value>> {
+    let (uri, file_id) = document_argument(&snapshot, RUN, arguments)?;
+
+    let items = snapshot.analysis.items(file_id)?;
+    if !items.iter().any(|item| item.name == "main") {
+        let message = format!("{} has no `main` function", uri);
+        return Err(LspError::new(ErrorCode::InvalidParams, message).into());
+    }
+
+    let has_errors = snapshot
+        .analysis
+        .diagnostics(file_id)?
+        .iter()
+        .any(|diagnostic| diagnostic.severity >= Severity::Error);
+
+    if has_errors {
+        let message = format!("{} can't run until its errors are fixed", uri);
+        return Err(LspError::new(ErrorCode::InvalidRequest, message).into());
+    }
+
+    let path = snapshot.analysis.file_path(file_id);
+    Ok(Some(json!({ "program": path })))
+}
+
+fn show_syntax_tree(
+    snapshot: StateSnapshot,
+    arguments: &[Value],
+) -> Result<Option<Value>> {
+    let (_, file_id) =
+        document_argument(&snapshot, SHOW_SYNTAX_TREE, arguments)?;
+    let tree = snapshot.analysis.parse(file_id)?.debug_tree();
+    Ok(Some(Value::String(tree)))
+}
+
+/// Reloads every loaded file from the disk (the documents open in the editor
+/// are kept as they are) and publishes the diagnostics of the files that
+/// changed (or asks the client to pull them again).
+fn reload_workspace(state: &mut State) -> Result<Option<Value>> {
+    for file_id in state.loaded_files.iter() {
+        let path = state.host.file_path(*file_id);
+        state.vfs.reload_file(&path);
+    }
+
+    for file_id in state.apply_vfs_changes() {
+        let path = state.host.file_path(file_id);
+        if let Some(uri) = notifications::document_uri(&path) {
+            notifications::publish_diagnostics(state, file_id, uri, None);
+        }
+    }
+
+    notifications::refresh_diagnostics(state);
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_server::TestServer;
+    use lsp_types::notification::*;
+    use lsp_types::*;
+    use serde_json::{json, Value};
+
+    fn execute(
+        server: &mut TestServer,
+        command: &str,
+        arguments: Vec<Value>,
+    ) -> crate::protocol::Response {
+        server.send_request(
+            "workspace/executeCommand",
+            ExecuteCommandParams {
+                command: command.to_string(),
+                arguments,
+                work_done_progress_params: WorkDoneProgressParams::default(),
+            },
+        )
+    }
+
+    #[test]
+    fn test_unknown_command() {
+        let mut server = TestServer::new();
+        let response = execute(&mut server, "helios.unknown", vec![]);
+        assert!(response.error.is_some());
+    }
+
+    #[test]
+    fn test_run() {
+        let mut server = TestServer::new();
+
+        let uri = server.open("file:///Main.he", "let main = 1\n");
+        let response = execute(&mut server, "helios.run", vec![json!(uri)]);
+        assert_eq!(response.result, Some(json!({ "program": "/Main.he" })));
+
+        let uri = server.open("file:///Main.he", "let main = \n");
+        let response = execute(&mut server, "helios.run", vec![json!(uri)]);
+        assert!(response.error.is_some());
+
+        let uri = server.open("file:///Lib.he", "let a = 1\n");
+        let response = execute(&mut server, "helios.run", vec![json!(uri)]);
+        assert!(response.error.is_some());
+
+        let response = execute(&mut server, "helios.run", vec![]);
+        assert!(response.error.is_some());
+    }
+
+    #[test]
+    fn test_show_syntax_tree() {
+        let mut server = TestServer::new();
+        let uri = server.open("file:///Foo.he", "let a = 1");
+
+        let response =
+            execute(&mut server, "helios.showSyntaxTree", vec![json!(uri)]);
+        let tree = response.result.unwrap();
+        let tree = tree.as_str().unwrap();
+        assert!(tree.starts_with("Root@0..9\n"));
+        assert!(tree.contains("Dec_GlobalBinding@0..9\n"));
+
+        let uri = json!("file:///Unknown.he");
+        let response = execute(&mut server, "helios.showSyntaxTree", vec![uri]);
+        assert!(response.error.is_some());
+    }
+
+    #[test]
+    fn test_reload_workspace() {
+        let mut server = TestServer::new();
+
+        let dir = std::env::temp_dir()
+            .join(format!("helios-ls-reload-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("Foo.he");
+        let uri = Url::from_file_path(&path).unwrap();
+
+        // Load the file from the disk, as if it had been opened and closed
+        std::fs::write(&path, "let a = 1").unwrap();
+        server.open(uri.as_str(), "let a = 1");
+        server.expect_notification::<PublishDiagnostics>();
+        server.notify::<DidCloseTextDocument>(DidCloseTextDocumentParams {
+            text_document: TextDocumentIdentifier::new(uri.clone()),
+        });
+
+        std::fs::write(&path, "let = 1").unwrap();
+        let response = execute(&mut server, "helios.reloadWorkspace", vec![]);
+        assert_eq!(response.result, Some(Value::Null));
+
+        let params = server.expect_notification::<PublishDiagnostics>();
+        assert_eq!(params.uri, uri);
+        assert_eq!(params.diagnostics.len(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,26 +1,91 @@
 use super::*;
+use crate::config::Config;
+use crate::proposed::WorkspaceDiagnosticRefresh;
 use crate::protocol::Notification;
 use crate::state::State;
+use crate::trace::SetTraceParams;
 use helios_diagnostics::{Diagnostic as HDiagnostic, Severity};
+use helios_parser::lints;
 use helios_query::input::FileId;
 use helios_query::*;
+use helios_text::Rope;
+use std::collections::HashMap;
 use std::ops::Range;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Instant;
 
-// FIXME: See `positions_from_range`.
-fn publish_diagnostics(
+/// Publishes the diagnostics of the given file, unless the client pulls them
+/// instead.
+pub(crate) fn publish_diagnostics(
     state: &mut State,
     file_id: FileId,
     uri: Url,
     version: Option<i32>,
 ) {
+    if state.can_pull_diagnostics {
+        return;
+    }
+
+    let db = state.host.raw_database();
+    let diagnostics = lsp_diagnostics(db, &state.config, file_id, &uri);
+    let params = PublishDiagnosticsParams {
+        uri,
+        version,
+        diagnostics,
+    };
+
+    state.send(Notification::new("textDocument/publishDiagnostics", params));
+}
+
+/// Publishes the diagnostics of the changed documents that are due (see
+/// [`Scheduler`]), recording their revisions and computing the queries that
+/// requests need for them in the background as well.
+///
+/// [`Scheduler`]: crate::scheduler::Scheduler
+pub(crate) fn publish_due_diagnostics(state: &mut State) {
+    for (file_id, pending) in state.scheduler.take_due(Instant::now()) {
+        state.record_revision(file_id, pending.version);
+        publish_diagnostics(state, file_id, pending.uri, pending.version);
+        state.revalidate_in_background(file_id);
+    }
+}
+
+/// Asks the client to pull the diagnostics of its documents again, if it
+/// pulls them (after changes it doesn't know about, such as those of files
+/// changed on the disk).
+pub(crate) fn refresh_diagnostics(state: &mut State) {
+    if state.can_pull_diagnostics && state.can_refresh_diagnostics {
+        use lsp_types::request::Request as _;
+        state.send_request(WorkspaceDiagnosticRefresh::METHOD, ());
+    }
+}
+
+/// The diagnostics of the given file, at the levels and above the severity
+/// threshold set in the given configuration.
+pub(crate) fn lsp_diagnostics(
+    db: &HeliosDatabase,
+    config: &Config,
+    file_id: FileId,
+    uri: &Url,
+) -> Vec<Diagnostic> {
     let mut emitted_ranges = Vec::new();
     let mut diagnostics = Vec::new();
-    let h_diagnostics: Arc<Vec<HDiagnostic<_>>> = state.db.diagnostics(file_id);
+    let h_diagnostics: Arc<Vec<HDiagnostic<_>>> = db.diagnostics(file_id);
+    let lint_levels = config.diagnostics.lint_levels();
 
     for h_diagnostic in h_diagnostics.iter() {
+        let h_diagnostic = match lint_levels.apply(h_diagnostic.clone()) {
+            Some(h_diagnostic) => h_diagnostic,
+            None => continue,
+        };
+
+        if !config.diagnostics.allows(h_diagnostic.severity) {
+            continue;
+        }
+
         let (start, end) = positions_from_range(
-            state,
+            db,
             file_id,
             h_diagnostic.location.range.clone(),
         );
@@ -33,60 +98,174 @@ fn publish_diagnostics(
             emitted_ranges.push(range);
         }
 
-        let source = Some("helios-ls".to_string());
-        let message = format!("{}", h_diagnostic.title);
-        let related_message = format!("{}", h_diagnostic.message);
+        diagnostics.push(lsp_diagnostic(db, uri, range, &h_diagnostic));
+    }
 
-        let severity = Some(match h_diagnostic.severity {
-            Severity::Bug | Severity::Error => DiagnosticSeverity::Error,
-            Severity::Warning => DiagnosticSeverity::Warning,
-            Severity::Note => DiagnosticSeverity::Information,
-        });
+    diagnostics
+}
 
-        let related_information = Some(vec![DiagnosticRelatedInformation {
-            location: Location::new(uri.clone(), range),
-            message: related_message.trim_end().to_string(),
-        }]);
+/// Converts the given diagnostic (of the document with the given URI and at
+/// the given range in it) to an LSP diagnostic.
+///
+/// Its labels become its related information, which may be in other files.
+fn lsp_diagnostic(
+    db: &HeliosDatabase,
+    uri: &Url,
+    range: lsp_types::Range,
+    h_diagnostic: &HDiagnostic<FileId>,
+) -> Diagnostic {
+    let severity = Some(match h_diagnostic.severity {
+        Severity::Bug | Severity::Error => DiagnosticSeverity::Error,
+        Severity::Warning => DiagnosticSeverity::Warning,
+        Severity::Note => DiagnosticSeverity::Information,
+    });
+
+    let related_message = h_diagnostic.message.plain().to_string();
+    let mut related_information = vec![DiagnosticRelatedInformation {
+        location: Location::new(uri.clone(), range),
+        message: related_message.trim_end().to_string(),
+    }];
+
+    for label in &h_diagnostic.labels {
+        let label_file_id = label.location.file_id;
+        let label_uri = if label_file_id == h_diagnostic.location.file_id {
+            uri.clone()
+        } else {
+            let path = db.lookup_intern_file_path(label_file_id);
+            match document_uri(&path) {
+                Some(uri) => uri,
+                None => continue,
+            }
+        };
 
-        diagnostics.push(Diagnostic {
-            range,
-            source,
-            message,
-            severity,
-            related_information,
-            ..Diagnostic::default()
-        })
-    }
+        let (start, end) = positions_from_range(
+            db,
+            label_file_id,
+            label.location.range.clone(),
+        );
 
-    let params = PublishDiagnosticsParams {
-        uri,
-        version,
-        diagnostics,
-    };
+        related_information.push(DiagnosticRelatedInformation {
+            location: Location::new(
+                label_uri,
+                lsp_types::Range::new(start, end),
+            ),
+            message: label.message.plain().to_string().trim_end().to_string(),
+        });
+    }
 
-    state.send(Notification::new("textDocument/publishDiagnostics", params));
+    let code_description = h_diagnostic
+        .code
+        .and_then(lints::documentation_url)
+        .and_then(|url| Url::parse(&url).ok())
+        .map(|href| CodeDescription { href });
+
+    Diagnostic {
+        range,
+        severity,
+        code: h_diagnostic
+            .code
+            .map(|code| NumberOrString::String(code.to_string())),
+        code_description,
+        source: Some("helios-ls".to_string()),
+        message: h_diagnostic.title.to_string(),
+        related_information: Some(related_information),
+        ..Diagnostic::default()
+    }
 }
 
-// FIXME: These positions assume a UTF-8 input, which the LSP does not provide.
+/// The LSP positions of the given range, whose characters are UTF-16 code
+/// units.
 fn positions_from_range(
-    state: &mut State,
+    db: &HeliosDatabase,
     file_id: FileId,
     range: Range<usize>,
 ) -> (Position, Position) {
-    let (s_ln, s_cl) = state.db.source_position_at_offset(file_id, range.start);
-    let (e_ln, e_cl) = state.db.source_position_at_offset(file_id, range.end);
+    let lines = db.source_lines(file_id);
+    let start = lines.utf16_line_col(range.start);
+    let end = lines.utf16_line_col(range.end);
 
-    let start = Position::new(s_ln as u32, s_cl as u32);
-    let end = Position::new(e_ln as u32, e_cl as u32);
+    let start = Position::new(start.line as u32, start.col as u32);
+    let end = Position::new(end.line as u32, end.col as u32);
 
     (start, end)
 }
 
+/// The cancel notification is sent from the client to the server to cancel a
+/// request.
+///
+/// Requests are currently handled one after the other on the main loop, so
+/// the request has always been responded to by the time we receive this
+/// notification.
+pub fn cancel_request(_: &mut State, params: CancelParams) {
+    log::trace!("Ignoring cancellation of request {:?}", params.id);
+}
+
+/// The set trace notification is sent from the client to the server to modify
+/// the trace setting of the server.
+pub fn set_trace(state: &mut State, params: SetTraceParams) {
+    state.tracer.set_level(params.value);
+}
+
 /// The initialized notification is sent from the client to the server after
 /// the client received the result of the `initialize` request but before the
 /// client is sending any other request or notification to the server.
-pub fn initialized(_: &mut State, _: InitializedParams) {
+pub fn initialized(state: &mut State, _: InitializedParams) {
     log::trace!("Successfully initialized");
+
+    // Files can be changed outside of the editor (by checking out a branch,
+    // for example), which we'll only know about if the client tells us
+    if state.can_watch_files {
+        let options = DidChangeWatchedFilesRegistrationOptions {
+            watchers: vec![FileSystemWatcher {
+                glob_pattern: "**/*.he".to_string(),
+                kind: None,
+            }],
+        };
+
+        let params = RegistrationParams {
+            registrations: vec![Registration {
+                id: "helios/watchFiles".to_string(),
+                method: "workspace/didChangeWatchedFiles".to_string(),
+                register_options: serde_json::to_value(options).ok(),
+            }],
+        };
+
+        state.send_request("client/registerCapability", params);
+    }
+}
+
+/// The configuration change notification is sent from the client to the
+/// server to signal the change of configuration settings.
+pub fn did_change_configuration(
+    state: &mut State,
+    params: DidChangeConfigurationParams,
+) {
+    state.update_config(&params.settings);
+
+    // The lint levels or the severity threshold may have changed
+    refresh_diagnostics(state);
+}
+
+/// The path of the document with the given URI.
+///
+/// Documents that aren't saved on disk (such as `untitled:` documents) are
+/// identified by their URI instead of a file path.
+pub(crate) fn document_path(uri: &Url) -> PathBuf {
+    uri.to_file_path()
+        .unwrap_or_else(|_| PathBuf::from(uri.as_str()))
+}
+
+/// The URI of the document at the given path (the inverse of
+/// [`document_path`]).
+pub(crate) fn document_uri(path: &Path) -> Option<Url> {
+    Url::from_file_path(path)
+        .or_else(|_| Url::parse(path.to_str().unwrap_or_default()))
+        .ok()
+}
+
+/// The [`FileId`] of the document with the given URI.
+fn file_id(state: &mut State, uri: &Url) -> FileId {
+    state.host.file_id(document_path(uri))
 }
 
 /// The document open notification is sent from the client to the server to
@@ -96,89 +275,131 @@ pub fn did_open_text_document(
     state: &mut State,
     params: DidOpenTextDocumentParams,
 ) {
-    // We need to generate a new file id when we open a document
-    let file_id = FileId(0);
-    let source = Arc::new(params.text_document.text);
-    state.db.set_source(file_id, source);
-
-    publish_diagnostics(
-        state,
-        file_id,
-        params.text_document.uri,
-        Some(params.text_document.version),
-    );
+    let uri = params.text_document.uri;
+    state
+        .vfs
+        .set_file(document_path(&uri), params.text_document.text);
+    state.apply_vfs_changes();
+
+    let file_id = file_id(state, &uri);
+    let version = Some(params.text_document.version);
+    state.scheduler.cancel(file_id);
+    state.record_revision(file_id, version);
+    publish_diagnostics(state, file_id, uri, version);
 }
 
 /// The document change notification is sent from the client to the server to
 /// signal changes to a text document.
+///
+/// The changes are applied right away, but the diagnostics of the document are
+/// only published once it stops changing (see [`publish_due_diagnostics`]).
 pub fn did_change_text_document(
     state: &mut State,
     params: DidChangeTextDocumentParams,
 ) {
-    let file_id = FileId(0);
-    let old_source: Arc<String> = state.db.source(file_id);
-    let new_source = apply_content_changes(&old_source, params.content_changes);
-
-    log::trace!("New source: {:?}", new_source);
-    state.db.set_source(file_id, Arc::new(new_source));
-
-    publish_diagnostics(
-        state,
-        file_id,
-        params.text_document.uri,
-        Some(params.text_document.version),
-    );
+    let uri = params.text_document.uri;
+    let file_id = file_id(state, &uri);
+    let mut source = state.host.raw_database().source(file_id);
+    apply_content_changes(&mut source, params.content_changes);
+
+    log::trace!("New source: {:?}", source);
+    state.vfs.set_file(document_path(&uri), source);
+    state.apply_vfs_changes();
+
+    let version = Some(params.text_document.version);
+    let delay = state.config.diagnostics.delay_duration();
+    state.scheduler.schedule(file_id, uri, version, delay);
+}
+
+/// The document close notification is sent from the client to the server when
+/// the document got closed in the client. The document's contents are read
+/// from the disk again from then on.
+pub fn did_close_text_document(
+    state: &mut State,
+    params: DidCloseTextDocumentParams,
+) {
+    let file_id = file_id(state, &params.text_document.uri);
+    state.scheduler.cancel(file_id);
+    Arc::make_mut(&mut state.good_revisions).remove(&file_id);
+
+    let path = document_path(&params.text_document.uri);
+    state.vfs.remove_file(&path);
+    state.apply_vfs_changes();
+}
+
+/// The watched files notification is sent from the client to the server when
+/// the client detects changes to files watched by the language client.
+///
+/// The files that are open in the editor are managed by the client, so only
+/// the changes to the other files are read from the disk. A file that was
+/// deleted is forgotten, and its diagnostics are cleared.
+pub fn did_change_watched_files(
+    state: &mut State,
+    params: DidChangeWatchedFilesParams,
+) {
+    let mut uris = HashMap::new();
+    for change in params.changes {
+        let path = document_path(&change.uri);
+        state.vfs.reload_file(&path);
+        uris.insert(file_id(state, &change.uri), change.uri);
+    }
+
+    for file_id in state.apply_vfs_changes() {
+        if let Some(uri) = uris.remove(&file_id) {
+            state.record_revision(file_id, None);
+            publish_diagnostics(state, file_id, uri, None);
+        }
+    }
+
+    // The files that weren't updated couldn't be read, unless they are held
+    // in memory
+    for (file_id, uri) in uris {
+        if !state.vfs.is_file(&document_path(&uri)) {
+            Arc::make_mut(&mut state.loaded_files).remove(&file_id);
+            Arc::make_mut(&mut state.good_revisions).remove(&file_id);
+            if !state.can_pull_diagnostics {
+                let params = PublishDiagnosticsParams::new(uri, vec![], None);
+                state.send(Notification::new(
+                    "textDocument/publishDiagnostics",
+                    params,
+                ));
+            }
+        }
+    }
+
+    refresh_diagnostics(state);
 }
 
-/// Applies `TextDocumentContentChangeEvent` changes over a provided string,
-/// returning a new string with the changes applied.
+/// Applies the given changes to the text of a document in place, where each
+/// change only rebuilds the part of the rope it edits (rather than copying
+/// the whole text).
 fn apply_content_changes(
-    old_text: &str,
+    text: &mut Rope,
     content_changes: Vec<TextDocumentContentChangeEvent>,
-) -> String {
-    // LSP encodes character offsets based on a UTF-16 string representation
-    let mut utf16_bytes = old_text.encode_utf16().collect::<Vec<_>>();
-
+) {
     for change in content_changes {
         if let Some(range) = change.range {
-            let edited_range = range_over(&utf16_bytes, range);
-            utf16_bytes.splice(edited_range, change.text.encode_utf16());
+            let edited_range = range_over(text, range);
+            text.replace(edited_range, &change.text);
         } else {
             // If no range is given, the user has replaced all the characters
             // in the file with the given text
-            utf16_bytes.clear();
-            utf16_bytes.extend(change.text.encode_utf16());
+            *text = Rope::from(change.text);
         }
     }
-
-    // For now we'll ignore invalid characters
-    String::from_utf16_lossy(&utf16_bytes)
 }
 
-/// Calculates the byte offset range over a UTF-16-encoded string (in bytes)
-/// from the given [`lsp_types::Range`].
-fn range_over(bytes: &Vec<u16>, range: lsp_types::Range) -> Range<usize> {
-    let (s_l, s_c) = (range.start.line, range.start.character);
-    let (e_l, e_c) = (range.end.line, range.end.character);
-
-    fn line_indices(bytes: &Vec<u16>) -> Vec<usize> {
-        std::iter::once(0)
-            .chain(
-                bytes
-                    .iter()
-                    .copied()
-                    .enumerate()
-                    .filter(|(_, byte)| *byte == b'\n' as u16)
-                    .map(|(i, _)| i + 1),
-            )
-            .collect()
-    }
-
-    let indices = line_indices(bytes);
-    let start = indices[s_l as usize] + s_c as usize;
-    let end = indices[e_l as usize] + e_c as usize;
+/// Calculates the byte offset range in the given text of the given
+/// [`lsp_types::Range`], whose characters are UTF-16 code units. Positions
+/// past the end of a line (or of the text) are clamped to its end.
+fn range_over(text: &Rope, range: lsp_types::Range) -> Range<usize> {
+    let offset = |position: lsp_types::Position| {
+        let (line, col) = (position.line as usize, position.character as usize);
+        text.utf16_offset(line, col).unwrap_or_else(|| text.len())
+    };
 
-    start..end
+    let start = offset(range.start);
+    start..std::cmp::max(start, offset(range.end))
 }
 
 /// The document save notification is sent from the client to the server when
@@ -190,6 +411,354 @@ pub fn did_save_text_document(_: &mut State, _: DidSaveTextDocumentParams) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_server::TestServer;
+    use lsp_types::notification::{
+        DidChangeConfiguration, DidChangeTextDocument, DidChangeWatchedFiles,
+        PublishDiagnostics,
+    };
+
+    #[test]
+    fn test_refresh_diagnostics_requests_have_unique_ids() {
+        use crate::protocol::Message;
+        use crate::trace::Tracer;
+
+        let (sender, receiver) = flume::unbounded();
+        let mut state = State::new(sender, Arc::new(Tracer::disabled()));
+        state.can_pull_diagnostics = true;
+        state.can_refresh_diagnostics = true;
+
+        refresh_diagnostics(&mut state);
+        refresh_diagnostics(&mut state);
+
+        let ids = receiver
+            .try_iter()
+            .map(|message| match message {
+                Message::Request(request) => {
+                    assert_eq!(request.method, "workspace/diagnostic/refresh");
+                    request.id
+                }
+                message => panic!("Unexpected message: {:?}", message),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(ids.len(), 2);
+        assert_ne!(ids[0], ids[1]);
+    }
+
+    #[test]
+    fn test_did_open_text_document_publishes_diagnostics() {
+        let mut server = TestServer::new();
+
+        let uri = server.open("file:///Foo.he", "let a = 1");
+        let params = server.expect_notification::<PublishDiagnostics>();
+        assert_eq!(params.uri, uri);
+        assert!(params.diagnostics.is_empty());
+
+        let uri = server.open("file:///Foo.he", "let = 1");
+        let params = server.expect_notification::<PublishDiagnostics>();
+        assert_eq!(params.uri, uri);
+        assert_eq!(params.diagnostics.len(), 1);
+
+        let diagnostic = &params.diagnostics[0];
+        assert_eq!(diagnostic.message, "Missing identifier");
+        assert_eq!(diagnostic.severity, Some(DiagnosticSeverity::Error));
+        assert_eq!(diagnostic.range.start, Position::new(0, 4));
+    }
+
+    #[test]
+    fn test_diagnostic_columns_are_utf16() {
+        let mut server = TestServer::new();
+
+        // `🍕` is four bytes long, but only two UTF-16 code units
+        server.open("file:///Foo.he", "let a = \"🍕\" let = 1");
+        let params = server.expect_notification::<PublishDiagnostics>();
+        assert_eq!(params.diagnostics.len(), 1);
+        assert_eq!(params.diagnostics[0].range.start, Position::new(0, 17));
+    }
+
+    #[test]
+    fn test_diagnostic_labels_are_related_information() {
+        let mut server = TestServer::new();
+
+        server.open("file:///Foo.he", "let a = 1\nlet a = 2");
+        let params = server.expect_notification::<PublishDiagnostics>();
+        assert_eq!(params.diagnostics.len(), 1);
+
+        let diagnostic = &params.diagnostics[0];
+        assert_eq!(diagnostic.message, "Duplicate definition");
+        assert_eq!(diagnostic.range.start, Position::new(1, 4));
+
+        let related_information = diagnostic.related_information.as_ref();
+        let label = &related_information.unwrap()[1];
+        assert_eq!(label.location.range.start, Position::new(0, 4));
+        assert_eq!(label.message, "It was first defined here.");
+    }
+
+    #[test]
+    fn test_did_change_text_document_publishes_diagnostics() {
+        let mut server = TestServer::new();
+
+        let uri = server.open("file:///Foo.he", "let a = 1");
+        server.expect_notification::<PublishDiagnostics>();
+
+        server.notify::<DidChangeTextDocument>(DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier::new(uri, 1),
+            content_changes: vec![TextDocumentContentChangeEvent {
+                range: Some(lsp_types::Range::new(
+                    Position::new(0, 4),
+                    Position::new(0, 5),
+                )),
+                range_length: None,
+                text: String::new(),
+            }],
+        });
+
+        let params = server.expect_notification::<PublishDiagnostics>();
+        assert_eq!(params.version, Some(1));
+        assert_eq!(params.diagnostics.len(), 1);
+    }
+
+    #[test]
+    fn test_did_change_text_document_is_debounced() {
+        use crate::extensions::{SyntaxTree, SyntaxTreeParams};
+
+        let mut server = TestServer::new();
+
+        let uri = server.open("file:///Foo.he", "let a = 1");
+        server.expect_notification::<PublishDiagnostics>();
+
+        let change = |version, text: &str| DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier::new(
+                uri.clone(),
+                version,
+            ),
+            content_changes: vec![TextDocumentContentChangeEvent {
+                range: None,
+                range_length: None,
+                text: text.to_string(),
+            }],
+        };
+        server.notify::<DidChangeTextDocument>(change(1, "let = 1"));
+        server.notify::<DidChangeTextDocument>(change(2, "let b = 2"));
+
+        // Requests see the latest text before its diagnostics are published
+        let tree = server.request::<SyntaxTree>(SyntaxTreeParams {
+            text_document: TextDocumentIdentifier::new(uri.clone()),
+            range: None,
+        });
+        assert!(tree.contains("\"b\""), "{}", tree);
+
+        // Only the diagnostics of the last change are published
+        let params = server.expect_notification::<PublishDiagnostics>();
+        assert_eq!(params.version, Some(2));
+        assert!(params.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_documents_have_separate_sources() {
+        let mut server = TestServer::new();
+
+        let foo = server.open("file:///Foo.he", "let a = 1");
+        server.expect_notification::<PublishDiagnostics>();
+        server.open("file:///Bar.he", "let = 1");
+        server.expect_notification::<PublishDiagnostics>();
+
+        // An empty edit, so that the diagnostics of `Foo.he` are published
+        // again from its own source (which has no errors)
+        server.notify::<DidChangeTextDocument>(DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier::new(foo.clone(), 1),
+            content_changes: vec![TextDocumentContentChangeEvent {
+                range: Some(lsp_types::Range::new(
+                    Position::new(0, 0),
+                    Position::new(0, 0),
+                )),
+                range_length: None,
+                text: String::new(),
+            }],
+        });
+
+        let params = server.expect_notification::<PublishDiagnostics>();
+        assert_eq!(params.uri, foo);
+        assert!(params.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_watched_files_are_read_from_disk() {
+        let mut server = TestServer::new();
+
+        let dir = std::env::temp_dir()
+            .join(format!("helios-ls-watched-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("Foo.he");
+        let uri = Url::from_file_path(&path).unwrap();
+
+        let mut change = |text: &str, typ: FileChangeType| {
+            std::fs::write(&path, text).unwrap();
+            server.notify::<DidChangeWatchedFiles>(
+                DidChangeWatchedFilesParams {
+                    changes: vec![FileEvent::new(uri.clone(), typ)],
+                },
+            );
+            server.expect_notification::<PublishDiagnostics>()
+        };
+
+        let params = change("let a = 1", FileChangeType::Created);
+        assert_eq!(params.uri, uri);
+        assert!(params.diagnostics.is_empty());
+
+        let params = change("let = 1", FileChangeType::Changed);
+        assert_eq!(params.diagnostics.len(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_deleted_watched_files_are_forgotten() {
+        let mut server = TestServer::new();
+
+        let dir = std::env::temp_dir()
+            .join(format!("helios-ls-deleted-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("Foo.he");
+        let uri = Url::from_file_path(&path).unwrap();
+
+        let mut change = |typ: FileChangeType| {
+            server.notify::<DidChangeWatchedFiles>(
+                DidChangeWatchedFilesParams {
+                    changes: vec![FileEvent::new(uri.clone(), typ)],
+                },
+            );
+            server.expect_notification::<PublishDiagnostics>()
+        };
+
+        std::fs::write(&path, "let = 1").unwrap();
+        let params = change(FileChangeType::Created);
+        assert_eq!(params.diagnostics.len(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        let params = change(FileChangeType::Deleted);
+        assert_eq!(params.uri, uri);
+        assert!(params.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_configuration_filters_diagnostics() {
+        let options = serde_json::json!({
+            "diagnostics": { "severityThreshold": "error" }
+        });
+        let mut server = TestServer::with_initialization_options(options);
+
+        server.open("file:///Foo.he", "let = 1");
+        let params = server.expect_notification::<PublishDiagnostics>();
+        assert_eq!(params.diagnostics.len(), 1);
+
+        let settings = serde_json::json!({
+            "helios": { "diagnostics": { "enable": false } }
+        });
+        server.notify::<DidChangeConfiguration>(DidChangeConfigurationParams {
+            settings,
+        });
+
+        server.open("file:///Foo.he", "let = 1");
+        let params = server.expect_notification::<PublishDiagnostics>();
+        assert!(params.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_configuration_sets_lint_levels() {
+        let options = serde_json::json!({
+            "diagnostics": {
+                "severityThreshold": "error",
+                "lints": { "never-mutated": "deny" },
+            }
+        });
+        let mut server = TestServer::with_initialization_options(options);
+
+        server.open("file:///Foo.he", "var a = 1");
+        let params = server.expect_notification::<PublishDiagnostics>();
+        assert_eq!(params.diagnostics.len(), 1);
+        let diagnostic = &params.diagnostics[0];
+        assert_eq!(diagnostic.severity, Some(DiagnosticSeverity::Error));
+        assert_eq!(
+            diagnostic.code,
+            Some(NumberOrString::String("never-mutated".to_string()))
+        );
+        assert_eq!(
+            diagnostic.code_description.as_ref().unwrap().href.as_str(),
+            "https://github.com/helios-lang/helios/blob/master/docs/lints.md\
+             #never-mutated"
+        );
+
+        let settings = serde_json::json!({
+            "diagnostics": { "lints": { "never-mutated": "allow" } }
+        });
+        server.notify::<DidChangeConfiguration>(DidChangeConfigurationParams {
+            settings,
+        });
+
+        server.open("file:///Foo.he", "var a = 1");
+        let params = server.expect_notification::<PublishDiagnostics>();
+        assert!(params.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_configuration_enables_features() {
+        let mut server = TestServer::new();
+        server.open("file:///Foo.he", "let a = f <| 1");
+        let params = server.expect_notification::<PublishDiagnostics>();
+        assert_eq!(params.diagnostics.len(), 1);
+
+        let settings = serde_json::json!({
+            "helios": { "features": ["backward-pipelines"] }
+        });
+        server.notify::<DidChangeConfiguration>(DidChangeConfigurationParams {
+            settings,
+        });
+
+        server.open("file:///Foo.he", "let a = f <| 1");
+        let params = server.expect_notification::<PublishDiagnostics>();
+        assert!(params.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_related_information_in_other_files() {
+        let mut db = HeliosDatabase::default();
+        let foo = db.intern_file_path(PathBuf::from("/Foo.he"));
+        let bar = db.intern_file_path(PathBuf::from("/Bar.he"));
+        db.set_source(foo, Rope::from("let a = 1\n"));
+        db.set_source(bar, Rope::from("\nlet a = 2\n"));
+
+        let h_diagnostic = HDiagnostic::error("Duplicate definition")
+            .location(helios_diagnostics::Location::new(foo, 4..5))
+            .message("`a` is defined twice.")
+            .label(helios_diagnostics::Location::new(bar, 5..6), "Here");
+
+        let uri = Url::parse("file:///Foo.he").unwrap();
+        let range =
+            lsp_types::Range::new(Position::new(0, 4), Position::new(0, 5));
+        let diagnostic = lsp_diagnostic(&db, &uri, range, &h_diagnostic);
+
+        let related_information = diagnostic.related_information.unwrap();
+        assert_eq!(
+            related_information,
+            vec![
+                DiagnosticRelatedInformation {
+                    location: Location::new(uri, range),
+                    message: "`a` is defined twice.".to_string(),
+                },
+                DiagnosticRelatedInformation {
+                    location: Location::new(
+                        Url::parse("file:///Bar.he").unwrap(),
+                        lsp_types::Range::new(
+                            Position::new(1, 4),
+                            Position::new(1, 5)
+                        ),
+                    ),
+                    message: "Here".to_string(),
+                },
+            ]
+        );
+        assert_eq!(diagnostic.code_description, None);
+    }
 
     #[test]
     fn test_apply_content_changes() {
@@ -225,15 +794,17 @@ mod tests {
                 $changed_text:tt @ $s_l:tt:$s_c:tt => $e_l:tt:$e_c:tt,
                 $expected_text:tt
             ) => {{
-                let new_text = apply_content_changes(
-                    $old_text,
+                let mut new_text = Rope::from($old_text);
+                apply_content_changes(
+                    &mut new_text,
                     changes![$changed_text @ $s_l:$s_c => $e_l:$e_c],
                 );
                 assert_eq!(new_text, $expected_text);
                 $expected_text
             }};
             ($old_text:tt, $changes:expr, $expected_text:tt) => {{
-                let new_text = apply_content_changes($old_text, $changes);
+                let mut new_text = Rope::from($old_text);
+                apply_content_changes(&mut new_text, $changes);
                 assert_eq!(new_text, $expected_text);
                 $expected_text
             }};
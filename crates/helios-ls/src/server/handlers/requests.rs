@@ -1,12 +1,25 @@
 use super::*;
-use crate::state::StateSnapshot;
-// use helios_query::*;
-// use std::sync::Arc;
+use crate::completion::{self, completion_context, CompletionContext, Rank};
+use crate::error::LspError;
+use crate::extensions::SyntaxTreeParams;
+use crate::proposed::{
+    self, DocumentDiagnosticParams, DocumentDiagnosticReport,
+};
+use crate::protocol::ErrorCode;
+use crate::state::{Revision, StateSnapshot};
+use helios_diagnostics::Applicability;
+use helios_query::*;
+use helios_text::LineCol;
+use serde_json::json;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+use std::sync::Arc;
 
 pub fn initialize(
     _: StateSnapshot,
     _: InitializeParams,
-) -> Result<InitializeResult> {
+) -> Result<proposed::InitializeResult> {
     let server_info = ServerInfo {
         name: "Helios-LS".to_string(),
         version: Some(env!("CARGO_PKG_VERSION").into()),
@@ -17,6 +30,19 @@ pub fn initialize(
             TextDocumentSyncKind::Incremental,
         )),
         hover_provider: Some(HoverProviderCapability::Simple(true)),
+        document_highlight_provider: Some(OneOf::Left(true)),
+        call_hierarchy_provider: Some(true.into()),
+        code_lens_provider: Some(CodeLensOptions {
+            resolve_provider: Some(true),
+        }),
+        code_action_provider: Some(true.into()),
+        execute_command_provider: Some(ExecuteCommandOptions {
+            commands: commands::COMMANDS
+                .iter()
+                .map(|command| command.to_string())
+                .collect(),
+            work_done_progress_options: WorkDoneProgressOptions::default(),
+        }),
         completion_provider: Some(CompletionOptions {
             resolve_provider: Some(true),
             trigger_characters: Some(vec![".".into()]),
@@ -27,9 +53,19 @@ pub fn initialize(
         ..ServerCapabilities::default()
     };
 
-    Ok(InitializeResult {
+    // The diagnostics of a file only depend on its own source
+    let diagnostic_provider = proposed::DiagnosticOptions {
+        identifier: None,
+        inter_file_dependencies: false,
+        workspace_diagnostics: false,
+    };
+
+    Ok(proposed::InitializeResult {
         server_info: Some(server_info),
-        capabilities,
+        capabilities: proposed::ServerCapabilities {
+            base: capabilities,
+            diagnostic_provider: Some(diagnostic_provider),
+        },
     })
 }
 
@@ -38,39 +74,1413 @@ pub fn shutdown(_: StateSnapshot, _: ()) -> Result<()> {
     Ok(())
 }
 
+/// The [`FileId`] of the document with the given URI, if it has been loaded.
+fn loaded_file(snapshot: &StateSnapshot, uri: &Url) -> Option<FileId> {
+    let path = notifications::document_path(uri);
+    let file_id = snapshot.analysis.file_id(path);
+    snapshot.loaded_files.contains(&file_id).then_some(file_id)
+}
+
+fn line_col(position: Position) -> LineCol {
+    LineCol::new(position.line as usize, position.character as usize)
+}
+
+/// The byte offset of the given position in a file, if it is within it.
+fn offset(
+    snapshot: &StateSnapshot,
+    file_id: FileId,
+    position: Position,
+) -> Result<Option<usize>> {
+    let lines = snapshot.analysis.source_lines(file_id)?;
+    Ok(lines.try_utf16_offset(line_col(position)).ok())
+}
+
+/// The byte offset of the given position in a file, where positions past the
+/// end of a line (or of the file) are clamped to its end, since editors may
+/// send positions computed before their last edits reached the server.
+fn clamped_offset(
+    snapshot: &StateSnapshot,
+    file_id: FileId,
+    position: Position,
+) -> Result<usize> {
+    let lines = snapshot.analysis.source_lines(file_id)?;
+    let position = lines.clamp_position(line_col(position));
+    let offset = lines.try_utf16_offset(position);
+    Ok(offset.expect("Clamped positions are in the text"))
+}
+
+/// The [`FileId`] and byte offset of the given position in a document
+/// (clamped to the document, see [`clamped_offset`]), if the document has
+/// been loaded.
+fn file_position(
+    snapshot: &StateSnapshot,
+    position: &TextDocumentPositionParams,
+) -> Result<Option<(FileId, usize)>> {
+    let file_id = match loaded_file(snapshot, &position.text_document.uri) {
+        Some(file_id) => file_id,
+        None => return Ok(None),
+    };
+
+    let offset = clamped_offset(snapshot, file_id, position.position)?;
+    Ok(Some((file_id, offset)))
+}
+
+/// The documentation of the given item, as shown by the editor.
+fn item_documentation(item: &Item) -> MarkupContent {
+    let mut value = format!("```helios\n{}\n```", item.signature);
+    if let Some(docs) = &item.docs {
+        value.push_str("\n\n");
+        value.push_str(docs);
+    }
+
+    MarkupContent {
+        kind: MarkupKind::Markdown,
+        value,
+    }
+}
+
+/// The last good revision of the given file if its current source doesn't
+/// parse, which requests answer from when they would otherwise have no
+/// results (since a source that doesn't parse may be missing most of its
+/// items halfway through an edit).
+fn stale_revision(
+    snapshot: &StateSnapshot,
+    file_id: FileId,
+) -> Result<Option<&Revision>> {
+    if !snapshot.analysis.parse(file_id)?.has_errors() {
+        return Ok(None);
+    }
+
+    Ok(snapshot.good_revisions.get(&file_id))
+}
+
+/// The note shown along the results taken from a [`stale_revision`].
+fn stale_note(revision: &Revision) -> String {
+    match revision.version {
+        Some(version) => format!(
+            "*Results may be stale: they are from version {} of the \
+             document, the last one without syntax errors.*",
+            version
+        ),
+        None => "*Results may be stale: they are from the last version of \
+                 the file without syntax errors.*"
+            .to_string(),
+    }
+}
+
+/// Appends the note of a [`stale_revision`] to some Markdown.
+fn append_stale_note(markdown: &mut String, revision: &Revision) {
+    if !markdown.is_empty() {
+        markdown.push_str("\n\n---\n\n");
+    }
+    markdown.push_str(&stale_note(revision));
+}
+
+/// The completion of the given item of the document, with the note of the
+/// revision it's from if it's stale.
+fn item_completion(item: &Item, stale: Option<&Revision>) -> CompletionItem {
+    let mut docs = item.docs.clone();
+    if let Some(revision) = stale {
+        append_stale_note(docs.get_or_insert_with(String::new), revision);
+    }
+
+    CompletionItem {
+        label: item.name.clone(),
+        kind: Some(match item.kind {
+            ItemKind::Binding => CompletionItemKind::Variable,
+            ItemKind::External => CompletionItemKind::Function,
+            ItemKind::Type => CompletionItemKind::Enum,
+            ItemKind::Interface => CompletionItemKind::Interface,
+        }),
+        detail: Some(item.signature.clone()),
+        documentation: docs.map(|docs| {
+            Documentation::MarkupContent(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: docs,
+            })
+        }),
+        ..CompletionItem::default()
+    }
+}
+
+/// Completes what fits at the cursor (see [`CompletionContext`]):
+///
+/// - nothing but a placeholder for the name of a new binding;
+/// - the names in scope where an expression is being typed (closest first),
+///   followed by the keywords, literals and snippets starting an expression;
+/// - the variants or methods of an item after a `.`;
+/// - every item of the document and the snippets of declarations at the
+///   start of a line;
+/// - every item of the document anywhere else.
+///
+/// Snippets (including the placeholder of a name) are left out if the client
+/// doesn't support them.
 pub fn completion(
-    _snapshot: StateSnapshot,
-    _: CompletionParams,
+    snapshot: StateSnapshot,
+    params: CompletionParams,
 ) -> Result<Option<CompletionResponse>> {
-    // let file_id = 0;
-    // let mut completion_items = Vec::new();
-    // let bindings: Arc<Vec<BindingId>> = snapshot.db.all_bindings(file_id);
-    // let source: Arc<String> = snapshot.db.source(file_id);
-    // log::trace!("SOURCE => {:?}", source);
+    let position = &params.text_document_position;
+    let (file_id, offset) = match file_position(&snapshot, position)? {
+        Some(position) => position,
+        None => return Ok(None),
+    };
+
+    let mut items = snapshot.analysis.items(file_id)?;
+    let mut stale = None;
+    if items.is_empty() {
+        if let Some(revision) = stale_revision(&snapshot, file_id)? {
+            items = Arc::clone(&revision.items);
+            stale = Some(revision);
+        }
+    }
+
+    let snippets = |snippets| {
+        if snapshot.can_complete_snippets {
+            completion::snippets(snippets)
+        } else {
+            Vec::new()
+        }
+    };
+
+    let root = snapshot.analysis.parse(file_id)?.syntax();
+    let completion_items = match completion_context(&root, offset) {
+        CompletionContext::Name if snapshot.can_complete_snippets => {
+            vec![completion::name_placeholder()]
+        }
+        CompletionContext::Name => Vec::new(),
+        CompletionContext::Expression(start) => {
+            let locals = completion::local_names(&root, start);
+            let values = completion::values_by_proximity(&items, start)
+                .into_iter()
+                // Parameters shadow the items of the same name
+                .filter(|item| !locals.contains(&item.name))
+                .enumerate()
+                .map(|(position, item)| CompletionItem {
+                    sort_text: Some(completion::sort_text(
+                        Rank::Item,
+                        position,
+                    )),
+                    ..item_completion(item, stale)
+                })
+                .collect::<Vec<_>>();
+
+            let mut completion_items = completion::locals(&locals);
+            completion_items.extend(values);
+            completion_items.extend(completion::keywords_and_literals());
+            completion_items.extend(snippets(completion::EXPRESSION_SNIPPETS));
+            completion_items
+        }
+        CompletionContext::Member(name) => completion::members(&root, &name),
+        CompletionContext::Declaration => {
+            let mut completion_items = items
+                .iter()
+                .enumerate()
+                .map(|(position, item)| CompletionItem {
+                    sort_text: Some(completion::sort_text(
+                        Rank::Item,
+                        position,
+                    )),
+                    ..item_completion(item, stale)
+                })
+                .collect::<Vec<_>>();
+            completion_items.extend(snippets(completion::DECLARATION_SNIPPETS));
+            completion_items
+        }
+        CompletionContext::Unknown => items
+            .iter()
+            .map(|item| item_completion(item, stale))
+            .collect(),
+    };
+
+    if completion_items.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(completion_items.into()))
+    }
+}
+
+pub fn hover(
+    snapshot: StateSnapshot,
+    params: HoverParams,
+) -> Result<Option<Hover>> {
+    let position = &params.text_document_position_params;
+    let (file_id, offset) = match file_position(&snapshot, position)? {
+        Some(position) => position,
+        None => return Ok(None),
+    };
+
+    if let Some(item) = snapshot.analysis.item_at_offset(file_id, offset)? {
+        return Ok(Some(Hover {
+            contents: HoverContents::Markup(item_documentation(&item)),
+            range: None,
+        }));
+    }
+
+    // The item may only be missing because the declaration doesn't parse at
+    // the moment, so it's looked up by name in the last good revision
+    let revision = match stale_revision(&snapshot, file_id)? {
+        Some(revision) => revision,
+        None => return Ok(None),
+    };
+    let name = match snapshot.analysis.identifier_at_offset(file_id, offset)? {
+        Some(name) => name,
+        None => return Ok(None),
+    };
+
+    let item = revision.items.iter().find(|item| item.name == name);
+    Ok(item.map(|item| {
+        let mut contents = item_documentation(item);
+        append_stale_note(&mut contents.value, revision);
+        Hover {
+            contents: HoverContents::Markup(contents),
+            range: None,
+        }
+    }))
+}
+
+/// Highlights the bracket at the cursor and the bracket matching it (see
+/// [`helios_parser::editor::bracket_pair_at`]).
+pub fn document_highlight(
+    snapshot: StateSnapshot,
+    params: DocumentHighlightParams,
+) -> Result<Option<Vec<DocumentHighlight>>> {
+    let position = &params.text_document_position_params;
+    let (file_id, offset) = match file_position(&snapshot, position)? {
+        Some(position) => position,
+        None => return Ok(None),
+    };
+
+    let root = snapshot.analysis.parse(file_id)?.syntax();
+    let (bracket, matching) =
+        match helios_parser::editor::bracket_pair_at(&root, offset) {
+            Some(pair) => pair,
+            None => return Ok(None),
+        };
+
+    let highlights = vec![bracket, matching]
+        .into_iter()
+        .map(|range| {
+            Ok(DocumentHighlight {
+                range: lsp_range(&snapshot, file_id, range)?,
+                kind: Some(DocumentHighlightKind::Text),
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(Some(highlights))
+}
+
+fn lsp_range(
+    snapshot: &StateSnapshot,
+    file_id: FileId,
+    range: Range<usize>,
+) -> Result<lsp_types::Range> {
+    let lines = snapshot.analysis.source_lines(file_id)?;
+    let start = lines.utf16_line_col(range.start);
+    let end = lines.utf16_line_col(range.end);
+
+    Ok(lsp_types::Range::new(
+        Position::new(start.line as u32, start.col as u32),
+        Position::new(end.line as u32, end.col as u32),
+    ))
+}
+
+fn call_hierarchy_item(
+    snapshot: &StateSnapshot,
+    uri: &Url,
+    file_id: FileId,
+    item: &Item,
+) -> Result<CallHierarchyItem> {
+    Ok(CallHierarchyItem {
+        name: item.name.clone(),
+        kind: match item.kind {
+            ItemKind::Binding => SymbolKind::Variable,
+            ItemKind::External => SymbolKind::Function,
+            ItemKind::Type => SymbolKind::Enum,
+            ItemKind::Interface => SymbolKind::Interface,
+        },
+        tags: None,
+        detail: Some(item.signature.clone()),
+        uri: uri.clone(),
+        range: lsp_range(snapshot, file_id, item.range.clone())?,
+        selection_range: lsp_range(snapshot, file_id, item.name_range.clone())?,
+        data: None,
+    })
+}
+
+/// The file and the index (in the file's items) of the item that the given
+/// call hierarchy item was made from.
+fn call_hierarchy_item_index(
+    snapshot: &StateSnapshot,
+    item: &CallHierarchyItem,
+) -> Result<Option<(FileId, usize)>> {
+    let position = TextDocumentPositionParams::new(
+        TextDocumentIdentifier::new(item.uri.clone()),
+        item.selection_range.start,
+    );
+
+    let (file_id, offset) = match file_position(snapshot, &position)? {
+        Some(position) => position,
+        None => return Ok(None),
+    };
+
+    let items = snapshot.analysis.items(file_id)?;
+    let index = items.iter().position(|it| it.name_range.start == offset);
+    Ok(index.map(|index| (file_id, index)))
+}
+
+/// Groups the ranges of the given calls by the item given by `key`, in order
+/// of appearance.
+fn group_calls<'a>(
+    calls: impl Iterator<Item = &'a Call>,
+    key: impl Fn(&Call) -> usize,
+) -> Vec<(usize, Vec<Range<usize>>)> {
+    let mut groups: Vec<(usize, Vec<Range<usize>>)> = Vec::new();
+    for call in calls {
+        let index = key(call);
+        match groups.iter_mut().find(|(it, _)| *it == index) {
+            Some((_, ranges)) => ranges.push(call.range.clone()),
+            None => groups.push((index, vec![call.range.clone()])),
+        }
+    }
+
+    groups
+}
+
+pub fn prepare_call_hierarchy(
+    snapshot: StateSnapshot,
+    params: CallHierarchyPrepareParams,
+) -> Result<Option<Vec<CallHierarchyItem>>> {
+    let position = &params.text_document_position_params;
+    let (file_id, offset) = match file_position(&snapshot, position)? {
+        Some(position) => position,
+        None => return Ok(None),
+    };
+
+    let uri = &position.text_document.uri;
+    match snapshot.analysis.item_at_offset(file_id, offset)? {
+        Some(item) => {
+            let item = call_hierarchy_item(&snapshot, uri, file_id, &item)?;
+            Ok(Some(vec![item]))
+        }
+        None => Ok(None),
+    }
+}
+
+pub fn incoming_calls(
+    snapshot: StateSnapshot,
+    params: CallHierarchyIncomingCallsParams,
+) -> Result<Option<Vec<CallHierarchyIncomingCall>>> {
+    let (file_id, index) =
+        match call_hierarchy_item_index(&snapshot, &params.item)? {
+            Some(item) => item,
+            None => return Ok(None),
+        };
+
+    let items = snapshot.analysis.items(file_id)?;
+    let calls = snapshot.analysis.calls(file_id)?;
+    let calls = calls.iter().filter(|call| call.callee == index);
+
+    let incoming = group_calls(calls, |call| call.caller)
+        .into_iter()
+        .map(|(caller, ranges)| {
+            Ok(CallHierarchyIncomingCall {
+                from: call_hierarchy_item(
+                    &snapshot,
+                    &params.item.uri,
+                    file_id,
+                    &items[caller],
+                )?,
+                from_ranges: ranges
+                    .into_iter()
+                    .map(|range| lsp_range(&snapshot, file_id, range))
+                    .collect::<Result<_>>()?,
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(Some(incoming))
+}
+
+pub fn outgoing_calls(
+    snapshot: StateSnapshot,
+    params: CallHierarchyOutgoingCallsParams,
+) -> Result<Option<Vec<CallHierarchyOutgoingCall>>> {
+    let (file_id, index) =
+        match call_hierarchy_item_index(&snapshot, &params.item)? {
+            Some(item) => item,
+            None => return Ok(None),
+        };
+
+    let items = snapshot.analysis.items(file_id)?;
+    let calls = snapshot.analysis.calls(file_id)?;
+    let calls = calls.iter().filter(|call| call.caller == index);
+
+    let outgoing = group_calls(calls, |call| call.callee)
+        .into_iter()
+        .map(|(callee, ranges)| {
+            Ok(CallHierarchyOutgoingCall {
+                to: call_hierarchy_item(
+                    &snapshot,
+                    &params.item.uri,
+                    file_id,
+                    &items[callee],
+                )?,
+                from_ranges: ranges
+                    .into_iter()
+                    .map(|range| lsp_range(&snapshot, file_id, range))
+                    .collect::<Result<_>>()?,
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(Some(outgoing))
+}
+
+pub fn code_lens(
+    snapshot: StateSnapshot,
+    params: CodeLensParams,
+) -> Result<Option<Vec<CodeLens>>> {
+    let uri = params.text_document.uri;
+    let file_id = match loaded_file(&snapshot, &uri) {
+        Some(file_id) => file_id,
+        None => return Ok(None),
+    };
+
+    let mut lenses = Vec::new();
+    for (index, item) in snapshot.analysis.items(file_id)?.iter().enumerate() {
+        let range = lsp_range(&snapshot, file_id, item.name_range.clone())?;
+
+        if item.kind == ItemKind::Binding && item.name == "main" {
+            lenses.push(CodeLens {
+                range,
+                command: Some(Command::new(
+                    "▶ Run".to_string(),
+                    commands::RUN.to_string(),
+                    Some(vec![json!(uri)]),
+                )),
+                data: None,
+            });
+        }
+
+        // The references are counted when the lens is resolved, since only
+        // the visible lenses are resolved
+        if !matches!(item.kind, ItemKind::Type | ItemKind::Interface) {
+            lenses.push(CodeLens {
+                range,
+                command: None,
+                data: Some(json!({ "uri": uri, "item": index })),
+            });
+        }
+    }
+
+    Ok(Some(lenses))
+}
+
+pub fn code_lens_resolve(
+    snapshot: StateSnapshot,
+    mut lens: CodeLens,
+) -> Result<CodeLens> {
+    let data = lens.data.take().unwrap_or_default();
+    let (uri, index) = match (
+        serde_json::from_value::<Url>(data["uri"].clone()),
+        data["item"].as_u64(),
+    ) {
+        (Ok(uri), Some(index)) => (uri, index as usize),
+        _ => {
+            let message = "invalid code lens data";
+            return Err(LspError::new(ErrorCode::InvalidParams, message).into());
+        }
+    };
+
+    let count = match loaded_file(&snapshot, &uri) {
+        Some(file_id) => {
+            let references = snapshot.analysis.references(file_id)?;
+            references.iter().filter(|it| it.item == index).count()
+        }
+        None => 0,
+    };
+
+    let title = match count {
+        1 => "1 reference".to_string(),
+        count => format!("{} references", count),
+    };
+
+    lens.command = Some(Command::new(title, String::new(), None));
+    Ok(lens)
+}
+
+/// The quick fixes of a range are the suggestions of the diagnostics there
+/// (which replace some source code of the document).
+pub fn code_action(
+    snapshot: StateSnapshot,
+    params: CodeActionParams,
+) -> Result<Option<CodeActionResponse>> {
+    let uri = params.text_document.uri;
+    let file_id = match loaded_file(&snapshot, &uri) {
+        Some(file_id) => file_id,
+        None => return Ok(None),
+    };
 
-    // for binding in bindings.iter() {
-    //     let binding_data: BindingData =
-    //         snapshot.db.lookup_intern_binding(*binding);
-    //     log::info!("{:?} => {:?}", binding, binding_data);
+    let requested = params.range;
+    let lint_levels = snapshot.config.diagnostics.lint_levels();
+    let mut actions = Vec::new();
+    for diagnostic in snapshot.analysis.diagnostics(file_id)?.iter() {
+        let diagnostic = match lint_levels.apply(diagnostic.clone()) {
+            Some(diagnostic) => diagnostic,
+            None => continue,
+        };
 
-    //     let completion_item = CompletionItem {
-    //         label: binding_data.identifier,
-    //         kind: Some(CompletionItemKind::Variable),
-    //         ..CompletionItem::default()
-    //     };
+        // The fixes are offered anywhere on the diagnostic, or on the code
+        // they replace
+        let overlaps = |range: &lsp_types::Range| {
+            range.start <= requested.end && requested.start <= range.end
+        };
+        let diagnostic_range =
+            lsp_range(&snapshot, file_id, diagnostic.location.range.clone())?;
 
-    //     completion_items.push(completion_item);
-    // }
+        for suggestion in &diagnostic.suggestions {
+            let range = lsp_range(
+                &snapshot,
+                file_id,
+                suggestion.location.range.clone(),
+            )?;
 
-    // if completion_items.is_empty() {
-    //     Ok(None)
-    // } else {
-    //     Ok(Some(completion_items.into()))
-    // }
+            if !overlaps(&diagnostic_range) && !overlaps(&range) {
+                continue;
+            }
 
-    Ok(None)
+            let replacement = &suggestion.replacement;
+            let title = if replacement.is_empty() {
+                "Remove this".to_string()
+            } else if range.start == range.end {
+                format!("Insert `{}`", replacement)
+            } else {
+                format!("Replace with `{}`", replacement)
+            };
+
+            let edit = TextEdit::new(range, replacement.clone());
+            let changes = vec![(uri.clone(), vec![edit])].into_iter().collect();
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title,
+                kind: Some(CodeActionKind::QUICKFIX),
+                edit: Some(WorkspaceEdit::new(changes)),
+                is_preferred: Some(
+                    suggestion.applicability
+                        == Applicability::MachineApplicable,
+                ),
+                ..CodeAction::default()
+            }));
+        }
+    }
+
+    Ok(Some(actions))
+}
+
+/// The document diagnostic request is sent from the client to the server to
+/// pull the diagnostics of a document.
+///
+/// The diagnostics are memoized by the database, so pulling them again is
+/// cheap until the document changes. Their result ID is a hash of the
+/// diagnostics reported, so the report is unchanged if that of the
+/// client's previous result ID is.
+pub fn document_diagnostic(
+    snapshot: StateSnapshot,
+    params: DocumentDiagnosticParams,
+) -> Result<DocumentDiagnosticReport> {
+    let uri = params.text_document.uri;
+    let items = match loaded_file(&snapshot, &uri) {
+        Some(file_id) => snapshot.analysis.with_db(|db| {
+            notifications::lsp_diagnostics(db, &snapshot.config, file_id, &uri)
+        })?,
+        None => Vec::new(),
+    };
+
+    let mut hasher = DefaultHasher::new();
+    serde_json::to_string(&items)?.hash(&mut hasher);
+    let result_id = format!("{:016x}", hasher.finish());
+
+    if params.previous_result_id.as_ref() == Some(&result_id) {
+        Ok(DocumentDiagnosticReport::Unchanged { result_id })
+    } else {
+        Ok(DocumentDiagnosticReport::Full {
+            result_id: Some(result_id),
+            items,
+        })
+    }
+}
+
+/// The `helios/syntaxTree` request is sent from the client to the server to
+/// get the syntax tree of a document (or only of its node covering a range).
+pub fn syntax_tree(
+    snapshot: StateSnapshot,
+    params: SyntaxTreeParams,
+) -> Result<String> {
+    let uri = &params.text_document.uri;
+    let file_id = loaded_file(&snapshot, uri).ok_or_else(|| {
+        let message = format!("unknown document: {}", uri);
+        LspError::new(ErrorCode::InvalidParams, message)
+    })?;
+
+    let root = snapshot.analysis.parse(file_id)?.syntax();
+    let range = match params.range {
+        Some(range) => {
+            let start = offset(&snapshot, file_id, range.start)?;
+            let end = offset(&snapshot, file_id, range.end)?;
+            match start.zip(end) {
+                Some((start, end)) if start <= end => start..end,
+                _ => {
+                    let message = "the range is outside of the document";
+                    let error =
+                        LspError::new(ErrorCode::InvalidParams, message);
+                    return Err(error.into());
+                }
+            }
+        }
+        None => return Ok(format!("{:#?}", root)),
+    };
+
+    // The nodes containing the range form a path from the root, so the last
+    // one in preorder is the smallest of them
+    let node = root
+        .descendants()
+        .filter(|node| {
+            let node_range = node.text_range();
+            usize::from(node_range.start()) <= range.start
+                && range.end <= usize::from(node_range.end())
+        })
+        .last()
+        .unwrap_or(root);
+
+    Ok(format!("{:#?}", node))
 }
 
-pub fn hover(_: StateSnapshot, _: HoverParams) -> Result<Option<Hover>> {
-    Ok(None)
+/// The `helios/memoryUsage` request is sent from the client to the server to
+/// get the memory usage statistics of the database.
+pub fn memory_usage(snapshot: StateSnapshot, _: ()) -> Result<String> {
+    let stats = snapshot.analysis.with_db(memory_stats)?;
+    Ok(stats.to_string())
+}
+
+/// The `helios/profile` request is sent from the client to the server to get
+/// the time spent in each phase of compiling each file.
+pub fn profile(snapshot: StateSnapshot, _: ()) -> Result<String> {
+    let analysis = &snapshot.analysis;
+    let report = analysis.with_db(|db| {
+        db.profiler()
+            .report(|file_id| analysis.file_path(file_id).display().to_string())
+    })?;
+
+    Ok(report.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_server::TestServer;
+    use lsp_types::*;
+
+    #[test]
+    fn test_initialize() {
+        let mut server = TestServer::uninitialized();
+
+        #[allow(deprecated)]
+        let result = server.request::<request::Initialize>(InitializeParams {
+            process_id: None,
+            root_path: None,
+            root_uri: None,
+            initialization_options: None,
+            capabilities: ClientCapabilities::default(),
+            trace: None,
+            workspace_folders: None,
+            client_info: None,
+            locale: None,
+        });
+
+        let server_info = result.server_info.unwrap();
+        assert_eq!(server_info.name, "Helios-LS");
+        assert_eq!(
+            result.capabilities.hover_provider,
+            Some(HoverProviderCapability::Simple(true))
+        );
+        assert_eq!(
+            result.capabilities.text_document_sync,
+            Some(TextDocumentSyncCapability::Kind(
+                TextDocumentSyncKind::Incremental
+            ))
+        );
+        assert_eq!(
+            result
+                .capabilities
+                .execute_command_provider
+                .unwrap()
+                .commands,
+            vec![
+                "helios.run",
+                "helios.showSyntaxTree",
+                "helios.reloadWorkspace"
+            ]
+        );
+
+        assert_eq!(
+            result.capabilities.code_action_provider,
+            Some(CodeActionProviderCapability::Simple(true))
+        );
+
+        server.notify::<notification::Initialized>(InitializedParams {});
+    }
+
+    #[test]
+    fn test_completion() {
+        let mut server = TestServer::new();
+        let uri = server.open("file:///Foo.he", "--| The answer.\nlet a = 1\n");
+
+        let params = CompletionParams {
+            text_document_position: TextDocumentPositionParams::new(
+                TextDocumentIdentifier::new(uri),
+                Position::new(2, 0),
+            ),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+            context: None,
+        };
+
+        let items = match server.request::<request::Completion>(params) {
+            Some(CompletionResponse::Array(items)) => items,
+            response => panic!("Unexpected response: {:?}", response),
+        };
+
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].label, "a");
+        assert_eq!(items[0].kind, Some(CompletionItemKind::Variable));
+        assert_eq!(items[0].detail.as_deref(), Some("let a"));
+        assert_eq!(
+            items[0].documentation,
+            Some(Documentation::MarkupContent(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: "The answer.".to_string(),
+            }))
+        );
+    }
+
+    #[test]
+    fn test_completion_in_incomplete_expression() {
+        let mut server = TestServer::new();
+        let uri = server.open(
+            "file:///Foo.he",
+            "type T = A | B\nlet a = 1\nlet b = a + \nlet c = ",
+        );
+
+        // The labels are in the order the editor shows them in
+        let mut labels = |line, character| {
+            let params = CompletionParams {
+                text_document_position: TextDocumentPositionParams::new(
+                    TextDocumentIdentifier::new(uri.clone()),
+                    Position::new(line, character),
+                ),
+                work_done_progress_params: WorkDoneProgressParams::default(),
+                partial_result_params: PartialResultParams::default(),
+                context: None,
+            };
+
+            let mut items = match server.request::<request::Completion>(params)
+            {
+                Some(CompletionResponse::Array(items)) => items,
+                response => panic!("Unexpected response: {:?}", response),
+            };
+            items.sort_by_key(|item| item.sort_text.clone());
+            items.into_iter().map(|item| item.label).collect::<Vec<_>>()
+        };
+
+        // The type isn't a value, so it's only offered outside expressions,
+        // and the binding being defined comes last
+        let keywords_and_literals = ["if", "match", "let", "()", "\"\""];
+        assert_eq!(
+            labels(2, 12),
+            [&["c", "a", "b"][..], &keywords_and_literals].concat()
+        );
+        assert_eq!(
+            labels(3, 8),
+            [&["b", "a", "c"][..], &keywords_and_literals].concat()
+        );
+        assert_eq!(labels(0, 0), vec!["T", "a", "b", "c"]);
+    }
+
+    /// The capabilities of a client that supports snippets in completions.
+    fn snippet_capabilities() -> ClientCapabilities {
+        ClientCapabilities {
+            text_document: Some(TextDocumentClientCapabilities {
+                completion: Some(CompletionClientCapabilities {
+                    completion_item: Some(CompletionItemCapability {
+                        snippet_support: Some(true),
+                        ..CompletionItemCapability::default()
+                    }),
+                    ..CompletionClientCapabilities::default()
+                }),
+                ..TextDocumentClientCapabilities::default()
+            }),
+            ..ClientCapabilities::default()
+        }
+    }
+
+    #[test]
+    fn test_completion_context() {
+        let mut server = TestServer::with_capabilities(snippet_capabilities());
+        let uri = server.open(
+            "file:///Foo.he",
+            "type T =\n  | A\n  | B\nlet a = 1\nlet f = \\a x -> \n\
+             let g = T.\nlet ",
+        );
+
+        let mut completions = |line, character| {
+            let params = CompletionParams {
+                text_document_position: TextDocumentPositionParams::new(
+                    TextDocumentIdentifier::new(uri.clone()),
+                    Position::new(line, character),
+                ),
+                work_done_progress_params: WorkDoneProgressParams::default(),
+                partial_result_params: PartialResultParams::default(),
+                context: None,
+            };
+
+            let mut items = match server.request::<request::Completion>(params)
+            {
+                Some(CompletionResponse::Array(items)) => items,
+                response => panic!("Unexpected response: {:?}", response),
+            };
+            items.sort_by_key(|item| item.sort_text.clone());
+            items
+                .into_iter()
+                .map(|item| (item.label, item.kind.unwrap()))
+                .collect::<Vec<_>>()
+        };
+
+        // The parameter `a` shadows the binding
+        let items = completions(4, 16);
+        assert_eq!(
+            &items[..4],
+            &[
+                ("a".to_string(), CompletionItemKind::Variable),
+                ("x".to_string(), CompletionItemKind::Variable),
+                ("g".to_string(), CompletionItemKind::Variable),
+                ("f".to_string(), CompletionItemKind::Variable),
+            ]
+        );
+        assert_eq!(items[4], ("if".to_string(), CompletionItemKind::Keyword));
+
+        assert_eq!(
+            completions(5, 10),
+            vec![
+                ("A".to_string(), CompletionItemKind::EnumMember),
+                ("B".to_string(), CompletionItemKind::EnumMember),
+            ]
+        );
+        assert_eq!(
+            completions(6, 4),
+            vec![("name".to_string(), CompletionItemKind::Snippet)]
+        );
+    }
+
+    #[test]
+    fn test_snippet_completions() {
+        let source = "let a = \nlet ";
+        let completion =
+            |server: &mut TestServer, uri: &Url, line, character| {
+                let params = CompletionParams {
+                    text_document_position: TextDocumentPositionParams::new(
+                        TextDocumentIdentifier::new(uri.clone()),
+                        Position::new(line, character),
+                    ),
+                    work_done_progress_params: WorkDoneProgressParams::default(
+                    ),
+                    partial_result_params: PartialResultParams::default(),
+                    context: None,
+                };
+
+                match server.request::<request::Completion>(params) {
+                    Some(CompletionResponse::Array(items)) => items,
+                    None => Vec::new(),
+                    response => panic!("Unexpected response: {:?}", response),
+                }
+            };
+        let snippets = |items: Vec<CompletionItem>| {
+            items
+                .into_iter()
+                .filter(|item| item.kind == Some(CompletionItemKind::Snippet))
+                .map(|item| {
+                    assert_eq!(
+                        item.insert_text_format,
+                        Some(InsertTextFormat::Snippet)
+                    );
+                    (item.label, item.insert_text.unwrap())
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut server = TestServer::with_capabilities(snippet_capabilities());
+        let uri = server.open("file:///Foo.he", source);
+        let labels = snippets(completion(&mut server, &uri, 0, 8))
+            .into_iter()
+            .map(|(label, _)| label)
+            .collect::<Vec<_>>();
+        assert_eq!(labels, vec!["if", "match"]);
+        assert_eq!(
+            snippets(completion(&mut server, &uri, 1, 0)),
+            vec![
+                ("let".to_string(), "let ${1:name} = ${0:()}".to_string()),
+                (
+                    "function".to_string(),
+                    "let ${1:name} = \\\\${2:x} -> ${0:()}".to_string()
+                ),
+            ]
+        );
+
+        // Without support for snippets, there is nothing to offer for a name
+        let mut server = TestServer::new();
+        let uri = server.open("file:///Foo.he", source);
+        assert!(snippets(completion(&mut server, &uri, 0, 8)).is_empty());
+        assert!(snippets(completion(&mut server, &uri, 1, 0)).is_empty());
+        assert!(completion(&mut server, &uri, 1, 4).is_empty());
+    }
+
+    #[test]
+    fn test_completion_without_items() {
+        let mut server = TestServer::new();
+        let uri = server.open("file:///Foo.he", "-- Nothing\n");
+
+        let params = CompletionParams {
+            text_document_position: TextDocumentPositionParams::new(
+                TextDocumentIdentifier::new(uri),
+                Position::new(1, 0),
+            ),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+            context: None,
+        };
+
+        assert_eq!(server.request::<request::Completion>(params), None);
+    }
+
+    #[test]
+    fn test_hover_shows_documentation() {
+        let mut server = TestServer::new();
+        let uri = server.open(
+            "file:///Foo.he",
+            "--| Adds one.\nlet f = \\x -> x + 1\nlet b = f\n",
+        );
+
+        let hover = |server: &mut TestServer, line, character| {
+            server.request::<request::HoverRequest>(HoverParams {
+                text_document_position_params: TextDocumentPositionParams::new(
+                    TextDocumentIdentifier::new(uri.clone()),
+                    Position::new(line, character),
+                ),
+                work_done_progress_params: WorkDoneProgressParams::default(),
+            })
+        };
+
+        let expected = Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: "```helios\nlet f\n```\n\nAdds one.".to_string(),
+            }),
+            range: None,
+        });
+
+        assert_eq!(hover(&mut server, 1, 4), expected);
+        assert_eq!(hover(&mut server, 2, 8), expected);
+        assert_eq!(hover(&mut server, 1, 9), None);
+        assert_eq!(hover(&mut server, 9, 0), None);
+
+        // Positions past the end of a line are clamped to its end
+        assert_eq!(hover(&mut server, 2, 99), expected);
+    }
+
+    #[test]
+    fn test_hover_falls_back_on_last_good_revision() {
+        let mut server = TestServer::new();
+        let uri =
+            server.open("file:///Foo.he", "--| Adds one.\nlet f = \\x -> x\n");
+
+        let hover = |server: &mut TestServer, line, character| {
+            let hover = server.request::<request::HoverRequest>(HoverParams {
+                text_document_position_params: TextDocumentPositionParams::new(
+                    TextDocumentIdentifier::new(uri.clone()),
+                    Position::new(line, character),
+                ),
+                work_done_progress_params: WorkDoneProgressParams::default(),
+            });
+
+            hover.map(|hover| match hover.contents {
+                HoverContents::Markup(contents) => contents.value,
+                contents => panic!("Unexpected contents: {:?}", contents),
+            })
+        };
+
+        // Halfway through rewriting the declaration, nothing parses
+        server.notify::<notification::DidChangeTextDocument>(
+            DidChangeTextDocumentParams {
+                text_document: VersionedTextDocumentIdentifier::new(
+                    uri.clone(),
+                    2,
+                ),
+                content_changes: vec![TextDocumentContentChangeEvent {
+                    range: None,
+                    range_length: None,
+                    text: "(\\x -> x +\nf\n".to_string(),
+                }],
+            },
+        );
+
+        let contents = hover(&mut server, 1, 0).unwrap();
+        assert!(
+            contents.starts_with("```helios\nlet f\n```\n\nAdds one."),
+            "{}",
+            contents
+        );
+        assert!(contents.ends_with(
+            "*Results may be stale: they are from version 0 of the document, \
+             the last one without syntax errors.*"
+        ));
+
+        // Names that weren't items then aren't either
+        assert_eq!(hover(&mut server, 0, 1), None);
+    }
+
+    #[test]
+    fn test_document_highlight() {
+        let mut server = TestServer::new();
+        let uri = server.open("file:///Foo.he", "let a = (\n  {1}\n)\n");
+
+        let highlight = |server: &mut TestServer, line, character| {
+            let params = DocumentHighlightParams {
+                text_document_position_params: TextDocumentPositionParams::new(
+                    TextDocumentIdentifier::new(uri.clone()),
+                    Position::new(line, character),
+                ),
+                work_done_progress_params: WorkDoneProgressParams::default(),
+                partial_result_params: PartialResultParams::default(),
+            };
+
+            let highlights =
+                server.request::<request::DocumentHighlightRequest>(params);
+            highlights.map(|highlights| {
+                highlights
+                    .into_iter()
+                    .map(|highlight| {
+                        let start = highlight.range.start;
+                        (start.line, start.character)
+                    })
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        assert_eq!(highlight(&mut server, 2, 0), Some(vec![(2, 0), (0, 8)]));
+        assert_eq!(highlight(&mut server, 1, 5), Some(vec![(1, 4), (1, 2)]));
+        assert_eq!(highlight(&mut server, 0, 4), None);
+    }
+
+    #[test]
+    fn test_call_hierarchy() {
+        let mut server = TestServer::new();
+        let uri = server.open(
+            "file:///Foo.he",
+            "let inc = \\x -> x + 1\n\
+             let twice = \\x -> x |> inc |> inc\n\
+             let main = 1 |> twice\n",
+        );
+
+        let items = server
+            .request::<request::CallHierarchyPrepare>(
+                CallHierarchyPrepareParams {
+                    text_document_position_params:
+                        TextDocumentPositionParams::new(
+                            TextDocumentIdentifier::new(uri.clone()),
+                            Position::new(1, 5),
+                        ),
+                    work_done_progress_params: WorkDoneProgressParams::default(
+                    ),
+                },
+            )
+            .unwrap();
+
+        assert_eq!(items.len(), 1);
+        let twice = items[0].clone();
+        assert_eq!(twice.name, "twice");
+        assert_eq!(twice.detail.as_deref(), Some("let twice"));
+        assert_eq!(
+            twice.selection_range,
+            lsp_types::Range::new(Position::new(1, 4), Position::new(1, 9))
+        );
+
+        let incoming = server
+            .request::<request::CallHierarchyIncomingCalls>(
+                CallHierarchyIncomingCallsParams {
+                    item: twice.clone(),
+                    work_done_progress_params: WorkDoneProgressParams::default(
+                    ),
+                    partial_result_params: PartialResultParams::default(),
+                },
+            )
+            .unwrap();
+
+        assert_eq!(incoming.len(), 1);
+        assert_eq!(incoming[0].from.name, "main");
+        assert_eq!(
+            incoming[0].from_ranges,
+            vec![lsp_types::Range::new(
+                Position::new(2, 16),
+                Position::new(2, 21)
+            )]
+        );
+
+        let outgoing = server
+            .request::<request::CallHierarchyOutgoingCalls>(
+                CallHierarchyOutgoingCallsParams {
+                    item: twice,
+                    work_done_progress_params: WorkDoneProgressParams::default(
+                    ),
+                    partial_result_params: PartialResultParams::default(),
+                },
+            )
+            .unwrap();
+
+        assert_eq!(outgoing.len(), 1);
+        assert_eq!(outgoing[0].to.name, "inc");
+        assert_eq!(outgoing[0].from_ranges.len(), 2);
+    }
+
+    #[test]
+    fn test_code_lens() {
+        let mut server = TestServer::new();
+        let uri = server.open(
+            "file:///Foo.he",
+            "let inc = \\x -> x + 1\n\
+             let main = 1 |> inc |> inc\n\
+             type T = A\n",
+        );
+
+        let lenses = server
+            .request::<request::CodeLensRequest>(CodeLensParams {
+                text_document: TextDocumentIdentifier::new(uri.clone()),
+                work_done_progress_params: WorkDoneProgressParams::default(),
+                partial_result_params: PartialResultParams::default(),
+            })
+            .unwrap();
+
+        assert_eq!(lenses.len(), 3);
+
+        let titles = lenses
+            .into_iter()
+            .map(|lens| match lens.command {
+                Some(command) => (command.title, command.command),
+                None => {
+                    let lens = server.request::<request::CodeLensResolve>(lens);
+                    let command = lens.command.unwrap();
+                    (command.title, command.command)
+                }
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            titles,
+            vec![
+                ("2 references".to_string(), String::new()),
+                ("▶ Run".to_string(), "helios.run".to_string()),
+                ("0 references".to_string(), String::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_code_action() {
+        let mut server = TestServer::new();
+        let uri = server.open("file:///Foo.he", "var a = 1\nlet b = a\n");
+        let params = |range| CodeActionParams {
+            text_document: TextDocumentIdentifier::new(uri.clone()),
+            range,
+            context: CodeActionContext::default(),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+
+        let range = Range::new(Position::new(0, 4), Position::new(0, 5));
+        let actions = server
+            .request::<request::CodeActionRequest>(params(range))
+            .unwrap();
+        assert_eq!(actions.len(), 1);
+
+        let action = match &actions[0] {
+            CodeActionOrCommand::CodeAction(action) => action,
+            action => panic!("Unexpected action: {:?}", action),
+        };
+        assert_eq!(action.title, "Replace with `let`");
+        assert_eq!(action.kind, Some(CodeActionKind::QUICKFIX));
+        assert_eq!(action.is_preferred, Some(true));
+
+        let changes = action.edit.as_ref().unwrap().changes.as_ref().unwrap();
+        let edit = TextEdit::new(
+            Range::new(Position::new(0, 0), Position::new(0, 3)),
+            "let".to_string(),
+        );
+        assert_eq!(changes.get(&uri), Some(&vec![edit]));
+
+        // No diagnostic has a suggestion on the second line
+        let range = Range::new(Position::new(1, 0), Position::new(1, 9));
+        let actions = server
+            .request::<request::CodeActionRequest>(params(range))
+            .unwrap();
+        assert!(actions.is_empty());
+    }
+
+    #[test]
+    fn test_document_diagnostic() {
+        use crate::proposed::*;
+
+        let mut server = TestServer::uninitialized();
+        let response = server.send_request(
+            "initialize",
+            serde_json::json!({
+                "capabilities": { "textDocument": { "diagnostic": {} } },
+            }),
+        );
+        assert_eq!(
+            response.result.unwrap()["capabilities"]["diagnosticProvider"],
+            serde_json::json!({
+                "interFileDependencies": false,
+                "workspaceDiagnostics": false,
+            })
+        );
+        server.notify::<notification::Initialized>(InitializedParams {});
+
+        let uri = server.open("file:///Foo.he", "var a = 1");
+        let params = |previous_result_id| DocumentDiagnosticParams {
+            text_document: TextDocumentIdentifier::new(uri.clone()),
+            identifier: None,
+            previous_result_id,
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+
+        let result_id =
+            match server.request::<DocumentDiagnosticRequest>(params(None)) {
+                DocumentDiagnosticReport::Full {
+                    result_id: Some(result_id),
+                    items,
+                } => {
+                    assert_eq!(items.len(), 1);
+                    assert_eq!(items[0].message, "Binding is never mutated");
+                    result_id
+                }
+                report => panic!("Unexpected report: {:?}", report),
+            };
+
+        // The diagnostics are the same until the document changes
+        let report = server.request::<DocumentDiagnosticRequest>(params(Some(
+            result_id.clone(),
+        )));
+        assert_eq!(
+            report,
+            DocumentDiagnosticReport::Unchanged {
+                result_id: result_id.clone()
+            }
+        );
+
+        server.notify::<notification::DidChangeTextDocument>(
+            DidChangeTextDocumentParams {
+                text_document: VersionedTextDocumentIdentifier::new(
+                    uri.clone(),
+                    1,
+                ),
+                content_changes: vec![TextDocumentContentChangeEvent {
+                    range: None,
+                    range_length: None,
+                    text: "let a = 1".to_string(),
+                }],
+            },
+        );
+
+        let report = server
+            .request::<DocumentDiagnosticRequest>(params(Some(result_id)));
+        match report {
+            DocumentDiagnosticReport::Full { items, .. } => {
+                assert!(items.is_empty())
+            }
+            report => panic!("Unexpected report: {:?}", report),
+        }
+    }
+
+    #[test]
+    fn test_syntax_tree() {
+        use crate::extensions::{SyntaxTree, SyntaxTreeParams};
+
+        let mut server = TestServer::new();
+        let uri = server.open("file:///Foo.he", "let a = 1\nlet b = 2\n");
+        let params = |range| SyntaxTreeParams {
+            text_document: TextDocumentIdentifier::new(uri.clone()),
+            range,
+        };
+
+        let tree = server.request::<SyntaxTree>(params(None));
+        assert!(tree.starts_with("Root@0..20\n"));
+
+        let range = Range::new(Position::new(1, 4), Position::new(1, 9));
+        let tree = server.request::<SyntaxTree>(params(Some(range)));
+        assert!(tree.starts_with("Dec_GlobalBinding@10..19\n"));
+
+        let range = Range::new(Position::new(1, 8), Position::new(1, 8));
+        let tree = server.request::<SyntaxTree>(params(Some(range)));
+        assert!(tree.starts_with("Exp_Literal@18..19\n"));
+
+        // The tree follows the changes made to the document
+        server.notify::<notification::DidChangeTextDocument>(
+            DidChangeTextDocumentParams {
+                text_document: VersionedTextDocumentIdentifier::new(
+                    uri.clone(),
+                    1,
+                ),
+                content_changes: vec![TextDocumentContentChangeEvent {
+                    range: Some(Range::new(
+                        Position::new(1, 8),
+                        Position::new(1, 9),
+                    )),
+                    range_length: None,
+                    text: "c".to_string(),
+                }],
+            },
+        );
+
+        let range = Range::new(Position::new(1, 8), Position::new(1, 8));
+        let tree = server.request::<SyntaxTree>(params(Some(range)));
+        assert!(tree.starts_with("Exp_VariableRef@18..19\n"));
+
+        let range = Range::new(Position::new(9, 0), Position::new(9, 0));
+        let response =
+            server.send_request("helios/syntaxTree", params(Some(range)));
+        assert!(response.error.is_some());
+    }
+
+    #[test]
+    fn test_memory_usage() {
+        use crate::extensions::MemoryUsage;
+
+        let mut server = TestServer::new();
+        server.open("file:///Foo.he", "let a = 1\n");
+
+        let stats = server.request::<MemoryUsage>(());
+        assert!(stats.starts_with("Query "));
+        assert!(stats.contains("Interned file paths: 1\n"));
+        assert!(stats.contains("Syntax trees: 1 "));
+    }
+
+    #[test]
+    fn test_profile() {
+        use crate::extensions::Profile;
+
+        let mut server = TestServer::new();
+        server.open("file:///Foo.he", "let a = 1\n");
+
+        let report = server.request::<Profile>(());
+        assert!(report.starts_with("File "));
+        assert!(report.contains("\n/Foo.he  lexing "));
+        assert!(report.contains("\nTotal    parsing "));
+    }
 }
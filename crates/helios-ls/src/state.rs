@@ -1,45 +1,175 @@
 #![allow(unused)]
 
-use crate::protocol::Message;
+use crate::config::Config;
+use crate::protocol::{Message, Request};
+use crate::scheduler::Scheduler;
+use crate::trace::Tracer;
 use flume::Sender;
-use helios_query::HeliosDatabase;
+use helios_query::{Analysis, AnalysisHost, FileId, Input, Item, Items};
+use helios_vfs::Vfs;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
 use std::default::Default;
+use std::sync::Arc;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub enum Status {
+    #[default]
     Loading,
     Ready,
     Error,
 }
 
-impl Default for Status {
-    fn default() -> Self {
-        Self::Loading
-    }
+/// The results of the last analysis of a document whose text parsed without
+/// errors, which requests fall back on while its latest text doesn't parse
+/// (such as halfway through an edit).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Revision {
+    /// The version of the document that was analysed (if it's open).
+    pub(crate) version: Option<i32>,
+    pub(crate) items: Arc<Vec<Item>>,
 }
 
 pub struct State {
     pub(crate) sender: Sender<Message>,
-    pub(crate) db: HeliosDatabase,
+    pub(crate) host: AnalysisHost,
+    pub(crate) vfs: Vfs,
+    /// The files whose source has been set in the database.
+    pub(crate) loaded_files: Arc<HashSet<FileId>>,
+    /// The last revision of each file that parsed without errors.
+    pub(crate) good_revisions: Arc<HashMap<FileId, Revision>>,
+    /// Whether the client can watch files for changes on our behalf.
+    pub(crate) can_watch_files: bool,
+    /// Whether the client pulls the diagnostics of the documents (instead of
+    /// the server publishing them).
+    pub(crate) can_pull_diagnostics: bool,
+    /// Whether the client can be asked to pull the diagnostics again.
+    pub(crate) can_refresh_diagnostics: bool,
+    /// Whether the client inserts completions with tab stops and
+    /// placeholders (see [`crate::completion::snippets`]).
+    pub(crate) can_complete_snippets: bool,
+    /// The changed documents whose diagnostics are waiting to be published.
+    pub(crate) scheduler: Scheduler,
     pub(crate) status: Status,
+    pub(crate) config: Arc<Config>,
+    pub(crate) tracer: Arc<Tracer>,
+    /// The number of requests sent to the client so far, which gives the id
+    /// of the next one.
+    requests_sent: u64,
 }
 
 impl State {
-    pub fn new(sender: Sender<Message>) -> Self {
+    pub fn new(sender: Sender<Message>, tracer: Arc<Tracer>) -> Self {
         Self {
             sender,
-            db: HeliosDatabase::default(),
+            host: AnalysisHost::new(),
+            vfs: Vfs::new(),
+            loaded_files: Arc::default(),
+            good_revisions: Arc::default(),
+            can_watch_files: false,
+            can_pull_diagnostics: false,
+            can_refresh_diagnostics: false,
+            can_complete_snippets: false,
+            scheduler: Scheduler::default(),
             status: Status::default(),
+            config: Arc::new(Config::default()),
+            tracer,
+            requests_sent: 0,
         }
     }
 
     pub fn snapshot(&self) -> StateSnapshot {
-        use salsa::ParallelDatabase;
         StateSnapshot {
-            db: self.db.snapshot(),
+            analysis: self.host.analysis(),
+            loaded_files: Arc::clone(&self.loaded_files),
+            good_revisions: Arc::clone(&self.good_revisions),
+            config: Arc::clone(&self.config),
+            can_complete_snippets: self.can_complete_snippets,
         }
     }
 
+    /// Updates the server's configuration with the given settings sent by the
+    /// client.
+    pub fn update_config(&mut self, settings: &serde_json::Value) {
+        let mut config = Config::clone(&self.config);
+        config.update(settings);
+        log::trace!("Updated configuration: {:?}", config);
+        crate::logger::set_filter(config.log.filter.as_deref());
+        self.host.set_features(config.features());
+        self.config = Arc::new(config);
+    }
+
+    /// Updates the sources in the database with the files that changed in
+    /// the virtual file system since the last update, returning the files
+    /// that were updated.
+    ///
+    /// The documents opened in the editor change all the time, unlike the
+    /// files read from the disk, so the sources of the files on the disk are
+    /// more durable: the queries that only depend on them aren't validated
+    /// again after each edit of a document.
+    pub fn apply_vfs_changes(&mut self) -> Vec<FileId> {
+        use helios_query::Durability;
+
+        let mut file_ids = Vec::new();
+        for path in self.vfs.take_changes() {
+            // A file removed from memory that doesn't exist on the disk
+            // either keeps its last contents, since inputs can't be removed
+            if let Ok(source) = self.vfs.read(&path) {
+                let durability = if self.vfs.is_in_memory(&path) {
+                    Durability::LOW
+                } else {
+                    Durability::MEDIUM
+                };
+
+                let file_id = self.host.file_id(path);
+                self.host.set_source(file_id, source, durability);
+                Arc::make_mut(&mut self.loaded_files).insert(file_id);
+                file_ids.push(file_id);
+            }
+        }
+
+        file_ids
+    }
+
+    /// Remembers the results of the current source of the given file if it
+    /// parses without errors, as its last good revision (see [`Revision`]).
+    pub fn record_revision(&mut self, file_id: FileId, version: Option<i32>) {
+        let db = self.host.raw_database();
+        if db.parse(file_id).has_errors() {
+            return;
+        }
+
+        let revision = Revision {
+            version,
+            items: db.items(file_id),
+        };
+        Arc::make_mut(&mut self.good_revisions).insert(file_id, revision);
+    }
+
+    /// Computes the queries of the given file that requests need on another
+    /// thread, so that the first request after an edit doesn't have to (see
+    /// [`helios_query::revalidate`]).
+    pub fn revalidate_in_background(&self, file_id: FileId) {
+        let analysis = self.host.analysis();
+        std::thread::spawn(move || {
+            let revalidated = analysis
+                .with_db(|db| helios_query::revalidate(db, file_id))
+                .and_then(|it| it);
+            if revalidated.is_err() {
+                log::trace!("Cancelled the revalidation of {:?}", file_id);
+            }
+        });
+    }
+
+    /// Sends a request to the client, with an id that no other request sent
+    /// by the server has (so that the responses of the client can be told
+    /// apart).
+    pub fn send_request(&mut self, method: &str, params: impl Serialize) {
+        let id = format!("helios/{}", self.requests_sent);
+        self.requests_sent += 1;
+        self.send(Request::new(id, method, params));
+    }
+
     pub fn send(&mut self, message: impl Into<Message>) {
         self.sender
             .send(message.into())
@@ -48,5 +178,9 @@ impl State {
 }
 
 pub struct StateSnapshot {
-    pub(crate) db: salsa::Snapshot<HeliosDatabase>,
+    pub(crate) analysis: Analysis,
+    pub(crate) loaded_files: Arc<HashSet<FileId>>,
+    pub(crate) good_revisions: Arc<HashMap<FileId, Revision>>,
+    pub(crate) config: Arc<Config>,
+    pub(crate) can_complete_snippets: bool,
 }
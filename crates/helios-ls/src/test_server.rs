@@ -0,0 +1,240 @@
+//! An in-process test harness for the language server.
+//!
+//! The [`TestServer`] runs the server in a separate thread and communicates with
+//! it through in-memory channels instead of standard input and output. This
+//! allows us to test every handler end-to-end (from the JSON-RPC messages sent
+//! by the client to the messages sent back by the server) without having to
+//! spawn a process.
+
+use crate::protocol::{Message, Notification, Request, Response};
+use crate::server::Server;
+use crate::state::State;
+use crate::Result;
+use flume::{Receiver, Sender};
+use lsp_types::notification::Notification as LspNotification;
+use lsp_types::request::Request as LspRequest;
+use lsp_types::*;
+use std::collections::VecDeque;
+use std::thread;
+use std::time::Duration;
+
+/// How long we'll wait for the server to reply before failing the test.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+pub(crate) struct TestServer {
+    sender: Sender<Message>,
+    receiver: Receiver<Message>,
+    notifications: VecDeque<Notification>,
+    thread: Option<thread::JoinHandle<Result<()>>>,
+    next_id: i32,
+}
+
+impl TestServer {
+    /// Starts a new server that has already been initialized with the default
+    /// configuration.
+    pub(crate) fn new() -> Self {
+        Self::with_initialization_options(None)
+    }
+
+    /// Starts a new server that has already been initialized with the given
+    /// `initializationOptions`.
+    pub(crate) fn with_initialization_options(
+        options: impl Into<Option<serde_json::Value>>,
+    ) -> Self {
+        let mut server = Self::uninitialized();
+        server.initialize(options);
+        server
+    }
+
+    /// Starts a new server that has already been initialized with the given
+    /// client capabilities (and the default configuration).
+    pub(crate) fn with_capabilities(capabilities: ClientCapabilities) -> Self {
+        let mut server = Self::uninitialized();
+        server.initialize_with(None, capabilities);
+        server
+    }
+
+    /// Sends the `initialize` request and `initialized` notification to the
+    /// server.
+    pub(crate) fn initialize(
+        &mut self,
+        options: impl Into<Option<serde_json::Value>>,
+    ) {
+        self.initialize_with(options.into(), ClientCapabilities::default());
+    }
+
+    fn initialize_with(
+        &mut self,
+        options: Option<serde_json::Value>,
+        capabilities: ClientCapabilities,
+    ) {
+        #[allow(deprecated)]
+        let params = InitializeParams {
+            process_id: None,
+            root_path: None,
+            root_uri: None,
+            initialization_options: options,
+            capabilities,
+            trace: None,
+            workspace_folders: None,
+            client_info: None,
+            locale: None,
+        };
+
+        self.request::<request::Initialize>(params);
+        self.notify::<notification::Initialized>(InitializedParams {});
+    }
+
+    /// Starts a new server without initializing it.
+    pub(crate) fn uninitialized() -> Self {
+        let (client_tx, server_rx) = flume::unbounded();
+        let (server_tx, client_rx) = flume::unbounded();
+
+        let thread = thread::spawn(move || {
+            let mut state = State::new(server_tx, Default::default());
+            Server::new(server_rx, &mut state).initialize()?.run()
+        });
+
+        Self {
+            sender: client_tx,
+            receiver: client_rx,
+            notifications: VecDeque::new(),
+            thread: Some(thread),
+            next_id: 0,
+        }
+    }
+
+    /// Sends a typed request to the server and waits for its result.
+    ///
+    /// This method will panic if the server replies with an error.
+    pub(crate) fn request<R>(&mut self, params: R::Params) -> R::Result
+    where
+        R: LspRequest,
+    {
+        let response = self.send_request(R::METHOD, params);
+
+        if let Some(error) = response.error {
+            panic!("Request `{}` failed: {:?}", R::METHOD, error);
+        }
+
+        let result = response.result.unwrap_or_default();
+        serde_json::from_value(result).unwrap_or_else(|error| {
+            panic!("Invalid result for `{}`: {}", R::METHOD, error)
+        })
+    }
+
+    /// Sends a request to the server and waits for its response, which may
+    /// either be a result or an error.
+    pub(crate) fn send_request(
+        &mut self,
+        method: &str,
+        params: impl serde::Serialize,
+    ) -> Response {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.send(Request::new(id, method, params));
+
+        loop {
+            match self.recv() {
+                Message::Response(response) if response.id == id.into() => {
+                    return response
+                }
+                Message::Notification(notification) => {
+                    self.notifications.push_back(notification)
+                }
+                message => panic!("Unexpected message: {:?}", message),
+            }
+        }
+    }
+
+    /// Sends a typed notification to the server.
+    pub(crate) fn notify<N>(&mut self, params: N::Params)
+    where
+        N: LspNotification,
+    {
+        self.send_notification(N::METHOD, params);
+    }
+
+    /// Sends a notification to the server.
+    pub(crate) fn send_notification(
+        &mut self,
+        method: &str,
+        params: impl serde::Serialize,
+    ) {
+        self.send(Notification::new(method, params));
+    }
+
+    /// Waits for the next notification of the given type sent by the server,
+    /// skipping any other notifications received before it.
+    pub(crate) fn expect_notification<N>(&mut self) -> N::Params
+    where
+        N: LspNotification,
+    {
+        let notification = loop {
+            let notification = match self.notifications.pop_front() {
+                Some(notification) => notification,
+                None => match self.recv() {
+                    Message::Notification(notification) => notification,
+                    message => panic!("Unexpected message: {:?}", message),
+                },
+            };
+
+            if notification.method == N::METHOD {
+                break notification;
+            }
+        };
+
+        serde_json::from_value(notification.params).unwrap_or_else(|error| {
+            panic!("Invalid params for `{}`: {}", N::METHOD, error)
+        })
+    }
+
+    /// Opens a document with the given URI and text.
+    pub(crate) fn open(&mut self, uri: &str, text: &str) -> Url {
+        let uri = Url::parse(uri).expect("Invalid URI");
+        let params = DidOpenTextDocumentParams {
+            text_document: TextDocumentItem::new(
+                uri.clone(),
+                "helios".to_string(),
+                0,
+                text.to_string(),
+            ),
+        };
+
+        self.notify::<notification::DidOpenTextDocument>(params);
+        uri
+    }
+
+    fn send(&self, message: impl Into<Message>) {
+        self.sender
+            .send(message.into())
+            .expect("Server has disconnected");
+    }
+
+    fn recv(&self) -> Message {
+        self.receiver
+            .recv_timeout(TIMEOUT)
+            .expect("Server did not reply in time")
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        // There's no use in shutting down gracefully if the server has already
+        // stopped (or if we're panicking because of it)
+        if thread::panicking() || self.sender.is_disconnected() {
+            return;
+        }
+
+        self.request::<request::Shutdown>(());
+        self.notify::<notification::Exit>(());
+
+        if let Some(thread) = self.thread.take() {
+            thread
+                .join()
+                .expect("Server thread panicked")
+                .expect("Server failed");
+        }
+    }
+}
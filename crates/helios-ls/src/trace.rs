@@ -0,0 +1,346 @@
+//! Tracing of the messages exchanged between the client and the server.
+//!
+//! Every message read from or written to the client is passed to a [`Tracer`],
+//! which logs it (along with a timestamp and its direction) to standard error
+//! or to a file. This makes it possible to diagnose protocol mismatches between
+//! a client and the server without having to attach a debugger to either.
+//!
+//! The tracer is enabled either with the `--trace` command line option or by
+//! the client with the `trace` field of the `initialize` request and the
+//! `$/setTrace` notification.
+
+use crate::protocol::Message;
+use lsp_types::notification::Notification;
+use lsp_types::TraceOption;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display};
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The `$/setTrace` notification, which isn't provided by `lsp_types`.
+pub(crate) enum SetTrace {}
+
+impl Notification for SetTrace {
+    type Params = SetTraceParams;
+    const METHOD: &'static str = "$/setTrace";
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct SetTraceParams {
+    /// The new value that should be assigned to the trace setting.
+    pub(crate) value: TraceOption,
+}
+
+/// The direction in which a traced message travelled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// The message was sent by the client to the server.
+    Incoming,
+    /// The message was sent by the server to the client.
+    Outgoing,
+}
+
+impl Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Direction::Incoming => write!(f, "-->"),
+            Direction::Outgoing => write!(f, "<--"),
+        }
+    }
+}
+
+/// Where the traced messages should be written to.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum TraceOutput {
+    /// Write the messages to standard error.
+    #[default]
+    Stderr,
+    /// Write the messages to the file at the given path, which is truncated
+    /// beforehand.
+    File(PathBuf),
+}
+
+/// The options the tracer is started with.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TraceOptions {
+    /// Whether the tracer is enabled from the start (with `--trace`).
+    pub enabled: bool,
+    /// Where the traced messages should be written to.
+    pub output: TraceOutput,
+    /// The maximum number of bytes of a message's payload to write. Longer
+    /// payloads are truncated. If `None`, the payload is never truncated.
+    pub max_payload_len: Option<usize>,
+}
+
+/// Logs the messages exchanged between the client and the server.
+///
+/// The tracer is shared between the threads reading and writing messages and
+/// the server (which changes its level when receiving `$/setTrace`), hence the
+/// interior mutability.
+pub struct Tracer {
+    level: AtomicU8,
+    forced: bool,
+    max_payload_len: Option<usize>,
+    output: Mutex<Box<dyn Write + Send>>,
+}
+
+impl Tracer {
+    pub fn new(options: TraceOptions) -> io::Result<Self> {
+        let output: Box<dyn Write + Send> = match &options.output {
+            TraceOutput::Stderr => Box::new(io::stderr()),
+            TraceOutput::File(path) => Box::new(File::create(path)?),
+        };
+
+        Ok(Self::with_output(options, output))
+    }
+
+    fn with_output(
+        options: TraceOptions,
+        output: Box<dyn Write + Send>,
+    ) -> Self {
+        let level = if options.enabled {
+            TraceOption::Verbose
+        } else {
+            TraceOption::Off
+        };
+
+        Self {
+            level: AtomicU8::new(level_to_u8(level)),
+            forced: options.enabled,
+            max_payload_len: options.max_payload_len,
+            output: Mutex::new(output),
+        }
+    }
+
+    /// A tracer that starts disabled and writes to standard error.
+    pub fn disabled() -> Self {
+        Self::with_output(TraceOptions::default(), Box::new(io::stderr()))
+    }
+
+    pub fn level(&self) -> TraceOption {
+        level_from_u8(self.level.load(Ordering::Relaxed))
+    }
+
+    /// Sets the level of the tracer as requested by the client.
+    pub fn set_level(&self, level: TraceOption) {
+        log::trace!("Setting trace level to {:?}", level);
+        self.level.store(level_to_u8(level), Ordering::Relaxed);
+    }
+
+    /// Sets the initial level of the tracer as sent in the `initialize`
+    /// request.
+    ///
+    /// Clients usually send `off` by default, so this is ignored if tracing was
+    /// explicitly enabled on the command line (a later `$/setTrace` is not).
+    pub fn set_initial_level(&self, level: TraceOption) {
+        if !self.forced {
+            self.set_level(level);
+        }
+    }
+
+    /// Traces a message with the given raw JSON content.
+    ///
+    /// At the `messages` level, only a summary of the message (its kind,
+    /// method and id) is written. At the `verbose` level, its (possibly
+    /// truncated) content is written as well.
+    pub fn trace(
+        &self,
+        direction: Direction,
+        message: &Message,
+        content: &str,
+    ) {
+        self.write(direction, MessageSummary(message), content);
+    }
+
+    /// Traces the raw content of a message that couldn't be parsed, which is
+    /// summarized as malformed (since it has no kind, method or id).
+    pub fn trace_malformed(&self, direction: Direction, content: &str) {
+        self.write(direction, "malformed message", content);
+    }
+
+    fn write(
+        &self,
+        direction: Direction,
+        summary: impl Display,
+        content: &str,
+    ) {
+        let level = self.level();
+        if level == TraceOption::Off {
+            return;
+        }
+
+        let mut line = format!("[{}] {} {}", timestamp(), direction, summary);
+
+        if level == TraceOption::Verbose {
+            line.push_str(": ");
+            line.push_str(&self.truncate(content));
+        }
+
+        let mut output = match self.output.lock() {
+            Ok(output) => output,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        if let Err(error) = writeln!(output, "{}", line) {
+            log::error!("Failed to write trace: {}", error);
+        }
+    }
+
+    fn truncate(&self, content: &str) -> String {
+        match self.max_payload_len {
+            Some(max_len) if content.len() > max_len => {
+                let mut end = max_len;
+                while !content.is_char_boundary(end) {
+                    end -= 1;
+                }
+
+                let truncated = content.len() - end;
+                format!("{}... ({} more bytes)", &content[..end], truncated)
+            }
+            _ => content.to_string(),
+        }
+    }
+}
+
+impl Default for Tracer {
+    fn default() -> Self {
+        Self::disabled()
+    }
+}
+
+fn level_to_u8(level: TraceOption) -> u8 {
+    match level {
+        TraceOption::Off => 0,
+        TraceOption::Messages => 1,
+        TraceOption::Verbose => 2,
+    }
+}
+
+fn level_from_u8(level: u8) -> TraceOption {
+    match level {
+        0 => TraceOption::Off,
+        1 => TraceOption::Messages,
+        _ => TraceOption::Verbose,
+    }
+}
+
+/// The current time as seconds (with millisecond precision) since the Unix
+/// epoch.
+pub(crate) fn timestamp() -> String {
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+
+    format!("{}.{:03}", elapsed.as_secs(), elapsed.subsec_millis())
+}
+
+struct MessageSummary<'a>(&'a Message);
+
+impl Display for MessageSummary<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Message::Request(request) => {
+                write!(f, "request `{}` ({})", request.method, request.id)
+            }
+            Message::Response(response) if response.error.is_some() => {
+                write!(f, "error response ({})", response.id)
+            }
+            Message::Response(response) => {
+                write!(f, "response ({})", response.id)
+            }
+            Message::Notification(notification) => {
+                write!(f, "notification `{}`", notification.method)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::Request;
+    use std::sync::Arc;
+
+    /// A writer that can be inspected after it has been given to the tracer.
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedBuffer {
+        fn take(&self) -> String {
+            let bytes = std::mem::take(&mut *self.0.lock().unwrap());
+            String::from_utf8(bytes).unwrap()
+        }
+    }
+
+    #[test]
+    fn test_trace_levels() {
+        let buffer = SharedBuffer::default();
+        let options = TraceOptions {
+            max_payload_len: Some(10),
+            ..TraceOptions::default()
+        };
+
+        let tracer = Tracer::with_output(options, Box::new(buffer.clone()));
+        let message = Request::new_without_params(0, "shutdown").into();
+        let content = r#"{"id":0,"method":"shutdown"}"#;
+
+        tracer.trace(Direction::Incoming, &message, content);
+        assert_eq!(buffer.take(), "");
+
+        tracer.set_level(TraceOption::Messages);
+        tracer.trace(Direction::Incoming, &message, content);
+        let line = buffer.take();
+        assert!(line.ends_with("] --> request `shutdown` (0)\n"), "{}", line);
+
+        tracer.set_level(TraceOption::Verbose);
+        tracer.trace(Direction::Outgoing, &message, content);
+        let line = buffer.take();
+        let expected =
+            r#"<-- request `shutdown` (0): {"id":0,"m... (18 more bytes)"#;
+        assert!(line.trim_end().ends_with(expected), "{}", line);
+    }
+
+    #[test]
+    fn test_trace_malformed_message() {
+        let buffer = SharedBuffer::default();
+        let options = TraceOptions {
+            enabled: true,
+            ..TraceOptions::default()
+        };
+
+        let tracer = Tracer::with_output(options, Box::new(buffer.clone()));
+        tracer.trace_malformed(Direction::Incoming, r#"{"id":"#);
+        let line = buffer.take();
+        let expected = r#"] --> malformed message: {"id":"#;
+        assert!(line.trim_end().ends_with(expected), "{}", line);
+    }
+
+    #[test]
+    fn test_forced_trace_level() {
+        let options = TraceOptions {
+            enabled: true,
+            ..TraceOptions::default()
+        };
+
+        let tracer = Tracer::with_output(options, Box::new(io::sink()));
+        tracer.set_initial_level(TraceOption::Off);
+        assert_eq!(tracer.level(), TraceOption::Verbose);
+
+        tracer.set_level(TraceOption::Messages);
+        assert_eq!(tracer.level(), TraceOption::Messages);
+    }
+}
@@ -0,0 +1,39 @@
+//! End-to-end tests of the command line of the `helios-ls` executable.
+
+use std::process::Command;
+
+/// Runs the executable with the given arguments, returning its exit code and
+/// its standard output and error.
+fn helios_ls(args: &[&str]) -> (Option<i32>, String, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_helios-ls"))
+        .args(args)
+        .env_remove("RUST_LOG")
+        .output()
+        .expect("Failed to run `helios-ls`");
+
+    (
+        output.status.code(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn test_usage_errors() {
+    let (code, stdout, stderr) = helios_ls(&["--frob"]);
+    assert_eq!(code, Some(2));
+    assert!(stdout.starts_with("USAGE:"), "{}", stdout);
+    assert_eq!(stderr, "ERROR: Unrecognised option `--frob`\n");
+
+    let (code, _, stderr) = helios_ls(&["--port"]);
+    assert_eq!(code, Some(2));
+    assert_eq!(stderr, "ERROR: Missing argument for option `--port`\n");
+
+    let (code, _, stderr) = helios_ls(&["--trace-max-len", "many"]);
+    assert_eq!(code, Some(2));
+    assert_eq!(stderr, "ERROR: Invalid payload length `many`\n");
+
+    let (code, stdout, _) = helios_ls(&["--help"]);
+    assert_eq!(code, Some(0));
+    assert!(stdout.starts_with("USAGE:"), "{}", stdout);
+}
@@ -38,9 +38,8 @@ impl<'source> Cursor<'source> {
 
     /// Advance to the next character in the iterator.
     pub fn advance(&mut self) -> Option<char> {
-        self.chars.next().map(|next_char| {
+        self.chars.next().inspect(|next_char| {
             self.pos += next_char.len_utf8();
-            next_char
         })
     }
 
@@ -123,7 +122,7 @@ mod tests {
 
     #[test]
     fn test_cursor_empty() {
-        let mut cursor = Cursor::new("".into());
+        let mut cursor = Cursor::new("");
         assert_eq!(cursor.source_len(), 0);
 
         // Peeking out-of-bounds character
@@ -132,7 +131,7 @@ mod tests {
 
         // Try to consume out-of-bounds character
         assert_eq!(cursor.advance(), None);
-        assert_eq!(cursor.is_at_end(), true);
+        assert!(cursor.is_at_end());
         assert_eq!(cursor.pos(), 0);
     }
 
@@ -151,7 +150,7 @@ mod tests {
         assert_eq!(cursor.advance(), Some('a'));
         assert_eq!(cursor.advance(), Some('b'));
         assert_eq!(cursor.advance(), Some('c'));
-        assert_eq!(cursor.is_at_end(), false);
+        assert!(!cursor.is_at_end());
         assert_eq!(cursor.pos(), 3);
 
         // Peeking next three characters
@@ -164,7 +163,7 @@ mod tests {
         assert_eq!(cursor.advance(), Some('1'));
         assert_eq!(cursor.advance(), Some('2'));
         assert_eq!(cursor.advance(), Some('3'));
-        assert_eq!(cursor.is_at_end(), true);
+        assert!(cursor.is_at_end());
         assert_eq!(cursor.pos(), 6);
 
         // Peeking out-of-bounds character
@@ -173,7 +172,7 @@ mod tests {
 
         // Try to consume out-of-bounds character
         assert_eq!(cursor.advance(), None);
-        assert_eq!(cursor.is_at_end(), true);
+        assert!(cursor.is_at_end());
         assert_eq!(cursor.pos(), 6);
     }
 
@@ -0,0 +1,207 @@
+//! Finding the edits between two versions of a source text.
+//!
+//! A formatter could replace a whole document with the formatted text, but
+//! editors then lose the cursor position and the undo history of the parts
+//! that didn't change. [`diff`] compares the tokens of the two texts instead
+//! (including the trivia), so that only the tokens that were changed (which
+//! are mostly whitespace) are edited.
+
+use crate::lexer::Token;
+use helios_text::TextEdit;
+
+/// The largest table compared with the longest common subsequence of two
+/// token streams. The tokens of larger changes (after leaving out their
+/// common start and end) are replaced all at once.
+const MAX_TABLE_SIZE: usize = 1 << 22;
+
+/// Finds the edits turning the original text into the formatted text, sorted
+/// by their ranges.
+///
+/// The edits are found by comparing the tokens of both texts, so they are
+/// made of whole tokens, but the text that a token shares with the token it
+/// replaces (such as the spaces of an indentation that was widened) is left
+/// out of the edit.
+pub fn diff(original: &str, formatted: &str) -> Vec<TextEdit> {
+    let (old, _) = crate::tokenize(0usize, original);
+    let (new, _) = crate::tokenize(0usize, formatted);
+
+    // The common start and end are left out of the table, since formatting
+    // usually doesn't change every line
+    let prefix = old
+        .iter()
+        .zip(&new)
+        .take_while(|(a, b)| is_same(a, b))
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| is_same(a, b))
+        .count();
+
+    let old_tokens = &old[prefix..old.len() - suffix];
+    let new_tokens = &new[prefix..new.len() - suffix];
+
+    // The offset of the given token of the original text (or the end of the
+    // tokens compared), where the tokens inserted before it go
+    let end = old.get(old.len() - suffix);
+    let end = end.map_or(original.len(), |token| token.range.start);
+    let offset = |i: usize| old_tokens.get(i).map_or(end, |it| it.range.start);
+
+    let mut diff = Diff {
+        original,
+        formatted,
+        edits: Vec::new(),
+    };
+
+    if old_tokens.len() * new_tokens.len() > MAX_TABLE_SIZE {
+        diff.push(old_tokens, new_tokens, offset(0));
+        return diff.edits;
+    }
+
+    // The length of the longest common subsequence of `old_tokens[i..]` and
+    // `new_tokens[j..]`, at `i * (new_tokens.len() + 1) + j`
+    let width = new_tokens.len() + 1;
+    let mut lengths = vec![0u32; (old_tokens.len() + 1) * width];
+    for i in (0..old_tokens.len()).rev() {
+        for j in (0..new_tokens.len()).rev() {
+            lengths[i * width + j] = if is_same(&old_tokens[i], &new_tokens[j])
+            {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                let skip_old = lengths[(i + 1) * width + j];
+                let skip_new = lengths[i * width + j + 1];
+                std::cmp::max(skip_old, skip_new)
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let (mut old_start, mut new_start) = (0, 0);
+    while i < old_tokens.len() && j < new_tokens.len() {
+        if is_same(&old_tokens[i], &new_tokens[j]) {
+            let (old, new) =
+                (&old_tokens[old_start..i], &new_tokens[new_start..j]);
+            diff.push(old, new, offset(i));
+            i += 1;
+            j += 1;
+            old_start = i;
+            new_start = j;
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    let (old, new) = (&old_tokens[old_start..], &new_tokens[new_start..]);
+    diff.push(old, new, end);
+    diff.edits
+}
+
+struct Diff<'a> {
+    original: &'a str,
+    formatted: &'a str,
+    edits: Vec<TextEdit>,
+}
+
+impl Diff<'_> {
+    /// Records the edit replacing the given (consecutive) tokens of the
+    /// original text with the given tokens of the formatted text, where the
+    /// new tokens are inserted at the given offset if there are no old ones.
+    fn push(&mut self, old: &[Token], new: &[Token], offset: usize) {
+        if old.is_empty() && new.is_empty() {
+            return;
+        }
+
+        let old_range = match (old.first(), old.last()) {
+            (Some(first), Some(last)) => first.range.start..last.range.end,
+            _ => offset..offset,
+        };
+        let new_range = match (new.first(), new.last()) {
+            (Some(first), Some(last)) => first.range.start..last.range.end,
+            _ => 0..0,
+        };
+
+        let old_text = &self.original[old_range.clone()];
+        let new_text = &self.formatted[new_range];
+
+        let prefix = common_prefix(old_text, new_text);
+        let (old_text, new_text) = (&old_text[prefix..], &new_text[prefix..]);
+        let suffix = common_suffix(old_text, new_text);
+
+        let start = old_range.start + prefix;
+        let end = old_range.end - suffix;
+        let replacement = &new_text[..new_text.len() - suffix];
+        self.edits.push(TextEdit::new(start..end, replacement));
+    }
+}
+
+fn is_same(a: &Token, b: &Token) -> bool {
+    a.kind == b.kind && a.text == b.text
+}
+
+/// The length in bytes of the common start of the given texts.
+fn common_prefix(a: &str, b: &str) -> usize {
+    a.char_indices()
+        .zip(b.chars())
+        .find(|((_, a), b)| a != b)
+        .map_or(std::cmp::min(a.len(), b.len()), |((index, _), _)| index)
+}
+
+/// The length in bytes of the common end of the given texts.
+fn common_suffix(a: &str, b: &str) -> usize {
+    a.chars()
+        .rev()
+        .zip(b.chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use helios_text::apply_edits;
+
+    fn check(original: &str, formatted: &str) -> Vec<TextEdit> {
+        let edits = diff(original, formatted);
+        assert_eq!(apply_edits(original, &edits), formatted);
+        edits
+    }
+
+    #[test]
+    fn test_diff_whitespace() {
+        assert_eq!(
+            check("let a=1\nlet  b = 2\n", "let a = 1\nlet b = 2\n"),
+            vec![
+                TextEdit::insert(5, " "),
+                TextEdit::insert(6, " "),
+                TextEdit::delete(12..13),
+            ]
+        );
+
+        // Only the spaces added to the indentation are inserted
+        assert_eq!(
+            check("type A =\n  | B\n  | C", "type A =\n    | B\n    | C"),
+            vec![TextEdit::insert(11, "  "), TextEdit::insert(17, "  ")]
+        );
+    }
+
+    #[test]
+    fn test_diff_tokens() {
+        assert_eq!(check("1 + 2", "1 + 2"), vec![]);
+        assert_eq!(
+            check("let a = (b)", "let a = b"),
+            vec![TextEdit::delete(8..9), TextEdit::delete(10..11)]
+        );
+        assert_eq!(
+            check("let λ = 1", "let λ = 1\nlet μ = 2"),
+            vec![TextEdit::insert(10, "\nlet μ = 2")]
+        );
+
+        check("", "let a = 1");
+        check("let a = 1", "");
+        check("a -- comment\nb", "a  -- comment\n\nb");
+    }
+}
@@ -0,0 +1,262 @@
+//! Queries about the structure of source code for editors, such as which
+//! bracket matches the one under the cursor, how deep to indent a line or
+//! whether an expression is being typed at the cursor.
+//!
+//! These are computed from the tokens of the syntax tree, so that brackets in
+//! strings, characters and comments are ignored like the parser ignores them.
+//! The brackets are paired by how they nest in the tokens rather than by the
+//! nodes of the tree, since the parser recovers from unbalanced brackets by
+//! putting them in different nodes.
+
+use crate::parser::{closing_delimiter, is_closing_delimiter};
+use helios_syntax::{SyntaxKind, SyntaxNode, SyntaxToken};
+use std::ops::Range;
+
+fn token_range(token: &SyntaxToken) -> Range<usize> {
+    let range = token.text_range();
+    range.start().into()..range.end().into()
+}
+
+/// Pairs the brackets of the given tree, returning the ranges of each pair in
+/// the order of their opening brackets.
+///
+/// A closing bracket closes the innermost opening bracket of its kind, and the
+/// opening brackets it skips over are left unpaired (like the brackets that
+/// are never closed and those that close nothing).
+fn bracket_pairs(root: &SyntaxNode) -> Vec<(Range<usize>, Range<usize>)> {
+    let mut open: Vec<(SyntaxKind, Range<usize>)> = Vec::new();
+    let mut pairs = Vec::new();
+
+    for token in root
+        .descendants_with_tokens()
+        .filter_map(|it| it.into_token())
+    {
+        let kind = token.kind();
+        if let Some(closing) = closing_delimiter(kind) {
+            open.push((closing, token_range(&token)));
+        } else if is_closing_delimiter(kind) {
+            if let Some(index) = open.iter().rposition(|(it, _)| *it == kind) {
+                let (_, opening) = open.remove(index);
+                open.truncate(index);
+                pairs.push((opening, token_range(&token)));
+            }
+        }
+    }
+
+    pairs.sort_by_key(|(opening, _)| opening.start);
+    pairs
+}
+
+/// The ranges of the bracket at the given offset of the tree and of the
+/// bracket matching it, if there is one.
+///
+/// The bracket at an offset is the one starting there, or else the one ending
+/// there (where the cursor is right after it).
+pub fn bracket_pair_at(
+    root: &SyntaxNode,
+    offset: usize,
+) -> Option<(Range<usize>, Range<usize>)> {
+    let pairs = bracket_pairs(root);
+    let find = |matches: &dyn Fn(&Range<usize>) -> bool| {
+        pairs.iter().find_map(|(opening, closing)| {
+            if matches(opening) {
+                Some((opening.clone(), closing.clone()))
+            } else if matches(closing) {
+                Some((closing.clone(), opening.clone()))
+            } else {
+                None
+            }
+        })
+    };
+
+    find(&|range| range.start == offset)
+        .or_else(|| find(&|range| range.end == offset))
+}
+
+/// The offset of the bracket matching the one at the given offset of the
+/// source (see [`bracket_pair_at`]), if there is one.
+pub fn matching_bracket(source: &str, offset: usize) -> Option<usize> {
+    let root = crate::parse((), source).syntax();
+    bracket_pair_at(&root, offset).map(|(_, matching)| matching.start)
+}
+
+/// The range of the missing expression at the given offset of the tree, if
+/// there is one, which is where the parser expected an expression that hasn't
+/// been written yet (such as after `let a = ` or `a + `, see
+/// [`SyntaxKind::Exp_Missing`]).
+///
+/// The cursor is at a missing expression when only whitespace separates it
+/// from the token before the expression (leaving out the tokens the parser
+/// inserted, such as the `=` of `let `). If the whitespace includes a line
+/// break, the rest of the cursor's line must also be blank, since the code
+/// already written there (such as the next declaration) isn't the expression.
+pub fn missing_expression_at(
+    root: &SyntaxNode,
+    offset: usize,
+) -> Option<Range<usize>> {
+    let text = root.to_string();
+    root.descendants()
+        .filter(|node| node.kind() == SyntaxKind::Exp_Missing)
+        .find_map(|node| {
+            let start = node.text_range().start().into();
+            let before = root
+                .descendants_with_tokens()
+                .filter_map(|it| it.into_token())
+                .filter(|token| {
+                    !token.kind().is_trivia() && !token.text().is_empty()
+                })
+                .map(|token| usize::from(token.text_range().end()))
+                .take_while(|&end| end <= start)
+                .last()
+                .unwrap_or(start);
+
+            let gap = text.get(before..offset)?;
+            let rest_of_line = text[offset..].split('\n').next().unwrap();
+            let is_blank = |text: &str| text.chars().all(char::is_whitespace);
+            if is_blank(gap) && (!gap.contains('\n') || is_blank(rest_of_line))
+            {
+                Some(start..start)
+            } else {
+                None
+            }
+        })
+}
+
+/// The number of levels of indentation of the given line (counting from
+/// `0`) of the tree, which is the number of brackets still open at its start.
+///
+/// A line starting with a closing bracket is indented like the line of the
+/// bracket it closes.
+pub fn indent_level_in(root: &SyntaxNode, line: usize) -> usize {
+    let text = root.to_string();
+    let start = line_start(&text, line);
+
+    let pairs = bracket_pairs(root);
+    let open = pairs
+        .iter()
+        .filter(|(opening, closing)| {
+            opening.start < start && closing.start >= start
+        })
+        .count();
+    let unclosed = unclosed_brackets(root, &pairs, start);
+
+    let level = open + unclosed;
+    let first = root
+        .descendants_with_tokens()
+        .filter_map(|it| it.into_token())
+        .skip_while(|token| usize::from(token.text_range().start()) < start)
+        .find(|token| {
+            token.kind() != SyntaxKind::Whitespace
+                || token.text().contains('\n')
+        });
+
+    match first {
+        Some(token) if is_closing_delimiter(token.kind()) => {
+            let offset = token.text_range().start().into();
+            let closes =
+                pairs.iter().any(|(_, closing)| closing.start == offset);
+            level - usize::from(closes && level > 0)
+        }
+        _ => level,
+    }
+}
+
+/// The number of levels of indentation of the given line of the source (see
+/// [`indent_level_in`]).
+pub fn indent_level_at(source: &str, line: usize) -> usize {
+    indent_level_in(&crate::parse((), source).syntax(), line)
+}
+
+/// The number of opening brackets before the given offset that are never
+/// closed (such as while typing a block).
+fn unclosed_brackets(
+    root: &SyntaxNode,
+    pairs: &[(Range<usize>, Range<usize>)],
+    offset: usize,
+) -> usize {
+    root.descendants_with_tokens()
+        .filter_map(|it| it.into_token())
+        .filter(|token| closing_delimiter(token.kind()).is_some())
+        .map(|token| token_range(&token))
+        .filter(|range| range.start < offset)
+        .filter(|range| pairs.iter().all(|(opening, _)| opening != range))
+        .count()
+}
+
+/// The offset of the start of the given line of the text (or of its end, if
+/// it has fewer lines).
+fn line_start(text: &str, line: usize) -> usize {
+    if line == 0 {
+        return 0;
+    }
+
+    text.match_indices('\n')
+        .nth(line - 1)
+        .map_or(text.len(), |(index, _)| index + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matching_bracket() {
+        let source = "let a = ({ [1] }, \"(\")\n";
+        assert_eq!(matching_bracket(source, 8), Some(21));
+        assert_eq!(matching_bracket(source, 21), Some(8));
+        assert_eq!(matching_bracket(source, 9), Some(15));
+        assert_eq!(matching_bracket(source, 11), Some(13));
+
+        // Right after a bracket
+        assert_eq!(matching_bracket(source, 22), Some(8));
+
+        // Not a bracket, or in a string
+        assert_eq!(matching_bracket(source, 4), None);
+        assert_eq!(matching_bracket(source, 19), None);
+    }
+
+    #[test]
+    fn test_unbalanced_brackets() {
+        // The parenthesis skipped over by the brace is left unpaired
+        let source = "{ ( }";
+        assert_eq!(matching_bracket(source, 0), Some(4));
+        assert_eq!(matching_bracket(source, 2), None);
+        assert_eq!(matching_bracket("a )", 2), None);
+    }
+
+    #[test]
+    fn test_missing_expression_at() {
+        let missing_expression = |source: &str, offset| {
+            let root = crate::parse((), source).syntax();
+            missing_expression_at(&root, offset)
+        };
+
+        let source = "let a = 1\nlet b = ";
+        assert_eq!(missing_expression(source, 18), Some(18..18));
+        assert_eq!(missing_expression(source, 17), Some(18..18));
+        assert_eq!(missing_expression(source, 8), None);
+
+        let source = "let c = a +  \nlet d = 2";
+        assert_eq!(missing_expression(source, 11), Some(11..11));
+        assert_eq!(missing_expression(source, 13), Some(11..11));
+        assert_eq!(missing_expression(source, 10), None);
+        assert_eq!(missing_expression(source, 14), None);
+
+        let source = "let e =\n\nlet f = 3";
+        assert_eq!(missing_expression(source, 8), Some(7..7));
+        assert_eq!(missing_expression(source, 9), None);
+
+        // The `=` is missing, rather than written right before the cursor
+        assert_eq!(missing_expression("let ", 4), Some(4..4));
+        assert_eq!(missing_expression("let", 3), Some(3..3));
+    }
+
+    #[test]
+    fn test_indent_level_at() {
+        let source = "let a = f(\n  {\n    1\n  }\n)\nlet b = [\n";
+        let levels = (0..7)
+            .map(|line| indent_level_at(source, line))
+            .collect::<Vec<_>>();
+        assert_eq!(levels, vec![0, 1, 2, 1, 0, 0, 1]);
+    }
+}
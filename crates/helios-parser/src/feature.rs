@@ -0,0 +1,125 @@
+//! Experimental parts of the grammar, which have to be enabled to be used.
+//!
+//! The syntax of a feature is always parsed, whether the feature is enabled
+//! or not, so that the grammar can evolve without breaking the code not using
+//! it. Using a feature that isn't enabled is reported as an error at the
+//! syntax introducing it (see [`ParseOptions::features`]).
+//!
+//! [`ParseOptions::features`]: crate::ParseOptions::features
+
+use std::str::FromStr;
+
+/// An experimental part of the language, which has to be enabled to be used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Feature {
+    /// Blocks delimited by indentation instead of braces (see the [`layout`]
+    /// module for the rules).
+    ///
+    /// [`layout`]: crate::layout
+    IndentationBlocks,
+    /// The backward pipeline operator (`f <| x`, which is `x |> f`).
+    BackwardPipelines,
+}
+
+impl Feature {
+    /// Every feature, in the order they're listed in messages.
+    pub const ALL: &'static [Feature] =
+        &[Self::IndentationBlocks, Self::BackwardPipelines];
+
+    /// The name of the feature in manifests and on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::IndentationBlocks => "indentation-blocks",
+            Self::BackwardPipelines => "backward-pipelines",
+        }
+    }
+
+    /// A description of the syntax of the feature, for messages.
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::IndentationBlocks => "Blocks delimited by indentation",
+            Self::BackwardPipelines => "The backward pipeline operator `<|`",
+        }
+    }
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+impl FromStr for Feature {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|feature| feature.name() == s)
+            .ok_or_else(|| format!("Unknown feature `{}`", s))
+    }
+}
+
+/// A set of features.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Features(u8);
+
+impl Features {
+    /// Constructs an empty set of features.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every feature there is.
+    pub fn all() -> Self {
+        Feature::ALL
+            .iter()
+            .fold(Self::new(), |features, &feature| features.with(feature))
+    }
+
+    /// The same set of features, with the given one enabled.
+    pub fn with(mut self, feature: Feature) -> Self {
+        self.enable(feature);
+        self
+    }
+
+    pub fn enable(&mut self, feature: Feature) {
+        self.0 |= feature.bit();
+    }
+
+    pub fn is_enabled(self, feature: Feature) -> bool {
+        self.0 & feature.bit() != 0
+    }
+
+    /// The features enabled, in the order of [`Feature::ALL`].
+    pub fn iter(self) -> impl Iterator<Item = Feature> {
+        Feature::ALL
+            .iter()
+            .copied()
+            .filter(move |&feature| self.is_enabled(feature))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_features() {
+        let features = Features::new().with(Feature::BackwardPipelines);
+        assert!(features.is_enabled(Feature::BackwardPipelines));
+        assert!(!features.is_enabled(Feature::IndentationBlocks));
+        assert_eq!(
+            features.iter().collect::<Vec<_>>(),
+            vec![Feature::BackwardPipelines]
+        );
+
+        assert_eq!(Features::all().iter().collect::<Vec<_>>(), Feature::ALL);
+        for &feature in Feature::ALL {
+            assert_eq!(feature.name().parse(), Ok(feature));
+        }
+        assert_eq!(
+            "macros".parse::<Feature>(),
+            Err("Unknown feature `macros`".to_string())
+        );
+    }
+}
@@ -6,6 +6,8 @@ use helios_syntax::SyntaxKind;
 
 mod decl;
 mod expr;
+mod operator;
+mod ty;
 
 pub(crate) fn root<FileId>(p: &mut Parser<FileId>) -> CompletedMarker
 where
@@ -31,16 +33,16 @@ mod tests {
             "let a = 1\na",
             expect![[r#"
                 Root@0..11
-                  Dec_GlobalBinding@0..10
+                  Dec_GlobalBinding@0..9
                     Kwd_Let@0..3 "let"
                     Whitespace@3..4 " "
                     Identifier@4..5 "a"
                     Whitespace@5..6 " "
                     Sym_Eq@6..7 "="
                     Whitespace@7..8 " "
-                    Exp_Literal@8..10
+                    Exp_Literal@8..9
                       Lit_Integer@8..9 "1"
-                      Whitespace@9..10 "\n"
+                  Whitespace@9..10 "\n"
                   Exp_VariableRef@10..11
                     Identifier@10..11 "a"
             "#]],
@@ -1,22 +1,60 @@
+use super::operator::OPERATOR_TABLE;
 use super::*;
+use crate::parser::marker::Marker;
+use helios_syntax::Sym;
 
 pub(super) fn decl<FileId>(p: &mut Parser<FileId>) -> Option<CompletedMarker>
 where
     FileId: Clone + Default,
 {
-    if p.is_at(SyntaxKind::Kwd_Let) {
-        Some(global_binding(p))
+    // The node of the declaration starts before its visibility modifier
+    let m = p.start();
+
+    let is_public = p.is_at(SyntaxKind::Kwd_Public);
+    if is_public {
+        let visibility = p.start();
+        p.bump();
+        visibility.complete(p, SyntaxKind::Visibility);
+    }
+
+    if p.is_at_either(&[SyntaxKind::Kwd_Let, SyntaxKind::Kwd_Var])
+        .is_some()
+    {
+        Some(global_binding(p, m))
+    } else if p.is_at(SyntaxKind::Kwd_Type) {
+        Some(type_enum(p, m))
+    } else if p.is_at(SyntaxKind::Kwd_External) {
+        Some(external(p, m))
+    } else if p.is_at(SyntaxKind::Kwd_Interface) {
+        Some(interface(p, m))
+    } else if is_public {
+        // Only the declarations above may be public, so the modifier is
+        // skipped along with the token after it (in a single node)
+        if p.report_error(None) {
+            p.bump();
+        }
+        Some(m.complete(p, SyntaxKind::Error))
+    } else if p.is_at(SyntaxKind::Kwd_Import) {
+        m.abandon(p);
+        Some(import(p))
+    } else if p.is_at(SyntaxKind::Kwd_Extend) {
+        m.abandon(p);
+        Some(extend(p))
     } else {
+        m.abandon(p);
         expr::expr(p, 0)
     }
 }
 
-fn global_binding<FileId>(p: &mut Parser<FileId>) -> CompletedMarker
+/// Parses a global binding, which is either immutable (`let a = 1`) or
+/// mutable (`var a = 1`, which may then be assigned to with `a <- 2`).
+fn global_binding<FileId>(p: &mut Parser<FileId>, m: Marker) -> CompletedMarker
 where
     FileId: Clone + Default,
 {
-    assert!(p.is_at(SyntaxKind::Kwd_Let));
-    let m = p.start();
+    assert!(p
+        .is_at_either(&[SyntaxKind::Kwd_Let, SyntaxKind::Kwd_Var])
+        .is_some());
     p.bump();
 
     p.expect(SyntaxKind::Identifier, SyntaxKind::Dec_GlobalBinding);
@@ -27,9 +65,297 @@ where
     m.complete(p, SyntaxKind::Dec_GlobalBinding)
 }
 
+/// Parses an import of another module, such as `import Foo.Bar`.
+fn import<FileId>(p: &mut Parser<FileId>) -> CompletedMarker
+where
+    FileId: Clone + Default,
+{
+    assert!(p.is_at(SyntaxKind::Kwd_Import));
+    let m = p.start();
+    p.bump();
+
+    let path = p.start();
+    p.expect(SyntaxKind::Identifier, SyntaxKind::ModulePath);
+    while p.is_at(Sym!["."]) {
+        p.bump();
+        p.expect(SyntaxKind::Identifier, SyntaxKind::ModulePath);
+    }
+    path.complete(p, SyntaxKind::ModulePath);
+
+    m.complete(p, SyntaxKind::Dec_Import)
+}
+
+/// Parses the declaration of a function provided by the host, such as
+/// `external function puts(s: String) -> Int`.
+///
+/// The return type may be omitted.
+fn external<FileId>(p: &mut Parser<FileId>, m: Marker) -> CompletedMarker
+where
+    FileId: Clone + Default,
+{
+    assert!(p.is_at(SyntaxKind::Kwd_External));
+    p.bump();
+
+    p.expect(SyntaxKind::Kwd_Function, SyntaxKind::Dec_External);
+    p.expect(SyntaxKind::Identifier, SyntaxKind::Dec_External);
+    param_list(p);
+
+    if p.is_at(Sym!["->"]) {
+        p.bump();
+        ty::ty(p);
+    }
+
+    m.complete(p, SyntaxKind::Dec_External)
+}
+
+/// Parses a parenthesized list of parameters with type annotations, such as
+/// `(a: Int, b: Int)`.
+fn param_list<FileId>(p: &mut Parser<FileId>) -> CompletedMarker
+where
+    FileId: Clone + Default,
+{
+    let m = p.start();
+    let is_opened = p.is_at(Sym!["("]);
+    if is_opened {
+        p.open_delimiter();
+    } else {
+        p.error(SyntaxKind::ParamList);
+    }
+
+    if p.is_at(SyntaxKind::Identifier) {
+        param(p);
+        while p.is_at(Sym![","]) {
+            p.bump();
+            param(p);
+        }
+    }
+
+    if is_opened {
+        p.expect_closing(SyntaxKind::ParamList);
+    } else {
+        p.expect(Sym![")"], SyntaxKind::ParamList);
+    }
+    m.complete(p, SyntaxKind::ParamList)
+}
+
+fn param<FileId>(p: &mut Parser<FileId>) -> CompletedMarker
+where
+    FileId: Clone + Default,
+{
+    let m = p.start();
+    p.expect(SyntaxKind::Identifier, SyntaxKind::Param);
+
+    if p.is_at(Sym![":"]) {
+        p.bump();
+        ty::ty(p);
+    } else {
+        p.error(SyntaxKind::Param);
+    }
+    m.complete(p, SyntaxKind::Param)
+}
+
+/// Parses a sum type declaration, such as
+/// `type Shape = Circle(Float) | Square(Float)`.
+///
+/// The first variant may optionally be preceded by a `|`, so that every
+/// variant can be written on its own line.
+fn type_enum<FileId>(p: &mut Parser<FileId>, m: Marker) -> CompletedMarker
+where
+    FileId: Clone + Default,
+{
+    assert!(p.is_at(SyntaxKind::Kwd_Type));
+    p.bump();
+
+    p.expect(SyntaxKind::Identifier, SyntaxKind::Dec_TypeEnum);
+    p.expect(SyntaxKind::Sym_Eq, SyntaxKind::Dec_TypeEnum);
+
+    // With layout enabled, variants written on their own (indented) lines
+    // form a block
+    let in_block = p.is_at(SyntaxKind::Lay_Begin);
+    if in_block {
+        p.bump();
+    }
+
+    if p.is_at(Sym!["|"]) {
+        p.bump();
+    }
+
+    enum_variant(p);
+    loop {
+        if in_block && p.is_at(SyntaxKind::Lay_Newline) {
+            p.bump();
+        }
+
+        if !p.is_at(Sym!["|"]) {
+            break;
+        }
+
+        p.bump();
+        enum_variant(p);
+    }
+
+    if in_block {
+        p.expect(SyntaxKind::Lay_End, SyntaxKind::Dec_TypeEnum);
+    }
+
+    m.complete(p, SyntaxKind::Dec_TypeEnum)
+}
+
+/// Parses the declaration of an interface, made of the signatures of the
+/// methods that the types implementing it must define, such as:
+///
+/// ```text
+/// interface Show = {
+///     function show(value: Self) -> String;
+///     function debug(value: Self) -> String
+/// }
+/// ```
+fn interface<FileId>(p: &mut Parser<FileId>, m: Marker) -> CompletedMarker
+where
+    FileId: Clone + Default,
+{
+    assert!(p.is_at(SyntaxKind::Kwd_Interface));
+    p.bump();
+
+    p.expect(SyntaxKind::Identifier, SyntaxKind::Dec_Interface);
+    p.expect(SyntaxKind::Sym_Eq, SyntaxKind::Dec_Interface);
+    members(p, SyntaxKind::Dec_Interface, method_signature);
+
+    m.complete(p, SyntaxKind::Dec_Interface)
+}
+
+/// Parses the signature of a method of an interface, such as
+/// `function show(value: Self) -> String`.
+///
+/// The return type may be omitted.
+fn method_signature<FileId>(p: &mut Parser<FileId>)
+where
+    FileId: Clone + Default,
+{
+    let m = p.start();
+    p.expect(SyntaxKind::Kwd_Function, SyntaxKind::MethodSignature);
+    p.expect(SyntaxKind::Identifier, SyntaxKind::MethodSignature);
+    param_list(p);
+
+    if p.is_at(Sym!["->"]) {
+        p.bump();
+        ty::ty(p);
+    }
+
+    m.complete(p, SyntaxKind::MethodSignature);
+}
+
+/// Parses the implementation of an interface for a type, made of the
+/// definitions of the interface's methods, such as:
+///
+/// ```text
+/// extend Point with Show = {
+///     let show = \point -> "a point";
+///     let debug = \point -> "Point"
+/// }
+/// ```
+fn extend<FileId>(p: &mut Parser<FileId>) -> CompletedMarker
+where
+    FileId: Clone + Default,
+{
+    assert!(p.is_at(SyntaxKind::Kwd_Extend));
+    let m = p.start();
+    p.bump();
+
+    ty::ty(p);
+    p.expect(SyntaxKind::Kwd_With, SyntaxKind::Dec_Extend);
+    p.expect(SyntaxKind::Identifier, SyntaxKind::Dec_Extend);
+    p.expect(SyntaxKind::Sym_Eq, SyntaxKind::Dec_Extend);
+    members(p, SyntaxKind::Dec_Extend, method);
+
+    m.complete(p, SyntaxKind::Dec_Extend)
+}
+
+/// Parses the definition of a method in an extension, which is written like
+/// a global binding (such as `let show = \point -> "a point"`).
+fn method<FileId>(p: &mut Parser<FileId>)
+where
+    FileId: Clone + Default,
+{
+    let m = p.start();
+    p.expect(SyntaxKind::Kwd_Let, SyntaxKind::Dec_GlobalBinding);
+    p.expect(SyntaxKind::Identifier, SyntaxKind::Dec_GlobalBinding);
+    p.expect(SyntaxKind::Sym_Eq, SyntaxKind::Dec_GlobalBinding);
+
+    // The value stops at the end of the member
+    expr::expr(p, OPERATOR_TABLE.separator_binding_power());
+
+    m.complete(p, SyntaxKind::Dec_GlobalBinding);
+}
+
+/// Parses the members of an interface or an extension with `member`. Like the
+/// expressions of a block, they are surrounded by braces and separated by
+/// `;`, or written on their own (indented) lines.
+fn members<FileId>(
+    p: &mut Parser<FileId>,
+    context: SyntaxKind,
+    member: fn(&mut Parser<FileId>),
+) where
+    FileId: Clone + Default,
+{
+    let closer = if p.is_at(SyntaxKind::Sym_LBrace) {
+        SyntaxKind::Sym_RBrace
+    } else if p.is_at(SyntaxKind::Lay_Begin) {
+        SyntaxKind::Lay_End
+    } else {
+        p.error(context);
+        return;
+    };
+
+    if closer == SyntaxKind::Sym_RBrace {
+        p.open_delimiter();
+    } else {
+        p.bump();
+    }
+
+    while !p.is_at(closer) && !p.is_at_end() {
+        member(p);
+
+        if p.is_at_either(&[Sym![";"], SyntaxKind::Lay_Newline])
+            .is_none()
+        {
+            break;
+        }
+        p.bump();
+    }
+
+    if closer == SyntaxKind::Sym_RBrace {
+        p.expect_closing(context);
+    } else {
+        p.expect(closer, context);
+    }
+}
+
+fn enum_variant<FileId>(p: &mut Parser<FileId>) -> CompletedMarker
+where
+    FileId: Clone + Default,
+{
+    let m = p.start();
+    p.expect(SyntaxKind::Identifier, SyntaxKind::EnumVariant);
+
+    if p.is_at(Sym!["("]) {
+        p.open_delimiter();
+
+        ty::ty(p);
+        while p.is_at(Sym![","]) {
+            p.bump();
+            ty::ty(p);
+        }
+
+        p.expect_closing(SyntaxKind::EnumVariant);
+    }
+
+    m.complete(p, SyntaxKind::EnumVariant)
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::check;
+    use crate::{check, check_layout};
     use expect_test::expect;
 
     #[test]
@@ -50,4 +376,573 @@ mod tests {
             "#]],
         );
     }
+
+    #[test]
+    fn test_parse_mutable_global_binding_declaration() {
+        check(
+            "var count = 0",
+            expect![[r#"
+                Root@0..13
+                  Dec_GlobalBinding@0..13
+                    Kwd_Var@0..3 "var"
+                    Whitespace@3..4 " "
+                    Identifier@4..9 "count"
+                    Whitespace@9..10 " "
+                    Sym_Eq@10..11 "="
+                    Whitespace@11..12 " "
+                    Exp_Literal@12..13
+                      Lit_Integer@12..13 "0"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_interface_declaration() {
+        check(
+            "public interface Show = { function show(a: Self) -> String; }",
+            expect![[r#"
+                Root@0..61
+                  Dec_Interface@0..61
+                    Visibility@0..7
+                      Kwd_Public@0..6 "public"
+                      Whitespace@6..7 " "
+                    Kwd_Interface@7..16 "interface"
+                    Whitespace@16..17 " "
+                    Identifier@17..21 "Show"
+                    Whitespace@21..22 " "
+                    Sym_Eq@22..23 "="
+                    Whitespace@23..24 " "
+                    Sym_LBrace@24..25 "{"
+                    Whitespace@25..26 " "
+                    MethodSignature@26..58
+                      Kwd_Function@26..34 "function"
+                      Whitespace@34..35 " "
+                      Identifier@35..39 "show"
+                      ParamList@39..49
+                        Sym_LParen@39..40 "("
+                        Param@40..47
+                          Identifier@40..41 "a"
+                          Sym_Colon@41..42 ":"
+                          Whitespace@42..43 " "
+                          Typ_Named@43..47
+                            Identifier@43..47 "Self"
+                        Sym_RParen@47..48 ")"
+                        Whitespace@48..49 " "
+                      Sym_RThinArrow@49..51 "->"
+                      Whitespace@51..52 " "
+                      Typ_Named@52..58
+                        Identifier@52..58 "String"
+                    Sym_Semicolon@58..59 ";"
+                    Whitespace@59..60 " "
+                    Sym_RBrace@60..61 "}"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_interface_declaration_with_layout() {
+        check_layout(
+            "interface Eq =\n  function eq(a: Self, b: Self) -> Bool\n  function hash(a: Self)\n",
+            expect![[r#"
+                Root@0..80
+                  Dec_Interface@0..79
+                    Kwd_Interface@0..9 "interface"
+                    Whitespace@9..10 " "
+                    Identifier@10..12 "Eq"
+                    Whitespace@12..13 " "
+                    Sym_Eq@13..14 "="
+                    Whitespace@14..17 "\n  "
+                    Lay_Begin@17..17 ""
+                    MethodSignature@17..54
+                      Kwd_Function@17..25 "function"
+                      Whitespace@25..26 " "
+                      Identifier@26..28 "eq"
+                      ParamList@28..47
+                        Sym_LParen@28..29 "("
+                        Param@29..36
+                          Identifier@29..30 "a"
+                          Sym_Colon@30..31 ":"
+                          Whitespace@31..32 " "
+                          Typ_Named@32..36
+                            Identifier@32..36 "Self"
+                        Sym_Comma@36..37 ","
+                        Whitespace@37..38 " "
+                        Param@38..45
+                          Identifier@38..39 "b"
+                          Sym_Colon@39..40 ":"
+                          Whitespace@40..41 " "
+                          Typ_Named@41..45
+                            Identifier@41..45 "Self"
+                        Sym_RParen@45..46 ")"
+                        Whitespace@46..47 " "
+                      Sym_RThinArrow@47..49 "->"
+                      Whitespace@49..50 " "
+                      Typ_Named@50..54
+                        Identifier@50..54 "Bool"
+                    Whitespace@54..57 "\n  "
+                    Lay_Newline@57..57 ""
+                    MethodSignature@57..79
+                      Kwd_Function@57..65 "function"
+                      Whitespace@65..66 " "
+                      Identifier@66..70 "hash"
+                      ParamList@70..79
+                        Sym_LParen@70..71 "("
+                        Param@71..78
+                          Identifier@71..72 "a"
+                          Sym_Colon@72..73 ":"
+                          Whitespace@73..74 " "
+                          Typ_Named@74..78
+                            Identifier@74..78 "Self"
+                        Sym_RParen@78..79 ")"
+                    Lay_End@79..79 ""
+                  Whitespace@79..80 "\n"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_extend_declaration() {
+        check(
+            "extend Point with Show = { let show = \\p -> \"p\"; let debug = show }",
+            expect![[r#"
+                Root@0..67
+                  Dec_Extend@0..67
+                    Kwd_Extend@0..6 "extend"
+                    Whitespace@6..7 " "
+                    Typ_Named@7..13
+                      Identifier@7..12 "Point"
+                      Whitespace@12..13 " "
+                    Kwd_With@13..17 "with"
+                    Whitespace@17..18 " "
+                    Identifier@18..22 "Show"
+                    Whitespace@22..23 " "
+                    Sym_Eq@23..24 "="
+                    Whitespace@24..25 " "
+                    Sym_LBrace@25..26 "{"
+                    Whitespace@26..27 " "
+                    Dec_GlobalBinding@27..47
+                      Kwd_Let@27..30 "let"
+                      Whitespace@30..31 " "
+                      Identifier@31..35 "show"
+                      Whitespace@35..36 " "
+                      Sym_Eq@36..37 "="
+                      Whitespace@37..38 " "
+                      Exp_Lambda@38..47
+                        Sym_BackSlash@38..39 "\\"
+                        ParamList@39..41
+                          Identifier@39..40 "p"
+                          Whitespace@40..41 " "
+                        Sym_RThinArrow@41..43 "->"
+                        Whitespace@43..44 " "
+                        Exp_Literal@44..47
+                          Lit_String@44..47 "\"p\""
+                    Sym_Semicolon@47..48 ";"
+                    Whitespace@48..49 " "
+                    Dec_GlobalBinding@49..66
+                      Kwd_Let@49..52 "let"
+                      Whitespace@52..53 " "
+                      Identifier@53..58 "debug"
+                      Whitespace@58..59 " "
+                      Sym_Eq@59..60 "="
+                      Whitespace@60..61 " "
+                      Exp_VariableRef@61..65
+                        Identifier@61..65 "show"
+                      Whitespace@65..66 " "
+                    Sym_RBrace@66..67 "}"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_extend_declaration_with_layout() {
+        check_layout(
+            "extend Point with Eq =\n  let eq = \\a b -> a = b\n  let hash = \\a -> 0\n",
+            expect![[r#"
+                Root@0..69
+                  Dec_Extend@0..68
+                    Kwd_Extend@0..6 "extend"
+                    Whitespace@6..7 " "
+                    Typ_Named@7..13
+                      Identifier@7..12 "Point"
+                      Whitespace@12..13 " "
+                    Kwd_With@13..17 "with"
+                    Whitespace@17..18 " "
+                    Identifier@18..20 "Eq"
+                    Whitespace@20..21 " "
+                    Sym_Eq@21..22 "="
+                    Whitespace@22..25 "\n  "
+                    Lay_Begin@25..25 ""
+                    Dec_GlobalBinding@25..47
+                      Kwd_Let@25..28 "let"
+                      Whitespace@28..29 " "
+                      Identifier@29..31 "eq"
+                      Whitespace@31..32 " "
+                      Sym_Eq@32..33 "="
+                      Whitespace@33..34 " "
+                      Exp_Lambda@34..47
+                        Sym_BackSlash@34..35 "\\"
+                        ParamList@35..39
+                          Identifier@35..36 "a"
+                          Whitespace@36..37 " "
+                          Identifier@37..38 "b"
+                          Whitespace@38..39 " "
+                        Sym_RThinArrow@39..41 "->"
+                        Whitespace@41..42 " "
+                        Exp_Binary@42..47
+                          Exp_VariableRef@42..44
+                            Identifier@42..43 "a"
+                            Whitespace@43..44 " "
+                          Sym_Eq@44..45 "="
+                          Whitespace@45..46 " "
+                          Exp_VariableRef@46..47
+                            Identifier@46..47 "b"
+                    Whitespace@47..50 "\n  "
+                    Lay_Newline@50..50 ""
+                    Dec_GlobalBinding@50..68
+                      Kwd_Let@50..53 "let"
+                      Whitespace@53..54 " "
+                      Identifier@54..58 "hash"
+                      Whitespace@58..59 " "
+                      Sym_Eq@59..60 "="
+                      Whitespace@60..61 " "
+                      Exp_Lambda@61..68
+                        Sym_BackSlash@61..62 "\\"
+                        ParamList@62..64
+                          Identifier@62..63 "a"
+                          Whitespace@63..64 " "
+                        Sym_RThinArrow@64..66 "->"
+                        Whitespace@66..67 " "
+                        Exp_Literal@67..68
+                          Lit_Integer@67..68 "0"
+                    Lay_End@68..68 ""
+                  Whitespace@68..69 "\n"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_enum_type_declaration() {
+        check(
+            "type Shape = Circle(Float) | Rectangle(Float, Float)",
+            expect![[r#"
+                Root@0..52
+                  Dec_TypeEnum@0..52
+                    Kwd_Type@0..4 "type"
+                    Whitespace@4..5 " "
+                    Identifier@5..10 "Shape"
+                    Whitespace@10..11 " "
+                    Sym_Eq@11..12 "="
+                    Whitespace@12..13 " "
+                    EnumVariant@13..27
+                      Identifier@13..19 "Circle"
+                      Sym_LParen@19..20 "("
+                      Typ_Named@20..25
+                        Identifier@20..25 "Float"
+                      Sym_RParen@25..26 ")"
+                      Whitespace@26..27 " "
+                    Sym_Pipe@27..28 "|"
+                    Whitespace@28..29 " "
+                    EnumVariant@29..52
+                      Identifier@29..38 "Rectangle"
+                      Sym_LParen@38..39 "("
+                      Typ_Named@39..44
+                        Identifier@39..44 "Float"
+                      Sym_Comma@44..45 ","
+                      Whitespace@45..46 " "
+                      Typ_Named@46..51
+                        Identifier@46..51 "Float"
+                      Sym_RParen@51..52 ")"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_enum_type_declaration_with_leading_pipe() {
+        check(
+            "type Bool =\n  | True\n  | False",
+            expect![[r#"
+                Root@0..30
+                  Dec_TypeEnum@0..30
+                    Kwd_Type@0..4 "type"
+                    Whitespace@4..5 " "
+                    Identifier@5..9 "Bool"
+                    Whitespace@9..10 " "
+                    Sym_Eq@10..11 "="
+                    Whitespace@11..14 "\n  "
+                    Sym_Pipe@14..15 "|"
+                    Whitespace@15..16 " "
+                    EnumVariant@16..20
+                      Identifier@16..20 "True"
+                    Whitespace@20..23 "\n  "
+                    Sym_Pipe@23..24 "|"
+                    Whitespace@24..25 " "
+                    EnumVariant@25..30
+                      Identifier@25..30 "False"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_enum_type_declaration_missing_variant() {
+        check(
+            "type Foo = Bar |\nlet a = 1",
+            expect![[r#"
+                Root@0..26
+                  Dec_TypeEnum@0..16
+                    Kwd_Type@0..4 "type"
+                    Whitespace@4..5 " "
+                    Identifier@5..8 "Foo"
+                    Whitespace@8..9 " "
+                    Sym_Eq@9..10 "="
+                    Whitespace@10..11 " "
+                    EnumVariant@11..15
+                      Identifier@11..14 "Bar"
+                      Whitespace@14..15 " "
+                    Sym_Pipe@15..16 "|"
+                    EnumVariant@16..16
+                  Whitespace@16..17 "\n"
+                  Dec_GlobalBinding@17..26
+                    Kwd_Let@17..20 "let"
+                    Whitespace@20..21 " "
+                    Identifier@21..22 "a"
+                    Whitespace@22..23 " "
+                    Sym_Eq@23..24 "="
+                    Whitespace@24..25 " "
+                    Exp_Literal@25..26
+                      Lit_Integer@25..26 "1"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_external_declaration() {
+        check(
+            "external function puts(s: String) -> Int",
+            expect![[r#"
+                Root@0..40
+                  Dec_External@0..40
+                    Kwd_External@0..8 "external"
+                    Whitespace@8..9 " "
+                    Kwd_Function@9..17 "function"
+                    Whitespace@17..18 " "
+                    Identifier@18..22 "puts"
+                    ParamList@22..34
+                      Sym_LParen@22..23 "("
+                      Param@23..32
+                        Identifier@23..24 "s"
+                        Sym_Colon@24..25 ":"
+                        Whitespace@25..26 " "
+                        Typ_Named@26..32
+                          Identifier@26..32 "String"
+                      Sym_RParen@32..33 ")"
+                      Whitespace@33..34 " "
+                    Sym_RThinArrow@34..36 "->"
+                    Whitespace@36..37 " "
+                    Typ_Named@37..40
+                      Identifier@37..40 "Int"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_external_declaration_without_return_type() {
+        check(
+            "external function exit()",
+            expect![[r#"
+                Root@0..24
+                  Dec_External@0..24
+                    Kwd_External@0..8 "external"
+                    Whitespace@8..9 " "
+                    Kwd_Function@9..17 "function"
+                    Whitespace@17..18 " "
+                    Identifier@18..22 "exit"
+                    ParamList@22..24
+                      Sym_LParen@22..23 "("
+                      Sym_RParen@23..24 ")"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_external_declaration_missing_parameter_type() {
+        check(
+            "external function add(a: Int, b) -> Int",
+            expect![[r#"
+                Root@0..39
+                  Dec_External@0..39
+                    Kwd_External@0..8 "external"
+                    Whitespace@8..9 " "
+                    Kwd_Function@9..17 "function"
+                    Whitespace@17..18 " "
+                    Identifier@18..21 "add"
+                    ParamList@21..33
+                      Sym_LParen@21..22 "("
+                      Param@22..28
+                        Identifier@22..23 "a"
+                        Sym_Colon@23..24 ":"
+                        Whitespace@24..25 " "
+                        Typ_Named@25..28
+                          Identifier@25..28 "Int"
+                      Sym_Comma@28..29 ","
+                      Whitespace@29..30 " "
+                      Param@30..31
+                        Identifier@30..31 "b"
+                      Sym_RParen@31..32 ")"
+                      Whitespace@32..33 " "
+                    Sym_RThinArrow@33..35 "->"
+                    Whitespace@35..36 " "
+                    Typ_Named@36..39
+                      Identifier@36..39 "Int"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_import_declaration() {
+        check(
+            "import Foo.Bar\nlet a = 1",
+            expect![[r#"
+                Root@0..24
+                  Dec_Import@0..14
+                    Kwd_Import@0..6 "import"
+                    Whitespace@6..7 " "
+                    ModulePath@7..14
+                      Identifier@7..10 "Foo"
+                      Sym_Dot@10..11 "."
+                      Identifier@11..14 "Bar"
+                  Whitespace@14..15 "\n"
+                  Dec_GlobalBinding@15..24
+                    Kwd_Let@15..18 "let"
+                    Whitespace@18..19 " "
+                    Identifier@19..20 "a"
+                    Whitespace@20..21 " "
+                    Sym_Eq@21..22 "="
+                    Whitespace@22..23 " "
+                    Exp_Literal@23..24
+                      Lit_Integer@23..24 "1"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_import_declaration_missing_module() {
+        check(
+            "import Foo.",
+            expect![[r#"
+                Root@0..11
+                  Dec_Import@0..11
+                    Kwd_Import@0..6 "import"
+                    Whitespace@6..7 " "
+                    ModulePath@7..11
+                      Identifier@7..10 "Foo"
+                      Sym_Dot@10..11 "."
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_public_declarations() {
+        check(
+            "public let a = 1\npublic type B = C",
+            expect![[r#"
+                Root@0..34
+                  Dec_GlobalBinding@0..16
+                    Visibility@0..7
+                      Kwd_Public@0..6 "public"
+                      Whitespace@6..7 " "
+                    Kwd_Let@7..10 "let"
+                    Whitespace@10..11 " "
+                    Identifier@11..12 "a"
+                    Whitespace@12..13 " "
+                    Sym_Eq@13..14 "="
+                    Whitespace@14..15 " "
+                    Exp_Literal@15..16
+                      Lit_Integer@15..16 "1"
+                  Whitespace@16..17 "\n"
+                  Dec_TypeEnum@17..34
+                    Visibility@17..24
+                      Kwd_Public@17..23 "public"
+                      Whitespace@23..24 " "
+                    Kwd_Type@24..28 "type"
+                    Whitespace@28..29 " "
+                    Identifier@29..30 "B"
+                    Whitespace@30..31 " "
+                    Sym_Eq@31..32 "="
+                    Whitespace@32..33 " "
+                    EnumVariant@33..34
+                      Identifier@33..34 "C"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_public_modifier_without_declaration() {
+        check(
+            "public 1",
+            expect![[r#"
+                Root@0..8
+                  Error@0..8
+                    Visibility@0..7
+                      Kwd_Public@0..6 "public"
+                      Whitespace@6..7 " "
+                    Lit_Integer@7..8 "1"
+            "#]],
+        );
+
+        // The modifier is reported once, at the token following it
+        let parse = crate::parse(0u8, "public 1");
+        let ranges = parse
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| diagnostic.location.range.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(ranges, vec![7..8]);
+    }
+
+    #[test]
+    fn test_parse_enum_type_declaration_in_indented_block() {
+        check_layout(
+            "type Shape =\n  | Circle(Float)\n  | Square(Float)\nlet a = 1",
+            expect![[r#"
+                Root@0..58
+                  Dec_TypeEnum@0..48
+                    Kwd_Type@0..4 "type"
+                    Whitespace@4..5 " "
+                    Identifier@5..10 "Shape"
+                    Whitespace@10..11 " "
+                    Sym_Eq@11..12 "="
+                    Whitespace@12..15 "\n  "
+                    Lay_Begin@15..15 ""
+                    Sym_Pipe@15..16 "|"
+                    Whitespace@16..17 " "
+                    EnumVariant@17..30
+                      Identifier@17..23 "Circle"
+                      Sym_LParen@23..24 "("
+                      Typ_Named@24..29
+                        Identifier@24..29 "Float"
+                      Sym_RParen@29..30 ")"
+                    Whitespace@30..33 "\n  "
+                    Lay_Newline@33..33 ""
+                    Sym_Pipe@33..34 "|"
+                    Whitespace@34..35 " "
+                    EnumVariant@35..48
+                      Identifier@35..41 "Square"
+                      Sym_LParen@41..42 "("
+                      Typ_Named@42..47
+                        Identifier@42..47 "Float"
+                      Sym_RParen@47..48 ")"
+                    Lay_End@48..48 ""
+                  Whitespace@48..49 "\n"
+                  Dec_GlobalBinding@49..58
+                    Kwd_Let@49..52 "let"
+                    Whitespace@52..53 " "
+                    Identifier@53..54 "a"
+                    Whitespace@54..55 " "
+                    Sym_Eq@55..56 "="
+                    Whitespace@56..57 " "
+                    Exp_Literal@57..58
+                      Lit_Integer@57..58 "1"
+            "#]],
+        );
+    }
 }
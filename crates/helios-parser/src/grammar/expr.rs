@@ -1,47 +1,17 @@
+use super::operator::OPERATOR_TABLE;
 use super::*;
-use helios_syntax::Sym;
+use std::sync::LazyLock;
 
-const PREFIX_OPS: &[SyntaxKind] =
-    &[SyntaxKind::Sym_Minus, SyntaxKind::Sym_Bang];
+/// The kinds of every postfix and infix operator, which may follow the
+/// left-hand side of an expression.
+static OPERATOR_KINDS: LazyLock<Vec<SyntaxKind>> = LazyLock::new(|| {
+    [OPERATOR_TABLE.postfix_kinds(), OPERATOR_TABLE.infix_kinds()].concat()
+});
 
-/// Determines the prefix binding power of the given token. Currently, the only
-/// legal prefix symbols are `SyntaxKind::Sym_Minus` and `SyntaxKind::Sym_Bang`.
-fn prefix_binding_power(kind: SyntaxKind) -> ((), u8) {
-    match kind {
-        Sym!["-"] | Sym!["!"] => ((), 11),
-        _ => unreachable!("Invalid symbol as prefix operator: {:?}", kind),
-    }
-}
-
-const INFIX_OPS: &[SyntaxKind] = &[
-    SyntaxKind::Sym_Asterisk,
-    SyntaxKind::Sym_BangEq,
-    SyntaxKind::Sym_Eq,
-    SyntaxKind::Sym_ForwardSlash,
-    SyntaxKind::Sym_Gt,
-    SyntaxKind::Sym_GtEq,
-    SyntaxKind::Sym_Lt,
-    SyntaxKind::Sym_LtEq,
-    SyntaxKind::Sym_LThinArrow,
-    SyntaxKind::Sym_Minus,
-    SyntaxKind::Sym_Plus,
-    SyntaxKind::Sym_Semicolon,
-];
-
-/// Determines the infix binding power of the given token. A higher binding
-/// power means higher precedence, meaning that it is more likely to hold onto
-/// its adjacent operands.
-fn infix_binding_power(kind: SyntaxKind) -> (u8, u8) {
-    match kind {
-        Sym![";"] => (1, 2),
-        Sym!["<-"] => (3, 2),
-        Sym!["="] | Sym!["!="] => (4, 3),
-        Sym!["<"] | Sym![">"] | Sym!["<="] | Sym![">="] => (5, 6),
-        Sym!["+"] | Sym!["-"] => (7, 8),
-        Sym!["*"] | Sym!["/"] => (9, 10),
-        _ => unreachable!("Invalid symbol as infix operator: {:?}", kind),
-    }
-}
+/// The kinds that can start an expression: those of [`LHS_KINDS`] and of
+/// every prefix operator.
+static EXPR_START_KINDS: LazyLock<Vec<SyntaxKind>> =
+    LazyLock::new(|| [LHS_KINDS, &OPERATOR_TABLE.prefix_kinds()].concat());
 
 /// Parses an expression.
 pub(super) fn expr<FileId>(
@@ -53,12 +23,12 @@ where
 {
     let mut lhs = lhs(p)?;
 
-    loop {
-        // Peek the next `SyntaxKind`, assuming it's an operator
-        if let Some(operator) = p.is_at_either(INFIX_OPS) {
-            // Get the left and right binding power of the operator
-            let (left_bp, right_bp) = infix_binding_power(*operator);
-
+    // Peek the next `SyntaxKind`, assuming it's an operator. If what we peeked
+    // wasn't an operator, we don't know what to do next, so we'll return and
+    // let the caller decide.
+    while let Some(&kind) = p.is_at_either(&OPERATOR_KINDS) {
+        if let Some(operator) = OPERATOR_TABLE.postfix(kind) {
+            let (left_bp, ()) = operator.binding_power();
             if left_bp < min_bp {
                 break;
             }
@@ -67,15 +37,29 @@ where
             p.bump();
 
             let m = lhs.precede(p);
-            let parsed_rhs = expr(p, right_bp).is_some();
-            lhs = m.complete(p, SyntaxKind::Exp_Binary);
+            lhs = m.complete(p, SyntaxKind::Exp_UnaryPostfix);
+            continue;
+        }
 
-            if !parsed_rhs {
-                break;
-            }
-        } else {
-            // What we consumed wasn't an operator; we don't know what to do
-            // next, so we'll return and let the caller decide
+        // Get the left and right binding power of the operator
+        let operator = OPERATOR_TABLE.infix(kind).unwrap();
+        let (left_bp, right_bp) = operator.binding_power();
+
+        if left_bp < min_bp {
+            break;
+        }
+
+        // Consume the operator token (reporting it if it's experimental)
+        if let Some(feature) = operator.feature {
+            p.gate(feature);
+        }
+        p.bump();
+
+        let m = lhs.precede(p);
+        let parsed_rhs = expr(p, right_bp).is_some();
+        lhs = m.complete(p, operator.node_kind());
+
+        if !parsed_rhs {
             break;
         }
     }
@@ -88,30 +72,51 @@ const LHS_KINDS: &[SyntaxKind] = &[
     SyntaxKind::Lit_Float,
     SyntaxKind::Lit_Integer,
     SyntaxKind::Lit_String,
+    SyntaxKind::Lit_StringStart,
     SyntaxKind::Identifier,
+    SyntaxKind::Kwd_Return,
+    SyntaxKind::Sym_BackSlash,
     SyntaxKind::Sym_LParen,
+    SyntaxKind::Sym_LBrace,
+    SyntaxKind::Lay_Begin,
 ];
 
 /// Parses the left-hand side of an expression.
+///
+/// If there is none (such as after the `=` of `let a = ` or the `+` of
+/// `a + `), an empty [`SyntaxKind::Exp_Missing`] node takes its place, so that
+/// the enclosing nodes are shaped as if it were there (and tools can tell
+/// that an expression is expected at its offset).
 fn lhs<FileId>(p: &mut Parser<FileId>) -> Option<CompletedMarker>
 where
     FileId: Clone + Default,
 {
-    let lhs_kinds_or_prefix_ops = &[LHS_KINDS, PREFIX_OPS].concat();
-
     // We'll check if the next `SyntaxKind` can start a LHS expression (either
-    // any of `LHS_KINDS` or `PREFIX_OPS`)
-    let cm = if let Some(kind) = p.is_at_either(lhs_kinds_or_prefix_ops) {
+    // any of `LHS_KINDS` or a prefix operator)
+    let cm = if let Some(kind) = p.is_at_either(&EXPR_START_KINDS) {
         match kind {
-            SyntaxKind::Lit_Integer | SyntaxKind::Lit_Float => literal(p),
+            SyntaxKind::Lit_Character
+            | SyntaxKind::Lit_Float
+            | SyntaxKind::Lit_Integer
+            | SyntaxKind::Lit_String => literal(p),
+            SyntaxKind::Lit_StringStart => interpolation(p),
             SyntaxKind::Identifier => variable_ref(p),
+            SyntaxKind::Kwd_Return => return_expr(p),
+            SyntaxKind::Sym_BackSlash => lambda_expr(p),
             SyntaxKind::Sym_LParen => paren_expr(p),
-            kind if PREFIX_OPS.contains(kind) => unary_prefix_expr(p),
+            SyntaxKind::Sym_LBrace | SyntaxKind::Lay_Begin => block_expr(p),
+            &kind if OPERATOR_TABLE.prefix(kind).is_some() => {
+                unary_prefix_expr(p, kind)
+            }
             _ => unreachable!("Got unexpected kind for LHS: {:?}", kind),
         }
     } else {
+        // The node is added before any token skipped by the error, so that it
+        // is right after what's before it
+        let m = p.start();
+        let cm = m.complete(p, SyntaxKind::Exp_Missing);
         p.error(SyntaxKind::Exp_Unnamed);
-        return None;
+        return Some(cm);
     };
 
     Some(cm)
@@ -123,13 +128,41 @@ where
     FileId: Clone + Default,
 {
     use SyntaxKind::*;
-    assert!(p.is_at(Lit_Integer) || p.is_at(Lit_Float));
+    assert!(p
+        .is_at_either(&[Lit_Character, Lit_Float, Lit_Integer, Lit_String])
+        .is_some());
 
     let m = p.start();
     p.bump();
     m.complete(p, Exp_Literal)
 }
 
+/// Parses a string literal with interpolations (such as `"Hello, {name}!"`),
+/// which the lexer splits into fragments around each interpolated
+/// expression.
+fn interpolation<FileId>(p: &mut Parser<FileId>) -> CompletedMarker
+where
+    FileId: Clone + Default,
+{
+    assert!(p.is_at(SyntaxKind::Lit_StringStart));
+
+    let m = p.start();
+    p.bump();
+
+    loop {
+        expr(p, 0);
+
+        if p.is_at(SyntaxKind::Lit_StringMiddle) {
+            p.bump();
+        } else {
+            p.expect(SyntaxKind::Lit_StringEnd, SyntaxKind::Exp_Interpolation);
+            break;
+        }
+    }
+
+    m.complete(p, SyntaxKind::Exp_Interpolation)
+}
+
 /// Parses an identifier as a variable reference.
 fn variable_ref<FileId>(p: &mut Parser<FileId>) -> CompletedMarker
 where
@@ -143,15 +176,18 @@ where
 }
 
 /// Parses a unary expression with a prefixed operator.
-fn unary_prefix_expr<FileId>(p: &mut Parser<FileId>) -> CompletedMarker
+fn unary_prefix_expr<FileId>(
+    p: &mut Parser<FileId>,
+    kind: SyntaxKind,
+) -> CompletedMarker
 where
     FileId: Clone + Default,
 {
     let m = p.start();
 
     // Get the right binding power of the operator
-    let operator = SyntaxKind::Sym_Minus;
-    let ((), right_bp) = prefix_binding_power(operator);
+    let operator = OPERATOR_TABLE.prefix(kind).unwrap();
+    let ((), right_bp) = operator.binding_power();
 
     // Consume the operator token and the expression it holds
     p.bump();
@@ -160,7 +196,29 @@ where
     m.complete(p, SyntaxKind::Exp_UnaryPrefix)
 }
 
-/// Parses an expression surrounded by parenthesis.
+/// Parses an early return from a function (such as `return x + 1`), whose
+/// value may be left out.
+///
+/// The value extends as far to the right as possible without going past the
+/// end of the expression, so `return a; b` is parsed as `(return a); b`.
+fn return_expr<FileId>(p: &mut Parser<FileId>) -> CompletedMarker
+where
+    FileId: Clone + Default,
+{
+    assert!(p.is_at(SyntaxKind::Kwd_Return));
+
+    let m = p.start();
+    p.bump();
+
+    if p.is_at_either(&EXPR_START_KINDS).is_some() {
+        expr(p, OPERATOR_TABLE.separator_binding_power());
+    }
+
+    m.complete(p, SyntaxKind::Exp_Return)
+}
+
+/// Parses an expression surrounded by parenthesis, or the unit value `()` if
+/// there is nothing between them.
 fn paren_expr<FileId>(p: &mut Parser<FileId>) -> CompletedMarker
 where
     FileId: Clone + Default,
@@ -168,20 +226,85 @@ where
     assert!(p.is_at(SyntaxKind::Sym_LParen));
 
     let m = p.start();
+    p.open_delimiter();
 
-    // Consume the opening parenthesis and the expression inside
-    p.bump();
-    expr(p, 0);
+    if p.is_at(SyntaxKind::Sym_RParen) {
+        p.expect_closing(SyntaxKind::Exp_Unit);
+        return m.complete(p, SyntaxKind::Exp_Unit);
+    }
 
-    // Consume the closing parenthesis if possible
-    p.expect(SyntaxKind::Sym_RParen, SyntaxKind::Exp_Paren);
+    // Consume the expression inside and the closing parenthesis (recovering
+    // from a missing one)
+    expr(p, 0);
+    p.expect_closing(SyntaxKind::Exp_Paren);
 
     m.complete(p, SyntaxKind::Exp_Paren)
 }
 
+/// Parses a block, which is either surrounded by braces (such as
+/// `{ a; b }`) or delimited by the layout tokens inferred from indentation
+/// (see [`layout`]).
+///
+/// In both cases, the expressions of the block are sequenced by the
+/// separator operators of the [`OPERATOR_TABLE`] (`;` and the layout's new
+/// lines respectively).
+///
+/// [`layout`]: crate::layout
+fn block_expr<FileId>(p: &mut Parser<FileId>) -> CompletedMarker
+where
+    FileId: Clone + Default,
+{
+    let closer = if p.is_at(SyntaxKind::Sym_LBrace) {
+        SyntaxKind::Sym_RBrace
+    } else {
+        assert!(p.is_at(SyntaxKind::Lay_Begin));
+        SyntaxKind::Lay_End
+    };
+
+    let m = p.start();
+    if closer == SyntaxKind::Sym_RBrace {
+        p.open_delimiter();
+        expr(p, 0);
+        p.expect_closing(SyntaxKind::Exp_Block);
+    } else {
+        p.bump();
+        expr(p, 0);
+        p.expect(closer, SyntaxKind::Exp_Block);
+    }
+
+    m.complete(p, SyntaxKind::Exp_Block)
+}
+
+/// Parses an anonymous function (such as `\x y -> x + y`).
+///
+/// The body of the lambda extends as far to the right as possible without
+/// going past the end of the expression (like the value of a `return`), so
+/// `\x -> x + 1; y` is parsed as `(\x -> (x + 1)); y`. A body made of several
+/// expressions is written as a block.
+fn lambda_expr<FileId>(p: &mut Parser<FileId>) -> CompletedMarker
+where
+    FileId: Clone + Default,
+{
+    assert!(p.is_at(SyntaxKind::Sym_BackSlash));
+
+    let m = p.start();
+    p.bump();
+
+    let params = p.start();
+    while p.is_at(SyntaxKind::Identifier) {
+        p.bump();
+    }
+    params.complete(p, SyntaxKind::ParamList);
+
+    p.expect(SyntaxKind::Sym_RThinArrow, SyntaxKind::Exp_Lambda);
+    expr(p, OPERATOR_TABLE.separator_binding_power());
+
+    m.complete(p, SyntaxKind::Exp_Lambda)
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::check;
+    use crate::{check, check_layout};
     use expect_test::expect;
 
     #[test]
@@ -196,6 +319,71 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_lone_string() {
+        check(
+            r#""hello""#,
+            expect![[r#"
+                Root@0..7
+                  Exp_Literal@0..7
+                    Lit_String@0..7 "\"hello\""
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_lone_character() {
+        check(
+            r"'\n'",
+            expect![[r#"
+                Root@0..4
+                  Exp_Literal@0..4
+                    Lit_Character@0..4 "'\\n'"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_string_interpolation() {
+        check(
+            r#""a {b + 1} c {d}""#,
+            expect![[r#"
+                Root@0..17
+                  Exp_Interpolation@0..17
+                    Lit_StringStart@0..4 "\"a {"
+                    Exp_Binary@4..9
+                      Exp_VariableRef@4..6
+                        Identifier@4..5 "b"
+                        Whitespace@5..6 " "
+                      Sym_Plus@6..7 "+"
+                      Whitespace@7..8 " "
+                      Exp_Literal@8..9
+                        Lit_Integer@8..9 "1"
+                    Lit_StringMiddle@9..14 "} c {"
+                    Exp_VariableRef@14..15
+                      Identifier@14..15 "d"
+                    Lit_StringEnd@15..17 "}\""
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_string_interpolation_missing_end() {
+        check(
+            r#""a {b c"#,
+            expect![[r#"
+                Root@0..7
+                  Exp_Interpolation@0..7
+                    Lit_StringStart@0..4 "\"a {"
+                    Exp_VariableRef@4..6
+                      Identifier@4..5 "b"
+                      Whitespace@5..6 " "
+                    Error@6..7
+                      Identifier@6..7 "c"
+            "#]],
+        );
+    }
+
     #[test]
     fn test_parse_lone_identifier() {
         check(
@@ -222,6 +410,128 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_unary_postfix_expression_simple() {
+        check(
+            "foo?",
+            expect![[r#"
+                Root@0..4
+                  Exp_UnaryPostfix@0..4
+                    Exp_VariableRef@0..3
+                      Identifier@0..3 "foo"
+                    Sym_Question@3..4 "?"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_postfix_with_proper_binding_powers() {
+        check(
+            "-foo? + 1",
+            expect![[r#"
+                Root@0..9
+                  Exp_Binary@0..9
+                    Exp_UnaryPrefix@0..6
+                      Sym_Minus@0..1 "-"
+                      Exp_UnaryPostfix@1..5
+                        Exp_VariableRef@1..4
+                          Identifier@1..4 "foo"
+                        Sym_Question@4..5 "?"
+                      Whitespace@5..6 " "
+                    Sym_Plus@6..7 "+"
+                    Whitespace@7..8 " "
+                    Exp_Literal@8..9
+                      Lit_Integer@8..9 "1"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_pipeline_expression() {
+        check(
+            "a + 1 |> f |> g",
+            expect![[r#"
+                Root@0..15
+                  Exp_Binary@0..15
+                    Exp_Binary@0..11
+                      Exp_Binary@0..6
+                        Exp_VariableRef@0..2
+                          Identifier@0..1 "a"
+                          Whitespace@1..2 " "
+                        Sym_Plus@2..3 "+"
+                        Whitespace@3..4 " "
+                        Exp_Literal@4..5
+                          Lit_Integer@4..5 "1"
+                        Whitespace@5..6 " "
+                      Sym_PipeGt@6..8 "|>"
+                      Whitespace@8..9 " "
+                      Exp_VariableRef@9..10
+                        Identifier@9..10 "f"
+                      Whitespace@10..11 " "
+                    Sym_PipeGt@11..13 "|>"
+                    Whitespace@13..14 " "
+                    Exp_VariableRef@14..15
+                      Identifier@14..15 "g"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_backward_pipeline_expression() {
+        check(
+            "f <| x |> g",
+            expect![[r#"
+                Root@0..11
+                  Exp_Binary@0..11
+                    Exp_VariableRef@0..2
+                      Identifier@0..1 "f"
+                      Whitespace@1..2 " "
+                    Sym_LtPipe@2..4 "<|"
+                    Whitespace@4..5 " "
+                    Exp_Binary@5..11
+                      Exp_VariableRef@5..7
+                        Identifier@5..6 "x"
+                        Whitespace@6..7 " "
+                      Sym_PipeGt@7..9 "|>"
+                      Whitespace@9..10 " "
+                      Exp_VariableRef@10..11
+                        Identifier@10..11 "g"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_gate_backward_pipeline_expression() {
+        use crate::{parse_with_options, Feature, Features, ParseOptions};
+
+        // The operator is parsed the same way either way, but is an error
+        // unless its feature is enabled
+        let source = "let a = f <| 1";
+        let parse = crate::parse(0u8, source);
+        let titles = parse
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| {
+                (diagnostic.title.clone(), diagnostic.location.range.clone())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            titles,
+            vec![(
+                "Feature `backward-pipelines` is experimental".into(),
+                10..12
+            )]
+        );
+
+        let options = ParseOptions {
+            features: Features::new().with(Feature::BackwardPipelines),
+            ..ParseOptions::default()
+        };
+        let gated = parse_with_options(0u8, source, options);
+        assert!(gated.diagnostics().is_empty());
+        assert_eq!(gated.debug_tree(), parse.debug_tree());
+    }
+
     #[test]
     fn test_parse_with_proper_binding_powers() {
         check(
@@ -304,6 +614,71 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_lambda_expression() {
+        check(
+            "\\x y -> x + y",
+            expect![[r#"
+                Root@0..13
+                  Exp_Lambda@0..13
+                    Sym_BackSlash@0..1 "\\"
+                    ParamList@1..5
+                      Identifier@1..2 "x"
+                      Whitespace@2..3 " "
+                      Identifier@3..4 "y"
+                      Whitespace@4..5 " "
+                    Sym_RThinArrow@5..7 "->"
+                    Whitespace@7..8 " "
+                    Exp_Binary@8..13
+                      Exp_VariableRef@8..10
+                        Identifier@8..9 "x"
+                        Whitespace@9..10 " "
+                      Sym_Plus@10..11 "+"
+                      Whitespace@11..12 " "
+                      Exp_VariableRef@12..13
+                        Identifier@12..13 "y"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_lambda_expression_without_parameters() {
+        check(
+            "1 + \\-> 2",
+            expect![[r#"
+                Root@0..9
+                  Exp_Binary@0..9
+                    Exp_Literal@0..2
+                      Lit_Integer@0..1 "1"
+                      Whitespace@1..2 " "
+                    Sym_Plus@2..3 "+"
+                    Whitespace@3..4 " "
+                    Exp_Lambda@4..9
+                      Sym_BackSlash@4..5 "\\"
+                      ParamList@5..5
+                      Sym_RThinArrow@5..7 "->"
+                      Whitespace@7..8 " "
+                      Exp_Literal@8..9
+                        Lit_Integer@8..9 "2"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_lambda_expression_missing_arrow() {
+        check(
+            "\\x",
+            expect![[r#"
+                Root@0..2
+                  Exp_Lambda@0..2
+                    Sym_BackSlash@0..1 "\\"
+                    ParamList@1..2
+                      Identifier@1..2 "x"
+                    Exp_Missing@2..2
+            "#]],
+        );
+    }
+
     #[test]
     fn test_parenthesized_expression() {
         check(
@@ -327,6 +702,218 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_assignment() {
+        check(
+            "x <- x + 1; x",
+            expect![[r#"
+                Root@0..13
+                  Exp_Binary@0..13
+                    Exp_Assign@0..10
+                      Exp_VariableRef@0..2
+                        Identifier@0..1 "x"
+                        Whitespace@1..2 " "
+                      Sym_LThinArrow@2..4 "<-"
+                      Whitespace@4..5 " "
+                      Exp_Binary@5..10
+                        Exp_VariableRef@5..7
+                          Identifier@5..6 "x"
+                          Whitespace@6..7 " "
+                        Sym_Plus@7..8 "+"
+                        Whitespace@8..9 " "
+                        Exp_Literal@9..10
+                          Lit_Integer@9..10 "1"
+                    Sym_Semicolon@10..11 ";"
+                    Whitespace@11..12 " "
+                    Exp_VariableRef@12..13
+                      Identifier@12..13 "x"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_return() {
+        check(
+            "\\x -> return x + 1; x",
+            expect![[r#"
+                Root@0..21
+                  Exp_Binary@0..21
+                    Exp_Lambda@0..18
+                      Sym_BackSlash@0..1 "\\"
+                      ParamList@1..3
+                        Identifier@1..2 "x"
+                        Whitespace@2..3 " "
+                      Sym_RThinArrow@3..5 "->"
+                      Whitespace@5..6 " "
+                      Exp_Return@6..18
+                        Kwd_Return@6..12 "return"
+                        Whitespace@12..13 " "
+                        Exp_Binary@13..18
+                          Exp_VariableRef@13..15
+                            Identifier@13..14 "x"
+                            Whitespace@14..15 " "
+                          Sym_Plus@15..16 "+"
+                          Whitespace@16..17 " "
+                          Exp_Literal@17..18
+                            Lit_Integer@17..18 "1"
+                    Sym_Semicolon@18..19 ";"
+                    Whitespace@19..20 " "
+                    Exp_VariableRef@20..21
+                      Identifier@20..21 "x"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_return_without_value() {
+        check(
+            "{ return }",
+            expect![[r#"
+                Root@0..10
+                  Exp_Block@0..10
+                    Sym_LBrace@0..1 "{"
+                    Whitespace@1..2 " "
+                    Exp_Return@2..9
+                      Kwd_Return@2..8 "return"
+                      Whitespace@8..9 " "
+                    Sym_RBrace@9..10 "}"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_range() {
+        check(
+            "0..10",
+            expect![[r#"
+                Root@0..5
+                  Exp_Range@0..5
+                    Exp_Literal@0..1
+                      Lit_Integer@0..1 "0"
+                    Sym_DotDot@1..3 ".."
+                    Exp_Literal@3..5
+                      Lit_Integer@3..5 "10"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_inclusive_range() {
+        check(
+            "1..=n + 1 |> f",
+            expect![[r#"
+                Root@0..14
+                  Exp_Binary@0..14
+                    Exp_Range@0..10
+                      Exp_Literal@0..1
+                        Lit_Integer@0..1 "1"
+                      Sym_DotDotEq@1..4 "..="
+                      Exp_Binary@4..9
+                        Exp_VariableRef@4..6
+                          Identifier@4..5 "n"
+                          Whitespace@5..6 " "
+                        Sym_Plus@6..7 "+"
+                        Whitespace@7..8 " "
+                        Exp_Literal@8..9
+                          Lit_Integer@8..9 "1"
+                      Whitespace@9..10 " "
+                    Sym_PipeGt@10..12 "|>"
+                    Whitespace@12..13 " "
+                    Exp_VariableRef@13..14
+                      Identifier@13..14 "f"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_unit() {
+        check(
+            "( )",
+            expect![[r#"
+                Root@0..3
+                  Exp_Unit@0..3
+                    Sym_LParen@0..1 "("
+                    Whitespace@1..2 " "
+                    Sym_RParen@2..3 ")"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_nested_groups() {
+        check(
+            "((a) + ())",
+            expect![[r#"
+                Root@0..10
+                  Exp_Paren@0..10
+                    Sym_LParen@0..1 "("
+                    Exp_Binary@1..9
+                      Exp_Paren@1..5
+                        Sym_LParen@1..2 "("
+                        Exp_VariableRef@2..3
+                          Identifier@2..3 "a"
+                        Sym_RParen@3..4 ")"
+                        Whitespace@4..5 " "
+                      Sym_Plus@5..6 "+"
+                      Whitespace@6..7 " "
+                      Exp_Unit@7..9
+                        Sym_LParen@7..8 "("
+                        Sym_RParen@8..9 ")"
+                    Sym_RParen@9..10 ")"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_unclosed_group() {
+        check(
+            "(1 + (2",
+            expect![[r#"
+                Root@0..7
+                  Exp_Paren@0..7
+                    Sym_LParen@0..1 "("
+                    Exp_Binary@1..7
+                      Exp_Literal@1..3
+                        Lit_Integer@1..2 "1"
+                        Whitespace@2..3 " "
+                      Sym_Plus@3..4 "+"
+                      Whitespace@4..5 " "
+                      Exp_Paren@5..7
+                        Sym_LParen@5..6 "("
+                        Exp_Literal@6..7
+                          Lit_Integer@6..7 "2"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_group_with_stray_tokens() {
+        check(
+            "(1 2 (3)) + 4",
+            expect![[r#"
+                Root@0..13
+                  Exp_Binary@0..13
+                    Exp_Paren@0..10
+                      Sym_LParen@0..1 "("
+                      Exp_Literal@1..3
+                        Lit_Integer@1..2 "1"
+                        Whitespace@2..3 " "
+                      Error@3..8
+                        Lit_Integer@3..4 "2"
+                        Whitespace@4..5 " "
+                        Sym_LParen@5..6 "("
+                        Lit_Integer@6..7 "3"
+                        Sym_RParen@7..8 ")"
+                      Sym_RParen@8..9 ")"
+                      Whitespace@9..10 " "
+                    Sym_Plus@10..11 "+"
+                    Whitespace@11..12 " "
+                    Exp_Literal@12..13
+                      Lit_Integer@12..13 "4"
+            "#]],
+        );
+    }
+
     #[test]
     fn test_parse_complex_expression() {
         check(
@@ -419,23 +1006,190 @@ mod tests {
                 Root@0..37
                   Whitespace@0..1 "\n"
                   Exp_Binary@1..37
-                    Exp_Binary@1..22
-                      Exp_Literal@1..5
+                    Exp_Binary@1..19
+                      Exp_Literal@1..2
                         Lit_Integer@1..2 "1"
-                        Whitespace@2..5 "\n  "
+                      Whitespace@2..5 "\n  "
                       Sym_Plus@5..6 "+"
                       Whitespace@6..7 " "
-                      Exp_Literal@7..22
+                      Exp_Literal@7..8
                         Lit_Integer@7..8 "1"
-                        Whitespace@8..9 " "
-                        Comment@9..19 "-- Add one"
-                        Whitespace@19..22 "\n  "
+                      Whitespace@8..9 " "
+                      Comment@9..19 "-- Add one"
+                    Whitespace@19..22 "\n  "
                     Sym_Plus@22..23 "+"
                     Whitespace@23..24 " "
-                    Exp_Literal@24..37
+                    Exp_Literal@24..26
                       Lit_Integer@24..26 "10"
-                      Whitespace@26..27 " "
-                      Comment@27..37 "-- Add ten"
+                    Whitespace@26..27 " "
+                    Comment@27..37 "-- Add ten"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_block_expression() {
+        check(
+            "{ a; b + 1 }",
+            expect![[r#"
+                Root@0..12
+                  Exp_Block@0..12
+                    Sym_LBrace@0..1 "{"
+                    Whitespace@1..2 " "
+                    Exp_Binary@2..11
+                      Exp_VariableRef@2..3
+                        Identifier@2..3 "a"
+                      Sym_Semicolon@3..4 ";"
+                      Whitespace@4..5 " "
+                      Exp_Binary@5..10
+                        Exp_VariableRef@5..7
+                          Identifier@5..6 "b"
+                          Whitespace@6..7 " "
+                        Sym_Plus@7..8 "+"
+                        Whitespace@8..9 " "
+                        Exp_Literal@9..10
+                          Lit_Integer@9..10 "1"
+                      Whitespace@10..11 " "
+                    Sym_RBrace@11..12 "}"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_block_expression_missing_closing_brace() {
+        check(
+            "{ a; b",
+            expect![[r#"
+                Root@0..6
+                  Exp_Block@0..6
+                    Sym_LBrace@0..1 "{"
+                    Whitespace@1..2 " "
+                    Exp_Binary@2..6
+                      Exp_VariableRef@2..3
+                        Identifier@2..3 "a"
+                      Sym_Semicolon@3..4 ";"
+                      Whitespace@4..5 " "
+                      Exp_VariableRef@5..6
+                        Identifier@5..6 "b"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_indented_block_expression() {
+        check_layout(
+            "let a =\n  b\n  c + 1\nlet d = e",
+            expect![[r#"
+                Root@0..29
+                  Dec_GlobalBinding@0..19
+                    Kwd_Let@0..3 "let"
+                    Whitespace@3..4 " "
+                    Identifier@4..5 "a"
+                    Whitespace@5..6 " "
+                    Sym_Eq@6..7 "="
+                    Whitespace@7..10 "\n  "
+                    Exp_Block@10..19
+                      Lay_Begin@10..10 ""
+                      Exp_Binary@10..19
+                        Exp_VariableRef@10..11
+                          Identifier@10..11 "b"
+                        Whitespace@11..14 "\n  "
+                        Lay_Newline@14..14 ""
+                        Exp_Binary@14..19
+                          Exp_VariableRef@14..16
+                            Identifier@14..15 "c"
+                            Whitespace@15..16 " "
+                          Sym_Plus@16..17 "+"
+                          Whitespace@17..18 " "
+                          Exp_Literal@18..19
+                            Lit_Integer@18..19 "1"
+                      Lay_End@19..19 ""
+                  Whitespace@19..20 "\n"
+                  Dec_GlobalBinding@20..29
+                    Kwd_Let@20..23 "let"
+                    Whitespace@23..24 " "
+                    Identifier@24..25 "d"
+                    Whitespace@25..26 " "
+                    Sym_Eq@26..27 "="
+                    Whitespace@27..28 " "
+                    Exp_VariableRef@28..29
+                      Identifier@28..29 "e"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_nested_indented_block_expressions() {
+        check_layout(
+            "let f = \\x ->\n  \\y ->\n    y\n  x -- Comment\n",
+            expect![[r#"
+                Root@0..43
+                  Dec_GlobalBinding@0..42
+                    Kwd_Let@0..3 "let"
+                    Whitespace@3..4 " "
+                    Identifier@4..5 "f"
+                    Whitespace@5..6 " "
+                    Sym_Eq@6..7 "="
+                    Whitespace@7..8 " "
+                    Exp_Lambda@8..31
+                      Sym_BackSlash@8..9 "\\"
+                      ParamList@9..11
+                        Identifier@9..10 "x"
+                        Whitespace@10..11 " "
+                      Sym_RThinArrow@11..13 "->"
+                      Whitespace@13..16 "\n  "
+                      Exp_Block@16..31
+                        Lay_Begin@16..16 ""
+                        Exp_Binary@16..31
+                          Exp_Lambda@16..27
+                            Sym_BackSlash@16..17 "\\"
+                            ParamList@17..19
+                              Identifier@17..18 "y"
+                              Whitespace@18..19 " "
+                            Sym_RThinArrow@19..21 "->"
+                            Whitespace@21..26 "\n    "
+                            Exp_Block@26..27
+                              Lay_Begin@26..26 ""
+                              Exp_VariableRef@26..27
+                                Identifier@26..27 "y"
+                              Lay_End@27..27 ""
+                          Whitespace@27..30 "\n  "
+                          Lay_Newline@30..30 ""
+                          Exp_VariableRef@30..31
+                            Identifier@30..31 "x"
+                        Lay_End@31..31 ""
+                    Whitespace@31..32 " "
+                    Comment@32..42 "-- Comment"
+                  Whitespace@42..43 "\n"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_parse_layout_without_indentation() {
+        check_layout(
+            "let a = {\n  b;\n  c }",
+            expect![[r#"
+                Root@0..20
+                  Dec_GlobalBinding@0..20
+                    Kwd_Let@0..3 "let"
+                    Whitespace@3..4 " "
+                    Identifier@4..5 "a"
+                    Whitespace@5..6 " "
+                    Sym_Eq@6..7 "="
+                    Whitespace@7..8 " "
+                    Exp_Block@8..20
+                      Sym_LBrace@8..9 "{"
+                      Whitespace@9..12 "\n  "
+                      Exp_Binary@12..19
+                        Exp_VariableRef@12..13
+                          Identifier@12..13 "b"
+                        Sym_Semicolon@13..14 ";"
+                        Whitespace@14..17 "\n  "
+                        Exp_VariableRef@17..18
+                          Identifier@17..18 "c"
+                        Whitespace@18..19 " "
+                      Sym_RBrace@19..20 "}"
             "#]],
         );
     }
@@ -0,0 +1,338 @@
+//! The table of operators recognised in expressions.
+//!
+//! Every operator is described by an entry in [`OPERATOR_TABLE`], from which
+//! the binding powers used by the expression parser are derived. Adding a new
+//! operator only requires adding an entry to the table (as long as the lexer
+//! produces a [`SyntaxKind`] for it): its precedence determines how tightly it
+//! holds onto its operands relative to the other operators, and its
+//! associativity determines how a chain of operators of the same precedence is
+//! grouped. An experimental operator also names the feature gating it.
+
+use crate::Feature;
+use helios_syntax::{Sym, SyntaxKind};
+
+/// How a chain of operators with the same precedence is grouped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Associativity {
+    /// `a + b + c` is parsed as `(a + b) + c`.
+    Left,
+    /// `a ^ b ^ c` is parsed as `a ^ (b ^ c)`.
+    Right,
+}
+
+/// An operator that appears between its two operands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct InfixOperator {
+    pub(crate) kind: SyntaxKind,
+    pub(crate) precedence: u8,
+    pub(crate) associativity: Associativity,
+    /// The feature the operator is part of, if it's experimental.
+    pub(crate) feature: Option<Feature>,
+}
+
+/// An operator that appears before its operand.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct PrefixOperator {
+    pub(crate) kind: SyntaxKind,
+    pub(crate) precedence: u8,
+}
+
+/// An operator that appears after its operand.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct PostfixOperator {
+    pub(crate) kind: SyntaxKind,
+    pub(crate) precedence: u8,
+}
+
+pub(crate) struct OperatorTable {
+    infix: &'static [InfixOperator],
+    prefix: &'static [PrefixOperator],
+    postfix: &'static [PostfixOperator],
+}
+
+const fn infix(
+    kind: SyntaxKind,
+    precedence: u8,
+    associativity: Associativity,
+) -> InfixOperator {
+    InfixOperator {
+        kind,
+        precedence,
+        associativity,
+        feature: None,
+    }
+}
+
+/// An experimental infix operator, which is only accepted with the given
+/// feature enabled.
+const fn gated_infix(
+    kind: SyntaxKind,
+    precedence: u8,
+    associativity: Associativity,
+    feature: Feature,
+) -> InfixOperator {
+    InfixOperator {
+        feature: Some(feature),
+        ..infix(kind, precedence, associativity)
+    }
+}
+
+/// Every operator recognised in expressions, from the lowest to the highest
+/// precedence.
+///
+/// Prefix operators bind more tightly than every infix operator except `^`,
+/// so that `-a * b` is `(-a) * b` and `-a ^ b` is `-(a ^ b)`. Postfix operators
+/// bind more tightly than any other operator, so that `-a?` is `-(a?)`.
+///
+/// Ranges bind more loosely than the logical, comparison and arithmetic
+/// operators (so that `0..n + 1` is `0..(n + 1)`), but more tightly than `|>`
+/// (so that `0..n |> f` is `(0..n) |> f`). The backward pipeline `<|` binds
+/// more loosely than `|>` and groups to the right, so that `f <| x |> g` is
+/// `f <| (x |> g)` and `f <| g <| x` is `f <| (g <| x)`.
+pub(crate) const OPERATOR_TABLE: OperatorTable = {
+    use Associativity::*;
+    OperatorTable {
+        infix: &[
+            infix(Sym![";"], 1, Left),
+            infix(Sym!["<-"], 2, Right),
+            gated_infix(Sym!["<|"], 3, Right, Feature::BackwardPipelines),
+            infix(Sym!["|>"], 4, Left),
+            infix(Sym![".."], 5, Left),
+            infix(Sym!["..="], 5, Left),
+            infix(SyntaxKind::Kwd_Or, 6, Left),
+            infix(SyntaxKind::Kwd_And, 7, Left),
+            infix(Sym!["="], 8, Right),
+            infix(Sym!["!="], 8, Right),
+            infix(Sym!["<"], 9, Left),
+            infix(Sym![">"], 9, Left),
+            infix(Sym!["<="], 9, Left),
+            infix(Sym![">="], 9, Left),
+            infix(Sym!["+"], 10, Left),
+            infix(Sym!["-"], 10, Left),
+            infix(Sym!["*"], 11, Left),
+            infix(Sym!["/"], 11, Left),
+            infix(Sym!["^"], 13, Right),
+        ],
+        prefix: &[
+            PrefixOperator {
+                kind: Sym!["-"],
+                precedence: 12,
+            },
+            PrefixOperator {
+                kind: Sym!["!"],
+                precedence: 12,
+            },
+        ],
+        postfix: &[PostfixOperator {
+            kind: Sym!["?"],
+            precedence: 14,
+        }],
+    }
+};
+
+impl OperatorTable {
+    /// The kinds of every infix operator.
+    ///
+    /// This includes the new lines of indented blocks, which separate
+    /// expressions like `;` does (see [`OperatorTable::infix`]).
+    pub(crate) fn infix_kinds(&self) -> Vec<SyntaxKind> {
+        let kinds = self.infix.iter().map(|operator| operator.kind);
+        kinds.chain(Some(SyntaxKind::Lay_Newline)).collect()
+    }
+
+    /// The kinds of every prefix operator.
+    pub(crate) fn prefix_kinds(&self) -> Vec<SyntaxKind> {
+        self.prefix.iter().map(|operator| operator.kind).collect()
+    }
+
+    /// The kinds of every postfix operator.
+    pub(crate) fn postfix_kinds(&self) -> Vec<SyntaxKind> {
+        self.postfix.iter().map(|operator| operator.kind).collect()
+    }
+
+    pub(crate) fn infix(&self, kind: SyntaxKind) -> Option<&InfixOperator> {
+        // A new line in an indented block stands for a `;`
+        let kind = match kind {
+            SyntaxKind::Lay_Newline => Sym![";"],
+            kind => kind,
+        };
+
+        self.infix.iter().find(|operator| operator.kind == kind)
+    }
+
+    /// The minimum binding power with which an expression stops before the
+    /// next `;` (or new line of an indented block), so that it is only one of
+    /// the expressions separated by it.
+    pub(crate) fn separator_binding_power(&self) -> u8 {
+        let (_, right_bp) = self.infix(Sym![";"]).unwrap().binding_power();
+        right_bp
+    }
+
+    pub(crate) fn prefix(&self, kind: SyntaxKind) -> Option<&PrefixOperator> {
+        self.prefix.iter().find(|operator| operator.kind == kind)
+    }
+
+    pub(crate) fn postfix(&self, kind: SyntaxKind) -> Option<&PostfixOperator> {
+        self.postfix.iter().find(|operator| operator.kind == kind)
+    }
+}
+
+impl InfixOperator {
+    /// Determines the left and right binding power of the operator. A higher
+    /// binding power means higher precedence, meaning that it is more likely
+    /// to hold onto its adjacent operands.
+    pub(crate) fn binding_power(&self) -> (u8, u8) {
+        let bp = self.precedence * 2;
+        match self.associativity {
+            Associativity::Left => (bp, bp + 1),
+            Associativity::Right => (bp + 1, bp),
+        }
+    }
+
+    /// The kind of the node of an expression made with the operator:
+    /// [`SyntaxKind::Exp_Assign`] for `<-`, [`SyntaxKind::Exp_Range`] for `..`
+    /// and `..=`, and [`SyntaxKind::Exp_Binary`] for every other operator.
+    pub(crate) fn node_kind(&self) -> SyntaxKind {
+        match self.kind {
+            Sym!["<-"] => SyntaxKind::Exp_Assign,
+            Sym![".."] | Sym!["..="] => SyntaxKind::Exp_Range,
+            _ => SyntaxKind::Exp_Binary,
+        }
+    }
+}
+
+impl PrefixOperator {
+    /// Determines the right binding power of the operator.
+    pub(crate) fn binding_power(&self) -> ((), u8) {
+        ((), self.precedence * 2)
+    }
+}
+
+impl PostfixOperator {
+    /// Determines the left binding power of the operator.
+    pub(crate) fn binding_power(&self) -> (u8, ()) {
+        (self.precedence * 2, ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+    use helios_syntax::SyntaxNode;
+
+    fn text(kind: SyntaxKind) -> String {
+        match kind {
+            SyntaxKind::Kwd_And => "and".to_string(),
+            SyntaxKind::Kwd_Or => "or".to_string(),
+            kind => kind.code_repr().expect("operator should be a symbol"),
+        }
+    }
+
+    /// The kind of the operator of the given binary (or assignment)
+    /// expression.
+    fn operator(node: &SyntaxNode) -> SyntaxKind {
+        let operator = node
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .map(|token| token.kind())
+            .find(|kind| !kind.is_trivia())
+            .unwrap();
+
+        let infix = OPERATOR_TABLE.infix(operator).unwrap();
+        assert_eq!(node.kind(), infix.node_kind());
+        operator
+    }
+
+    #[test]
+    fn test_infix_precedence_and_associativity() {
+        for first in OPERATOR_TABLE.infix {
+            for second in OPERATOR_TABLE.infix {
+                let input =
+                    format!("a {} b {} c", text(first.kind), text(second.kind));
+
+                // We're only interested in the shape of the tree, so we'll
+                // ignore diagnostics from validation (assignments to `a • b`)
+                let syntax = parse(0u8, &input).syntax();
+                let has_errors = syntax
+                    .descendants()
+                    .any(|node| node.kind() == SyntaxKind::Error);
+                assert!(!has_errors, "{}", input);
+
+                let root = syntax.first_child().unwrap();
+                let groups_left = match first.precedence.cmp(&second.precedence)
+                {
+                    std::cmp::Ordering::Greater => true,
+                    std::cmp::Ordering::Less => false,
+                    std::cmp::Ordering::Equal => {
+                        first.associativity == Associativity::Left
+                    }
+                };
+
+                // `(a • b) ∘ c` has `∘` at its root, whereas `a • (b ∘ c)` has
+                // `•` at its root
+                let expected = if groups_left { second } else { first };
+                assert_eq!(operator(&root), expected.kind, "{}", input);
+            }
+        }
+    }
+
+    #[test]
+    fn test_prefix_precedence() {
+        for prefix in OPERATOR_TABLE.prefix {
+            for operator in OPERATOR_TABLE.infix {
+                let input =
+                    format!("{}a {} b", text(prefix.kind), text(operator.kind));
+
+                let parse = parse(0u8, &input);
+                let root = parse.syntax().first_child().unwrap();
+
+                let expected = if prefix.precedence > operator.precedence {
+                    operator.node_kind()
+                } else {
+                    SyntaxKind::Exp_UnaryPrefix
+                };
+
+                assert_eq!(root.kind(), expected, "{}", input);
+            }
+        }
+    }
+
+    #[test]
+    fn test_postfix_precedence() {
+        for postfix in OPERATOR_TABLE.postfix {
+            let postfix_text = text(postfix.kind);
+
+            for operator in OPERATOR_TABLE.infix {
+                let text = text(operator.kind);
+                let input = format!("a {} b{}", text, postfix_text);
+
+                let parse = parse(0u8, &input);
+                let root = parse.syntax().first_child().unwrap();
+
+                let expected = if postfix.precedence > operator.precedence {
+                    operator.node_kind()
+                } else {
+                    SyntaxKind::Exp_UnaryPostfix
+                };
+
+                assert_eq!(root.kind(), expected, "{}", input);
+            }
+
+            for prefix in OPERATOR_TABLE.prefix {
+                let input = format!("{}a{}", text(prefix.kind), postfix_text);
+
+                let parse = parse(0u8, &input);
+                let root = parse.syntax().first_child().unwrap();
+
+                let expected = if postfix.precedence > prefix.precedence {
+                    SyntaxKind::Exp_UnaryPrefix
+                } else {
+                    SyntaxKind::Exp_UnaryPostfix
+                };
+
+                assert_eq!(root.kind(), expected, "{}", input);
+            }
+        }
+    }
+}
@@ -0,0 +1,18 @@
+use super::*;
+
+/// Parses a type, such as the type of a field of an enum variant.
+///
+/// Only named types (such as `Float`) are currently supported.
+pub(super) fn ty<FileId>(p: &mut Parser<FileId>) -> Option<CompletedMarker>
+where
+    FileId: Clone + Default,
+{
+    if p.is_at(SyntaxKind::Identifier) {
+        let m = p.start();
+        p.bump();
+        Some(m.complete(p, SyntaxKind::Typ_Named))
+    } else {
+        p.error(SyntaxKind::Typ_Named);
+        None
+    }
+}
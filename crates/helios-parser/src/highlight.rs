@@ -0,0 +1,203 @@
+//! Classifying the tokens of a source file for syntax highlighting.
+//!
+//! Highlighting only uses the lexer, so it works on any text (even text that
+//! doesn't parse, such as a line being typed in the REPL), and the way tokens
+//! are shown is left to the caller (such as HTML for documentation), except
+//! for colors in a terminal (see [`to_ansi`]).
+
+use crate::lexer::Lexer;
+use crate::Token;
+use colored::{Color, Colorize};
+use helios_diagnostics::{SyntaxColors, Theme};
+use helios_syntax::SyntaxKind;
+use std::ops::Range;
+
+/// The kind of highlighting of a token, coarser than its [`SyntaxKind`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HighlightTag {
+    Keyword,
+    /// A symbol that isn't punctuation, such as `+` or `|>`.
+    Operator,
+    /// Brackets, commas, colons and semicolons.
+    Punctuation,
+    /// An integer or float literal.
+    Number,
+    /// A character or string literal (or part of one, around its
+    /// interpolations).
+    String,
+    /// A comment, including doc comments.
+    Comment,
+    Identifier,
+    /// A character the lexer doesn't recognise.
+    Error,
+}
+
+impl HighlightTag {
+    /// The tag of the given kind of token, if it's highlighted at all (unlike
+    /// whitespace).
+    pub fn of(kind: SyntaxKind) -> Option<Self> {
+        use SyntaxKind::*;
+        match kind {
+            kind if kind.is_keyword() => Some(Self::Keyword),
+            Sym_LBrace | Sym_RBrace | Sym_LBracket | Sym_RBracket
+            | Sym_LParen | Sym_RParen | Sym_Comma | Sym_Colon
+            | Sym_Semicolon => Some(Self::Punctuation),
+            kind if kind.is_symbol() => Some(Self::Operator),
+            Lit_Integer | Lit_Float => Some(Self::Number),
+            Lit_Character | Lit_String | Lit_StringStart | Lit_StringMiddle
+            | Lit_StringEnd => Some(Self::String),
+            kind if kind.is_comment() => Some(Self::Comment),
+            kind if kind.is_identifier() => Some(Self::Identifier),
+            UnknownChar => Some(Self::Error),
+            _ => None,
+        }
+    }
+
+    /// The name of the tag, such as in CSS classes.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Keyword => "keyword",
+            Self::Operator => "operator",
+            Self::Punctuation => "punctuation",
+            Self::Number => "number",
+            Self::String => "string",
+            Self::Comment => "comment",
+            Self::Identifier => "identifier",
+            Self::Error => "error",
+        }
+    }
+
+    /// The color of the tag in the given colors.
+    pub fn color(self, colors: &SyntaxColors) -> Option<Color> {
+        match self {
+            Self::Keyword => colors.keyword,
+            Self::Operator => colors.operator,
+            Self::Punctuation => colors.punctuation,
+            Self::Number => colors.number,
+            Self::String => colors.string,
+            Self::Comment => colors.comment,
+            Self::Identifier => colors.identifier,
+            Self::Error => colors.error,
+        }
+    }
+}
+
+/// A token of a source file with its highlighting.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Highlighted<'source> {
+    pub token: Token<'source>,
+    pub tag: Option<HighlightTag>,
+}
+
+/// Splits the given source into its tokens (without leaving any text out)
+/// and classifies each of them.
+pub fn highlight(source: &str) -> Vec<Highlighted<'_>> {
+    Lexer::<()>::new((), source)
+        .map(|(token, _)| Highlighted {
+            tag: HighlightTag::of(token.kind),
+            token,
+        })
+        .collect()
+}
+
+/// The ranges of the given source to color with the given colors, in order
+/// (which is the [`Highlighter`] of diagnostics).
+///
+/// [`Highlighter`]: helios_diagnostics::Highlighter
+pub fn colored_ranges(
+    source: &str,
+    colors: &SyntaxColors,
+) -> Vec<(Range<usize>, Color)> {
+    highlight(source)
+        .into_iter()
+        .filter_map(|it| {
+            let color = it.tag?.color(colors)?;
+            Some((it.token.range, color))
+        })
+        .collect()
+}
+
+/// Colors the given source with ANSI escape codes for a terminal.
+///
+/// Like the rest of the output, the source is left as it is when colorizing
+/// is turned off (such as by `--color never` or the `NO_COLOR` environment
+/// variable, see [`ColorChoice`]).
+///
+/// [`ColorChoice`]: helios_diagnostics::ColorChoice
+pub fn to_ansi(source: &str, colors: &SyntaxColors) -> String {
+    let mut ansi = String::with_capacity(source.len());
+    for it in highlight(source) {
+        match it.tag.and_then(|tag| tag.color(colors)) {
+            Some(color) => {
+                ansi.push_str(&it.token.text.color(color).to_string())
+            }
+            None => ansi.push_str(it.token.text),
+        }
+    }
+
+    ansi
+}
+
+/// The default theme of diagnostics, with the code of their snippets
+/// highlighted.
+pub fn theme() -> Theme {
+    Theme {
+        highlighter: Some(colored_ranges),
+        ..Theme::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight() {
+        let source = "let a = f(1, \"b\") -- c\n";
+        let highlighted = highlight(source);
+
+        let text = highlighted
+            .iter()
+            .map(|it| it.token.text)
+            .collect::<String>();
+        assert_eq!(text, source);
+
+        let tags = highlighted
+            .iter()
+            .filter_map(|it| it.tag.map(|tag| (tag, it.token.text)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tags,
+            vec![
+                (HighlightTag::Keyword, "let"),
+                (HighlightTag::Identifier, "a"),
+                (HighlightTag::Operator, "="),
+                (HighlightTag::Identifier, "f"),
+                (HighlightTag::Punctuation, "("),
+                (HighlightTag::Number, "1"),
+                (HighlightTag::Punctuation, ","),
+                (HighlightTag::String, "\"b\""),
+                (HighlightTag::Punctuation, ")"),
+                (HighlightTag::Comment, "-- c"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_colored_ranges() {
+        let colors = SyntaxColors {
+            identifier: Some(Color::Blue),
+            ..SyntaxColors::default()
+        };
+
+        // Operators aren't colored by default
+        assert_eq!(
+            colored_ranges("let a = 1", &colors),
+            vec![
+                (0..3, Color::Magenta),
+                (4..5, Color::Blue),
+                (8..9, Color::Cyan),
+            ]
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//! Comparing Unicode identifiers.
+//!
+//! Identifiers are made of any characters with the `XID_Start` and
+//! `XID_Continue` properties (see the lexer), following [UAX #31]. The same
+//! identifier may however be written with different sequences of characters
+//! (such as `é` as one character, or as `e` followed by a combining accent),
+//! so identifiers are always compared in their [NFC] form (see
+//! [`normalize_identifier`]).
+//!
+//! Different identifiers may also look the same, such as `pass` written with
+//! a Latin `a` and with a Cyrillic `а`. The validation of the syntax tree
+//! warns about those (see [`Script`] and [`skeleton`]).
+//!
+//! [UAX #31]: https://www.unicode.org/reports/tr31/
+//! [NFC]: https://www.unicode.org/reports/tr15/
+
+use std::borrow::Cow;
+use std::fmt::{self, Display};
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+
+/// The form of the given identifier that is used to compare it with other
+/// identifiers (its Normalization Form C).
+pub fn normalize_identifier(text: &str) -> Cow<'_, str> {
+    match is_nfc_quick(text.chars()) {
+        IsNormalized::Yes => Cow::Borrowed(text),
+        _ => Cow::Owned(text.nfc().collect()),
+    }
+}
+
+/// The scripts whose letters are most easily confused with one another (as
+/// many of their letters look alike).
+///
+/// Identifiers mixing letters of these scripts are reported, since they are
+/// almost always a mistake (or an attempt to deceive the reader). Letters of
+/// any other script may be mixed freely.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Script {
+    Latin,
+    Greek,
+    Cyrillic,
+}
+
+impl Script {
+    fn of(c: char) -> Option<Self> {
+        match c {
+            'a'..='z'
+            | 'A'..='Z'
+            | '\u{00C0}'..='\u{024F}'
+            | '\u{1E00}'..='\u{1EFF}' => Some(Self::Latin),
+            '\u{0370}'..='\u{03FF}' | '\u{1F00}'..='\u{1FFF}' => {
+                Some(Self::Greek)
+            }
+            '\u{0400}'..='\u{052F}'
+            | '\u{1C80}'..='\u{1C8F}'
+            | '\u{2DE0}'..='\u{2DFF}'
+            | '\u{A640}'..='\u{A69F}' => Some(Self::Cyrillic),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Script {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Latin => "Latin",
+            Self::Greek => "Greek",
+            Self::Cyrillic => "Cyrillic",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// The first two different [`Script`]s whose letters are mixed in the given
+/// identifier, if any.
+pub(crate) fn mixed_scripts(text: &str) -> Option<[Script; 2]> {
+    let mut scripts = text.chars().filter_map(Script::of);
+    let first = scripts.next()?;
+    let second = scripts.find(|&script| script != first)?;
+    Some([first, second])
+}
+
+/// The given (normalized) identifier with every letter that looks like a
+/// Latin letter replaced by that letter, so that two identifiers that look
+/// alike have the same skeleton.
+///
+/// This is a small subset of the confusables of [UTS #39], covering the Greek
+/// and Cyrillic letters that are indistinguishable from Latin ones in most
+/// fonts.
+///
+/// [UTS #39]: https://www.unicode.org/reports/tr39/
+pub(crate) fn skeleton(text: &str) -> String {
+    #[rustfmt::skip]
+    fn latin(c: char) -> char {
+        match c {
+            // Cyrillic
+            'а' => 'a', 'е' => 'e', 'о' => 'o', 'р' => 'p', 'с' => 'c',
+            'у' => 'y', 'х' => 'x', 'ѕ' => 's', 'і' => 'i', 'ј' => 'j',
+            'ԁ' => 'd', 'һ' => 'h', 'ԛ' => 'q', 'ԝ' => 'w', 'ӏ' => 'l',
+            'А' => 'A', 'В' => 'B', 'Е' => 'E', 'К' => 'K', 'М' => 'M',
+            'Н' => 'H', 'О' => 'O', 'Р' => 'P', 'С' => 'C', 'Т' => 'T',
+            'Х' => 'X', 'У' => 'Y', 'Ѕ' => 'S', 'І' => 'I', 'Ј' => 'J',
+            // Greek
+            'ο' => 'o', 'ν' => 'v', 'ι' => 'i', 'κ' => 'k', 'ρ' => 'p',
+            'υ' => 'u', 'Α' => 'A', 'Β' => 'B', 'Ε' => 'E', 'Ζ' => 'Z',
+            'Η' => 'H', 'Ι' => 'I', 'Κ' => 'K', 'Μ' => 'M', 'Ν' => 'N',
+            'Ο' => 'O', 'Ρ' => 'P', 'Τ' => 'T', 'Υ' => 'Y', 'Χ' => 'X',
+            c => c,
+        }
+    }
+
+    text.chars().map(latin).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_identifier() {
+        assert!(matches!(normalize_identifier("abc"), Cow::Borrowed("abc")));
+        assert!(matches!(normalize_identifier("été"), Cow::Borrowed("été")));
+        assert_eq!(normalize_identifier("e\u{301}t\u{e9}"), "\u{e9}t\u{e9}");
+        assert_eq!(normalize_identifier("\u{212B}"), "\u{C5}");
+    }
+
+    #[test]
+    fn test_mixed_scripts() {
+        assert_eq!(mixed_scripts("hello_world"), None);
+        assert_eq!(mixed_scripts("человек"), None);
+        assert_eq!(mixed_scripts("人的_name"), None);
+        assert_eq!(
+            mixed_scripts("p\u{0430}ss"),
+            Some([Script::Latin, Script::Cyrillic])
+        );
+        assert_eq!(
+            mixed_scripts("\u{03BF}k"),
+            Some([Script::Greek, Script::Latin])
+        );
+    }
+
+    #[test]
+    fn test_skeleton() {
+        assert_eq!(skeleton("p\u{0430}ss"), "pass");
+        assert_eq!(skeleton("\u{0421}\u{041E}\u{0423}NT"), "COYNT");
+        assert_eq!(skeleton("\u{03BF}k"), "ok");
+        assert_eq!(skeleton("человек"), "чeлoвeк");
+    }
+}
@@ -0,0 +1,251 @@
+//! Inferring blocks from the indentation of Helios source files.
+//!
+//! Blocks are normally delimited with braces and their expressions separated
+//! with semicolons (`{ a; b }`). With [`Feature::IndentationBlocks`] enabled,
+//! they may instead be written with indentation:
+//!
+//! ```text
+//! let a =
+//!     b
+//!     c
+//! ```
+//!
+//! This is done by a pre-pass over the tokens of the source text (the
+//! [`layout`] function), which inserts zero-width [`Lay_Begin`],
+//! [`Lay_Newline`] and [`Lay_End`] tokens where the braces and semicolons
+//! would have been. The parser then handles both kinds of blocks in the same
+//! way.
+//!
+//! [`Feature::IndentationBlocks`]: crate::Feature::IndentationBlocks
+//! [`Lay_Begin`]: helios_syntax::SyntaxKind::Lay_Begin
+//! [`Lay_Newline`]: helios_syntax::SyntaxKind::Lay_Newline
+//! [`Lay_End`]: helios_syntax::SyntaxKind::Lay_End
+
+use crate::lexer::Token;
+use helios_syntax::SyntaxKind;
+
+/// The tokens that may be followed by an indented block.
+const BLOCK_OPENERS: &[SyntaxKind] = &[
+    SyntaxKind::Kwd_Else,
+    SyntaxKind::Kwd_Of,
+    SyntaxKind::Kwd_Then,
+    SyntaxKind::Kwd_With,
+    SyntaxKind::Sym_Eq,
+    SyntaxKind::Sym_RThinArrow,
+    SyntaxKind::Sym_ThickArrow,
+];
+
+/// Inserts the layout tokens implied by the indentation of the given tokens.
+///
+/// The rules are as follows (where the column of a token is the number of
+/// characters preceding it on its line):
+///
+/// - A block starts after any of the [`BLOCK_OPENERS`] when the next token is
+///   at the start of a line and is indented further than the enclosing block.
+///   The column of that token is the indentation of the new block;
+/// - A token at the start of a line and at the indentation of the current
+///   block starts a new expression in that block; and
+/// - A token at the start of a line and indented less than the current block
+///   ends it (as does the end of the input).
+///
+/// Trivia never affects the layout, so comments may be indented freely.
+pub(crate) fn layout<'source>(
+    tokens: &[Token<'source>],
+) -> Vec<Token<'source>> {
+    let mut laid_out = Vec::with_capacity(tokens.len());
+    let mut blocks: Vec<usize> = Vec::new();
+
+    let mut column = 0;
+    let mut at_line_start = true;
+    let mut opens_block = false;
+    // The end of the last non-trivia token, where blocks are closed (so that
+    // the trivia following a block isn't part of it)
+    let mut last_end = 0;
+    // The trivia since the last non-trivia token, which is only added once
+    // it is known whether any block ends before it
+    let mut trivia = Vec::new();
+
+    for token in tokens {
+        if token.kind.is_trivia() {
+            trivia.push(token.clone());
+        } else {
+            if at_line_start {
+                let end =
+                    Token::new(SyntaxKind::Lay_End, "", last_end..last_end);
+                while blocks.last().is_some_and(|&block| block > column) {
+                    blocks.pop();
+                    laid_out.push(end.clone());
+                }
+            }
+
+            laid_out.append(&mut trivia);
+            if at_line_start {
+                let start = token.range.start;
+                let enclosing = blocks.last().copied().unwrap_or(0);
+                if opens_block && column > enclosing {
+                    blocks.push(column);
+                    laid_out.push(Token::new(
+                        SyntaxKind::Lay_Begin,
+                        "",
+                        start..start,
+                    ));
+                } else if blocks.last() == Some(&column) {
+                    laid_out.push(Token::new(
+                        SyntaxKind::Lay_Newline,
+                        "",
+                        start..start,
+                    ));
+                }
+            }
+
+            opens_block = BLOCK_OPENERS.contains(&token.kind);
+            last_end = token.range.end;
+            laid_out.push(token.clone());
+        }
+
+        match token.text.rfind('\n') {
+            Some(index) => {
+                column = token.text[index + 1..].chars().count();
+                at_line_start = token.kind.is_trivia();
+            }
+            None if token.kind.is_trivia() => {
+                column += token.text.chars().count();
+            }
+            None => {
+                column += token.text.chars().count();
+                at_line_start = false;
+            }
+        }
+    }
+
+    let end = Token::new(SyntaxKind::Lay_End, "", last_end..last_end);
+    laid_out.extend(blocks.iter().map(|_| end.clone()));
+    laid_out.append(&mut trivia);
+    laid_out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::{expect, Expect};
+
+    fn check(input: &str, expected: Expect) {
+        let (tokens, _) = crate::tokenize(0u8, input);
+        let actual = layout(&tokens)
+            .iter()
+            .filter(|token| !token.kind.is_trivia())
+            .map(|token| {
+                format!("{:?}@{:?} {:?}", token.kind, token.range, token.text)
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        expected.assert_eq(&actual);
+    }
+
+    #[test]
+    fn test_layout_single_line() {
+        check(
+            "let a = b",
+            expect![[r#"
+                Kwd_Let@0..3 "let"
+                Identifier@4..5 "a"
+                Sym_Eq@6..7 "="
+                Identifier@8..9 "b""#]],
+        );
+    }
+
+    #[test]
+    fn test_layout_block() {
+        check(
+            "let a =\n  b\n  c\nlet d = e",
+            expect![[r#"
+                Kwd_Let@0..3 "let"
+                Identifier@4..5 "a"
+                Sym_Eq@6..7 "="
+                Lay_Begin@10..10 ""
+                Identifier@10..11 "b"
+                Lay_Newline@14..14 ""
+                Identifier@14..15 "c"
+                Lay_End@15..15 ""
+                Kwd_Let@16..19 "let"
+                Identifier@20..21 "d"
+                Sym_Eq@22..23 "="
+                Identifier@24..25 "e""#]],
+        );
+    }
+
+    #[test]
+    fn test_layout_continued_line() {
+        check(
+            "let a =\n  b\n    + c\n  d",
+            expect![[r#"
+                Kwd_Let@0..3 "let"
+                Identifier@4..5 "a"
+                Sym_Eq@6..7 "="
+                Lay_Begin@10..10 ""
+                Identifier@10..11 "b"
+                Sym_Plus@16..17 "+"
+                Identifier@18..19 "c"
+                Lay_Newline@22..22 ""
+                Identifier@22..23 "d"
+                Lay_End@23..23 """#]],
+        );
+    }
+
+    #[test]
+    fn test_layout_nested_blocks() {
+        check(
+            "let f = \\x ->\n  \\y ->\n    y\n  x\n-- Comment\nlet g = f",
+            expect![[r#"
+                Kwd_Let@0..3 "let"
+                Identifier@4..5 "f"
+                Sym_Eq@6..7 "="
+                Sym_BackSlash@8..9 "\\"
+                Identifier@9..10 "x"
+                Sym_RThinArrow@11..13 "->"
+                Lay_Begin@16..16 ""
+                Sym_BackSlash@16..17 "\\"
+                Identifier@17..18 "y"
+                Sym_RThinArrow@19..21 "->"
+                Lay_Begin@26..26 ""
+                Identifier@26..27 "y"
+                Lay_End@27..27 ""
+                Lay_Newline@30..30 ""
+                Identifier@30..31 "x"
+                Lay_End@31..31 ""
+                Kwd_Let@43..46 "let"
+                Identifier@47..48 "g"
+                Sym_Eq@49..50 "="
+                Identifier@51..52 "f""#]],
+        );
+    }
+
+    #[test]
+    fn test_layout_not_indented() {
+        check(
+            "let a =\nb",
+            expect![[r#"
+                Kwd_Let@0..3 "let"
+                Identifier@4..5 "a"
+                Sym_Eq@6..7 "="
+                Identifier@8..9 "b""#]],
+        );
+    }
+
+    #[test]
+    fn test_layout_ignores_trivia() {
+        check(
+            "let a =\n  -- Comment\n    b -- Comment\n\n  -- Comment\n    c",
+            expect![[r#"
+                Kwd_Let@0..3 "let"
+                Identifier@4..5 "a"
+                Sym_Eq@6..7 "="
+                Lay_Begin@25..25 ""
+                Identifier@25..26 "b"
+                Lay_Newline@56..56 ""
+                Identifier@56..57 "c"
+                Lay_End@57..57 """#]],
+        );
+    }
+}
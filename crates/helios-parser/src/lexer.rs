@@ -25,8 +25,8 @@ use unicode_xid::UnicodeXID;
 /// `XID_Start` property.
 fn is_identifier_start(c: char) -> bool {
     // Fast-path for ASCII identifiers
-    ('a' <= c && c <= 'z')
-        || ('A' <= c && c <= 'Z')
+    c.is_ascii_lowercase()
+        || c.is_ascii_uppercase()
         || c == '_'
         || c.is_xid_start()
 }
@@ -36,9 +36,9 @@ fn is_identifier_start(c: char) -> bool {
 /// satisfies the `XID_Continue` property.
 fn is_identifier_continue(c: char) -> bool {
     // Fast-path for ASCII identifiers
-    ('a' <= c && c <= 'z')
-        || ('A' <= c && c <= 'Z')
-        || ('0' <= c && c <= '9')
+    c.is_ascii_lowercase()
+        || c.is_ascii_uppercase()
+        || c.is_ascii_digit()
         || c == '_'
         || c.is_xid_continue()
 }
@@ -46,17 +46,17 @@ fn is_identifier_continue(c: char) -> bool {
 /// Checks if the given character is a recognised symbol.
 #[rustfmt::skip]
 fn is_symbol(c: char) -> bool {
-    match c {
+    matches!(
+        c,
         '&' | '*' | '@' | '!' | '^' | ':' | ',' | '$' | '.' | '–' | '—' | '=' |
         '-' | '%' | '+' | '#' | '?' | ';' | '£' | '~' | '|' | '/' | '\\'| '<' |
-        '>' | '{' | '}' | '[' | ']' | '(' | ')' => true,
-        _ => false,
-    }
+        '>' | '{' | '}' | '[' | ']' | '(' | ')'
+    )
 }
 
 /// Checks if the given character is a digit.
 fn is_digit(c: char) -> bool {
-    matches!(c, '0'..='9')
+    c.is_ascii_digit()
 }
 
 /// Checks if the given character is a whitespace delimiter.
@@ -70,8 +70,8 @@ type LexerReturn<FileId> = (SyntaxKind, Option<Message<FileId>>);
 /// The unit of a tokenized Helios source file.
 ///
 /// This structure holds the [`SyntaxKind`] of a token, the text that formed it,
-/// and the range of the token (using `text_size::TextRange`). It is also the
-/// `Item` type of the [`Lexer`] iterator.
+/// and the byte range of the token in the source text. It is also the `Item`
+/// type of the [`Lexer`] iterator.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Token<'source> {
     pub kind: SyntaxKind,
@@ -97,16 +97,17 @@ impl<'source> Token<'source> {
 /// result, the lexer stores all the current modes in a LIFO stack. This would
 /// allow it to behave a little differently depending on its location in the
 /// source text.
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
 pub enum LexerMode {
     /// The default, normal mode.
+    #[default]
     Normal,
-}
-
-impl Default for LexerMode {
-    fn default() -> Self {
-        Self::Normal
-    }
+    /// Within the braces of an interpolation in a string literal (such as
+    /// `{name}` in `"Hello, {name}!"`), which is tokenized like the normal
+    /// mode until the closing brace (at the given depth of nested braces)
+    /// resumes the string, or until the end of the line if the string isn't
+    /// `multiline`.
+    Interpolation { multiline: bool, depth: usize },
 }
 
 /// A lazy, lossless lexer for the Helios programming language.
@@ -144,13 +145,11 @@ where
     }
 
     /// Pushes a new [`LexerMode`] to the mode stack.
-    #[allow(dead_code)]
     pub fn push_mode(&mut self, mode: LexerMode) {
         self.mode_stack.push(mode);
     }
 
     /// Pops off the last [`LexerMode`] from the mode stack.
-    #[allow(dead_code)]
     pub fn pop_mode(&mut self) -> Option<LexerMode> {
         self.mode_stack.pop()
     }
@@ -164,6 +163,9 @@ where
             c if c == '-' && self.peek() == '-' => self.lex_comment(c),
             c if is_whitespace(c) => self.lex_whitespace(c),
             c if is_symbol(c) => self.lex_symbol(c),
+            '"' => self.lex_string(start),
+            '\'' => self.lex_character(start),
+            'r' if self.peek() == '"' => self.lex_raw_string(start),
             c if is_identifier_start(c) => self.lex_identifier(c),
             c if is_digit(c) => self.lex_number(c),
             c => self.error(c, start),
@@ -175,6 +177,207 @@ where
         Some((Token::new(kind, text, start..end), message))
     }
 
+    /// Starts tokenizing the input in [`LexerMode::Interpolation`] mode.
+    fn tokenize_interpolation(
+        &mut self,
+        multiline: bool,
+        depth: usize,
+    ) -> Option<LexerItem<'source, FileId>> {
+        match self.peek() {
+            '{' => self.set_interpolation_depth(depth + 1),
+            '}' if depth > 0 => self.set_interpolation_depth(depth - 1),
+            '}' => {
+                // This brace closes the interpolation, so we're back in the
+                // string literal
+                self.pop_mode();
+                self.cursor.checkpoint();
+                let start = self.current_pos();
+                self.next_char();
+
+                let (kind, message) =
+                    self.lex_string_contents(start, multiline, false);
+
+                let end = self.current_pos();
+                let text = self.cursor.slice();
+                return Some((Token::new(kind, text, start..end), message));
+            }
+            _ => {}
+        }
+
+        let item = self.tokenize_normal();
+
+        // A string on a single line ends at the end of its line (whether it's
+        // closed or not), and so do the interpolations in it. The line break
+        // may be part of any trivia, such as the whitespace after `{a  `.
+        let (token, _) = item.as_ref()?;
+        if !multiline && token.kind.is_trivia() && token.text.contains('\n') {
+            while let Some(LexerMode::Interpolation {
+                multiline: false, ..
+            }) = self.mode_stack.last()
+            {
+                self.pop_mode();
+            }
+        }
+
+        item
+    }
+
+    fn set_interpolation_depth(&mut self, new_depth: usize) {
+        if let Some(LexerMode::Interpolation { depth, .. }) =
+            self.mode_stack.last_mut()
+        {
+            *depth = new_depth;
+        }
+    }
+
+    /// Tokenizes a string literal, which is either delimited by quotes (`"`)
+    /// on a single line, or by triple quotes (`"""`) over any number of lines.
+    ///
+    /// A string literal may contain interpolations (such as `{name}`), where
+    /// the string is split into fragments around the tokens of the
+    /// interpolations: `"a {b} c {d} e"` is tokenized as `"a {` (the start),
+    /// `b`, `} c {` (a middle fragment), `d` and `} e"` (the end).
+    fn lex_string(&mut self, start: usize) -> LexerReturn<FileId> {
+        let multiline = (self.peek(), self.peek_at(1)) == ('"', '"');
+        if multiline {
+            self.next_char();
+            self.next_char();
+        }
+
+        self.lex_string_contents(start, multiline, true)
+    }
+
+    /// Tokenizes the contents of a string literal up to (and including) its
+    /// closing quotes or the opening brace of an interpolation, whichever
+    /// comes first. The contents follow either the opening quotes of the
+    /// string (if `is_start` is `true`) or the closing brace of an
+    /// interpolation.
+    fn lex_string_contents(
+        &mut self,
+        start: usize,
+        multiline: bool,
+        is_start: bool,
+    ) -> LexerReturn<FileId> {
+        use SyntaxKind::*;
+
+        loop {
+            match self.peek() {
+                _ if self.is_at_end() => break,
+                '\n' if !multiline => break,
+                '\\' => {
+                    // Skip the escaped character, so that `\"` and `\{` don't
+                    // end the string or start an interpolation (nor does the
+                    // brace of a `\u{...}`)
+                    self.next_char();
+                    if self.peek() == 'u' && self.peek_at(1) == '{' {
+                        self.next_char();
+                        self.next_char();
+                        self.consume_while(|c| c.is_ascii_hexdigit());
+                        self.consume('}');
+                    } else if self.peek() != '\n' || multiline {
+                        self.next_char();
+                    }
+                }
+                '"' if !multiline => {
+                    self.next_char();
+                    return (
+                        if is_start { Lit_String } else { Lit_StringEnd },
+                        None,
+                    );
+                }
+                '"' if (self.peek_at(1), self.peek_at(2)) == ('"', '"') => {
+                    self.next_char();
+                    self.next_char();
+                    self.next_char();
+                    return (
+                        if is_start { Lit_String } else { Lit_StringEnd },
+                        None,
+                    );
+                }
+                '{' => {
+                    self.next_char();
+                    self.push_mode(LexerMode::Interpolation {
+                        multiline,
+                        depth: 0,
+                    });
+                    let kind = if is_start {
+                        Lit_StringStart
+                    } else {
+                        Lit_StringMiddle
+                    };
+                    return (kind, None);
+                }
+                _ => {
+                    self.next_char();
+                }
+            }
+        }
+
+        let kind = if is_start { Lit_String } else { Lit_StringEnd };
+        (kind, Some(self.unterminated_string(start)))
+    }
+
+    /// Tokenizes a raw string literal (such as `r"C:\Users"`), which contains
+    /// neither escape sequences nor interpolations.
+    fn lex_raw_string(&mut self, start: usize) -> LexerReturn<FileId> {
+        // Consume the opening quote
+        self.next_char();
+
+        self.consume_while(|c| c != '"' && c != '\n');
+        if self.consume('"') {
+            (SyntaxKind::Lit_String, None)
+        } else {
+            (
+                SyntaxKind::Lit_String,
+                Some(self.unterminated_string(start)),
+            )
+        }
+    }
+
+    /// Tokenizes a character literal (such as `'a'` or `'\n'`).
+    ///
+    /// Everything up to the closing quote on the same line is part of the
+    /// literal, so that a literal with the wrong number of characters or an
+    /// invalid escape sequence is a single token (which is then reported by
+    /// the validation of the syntax tree).
+    fn lex_character(&mut self, start: usize) -> LexerReturn<FileId> {
+        loop {
+            match self.peek() {
+                _ if self.is_at_end() => break,
+                '\n' => break,
+                '\\' => {
+                    // Skip the escaped character, so that `\'` doesn't end
+                    // the literal
+                    self.next_char();
+                    if self.peek() != '\n' {
+                        self.next_char();
+                    }
+                }
+                '\'' => {
+                    self.next_char();
+                    return (SyntaxKind::Lit_Character, None);
+                }
+                _ => {
+                    self.next_char();
+                }
+            }
+        }
+
+        let message = Message::new(
+            LexerMessage::UnterminatedCharacter,
+            Location::new(self.file_id.clone(), start..self.current_pos()),
+        );
+
+        (SyntaxKind::Lit_Character, Some(message))
+    }
+
+    fn unterminated_string(&self, start: usize) -> Message<FileId> {
+        Message::new(
+            LexerMessage::UnterminatedString,
+            Location::new(self.file_id.clone(), start..self.current_pos()),
+        )
+    }
+
     fn error(&self, character: char, start: usize) -> LexerReturn<FileId> {
         let message = Message::new(
             LexerMessage::UnknownCharacter(character),
@@ -219,7 +422,6 @@ impl<'source, FileId> Lexer<'source, FileId> {
 
     /// Attempts to consume the next character if it matches the provided
     /// character `c`. Returns a `bool` indicating if it was successful or not.
-    #[allow(dead_code)]
     fn consume(&mut self, c: char) -> bool {
         if self.peek() == c {
             self.next_char();
@@ -289,6 +491,12 @@ impl<'source, FileId> Lexer<'source, FileId> {
     /// ';' with ';').
     fn lex_symbol(&mut self, symbol: char) -> LexerReturn<FileId> {
         match symbol {
+            '.' if (self.peek(), self.peek_at(1)) == ('.', '=') => {
+                // Consume the next dot and the equals sign
+                self.next_char();
+                self.next_char();
+                (SyntaxKind::Sym_DotDotEq, None)
+            }
             '?' => {
                 if (self.peek(), self.peek_at(1)) == ('?', '?') {
                     // Consume the next two question marks
@@ -335,12 +543,15 @@ impl<'source, FileId> Lexer<'source, FileId> {
             "else"      => SyntaxKind::Kwd_Else,
             "end"       => SyntaxKind::Kwd_End,
             "export"    => SyntaxKind::Kwd_Export,
+            "extend"    => SyntaxKind::Kwd_Extend,
             "external"  => SyntaxKind::Kwd_External,
             "for"       => SyntaxKind::Kwd_For,
             "forall"    => SyntaxKind::Kwd_Forall,
+            "function"  => SyntaxKind::Kwd_Function,
             "if"        => SyntaxKind::Kwd_If,
             "import"    => SyntaxKind::Kwd_Import,
             "in"        => SyntaxKind::Kwd_In,
+            "interface" => SyntaxKind::Kwd_Interface,
             "let"       => SyntaxKind::Kwd_Let,
             "loop"      => SyntaxKind::Kwd_Loop,
             "match"     => SyntaxKind::Kwd_Match,
@@ -348,11 +559,14 @@ impl<'source, FileId> Lexer<'source, FileId> {
             "not"       => SyntaxKind::Kwd_Not,
             "of"        => SyntaxKind::Kwd_Of,
             "or"        => SyntaxKind::Kwd_Or,
+            "public"    => SyntaxKind::Kwd_Public,
             "rec"       => SyntaxKind::Kwd_Rec,
             "ref"       => SyntaxKind::Kwd_Ref,
+            "return"    => SyntaxKind::Kwd_Return,
             "then"      => SyntaxKind::Kwd_Then,
             "type"      => SyntaxKind::Kwd_Type,
             "val"       => SyntaxKind::Kwd_Val,
+            "var"       => SyntaxKind::Kwd_Var,
             "while"     => SyntaxKind::Kwd_While,
             "with"      => SyntaxKind::Kwd_With,
             _           => SyntaxKind::Identifier,
@@ -385,8 +599,11 @@ impl<'source, FileId> Lexer<'source, FileId> {
             // after it does NOT start an identifier, then this must be a float
             // literal. Otherwise, it may be a field access (e.g. `10.foo`)
             // which isn't valid anyway, but we don't need to worry about it
-            // here in the lexer.
-            if self.peek() == '.' && !is_identifier_start(self.peek_at(1)) {
+            // here in the lexer. A second dot starts a range (e.g. `0..10`).
+            if self.peek() == '.'
+                && self.peek_at(1) != '.'
+                && !is_identifier_start(self.peek_at(1))
+            {
                 self.next_char();
                 self.consume_while(is_digit_continue);
                 (SyntaxKind::Lit_Float, None)
@@ -406,6 +623,9 @@ where
     fn next(&mut self) -> Option<Self::Item> {
         match self.current_mode() {
             LexerMode::Normal => self.tokenize_normal(),
+            LexerMode::Interpolation { multiline, depth } => {
+                self.tokenize_interpolation(multiline, depth)
+            }
         }
     }
 }
@@ -446,12 +666,15 @@ mod tests {
         check("else", SyntaxKind::Kwd_Else);
         check("end", SyntaxKind::Kwd_End);
         check("export", SyntaxKind::Kwd_Export);
+        check("extend", SyntaxKind::Kwd_Extend);
         check("external", SyntaxKind::Kwd_External);
         check("for", SyntaxKind::Kwd_For);
         check("forall", SyntaxKind::Kwd_Forall);
+        check("function", SyntaxKind::Kwd_Function);
         check("if", SyntaxKind::Kwd_If);
         check("import", SyntaxKind::Kwd_Import);
         check("in", SyntaxKind::Kwd_In);
+        check("interface", SyntaxKind::Kwd_Interface);
         check("let", SyntaxKind::Kwd_Let);
         check("loop", SyntaxKind::Kwd_Loop);
         check("match", SyntaxKind::Kwd_Match);
@@ -459,11 +682,14 @@ mod tests {
         check("not", SyntaxKind::Kwd_Not);
         check("of", SyntaxKind::Kwd_Of);
         check("or", SyntaxKind::Kwd_Or);
+        check("public", SyntaxKind::Kwd_Public);
         check("rec", SyntaxKind::Kwd_Rec);
         check("ref", SyntaxKind::Kwd_Ref);
+        check("return", SyntaxKind::Kwd_Return);
         check("then", SyntaxKind::Kwd_Then);
         check("type", SyntaxKind::Kwd_Type);
         check("val", SyntaxKind::Kwd_Val);
+        check("var", SyntaxKind::Kwd_Var);
         check("while", SyntaxKind::Kwd_While);
         check("with", SyntaxKind::Kwd_With);
     }
@@ -501,6 +727,10 @@ mod tests {
         check("<-", SyntaxKind::Sym_LThinArrow);
         check("->", SyntaxKind::Sym_RThinArrow);
         check("=>", SyntaxKind::Sym_ThickArrow);
+        check("|>", SyntaxKind::Sym_PipeGt);
+        check("<|", SyntaxKind::Sym_LtPipe);
+        check("..", SyntaxKind::Sym_DotDot);
+        check("..=", SyntaxKind::Sym_DotDotEq);
 
         check("{", SyntaxKind::Sym_LBrace);
         check("}", SyntaxKind::Sym_RBrace);
@@ -587,6 +817,19 @@ mod tests {
         check("123.456", SyntaxKind::Lit_Float);
     }
 
+    #[test]
+    fn test_lex_ranges() {
+        use SyntaxKind::*;
+
+        let range =
+            [(Lit_Integer, "0"), (Sym_DotDot, ".."), (Lit_Integer, "10")];
+        check_tokens("0..10", &range, false);
+
+        let range =
+            [(Lit_Float, "0.5"), (Sym_DotDotEq, "..="), (Identifier, "n")];
+        check_tokens("0.5..=n", &range, false);
+    }
+
     #[test]
     fn test_lex_semantically_invalid_literal_floats() {
         check("0a0b0c.0d0e", SyntaxKind::Lit_Float);
@@ -637,4 +880,163 @@ mod tests {
         check("ମାନବ", SyntaxKind::Identifier); // Odia
         check("มนุษย์", SyntaxKind::Identifier); // Thai
     }
+
+    /// Checks that the given input is tokenized into the given tokens (with
+    /// whitespace omitted), and whether it is reported as unterminated.
+    fn check_tokens(input: &str, expected: &[(SyntaxKind, &str)], error: bool) {
+        let items = Lexer::new(0u8, input).collect::<Vec<_>>();
+        let tokens = items
+            .iter()
+            .map(|(token, _)| (token.kind, token.text))
+            .filter(|(kind, _)| *kind != SyntaxKind::Whitespace)
+            .collect::<Vec<_>>();
+
+        assert_eq!(tokens, expected);
+        assert_eq!(items.iter().any(|(_, message)| message.is_some()), error);
+    }
+
+    #[test]
+    fn test_lex_strings() {
+        check(r#""""#, SyntaxKind::Lit_String);
+        check(r#""hello, world!""#, SyntaxKind::Lit_String);
+        check(r#""a \" b \{ c \\""#, SyntaxKind::Lit_String);
+        check(r#""\u{1F600} \u{12""#, SyntaxKind::Lit_String);
+        check(
+            r#""""a "quoted"
+            multiline string""""#,
+            SyntaxKind::Lit_String,
+        );
+        check(r#"r"C:\Users\""#, SyntaxKind::Lit_String);
+        check(r#"r"{not interpolated}""#, SyntaxKind::Lit_String);
+    }
+
+    #[test]
+    fn test_lex_characters() {
+        check("'a'", SyntaxKind::Lit_Character);
+        check("'\\n'", SyntaxKind::Lit_Character);
+        check("'\\''", SyntaxKind::Lit_Character);
+        check("'\\u{1F600}'", SyntaxKind::Lit_Character);
+        check("'😀'", SyntaxKind::Lit_Character);
+        // Reported when validating the literal
+        check("''", SyntaxKind::Lit_Character);
+        check("'ab'", SyntaxKind::Lit_Character);
+    }
+
+    #[test]
+    fn test_lex_unterminated_characters() {
+        use SyntaxKind::*;
+
+        check_tokens(
+            "'a\nb",
+            &[(Lit_Character, "'a"), (Identifier, "b")],
+            true,
+        );
+        check_tokens("'\\'", &[(Lit_Character, "'\\'")], true);
+        check_tokens("'", &[(Lit_Character, "'")], true);
+    }
+
+    #[test]
+    fn test_lex_unterminated_strings() {
+        use SyntaxKind::*;
+
+        check_tokens(
+            "\"abc\nd",
+            &[(Lit_String, "\"abc"), (Identifier, "d")],
+            true,
+        );
+        check_tokens("\"\"\"abc\nd", &[(Lit_String, "\"\"\"abc\nd")], true);
+        check_tokens("r\"abc", &[(Lit_String, "r\"abc")], true);
+        check_tokens("\"abc\\", &[(Lit_String, "\"abc\\")], true);
+    }
+
+    #[test]
+    fn test_lex_string_interpolations() {
+        use SyntaxKind::*;
+
+        check_tokens(
+            r#""a {b} c {d + 1} e""#,
+            &[
+                (Lit_StringStart, "\"a {"),
+                (Identifier, "b"),
+                (Lit_StringMiddle, "} c {"),
+                (Identifier, "d"),
+                (Sym_Plus, "+"),
+                (Lit_Integer, "1"),
+                (Lit_StringEnd, "} e\""),
+            ],
+            false,
+        );
+
+        // Braces and strings nested in an interpolation
+        check_tokens(
+            r#""{ {a} "{b}" }" c"#,
+            &[
+                (Lit_StringStart, "\"{"),
+                (Sym_LBrace, "{"),
+                (Identifier, "a"),
+                (Sym_RBrace, "}"),
+                (Lit_StringStart, "\"{"),
+                (Identifier, "b"),
+                (Lit_StringEnd, "}\""),
+                (Lit_StringEnd, "}\""),
+                (Identifier, "c"),
+            ],
+            false,
+        );
+
+        check_tokens(
+            "\"\"\"a {b}\n\"c\"\"\"\"",
+            &[
+                (Lit_StringStart, "\"\"\"a {"),
+                (Identifier, "b"),
+                (Lit_StringEnd, "}\n\"c\"\"\""),
+                (Lit_String, "\""),
+            ],
+            true,
+        );
+
+        // An unclosed interpolation ends at the end of the line of its string
+        check_tokens(
+            "\"a {\"b {c\nd} e",
+            &[
+                (Lit_StringStart, "\"a {"),
+                (Lit_StringStart, "\"b {"),
+                (Identifier, "c"),
+                (Identifier, "d"),
+                (Sym_RBrace, "}"),
+                (Identifier, "e"),
+            ],
+            false,
+        );
+        check_tokens(
+            "\"a {b  \n} c",
+            &[
+                (Lit_StringStart, "\"a {"),
+                (Identifier, "b"),
+                (Sym_RBrace, "}"),
+                (Identifier, "c"),
+            ],
+            false,
+        );
+        check_tokens(
+            "\"a {b -- c\n} d",
+            &[
+                (Lit_StringStart, "\"a {"),
+                (Identifier, "b"),
+                (Comment, "-- c"),
+                (Sym_RBrace, "}"),
+                (Identifier, "d"),
+            ],
+            false,
+        );
+        check_tokens(
+            "\"\"\"a {b\n} c\"\"\"",
+            &[
+                (Lit_StringStart, "\"\"\"a {"),
+                (Identifier, "b"),
+                (Lit_StringEnd, "} c\"\"\""),
+            ],
+            false,
+        );
+    }
 }
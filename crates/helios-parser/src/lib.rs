@@ -7,24 +7,41 @@
 //! [`rowan`]: https://docs.rs/rowan/0.10.0/rowan
 
 mod cursor;
+mod diff;
+pub mod editor;
+mod feature;
 mod grammar;
+pub mod highlight;
+mod identifier;
+mod layout;
 mod lexer;
+mod literal;
 pub mod message;
 mod parser;
+pub mod trace;
+mod validation;
 
+pub use self::diff::diff;
+pub use self::feature::{Feature, Features};
+pub use self::identifier::{normalize_identifier, Script};
+pub use self::lexer::Token;
+pub use self::literal::{Literal, LiteralError, LiteralValue};
 pub use self::message::*;
 
-use self::lexer::{Lexer, Token};
+use self::lexer::Lexer;
 use self::parser::sink::Sink;
 use self::parser::source::Source;
 use self::parser::Parser;
+use self::trace::Trace;
+use helios_diagnostics::{Diagnostic, DiagnosticSink, Severity};
 use helios_syntax::SyntaxNode;
 use rowan::GreenNode;
+use std::time::{Duration, Instant};
 
 pub fn tokenize<FileId>(
     file_id: FileId,
     source: &str,
-) -> (Vec<Token>, Vec<Message<FileId>>)
+) -> (Vec<Token<'_>>, Vec<Message<FileId>>)
 where
     FileId: Clone + Default,
 {
@@ -34,7 +51,7 @@ where
     for (token, error) in Lexer::new(file_id, source) {
         tokens.push(token);
         if let Some(error) = error {
-            errors.push(error.into());
+            errors.push(error);
         }
     }
 
@@ -45,45 +62,157 @@ where
 ///
 /// This function parses the given source text (a `&str`) and returns a
 /// [`Parse`], which holds a [`GreenNode`] tree describing the structure of a
-/// Helios program.
+/// Helios program along with the diagnostics emitted while building it (by
+/// the lexer, the parser and the validation of the syntax tree, leaving out
+/// the errors of the validation caused by a syntax error).
 pub fn parse<FileId>(file_id: FileId, source: &str) -> Parse<FileId>
 where
-    FileId: Clone + Default,
+    FileId: Clone + Default + Ord,
 {
-    let (tokens, mut messages) = tokenize(file_id.clone(), source);
+    parse_with_options(file_id, source, ParseOptions::default())
+}
+
+/// Options changing the grammar accepted by [`parse_with_options`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ParseOptions {
+    /// The experimental features enabled (see the [`feature`] module), such
+    /// as blocks delimited by indentation instead of braces.
+    ///
+    /// [`feature`]: crate::feature
+    pub features: Features,
+    /// Whether the steps of the parser are recorded (see the [`trace`]
+    /// module), which is also turned on by the `HELIOS_PARSER_TRACE`
+    /// environment variable.
+    pub trace: bool,
+}
+
+/// Parses the given source text like [`parse`], with the given options.
+pub fn parse_with_options<FileId>(
+    file_id: FileId,
+    source: &str,
+    options: ParseOptions,
+) -> Parse<FileId>
+where
+    FileId: Clone + Default + Ord,
+{
+    parse_with_timings(file_id, source, options).0
+}
+
+/// The time spent in each phase of parsing a source text.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ParseTimings {
+    /// The time spent tokenizing the text (and inferring its layout).
+    pub lexing: Duration,
+    /// The time spent building and validating the syntax tree.
+    pub parsing: Duration,
+}
+
+/// Parses the given source text like [`parse_with_options`], also measuring
+/// the time spent in each phase (for profiling).
+pub fn parse_with_timings<FileId>(
+    file_id: FileId,
+    source: &str,
+    options: ParseOptions,
+) -> (Parse<FileId>, ParseTimings)
+where
+    FileId: Clone + Default + Ord,
+{
+    let start = Instant::now();
+    let source_text = source;
+    let (mut tokens, lexer_messages) = tokenize(file_id.clone(), source);
+    if options.features.is_enabled(Feature::IndentationBlocks) {
+        tokens = layout::layout(&tokens);
+    }
+
+    let lexed = Instant::now();
     let source = Source::new(&tokens);
 
-    let parser = Parser::new(file_id, source);
-    let (events, parser_messages) = parser.parse();
-    let sink = Sink::new(&tokens, events);
+    let trace_from_env = trace::is_enabled_by_env();
+    let mut parser = Parser::new(file_id.clone(), source, options.features);
+    if options.trace || trace_from_env {
+        parser = parser.with_trace();
+    }
+    let (events, parser_messages, trace) = parser.parse();
+    let green_node = Sink::new(&tokens, events).finish();
+
+    let mut diagnostics = DiagnosticSink::new();
+    for message in lexer_messages.iter().chain(&parser_messages) {
+        diagnostics.push_syntax(Diagnostic::from(message));
+    }
+
+    let root = SyntaxNode::new_root(green_node.clone());
+    for message in validation::validate(file_id, &root) {
+        diagnostics.push(Diagnostic::from(message));
+    }
+
+    let timings = ParseTimings {
+        lexing: lexed - start,
+        parsing: lexed.elapsed(),
+    };
 
-    messages.extend(parser_messages);
-    sink.finish(messages)
+    let mut parse = Parse::new(green_node, diagnostics.finish());
+    if let Some(trace) = trace {
+        if trace_from_env {
+            eprint!("{}", trace.render(source_text));
+        }
+        parse.trace = Some(trace);
+    }
+
+    (parse, timings)
 }
 
 /// The result of parsing a source text.
+///
+/// This is the single value produced for a file by the parser, so that callers
+/// always get the diagnostics alongside the syntax tree they describe.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Parse<FileId> {
     /// The root green node of the syntax tree.
     green_node: GreenNode,
-    messages: Vec<Message<FileId>>,
+    diagnostics: Vec<Diagnostic<FileId>>,
+    trace: Option<Trace>,
 }
 
 impl<FileId> Parse<FileId> {
-    /// Construct a [`Parse`] with the given [`GreenNode`].
-    pub fn new(green_node: GreenNode, messages: Vec<Message<FileId>>) -> Self {
+    /// Construct a [`Parse`] with the given [`GreenNode`] and diagnostics.
+    pub fn new(
+        green_node: GreenNode,
+        diagnostics: Vec<Diagnostic<FileId>>,
+    ) -> Self {
         Self {
             green_node,
-            messages,
+            diagnostics,
+            trace: None,
         }
     }
 
+    /// The root green node of the syntax tree.
+    pub fn green_node(&self) -> &GreenNode {
+        &self.green_node
+    }
+
+    /// The root node of the syntax tree.
     pub fn syntax(&self) -> SyntaxNode {
         SyntaxNode::new_root(self.green_node.clone())
     }
 
-    pub fn messages(&self) -> &[Message<FileId>] {
-        &self.messages
+    /// The diagnostics emitted while parsing, sorted by location (see
+    /// [`DiagnosticSink`]).
+    pub fn diagnostics(&self) -> &[Diagnostic<FileId>] {
+        &self.diagnostics
+    }
+
+    /// The steps the parser took, if it was tracing (see the [`trace`]
+    /// module).
+    pub fn trace(&self) -> Option<&Trace> {
+        self.trace.as_ref()
+    }
+
+    /// Determines if any of the diagnostics is an error (or a bug).
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity >= Severity::Error)
     }
 
     /// Returns a formatted string representation of the syntax tree.
@@ -93,8 +222,394 @@ impl<FileId> Parse<FileId> {
     }
 }
 
+/// Concatenates the text of every token in the given syntax tree (including
+/// trivia), in order.
+///
+/// Since the syntax tree is lossless, this is always the source text the tree
+/// was parsed from.
+pub fn syntax_text(node: &SyntaxNode) -> String {
+    node.descendants_with_tokens()
+        .filter_map(|it| it.into_token())
+        .map(|token| token.text().to_string())
+        .collect()
+}
+
 #[cfg(test)]
 fn check(input: &str, expected_tree: expect_test::Expect) {
     let parse = parse(0u8, input);
     expected_tree.assert_eq(&parse.debug_tree());
 }
+
+#[cfg(test)]
+fn check_layout(input: &str, expected_tree: expect_test::Expect) {
+    let options = ParseOptions {
+        features: Features::new().with(Feature::IndentationBlocks),
+        ..ParseOptions::default()
+    };
+    let parse = parse_with_options(0u8, input, options);
+    expected_tree.assert_eq(&parse.debug_tree());
+}
+
+#[cfg(test)]
+mod tests {
+    /// Fragments of Helios source (valid or not) that the generated inputs of
+    /// the round-trip test are made of.
+    const FRAGMENTS: &[&str] = &[
+        "let",
+        "import",
+        "external",
+        "type",
+        "public",
+        "function",
+        "if",
+        "then",
+        "else",
+        "match",
+        "with",
+        "and",
+        "or",
+        "not",
+        "fun",
+        "return",
+        "=",
+        "==",
+        "<",
+        ">=",
+        "+",
+        "-",
+        "*",
+        "/",
+        "%",
+        "->",
+        "=>",
+        "(",
+        ")",
+        "[",
+        "]",
+        "{",
+        "}",
+        ",",
+        ".",
+        ":",
+        "|",
+        "a",
+        "Foo",
+        "_",
+        "1",
+        "0x",
+        "1.5e",
+        "99999999999999999999",
+        "\"",
+        "'",
+        "\\",
+        "\"abc\"",
+        "'c'",
+        "-- c",
+        "--- doc",
+        "{- block",
+        "-}",
+        " ",
+        "\t",
+        "\n",
+        "\r\n",
+        "é",
+        "λ",
+        "🦀",
+        "\u{0}",
+        "$",
+        "#",
+        "@",
+    ];
+
+    /// A small deterministic pseudo-random number generator (xorshift), so
+    /// that failures of the round-trip test are reproducible.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: usize) -> usize {
+            (self.next() % n as u64) as usize
+        }
+    }
+
+    #[test]
+    fn test_lossless_round_trip() {
+        let mut rng = Rng(0x5eed_1e55_c0ff_ee00);
+
+        for _ in 0..2000 {
+            let len = rng.below(40);
+            let source = (0..len)
+                .map(|_| FRAGMENTS[rng.below(FRAGMENTS.len())])
+                .collect::<String>();
+
+            let root = super::parse(0u8, &source).syntax();
+            assert_eq!(super::syntax_text(&root), source);
+            assert_eq!(usize::from(root.text_range().end()), source.len());
+
+            let options = super::ParseOptions {
+                features: super::Features::all(),
+                ..super::ParseOptions::default()
+            };
+            let root = super::parse_with_options(0u8, &source, options);
+            assert_eq!(super::syntax_text(&root.syntax()), source);
+        }
+    }
+
+    /// Renders the syntax tree of the given source along with the parser's
+    /// diagnostics, as stored in the `.expected` files of `test_data/`.
+    fn render(source: &str) -> String {
+        let parse = super::parse(0u8, source);
+        let mut rendered = parse.debug_tree();
+
+        for diagnostic in parse.diagnostics() {
+            rendered.push_str(&format!(
+                "{:?} at {:?}: {}\n",
+                diagnostic.severity,
+                diagnostic.location.range,
+                diagnostic.title
+            ));
+        }
+
+        rendered
+    }
+
+    /// Parses every `.he` file in `test_data/` and compares the result with
+    /// the `.expected` file next to it.
+    ///
+    /// Setting the `UPDATE_EXPECT` environment variable (re)writes the
+    /// `.expected` files instead, like `expect_test` does.
+    #[test]
+    fn test_data() {
+        let dir =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("test_data");
+        let update = std::env::var_os("UPDATE_EXPECT").is_some();
+
+        let mut paths = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "he"))
+            .collect::<Vec<_>>();
+        paths.sort();
+        assert!(!paths.is_empty(), "no test data in {}", dir.display());
+
+        let mut mismatches = Vec::new();
+        for path in paths {
+            let source = std::fs::read_to_string(&path).unwrap();
+            let actual = render(&source);
+            let expected_path = path.with_extension("expected");
+
+            if update {
+                std::fs::write(&expected_path, &actual).unwrap();
+                continue;
+            }
+
+            match std::fs::read_to_string(&expected_path) {
+                Ok(expected) if expected == actual => {}
+                Ok(expected) => mismatches.push(format!(
+                    "{}:\n--- expected\n{}\n--- actual\n{}",
+                    path.display(),
+                    expected,
+                    actual
+                )),
+                Err(_) => mismatches.push(format!(
+                    "{}: missing {}",
+                    path.display(),
+                    expected_path.display()
+                )),
+            }
+        }
+
+        assert!(
+            mismatches.is_empty(),
+            "test data mismatches (rerun with UPDATE_EXPECT=1 to update):\n\n{}",
+            mismatches.join("\n")
+        );
+    }
+
+    #[test]
+    fn test_parse_diagnostics() {
+        let parse = super::parse(0u8, "let a = 1");
+        assert!(parse.diagnostics().is_empty());
+        assert!(!parse.has_errors());
+
+        let parse = super::parse(0u8, "let = 1\nlet b = 99999999999999999999");
+        let diagnostics = parse.diagnostics();
+        assert!(parse.has_errors());
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].title, "Missing identifier");
+        assert_eq!(diagnostics[0].location.range, 4..5);
+        assert_eq!(diagnostics[1].title, "Integer literal out of range");
+        assert_eq!(diagnostics[1].location.range, 16..36);
+    }
+
+    #[test]
+    fn test_expected_kinds_diagnostic() {
+        let message = |source| {
+            let parse = super::parse(0u8, source);
+            parse.diagnostics()[0].message.plain().to_string()
+        };
+
+        assert_eq!(
+            message("let = 1"),
+            "I expected an identifier (such as `foo`) here, but I found `=`."
+        );
+        assert_eq!(
+            message("interface Foo = 1"),
+            "I expected `{` or the start of an indented block here, but I \
+             found an integer literal."
+        );
+        assert_eq!(
+            message("let a"),
+            "I expected an equals symbol (`=`) here, but I found the end of \
+             the file."
+        );
+
+        // Longer sets of kinds are listed one per line
+        assert!(message("let a = =")
+            .starts_with("I found `=` here, but I expected one of the"));
+    }
+
+    #[test]
+    fn test_unclosed_delimiter_diagnostic() {
+        let parse = super::parse(0u8, "let a = (1 + 2\nlet b = 3");
+        let diagnostics = parse.diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].title, "Unclosed parenthesis");
+        assert_eq!(diagnostics[0].location.range, 8..9);
+        assert_eq!(diagnostics[0].labels[0].location.range, 15..18);
+    }
+
+    #[test]
+    fn test_missing_and_stray_token_suggestions() {
+        let suggestions = |source| {
+            let parse = super::parse(0u8, source);
+            let diagnostic = &parse.diagnostics()[0];
+            let suggestion = &diagnostic.suggestions[0];
+            (
+                diagnostic.title.clone(),
+                suggestion.location.range.clone(),
+                suggestion.replacement.clone(),
+            )
+        };
+
+        assert_eq!(
+            suggestions("let a b = 1"),
+            (
+                "Unexpected identifier before equals symbol".to_string(),
+                6..7,
+                String::new()
+            )
+        );
+        assert_eq!(
+            suggestions("let a 1"),
+            ("Missing equals symbol".to_string(), 6..6, "= ".to_string())
+        );
+        assert_eq!(
+            suggestions("let a -- Comment\n"),
+            ("Missing equals symbol".to_string(), 5..5, " =".to_string())
+        );
+    }
+
+    #[test]
+    fn test_mismatched_delimiter_diagnostic() {
+        let parse = super::parse(0u8, "let a = (1 + 2]\nlet b = 3");
+        let diagnostics = parse.diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].title, "Mismatched closing square bracket");
+        assert_eq!(diagnostics[0].location.range, 14..15);
+        assert_eq!(diagnostics[0].labels[0].location.range, 8..9);
+        assert_eq!(diagnostics[0].suggestions[0].replacement, ")");
+    }
+
+    #[test]
+    fn test_unmatched_delimiter_diagnostic() {
+        let titles = |source| {
+            super::parse(0u8, source)
+                .diagnostics()
+                .iter()
+                .map(|diagnostic| {
+                    (
+                        diagnostic.title.clone(),
+                        diagnostic.location.range.clone(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            titles("let a = 1)"),
+            vec![("Unmatched closing parenthesis".to_string(), 9..10)]
+        );
+
+        // The block is still closed by its brace
+        assert_eq!(
+            titles("let a = { 1 ) }\nlet b = 2"),
+            vec![("Unmatched closing parenthesis".to_string(), 12..13)]
+        );
+
+        // A closing delimiter of an enclosing group is left to it
+        assert_eq!(
+            titles("let a = ({ 1 )"),
+            vec![("Unclosed brace".to_string(), 9..10)]
+        );
+    }
+
+    #[test]
+    fn test_cascaded_validation_errors() {
+        let titles = |source| {
+            super::parse(0u8, source)
+                .diagnostics()
+                .iter()
+                .map(|diagnostic| diagnostic.title.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            titles("let a = (b + 1 <- 2)"),
+            vec!["Invalid assignment target"]
+        );
+
+        // The target is right after the unclosed parenthesis
+        assert_eq!(titles("let a = (b + 1 <- 2"), vec!["Unclosed parenthesis"]);
+    }
+
+    #[test]
+    fn test_structurally_eq_ignores_trivia() {
+        use helios_syntax::{diff_trees, make, structurally_eq, Sym};
+
+        let syntax = |source| super::parse(0u8, source).syntax();
+        let a = syntax("let a = (1 + b) * c\nvar d = a");
+        let b = syntax("let a=(1+b)*c -- comment\n\nvar d = a");
+        assert!(structurally_eq(&a, &b));
+
+        // The trees built with `make` place their whitespace differently
+        let sum = make::binary_expr(
+            make::integer_literal(1),
+            Sym!["+"],
+            make::name_ref("b"),
+        );
+        let product = make::binary_expr(
+            make::paren_expr(sum),
+            Sym!["*"],
+            make::name_ref("c"),
+        );
+        let made = make::root(vec![
+            make::let_binding("a", product),
+            make::var_binding("d", make::name_ref("a")),
+        ]);
+        assert_eq!(diff_trees(&a, &made), None);
+
+        let diff = diff_trees(&a, &syntax("let a = (1 + b) * c\nlet d = a"));
+        assert_eq!(
+            diff.unwrap().to_string(),
+            "In Root > Dec_GlobalBinding: expected Kwd_Var@20..23 \"var\", \
+             found Kwd_Let@20..23 \"let\""
+        );
+    }
+}
@@ -0,0 +1,375 @@
+//! Reading the values of literals.
+//!
+//! The lexer only finds where each literal ends, leaving its text as it is
+//! written. A [`Literal`] decodes that text into the value it stands for:
+//! digit separators (`_`) and radix prefixes of numbers, the quotes of
+//! characters and strings, and their escape sequences. A literal that can't be
+//! decoded gives a [`LiteralError`], which the validation of the syntax tree
+//! reports.
+
+use helios_syntax::{Sym, SyntaxKind, SyntaxNode, SyntaxToken};
+use std::num::IntErrorKind;
+use std::ops::Range;
+
+/// A literal expression (a node of the kind `Exp_Literal`).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Literal(SyntaxNode);
+
+impl Literal {
+    /// The given node as a literal, if it is one.
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        (node.kind() == SyntaxKind::Exp_Literal).then_some(Self(node))
+    }
+
+    pub fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+
+    /// The token of the literal (such as a `Lit_Integer`).
+    pub fn token(&self) -> SyntaxToken {
+        // The parser only builds literals around their token
+        self.0
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|token| !token.kind().is_trivia())
+            .expect("a literal always has a token")
+    }
+
+    /// Decodes the value of the literal.
+    ///
+    /// Integers must fit in a signed 64-bit integer: their value is the
+    /// magnitude of the number, which may be one more than [`i64::MAX`] when
+    /// the literal is directly negated (such as `-9223372036854775808`).
+    pub fn value(&self) -> Result<LiteralValue, LiteralError> {
+        let token = self.token();
+        let text = token.text();
+
+        match token.kind() {
+            SyntaxKind::Lit_Integer => {
+                integer(&text.replace('_', ""), is_negated(&self.0))
+                    .map(LiteralValue::Integer)
+            }
+            SyntaxKind::Lit_Float => {
+                float(&text.replace('_', "")).map(LiteralValue::Float)
+            }
+            SyntaxKind::Lit_Character => {
+                character(text).map(LiteralValue::Character)
+            }
+            SyntaxKind::Lit_String => string(text).map(LiteralValue::String),
+            kind => unreachable!("Got unexpected kind for literal: {:?}", kind),
+        }
+    }
+}
+
+/// The value of a [`Literal`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum LiteralValue {
+    Character(char),
+    Float(f64),
+    /// The magnitude of an integer (a `-` in front of it is an operator).
+    Integer(u64),
+    String(String),
+}
+
+/// The reason why a [`Literal`] can't be decoded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LiteralError {
+    /// The closing quotes of the literal are missing (which the lexer has
+    /// already reported).
+    Unterminated,
+    /// The number doesn't fit in its type.
+    OutOfRange,
+    /// The number contains a digit that isn't valid in its radix.
+    InvalidDigit { radix: u32 },
+    /// The escape sequence at the given range of the literal is invalid (the
+    /// range is within the text of the literal, including its quotes).
+    InvalidEscape(Range<usize>),
+    /// The character literal contains the given number of characters (other
+    /// than one).
+    CharacterLength(usize),
+}
+
+/// Parses the text of an integer literal (without any underscores), which must
+/// fit in a signed 64-bit integer.
+fn integer(text: &str, is_negated: bool) -> Result<u64, LiteralError> {
+    let (digits, radix) = match text.get(..2) {
+        Some("0b") => (&text[2..], 2),
+        Some("0o") => (&text[2..], 8),
+        Some("0x") => (&text[2..], 16),
+        _ => (text, 10),
+    };
+
+    let value =
+        u64::from_str_radix(digits, radix).map_err(|error| {
+            match error.kind() {
+                IntErrorKind::PosOverflow => LiteralError::OutOfRange,
+                _ => LiteralError::InvalidDigit { radix },
+            }
+        })?;
+
+    // The magnitude of the smallest integer is one more than the largest one
+    let max = if is_negated {
+        i64::MIN.unsigned_abs()
+    } else {
+        i64::MAX as u64
+    };
+
+    if value > max {
+        Err(LiteralError::OutOfRange)
+    } else {
+        Ok(value)
+    }
+}
+
+/// Parses the text of a float literal (without any underscores), which must
+/// be finite.
+fn float(text: &str) -> Result<f64, LiteralError> {
+    match text.parse::<f64>() {
+        Ok(value) if value.is_infinite() => Err(LiteralError::OutOfRange),
+        Ok(value) => Ok(value),
+        Err(_) => Err(LiteralError::InvalidDigit { radix: 10 }),
+    }
+}
+
+/// Parses the text of a character literal (including its quotes) into the
+/// character it stands for.
+fn character(text: &str) -> Result<char, LiteralError> {
+    let contents = text
+        .strip_prefix('\'')
+        .and_then(|text| text.strip_suffix('\''))
+        .ok_or(LiteralError::Unterminated)?;
+
+    let value = unescape(contents, 1)?;
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(LiteralError::CharacterLength(value.chars().count())),
+    }
+}
+
+/// Parses the text of a string literal without interpolations (including its
+/// quotes) into the string it stands for.
+///
+/// The contents of a raw string (such as `r"C:\Users"`) are taken as they are
+/// written, while the escape sequences of other strings (either between
+/// single quotes or between triple quotes) are replaced.
+fn string(text: &str) -> Result<String, LiteralError> {
+    if let Some(raw) = text.strip_prefix('r') {
+        return raw
+            .strip_prefix('"')
+            .and_then(|raw| raw.strip_suffix('"'))
+            .map(str::to_string)
+            .ok_or(LiteralError::Unterminated);
+    }
+
+    let quotes = if text.starts_with(r#"""""#) { 3 } else { 1 };
+    if text.len() < 2 * quotes || !text.ends_with(&text[..quotes]) {
+        return Err(LiteralError::Unterminated);
+    }
+
+    unescape(&text[quotes..text.len() - quotes], quotes)
+}
+
+/// Parses the text of a fragment of a string literal with interpolations (see
+/// the lexer) into the part of the string it stands for.
+pub(crate) fn string_fragment(
+    kind: SyntaxKind,
+    text: &str,
+    multiline: bool,
+) -> Result<String, LiteralError> {
+    let quotes = if multiline { r#"""""# } else { "\"" };
+    let (contents, offset) = match kind {
+        SyntaxKind::Lit_StringStart => (
+            text.strip_prefix(quotes)
+                .and_then(|text| text.strip_suffix('{')),
+            quotes.len(),
+        ),
+        SyntaxKind::Lit_StringMiddle => (
+            text.strip_prefix('}')
+                .and_then(|text| text.strip_suffix('{')),
+            1,
+        ),
+        SyntaxKind::Lit_StringEnd => (
+            text.strip_prefix('}')
+                .and_then(|text| text.strip_suffix(quotes)),
+            1,
+        ),
+        kind => unreachable!("Got unexpected kind for fragment: {:?}", kind),
+    };
+
+    unescape(contents.ok_or(LiteralError::Unterminated)?, offset)
+}
+
+/// Replaces the escape sequences of the given contents of a character or
+/// string literal, which start at the given offset of the literal.
+///
+/// The escape sequences are `\n`, `\r`, `\t`, `\0`, `\\`, `\'`, `\"`, `\{` and
+/// `\u{...}`, which is the Unicode scalar value with the given (one to six)
+/// hexadecimal digits.
+fn unescape(contents: &str, offset: usize) -> Result<String, LiteralError> {
+    let mut chars = contents.char_indices().peekable();
+    let mut value = String::with_capacity(contents.len());
+
+    while let Some((start, c)) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+
+        // The closing quote itself is escaped
+        let (index, escaped) =
+            chars.next().ok_or(LiteralError::Unterminated)?;
+        let mut end = index + escaped.len_utf8();
+        let unescaped = match escaped {
+            'n' => Some('\n'),
+            'r' => Some('\r'),
+            't' => Some('\t'),
+            '0' => Some('\0'),
+            '\\' | '\'' | '"' | '{' => Some(escaped),
+            'u' if chars.peek().is_some_and(|&(_, c)| c == '{') => {
+                chars.next();
+                end += 1;
+
+                let mut digits = String::new();
+                let mut is_closed = false;
+                while let Some(&(index, c)) = chars.peek() {
+                    if c != '}' && !c.is_ascii_hexdigit() {
+                        break;
+                    }
+
+                    chars.next();
+                    end = index + 1;
+                    if c == '}' {
+                        is_closed = true;
+                        break;
+                    }
+
+                    digits.push(c);
+                }
+
+                Some(digits)
+                    .filter(|digits| {
+                        is_closed && (1..=6).contains(&digits.len())
+                    })
+                    .and_then(|digits| u32::from_str_radix(&digits, 16).ok())
+                    .and_then(char::from_u32)
+            }
+            _ => None,
+        };
+
+        match unescaped {
+            Some(c) => value.push(c),
+            None => {
+                let range = offset + start..offset + end;
+                return Err(LiteralError::InvalidEscape(range));
+            }
+        }
+    }
+
+    Ok(value)
+}
+
+/// Determines if the given literal is directly negated by a prefix `-`.
+fn is_negated(node: &SyntaxNode) -> bool {
+    node.parent()
+        .filter(|parent| parent.kind() == SyntaxKind::Exp_UnaryPrefix)
+        .and_then(|parent| {
+            parent
+                .children_with_tokens()
+                .filter_map(|it| it.into_token())
+                .find(|token| !token.kind().is_trivia())
+        })
+        .is_some_and(|operator| operator.kind() == Sym!["-"])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn check(input: &str, expected: Result<LiteralValue, LiteralError>) {
+        let root = parse(0u8, input).syntax();
+        let literal = root.descendants().find_map(Literal::cast).unwrap();
+        assert_eq!(literal.value(), expected);
+    }
+
+    #[test]
+    fn test_integer_values() {
+        check("1_000", Ok(LiteralValue::Integer(1000)));
+        check("0x7f + 1", Ok(LiteralValue::Integer(127)));
+        check("0b1010", Ok(LiteralValue::Integer(10)));
+        check("0o17", Ok(LiteralValue::Integer(15)));
+        check("-9223372036854775808", Ok(LiteralValue::Integer(1 << 63)));
+        check("9223372036854775808", Err(LiteralError::OutOfRange));
+        check("0b102", Err(LiteralError::InvalidDigit { radix: 2 }));
+    }
+
+    #[test]
+    fn test_float_values() {
+        check("1_000.25", Ok(LiteralValue::Float(1000.25)));
+        check(
+            &format!("1{}.0", "0".repeat(309)),
+            Err(LiteralError::OutOfRange),
+        );
+    }
+
+    #[test]
+    fn test_character_values() {
+        check("'a'", Ok(LiteralValue::Character('a')));
+        check("'\\n'", Ok(LiteralValue::Character('\n')));
+        check("'\\u{1F600}'", Ok(LiteralValue::Character('😀')));
+        check("'\\q'", Err(LiteralError::InvalidEscape(1..3)));
+        check("'ab'", Err(LiteralError::CharacterLength(2)));
+        check("'a", Err(LiteralError::Unterminated));
+    }
+
+    #[test]
+    fn test_string_values() {
+        check("\"\"", Ok(LiteralValue::String(String::new())));
+        check(
+            "\"a\\tb \\\"c\\\" \\{d}\"",
+            Ok(LiteralValue::String("a\tb \"c\" {d}".to_string())),
+        );
+        check(
+            "\"\"\"a \"quoted\"\nstring\"\"\"",
+            Ok(LiteralValue::String("a \"quoted\"\nstring".to_string())),
+        );
+        check(
+            "r\"C:\\Users\\\"",
+            Ok(LiteralValue::String("C:\\Users\\".to_string())),
+        );
+        check("\"a \\u{110000}\"", Err(LiteralError::InvalidEscape(3..13)));
+        check("\"\"\"\\q\"\"\"", Err(LiteralError::InvalidEscape(3..5)));
+        check("\"abc", Err(LiteralError::Unterminated));
+        check("\"abc\\\"", Err(LiteralError::Unterminated));
+    }
+
+    #[test]
+    fn test_string_fragment_values() {
+        use SyntaxKind::*;
+
+        let fragment =
+            |kind, text, multiline| string_fragment(kind, text, multiline);
+
+        assert_eq!(
+            fragment(Lit_StringStart, "\"a\\n {", false),
+            Ok("a\n ".to_string())
+        );
+        assert_eq!(
+            fragment(Lit_StringMiddle, "} \\{ {", false),
+            Ok(" { ".to_string())
+        );
+        assert_eq!(
+            fragment(Lit_StringEnd, "} b\"\"\"", true),
+            Ok(" b".to_string())
+        );
+        assert_eq!(
+            fragment(Lit_StringEnd, "} \\q\"", false),
+            Err(LiteralError::InvalidEscape(2..4))
+        );
+        assert_eq!(
+            fragment(Lit_StringEnd, "} b", false),
+            Err(LiteralError::Unterminated)
+        );
+    }
+}
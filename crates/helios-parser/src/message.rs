@@ -1,6 +1,9 @@
-use helios_diagnostics::{Diagnostic, Location};
+use crate::identifier::Script;
+use crate::Feature;
+use helios_diagnostics::{Applicability, Diagnostic, Location};
 use helios_formatting::FormattedString;
 use helios_syntax::SyntaxKind;
+use std::ops::Range;
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Message<FileId> {
@@ -26,6 +29,7 @@ where
         match &self.kind {
             MessageKind::Lexer(it) => it.diagnostic(self.location.clone()),
             MessageKind::Parser(it) => it.diagnostic(self.location.clone()),
+            MessageKind::Validation(it) => it.diagnostic(self.location.clone()),
         }
     }
 }
@@ -52,6 +56,7 @@ where
 pub enum MessageKind {
     Lexer(LexerMessage),
     Parser(ParserMessage),
+    Validation(ValidationMessage),
 }
 
 impl From<LexerMessage> for MessageKind {
@@ -66,9 +71,17 @@ impl From<ParserMessage> for MessageKind {
     }
 }
 
+impl From<ValidationMessage> for MessageKind {
+    fn from(message: ValidationMessage) -> Self {
+        MessageKind::Validation(message)
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum LexerMessage {
     UnknownCharacter(char),
+    UnterminatedString,
+    UnterminatedCharacter,
 }
 
 impl LexerMessage {
@@ -94,6 +107,36 @@ impl LexerMessage {
                     .description(description)
                     .message(message)
             }
+            LexerMessage::UnterminatedString => {
+                let description = FormattedString::default()
+                    .text("I couldn't find the end of this string literal:");
+
+                let message = FormattedString::default()
+                    .text("A string literal ends with a ")
+                    .code("\"")
+                    .text(" on the same line it starts on (or with ")
+                    .code("\"\"\"")
+                    .text(" if it starts with them).");
+
+                Diagnostic::error("Unterminated string")
+                    .location(location)
+                    .description(description)
+                    .message(message)
+            }
+            LexerMessage::UnterminatedCharacter => {
+                let description = FormattedString::default()
+                    .text("I couldn't find the end of this character literal:");
+
+                let message = FormattedString::default()
+                    .text("A character literal ends with a ")
+                    .code("'")
+                    .text(" on the same line it starts on.");
+
+                Diagnostic::error("Unterminated character")
+                    .location(location)
+                    .description(description)
+                    .message(message)
+            }
         }
     }
 }
@@ -102,13 +145,107 @@ impl LexerMessage {
 pub enum ParserMessage {
     MissingKind {
         context: Option<SyntaxKind>,
+        given: Option<SyntaxKind>,
         expected: SyntaxKind,
+        /// Where the missing token goes, which is before the token found (or
+        /// after the last one, at the end of the file).
+        offset: usize,
     },
     UnexpectedKind {
         context: Option<SyntaxKind>,
         given: Option<SyntaxKind>,
         expected: Vec<SyntaxKind>,
     },
+    /// A token that was skipped since the expected kind of token came right
+    /// after it (such as the `b` of `let a b = 1`).
+    StrayKind {
+        context: Option<SyntaxKind>,
+        given: SyntaxKind,
+        expected: SyntaxKind,
+    },
+    /// A group (such as `(a + b)`) that isn't closed, reported at its opening
+    /// delimiter.
+    UnclosedDelimiter {
+        context: Option<SyntaxKind>,
+        opening: SyntaxKind,
+        closing: SyntaxKind,
+        /// Where the closing delimiter was expected.
+        stuck: Range<usize>,
+    },
+    /// A group closed by a delimiter of the wrong kind (such as the `]` of
+    /// `(a + b]`), reported at that delimiter.
+    MismatchedDelimiter {
+        context: Option<SyntaxKind>,
+        opening: SyntaxKind,
+        opening_range: Range<usize>,
+        given: SyntaxKind,
+    },
+    /// A closing delimiter that doesn't close any group (such as the `)` of
+    /// `a + b)`).
+    UnmatchedDelimiter {
+        context: Option<SyntaxKind>,
+        closing: SyntaxKind,
+    },
+    /// The syntax of an experimental feature that isn't enabled, reported at
+    /// the token introducing it.
+    FeatureGated(Feature),
+}
+
+/// The most kinds listed in the same sentence when a token isn't one of the
+/// kinds expected (more of them are listed one per line).
+const MAX_INLINE_EXPECTED: usize = 4;
+
+/// Appends a brief description of the given kind, which is its code if it
+/// has some (such as `=` or `let`).
+fn brief(message: FormattedString, kind: SyntaxKind) -> FormattedString {
+    if let Some(text) = crate::parser::token_text(kind) {
+        message.code(text)
+    } else {
+        let mut repr = kind.human_readable_repr();
+        repr.code_repr = None;
+        repr.example = None;
+        message.text(repr.to_string())
+    }
+}
+
+/// Appends the brief descriptions of the given kinds as a list in a sentence
+/// (such as "`=`, `:` or an identifier").
+fn brief_list(
+    mut message: FormattedString,
+    kinds: &[SyntaxKind],
+) -> FormattedString {
+    for (index, &kind) in kinds.iter().enumerate() {
+        if index > 0 {
+            let separator = if index + 1 == kinds.len() {
+                " or "
+            } else {
+                ", "
+            };
+            message = message.text(separator);
+        }
+        message = brief(message, kind);
+    }
+
+    message
+}
+
+/// The name of the given delimiter, such as "closing parenthesis".
+fn delimiter(kind: SyntaxKind) -> String {
+    let qualifier = kind.qualifier().map(|s| s + " ").unwrap_or_default();
+    qualifier + &kind.description().unwrap_or_else(|| kind.kind())
+}
+
+/// Appends what the parser found instead of what it expected, which is the
+/// given kind of token or the end of the file.
+fn found(
+    message: FormattedString,
+    given: Option<SyntaxKind>,
+) -> FormattedString {
+    let message = message.text("I found ");
+    match given {
+        Some(kind) => brief(message, kind),
+        None => message.text("the end of the file"),
+    }
 }
 
 impl ParserMessage {
@@ -117,10 +254,15 @@ impl ParserMessage {
         location: Location<FileId>,
     ) -> Diagnostic<FileId>
     where
-        FileId: Default,
+        FileId: Clone + Default,
     {
         match self {
-            ParserMessage::MissingKind { context, expected } => {
+            ParserMessage::MissingKind {
+                context,
+                given,
+                expected,
+                offset,
+            } => {
                 let error = format!(
                     "Missing {}{}",
                     expected.description().map(|s| s + " ").unwrap_or_default(),
@@ -134,13 +276,67 @@ impl ParserMessage {
                     })
                 ));
 
-                let message = FormattedString::default()
-                    .text(format!("I expected {} here.", expected));
+                let message = found(
+                    FormattedString::default()
+                        .text(format!("I expected {} here, but ", expected)),
+                    *given,
+                )
+                .text(".");
 
-                Diagnostic::error(error)
-                    .location(location)
+                let diagnostic = Diagnostic::error(error)
+                    .location(location.clone())
+                    .description(description)
+                    .message(message);
+
+                match crate::parser::token_text(*expected) {
+                    Some(text) => {
+                        let replacement = match given {
+                            Some(_) => text + " ",
+                            None => " ".to_string() + &text,
+                        };
+                        let location =
+                            Location::new(location.file_id, *offset..*offset);
+
+                        diagnostic.suggestion(
+                            location,
+                            replacement,
+                            Applicability::MaybeIncorrect,
+                        )
+                    }
+                    None => diagnostic,
+                }
+            }
+            ParserMessage::StrayKind {
+                context,
+                given,
+                expected,
+            } => {
+                let title = format!(
+                    "Unexpected {} before {}{}",
+                    given.kind(),
+                    expected.description().map(|s| s + " ").unwrap_or_default(),
+                    expected.kind()
+                );
+
+                let description = FormattedString::default().text(format!(
+                    "I was partway through {} when I got stuck here:",
+                    context.map_or("something".to_string(), |context| {
+                        context.to_string()
+                    })
+                ));
+
+                let message = found(
+                    FormattedString::default()
+                        .text(format!("I expected {} here, but ", expected)),
+                    Some(*given),
+                )
+                .text(" before it, so I skipped that.");
+
+                Diagnostic::error(title)
+                    .location(location.clone())
                     .description(description)
                     .message(message)
+                    .suggestion(location, "", Applicability::MaybeIncorrect)
             }
             ParserMessage::UnexpectedKind {
                 context,
@@ -165,8 +361,14 @@ impl ParserMessage {
                     if expected.len() == 1 {
                         let expected = expected[0];
 
-                        let message = FormattedString::default()
-                            .text(format!("I expected {} here.", expected));
+                        let message = found(
+                            FormattedString::default().text(format!(
+                                "I expected {} here, but ",
+                                expected
+                            )),
+                            *given,
+                        )
+                        .text(".");
 
                         let hint = match (expected, given) {
                             (SyntaxKind::Identifier, Some(kind))
@@ -188,9 +390,21 @@ impl ParserMessage {
                         };
 
                         (message, hint)
+                    } else if expected.len() <= MAX_INLINE_EXPECTED {
+                        let message = found(
+                            brief_list(
+                                FormattedString::default().text("I expected "),
+                                expected,
+                            )
+                            .text(" here, but "),
+                            *given,
+                        )
+                        .text(".");
+
+                        (message, None)
                     } else {
-                        let message = FormattedString::default()
-                            .text("I expected one of the following here:")
+                        let message = found(FormattedString::default(), *given)
+                            .text(" here, but I expected one of the following:")
                             .list(
                                 expected
                                     .iter()
@@ -217,6 +431,514 @@ impl ParserMessage {
                         .message(message)
                 }
             }
+            ParserMessage::UnclosedDelimiter {
+                context,
+                opening,
+                closing,
+                stuck,
+            } => {
+                let title = format!(
+                    "Unclosed {}",
+                    opening.description().unwrap_or_else(|| opening.kind())
+                );
+
+                let description = FormattedString::default().text(format!(
+                    "I was partway through {} when I found {} that is never \
+                     closed:",
+                    context.map_or("something".to_string(), |context| {
+                        context.to_string()
+                    }),
+                    opening
+                ));
+
+                let message = FormattedString::default()
+                    .text(format!("I expected {} to close it.", closing));
+
+                let label =
+                    FormattedString::default().text("I got stuck here.");
+                let stuck =
+                    Location::new(location.file_id.clone(), stuck.clone());
+
+                Diagnostic::error(title)
+                    .location(location)
+                    .description(description)
+                    .message(message)
+                    .label(stuck, label)
+            }
+            ParserMessage::MismatchedDelimiter {
+                context,
+                opening,
+                opening_range,
+                given,
+            } => {
+                let closing = crate::parser::closing_delimiter(*opening)
+                    .expect("groups are opened by opening delimiters");
+
+                let description = FormattedString::default().text(format!(
+                    "I was partway through {} when I got stuck here:",
+                    context.map_or("something".to_string(), |context| {
+                        context.to_string()
+                    })
+                ));
+
+                let message = found(
+                    brief(
+                        FormattedString::default().text("I expected "),
+                        closing,
+                    )
+                    .text(" to close the group here, but "),
+                    Some(*given),
+                )
+                .text(".");
+
+                let label = brief(
+                    FormattedString::default().text("The unclosed "),
+                    *opening,
+                )
+                .text(" was opened here.");
+                let opening_location = Location::new(
+                    location.file_id.clone(),
+                    opening_range.clone(),
+                );
+
+                let replacement = closing.code_repr().unwrap_or_default();
+                Diagnostic::error(format!("Mismatched {}", delimiter(*given)))
+                    .location(location.clone())
+                    .description(description)
+                    .message(message)
+                    .label(opening_location, label)
+                    .suggestion(
+                        location,
+                        replacement,
+                        Applicability::MaybeIncorrect,
+                    )
+            }
+            ParserMessage::UnmatchedDelimiter { context, closing } => {
+                let description = FormattedString::default().text(format!(
+                    "I was partway through {} when I found {} that doesn't \
+                     close anything:",
+                    context.map_or("something".to_string(), |context| {
+                        context.to_string()
+                    }),
+                    closing
+                ));
+
+                let opening = match closing {
+                    SyntaxKind::Sym_RBrace => SyntaxKind::Sym_LBrace,
+                    SyntaxKind::Sym_RBracket => SyntaxKind::Sym_LBracket,
+                    _ => SyntaxKind::Sym_LParen,
+                };
+                let message = brief(
+                    FormattedString::default().text("There is no unclosed "),
+                    opening,
+                )
+                .text(" before it, so it may be a leftover.");
+
+                Diagnostic::error(format!("Unmatched {}", delimiter(*closing)))
+                    .location(location.clone())
+                    .description(description)
+                    .message(message)
+                    .suggestion(location, "", Applicability::MaybeIncorrect)
+            }
+            ParserMessage::FeatureGated(feature) => {
+                let description = FormattedString::default()
+                    .text("I found some experimental syntax here:");
+
+                let message = FormattedString::default()
+                    .text(format!("{} is part of the feature ", feature.description()))
+                    .code(feature.name())
+                    .text(", which is experimental (so it may change or go away).");
+
+                let hint = FormattedString::default()
+                    .text("Enable it with ")
+                    .code(format!("--feature={}", feature.name()))
+                    .text(" (or with ")
+                    .code(format!("{} = true", feature.name()))
+                    .text(" in the ")
+                    .code("[features]")
+                    .text(" table of the project's manifest).");
+
+                Diagnostic::error(format!(
+                    "Feature `{}` is experimental",
+                    feature.name()
+                ))
+                .location(location)
+                .description(description)
+                .message(message)
+                .hint(hint)
+            }
+        }
+    }
+}
+
+/// The names of the lints reported by the validation, which are the codes of
+/// their diagnostics.
+pub mod lints {
+    /// A global binding defined with `var` that is never assigned.
+    pub const NEVER_MUTATED: &str = "never-mutated";
+    /// An identifier mixing letters of scripts that look alike.
+    pub const MIXED_SCRIPT: &str = "mixed-script";
+    /// Two different identifiers that look the same.
+    pub const CONFUSABLE: &str = "confusable";
+
+    /// Every lint, in alphabetical order.
+    pub const ALL: &[&str] = &[CONFUSABLE, MIXED_SCRIPT, NEVER_MUTATED];
+
+    /// A one-sentence description of the lint with the given name (for tools
+    /// listing the lints).
+    pub fn description(lint: &str) -> Option<&'static str> {
+        match lint {
+            NEVER_MUTATED => Some(
+                "A global binding is defined with `var`, but never assigned.",
+            ),
+            MIXED_SCRIPT => {
+                Some("An identifier mixes letters of scripts that look alike.")
+            }
+            CONFUSABLE => Some("Two different identifiers look the same."),
+            _ => None,
+        }
+    }
+
+    /// The URL of the documentation of the lint with the given name.
+    pub fn documentation_url(lint: &str) -> Option<String> {
+        const DOCUMENTATION: &str =
+            "https://github.com/helios-lang/helios/blob/master/docs/lints.md";
+
+        description(lint).map(|_| format!("{}#{}", DOCUMENTATION, lint))
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ValidationMessage {
+    NumberOutOfRange {
+        kind: SyntaxKind,
+        text: String,
+    },
+    InvalidDigit {
+        text: String,
+        radix: u32,
+    },
+    InvalidEscape {
+        text: String,
+    },
+    InvalidCharacterLength {
+        text: String,
+        length: usize,
+    },
+    InvalidAssignmentTarget {
+        target: SyntaxKind,
+    },
+    AssignmentToImmutable {
+        name: String,
+        /// The range of the name where the binding (or parameter) is defined.
+        definition: Range<usize>,
+        is_parameter: bool,
+    },
+    NeverMutated {
+        name: String,
+        /// The range of the `var` keyword defining the binding.
+        keyword: Range<usize>,
+    },
+    ReturnOutsideFunction,
+    DuplicateDefinition {
+        name: String,
+        first: Range<usize>,
+    },
+    MixedScriptIdentifier {
+        name: String,
+        scripts: [Script; 2],
+    },
+    ConfusableIdentifier {
+        name: String,
+        other: String,
+        first: Range<usize>,
+    },
+}
+
+impl ValidationMessage {
+    pub fn diagnostic<FileId>(
+        &self,
+        location: Location<FileId>,
+    ) -> Diagnostic<FileId>
+    where
+        FileId: Clone + Default,
+    {
+        match self {
+            ValidationMessage::NumberOutOfRange { kind, text } => {
+                let (title, limit) = if *kind == SyntaxKind::Lit_Float {
+                    ("Float literal out of range", "a 64-bit float")
+                } else {
+                    ("Integer literal out of range", "a 64-bit integer")
+                };
+
+                let description = FormattedString::default()
+                    .text("I found a number that is too large:");
+
+                let message = FormattedString::default()
+                    .text("The number ")
+                    .code(text)
+                    .text(format!(" does not fit in {}.", limit));
+
+                Diagnostic::error(title)
+                    .location(location)
+                    .description(description)
+                    .message(message)
+            }
+            ValidationMessage::InvalidDigit { text, radix } => {
+                let description = FormattedString::default()
+                    .text("I found a number that I don't know how to read:");
+
+                let message = FormattedString::default()
+                    .text("The number ")
+                    .code(text)
+                    .text(format!(
+                        " contains digits that are not valid in base {}.",
+                        radix
+                    ));
+
+                Diagnostic::error("Invalid number literal")
+                    .location(location)
+                    .description(description)
+                    .message(message)
+            }
+            ValidationMessage::InvalidEscape { text } => {
+                let description = FormattedString::default().text(
+                    "I found an escape sequence I don't know how to read:",
+                );
+
+                let message = FormattedString::default()
+                    .text("The escape sequence ")
+                    .code(text)
+                    .text(" is not valid.");
+
+                let hint =
+                    "The valid escape sequences are `\\n`, `\\r`, `\\t`, \
+                            `\\0`, `\\\\`, `\\'`, `\\\"`, `\\{` and \
+                            `\\u{...}` (with the hexadecimal value of a \
+                            Unicode character).";
+
+                Diagnostic::error("Invalid escape sequence")
+                    .location(location)
+                    .description(description)
+                    .message(message)
+                    .hint(hint)
+            }
+            ValidationMessage::InvalidCharacterLength { text, length } => {
+                let description = FormattedString::default().text(
+                    "I found a character literal that isn't one character:",
+                );
+
+                let message = if *length == 0 {
+                    FormattedString::default()
+                        .text("The character literal ")
+                        .code(text)
+                        .text(" is empty.")
+                } else {
+                    FormattedString::default()
+                        .text("The character literal ")
+                        .code(text)
+                        .text(format!(" contains {} characters.", length))
+                };
+
+                let hint = "A character literal contains exactly one \
+                            character. Try using a string literal instead.";
+
+                Diagnostic::error("Invalid character literal")
+                    .location(location)
+                    .description(description)
+                    .message(message)
+                    .hint(hint)
+            }
+            ValidationMessage::InvalidAssignmentTarget { target } => {
+                let description = FormattedString::default().text(
+                    "I found an assignment to something that isn't a \
+                           variable:",
+                );
+
+                let message = FormattedString::default().text(format!(
+                    "I expected a variable here, but found {} instead.",
+                    target
+                ));
+
+                let hint = "Only variables can be assigned a new value.";
+
+                Diagnostic::error("Invalid assignment target")
+                    .location(location)
+                    .description(description)
+                    .message(message)
+                    .hint(hint)
+            }
+            ValidationMessage::AssignmentToImmutable {
+                name,
+                definition,
+                is_parameter,
+            } => {
+                let description = FormattedString::default()
+                    .text("I found an assignment to a name that can't change:");
+
+                let (message, hint) = if *is_parameter {
+                    let message = FormattedString::default().code(name).text(
+                        " is a parameter, so it can't be assigned a new value.",
+                    );
+
+                    let hint = FormattedString::default()
+                        .text("Try defining a global binding with ")
+                        .code("var")
+                        .text(" to hold the value instead.");
+
+                    (message, hint)
+                } else {
+                    let message = FormattedString::default()
+                        .code(name)
+                        .text(" is defined with ")
+                        .code("let")
+                        .text(", so it can't be assigned a new value.");
+
+                    let hint = FormattedString::default()
+                        .text("Try defining it with ")
+                        .code("var")
+                        .text(" instead of ")
+                        .code("let")
+                        .text(" so that it can change.");
+
+                    (message, hint)
+                };
+
+                let label =
+                    FormattedString::default().text("It is defined here.");
+                let definition =
+                    Location::new(location.file_id.clone(), definition.clone());
+
+                Diagnostic::error("Assignment to immutable binding")
+                    .location(location)
+                    .description(description)
+                    .message(message)
+                    .label(definition, label)
+                    .hint(hint)
+            }
+            ValidationMessage::NeverMutated { name, keyword } => {
+                let description = FormattedString::default()
+                    .text("I found a mutable binding that never changes:");
+
+                let message = FormattedString::default()
+                    .code(name)
+                    .text(" is defined with ")
+                    .code("var")
+                    .text(", but it is never assigned a new value.");
+
+                let hint = FormattedString::default()
+                    .text("Try defining it with ")
+                    .code("let")
+                    .text(" instead.");
+
+                let keyword =
+                    Location::new(location.file_id.clone(), keyword.clone());
+                Diagnostic::warning("Binding is never mutated")
+                    .code(lints::NEVER_MUTATED)
+                    .location(location)
+                    .description(description)
+                    .message(message)
+                    .suggestion(
+                        keyword,
+                        "let",
+                        Applicability::MachineApplicable,
+                    )
+                    .hint(hint)
+            }
+            ValidationMessage::ReturnOutsideFunction => {
+                let description = FormattedString::default()
+                    .text("I found a return outside of a function:");
+
+                let message = FormattedString::default()
+                    .text("There is no function to return from here.");
+
+                let hint = FormattedString::default()
+                    .text("A ")
+                    .code("return")
+                    .text(" may only be used in the body of a lambda (such as ")
+                    .code("\\x -> return x")
+                    .text(").");
+
+                Diagnostic::error("Return outside of a function")
+                    .location(location)
+                    .description(description)
+                    .message(message)
+                    .hint(hint)
+            }
+            ValidationMessage::DuplicateDefinition { name, first } => {
+                let description = FormattedString::default().text(
+                    "I found a definition of a name that already exists:",
+                );
+
+                let message = FormattedString::default()
+                    .text("The name ")
+                    .code(name)
+                    .text(" is defined more than once in this file.");
+
+                let label = FormattedString::default()
+                    .text("It was first defined here.");
+
+                let hint = "Try renaming or removing one of the definitions.";
+                let first =
+                    Location::new(location.file_id.clone(), first.clone());
+
+                Diagnostic::error("Duplicate definition")
+                    .location(location)
+                    .description(description)
+                    .message(message)
+                    .label(first, label)
+                    .hint(hint)
+            }
+            ValidationMessage::MixedScriptIdentifier { name, scripts } => {
+                let description = FormattedString::default().text(
+                    "I found a name written with letters of different scripts:",
+                );
+
+                let message = FormattedString::default()
+                    .text("The name ")
+                    .code(name)
+                    .text(format!(
+                        " mixes {} and {} letters, many of which look alike.",
+                        scripts[0], scripts[1]
+                    ));
+
+                let hint =
+                    "Try writing the name with the letters of a single script.";
+
+                Diagnostic::warning("Mixed-script identifier")
+                    .code(lints::MIXED_SCRIPT)
+                    .location(location)
+                    .description(description)
+                    .message(message)
+                    .hint(hint)
+            }
+            ValidationMessage::ConfusableIdentifier { name, other, first } => {
+                let description = FormattedString::default()
+                    .text("I found a name that looks like another one:");
+
+                let message = FormattedString::default()
+                    .text("The name ")
+                    .code(name)
+                    .text(" can easily be confused with ")
+                    .code(other)
+                    .text(".");
+
+                let label = FormattedString::default()
+                    .text("The other name is first used here.");
+
+                let hint = "Try renaming one of them so that they can be told \
+                            apart.";
+                let first =
+                    Location::new(location.file_id.clone(), first.clone());
+
+                Diagnostic::warning("Confusable identifiers")
+                    .code(lints::CONFUSABLE)
+                    .location(location)
+                    .description(description)
+                    .message(message)
+                    .label(first, label)
+                    .hint(hint)
+            }
         }
     }
 }
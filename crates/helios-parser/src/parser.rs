@@ -9,11 +9,65 @@ use self::event::Event;
 use self::marker::Marker;
 use self::source::Source;
 use crate::message::ParserMessage;
-use crate::{lexer::Token, Message};
+use crate::trace::{Trace, TraceEvent};
+use crate::{lexer::Token, Feature, Features, Message};
 use helios_diagnostics::Location;
 use helios_syntax::SyntaxKind;
+use std::ops::Range;
 
-const RECOVERY_SET: [SyntaxKind; 1] = [SyntaxKind::Kwd_Let];
+const RECOVERY_SET: [SyntaxKind; 8] = [
+    SyntaxKind::Kwd_Extend,
+    SyntaxKind::Kwd_External,
+    SyntaxKind::Kwd_Import,
+    SyntaxKind::Kwd_Interface,
+    SyntaxKind::Kwd_Let,
+    SyntaxKind::Kwd_Public,
+    SyntaxKind::Kwd_Type,
+    SyntaxKind::Kwd_Var,
+];
+
+/// The kinds that end a group (such as `(a + b)`) even if it isn't closed:
+/// the start of a declaration and the end of the enclosing block.
+const GROUP_RECOVERY_SET: [SyntaxKind; 11] = [
+    SyntaxKind::Kwd_Extend,
+    SyntaxKind::Kwd_External,
+    SyntaxKind::Kwd_Import,
+    SyntaxKind::Kwd_Interface,
+    SyntaxKind::Kwd_Let,
+    SyntaxKind::Kwd_Public,
+    SyntaxKind::Kwd_Type,
+    SyntaxKind::Kwd_Var,
+    SyntaxKind::Sym_RBrace,
+    SyntaxKind::Lay_Newline,
+    SyntaxKind::Lay_End,
+];
+
+/// The kind of the delimiter closing the given opening delimiter, if it is
+/// one.
+pub(crate) fn closing_delimiter(kind: SyntaxKind) -> Option<SyntaxKind> {
+    match kind {
+        SyntaxKind::Sym_LParen => Some(SyntaxKind::Sym_RParen),
+        SyntaxKind::Sym_LBracket => Some(SyntaxKind::Sym_RBracket),
+        SyntaxKind::Sym_LBrace => Some(SyntaxKind::Sym_RBrace),
+        _ => None,
+    }
+}
+
+/// The text of the tokens of the given kind, if they always have the same one
+/// (such as `=` or `let`).
+pub(crate) fn token_text(kind: SyntaxKind) -> Option<String> {
+    kind.code_repr()
+        .or_else(|| kind.description().filter(|_| kind.is_keyword()))
+}
+
+pub(crate) fn is_closing_delimiter(kind: SyntaxKind) -> bool {
+    matches!(
+        kind,
+        SyntaxKind::Sym_RParen
+            | SyntaxKind::Sym_RBracket
+            | SyntaxKind::Sym_RBrace
+    )
+}
 
 /// A lazy, lossless, error-tolerant parser for the Helios programming language.
 pub struct Parser<'source, 'tokens, FileId> {
@@ -21,25 +75,45 @@ pub struct Parser<'source, 'tokens, FileId> {
     source: Source<'tokens, 'source>,
     events: Vec<Event>,
     expected_kinds: Vec<SyntaxKind>,
+    /// The opening delimiters of the groups being parsed (with their range),
+    /// the innermost one last.
+    delimiters: Vec<(SyntaxKind, Range<usize>)>,
     messages: Vec<Message<FileId>>,
+    features: Features,
+    trace: Option<Trace>,
 }
 
 impl<'source, 'tokens, FileId> Parser<'source, 'tokens, FileId>
 where
     FileId: Clone + Default,
 {
-    /// Constructs a new [`Parser`] with a [`Source`] and a channel that sends
-    /// [`Diagnostic`]s.
-    pub fn new(file_id: FileId, source: Source<'tokens, 'source>) -> Self {
+    /// Constructs a new [`Parser`] with a [`Source`] and the experimental
+    /// features it accepts.
+    pub fn new(
+        file_id: FileId,
+        source: Source<'tokens, 'source>,
+        features: Features,
+    ) -> Self {
         Self {
             file_id,
             source,
             events: Vec::new(),
             expected_kinds: Vec::new(),
+            delimiters: Vec::new(),
             messages: Vec::new(),
+            features,
+            trace: None,
         }
     }
 
+    /// Records the steps the parser takes (see the [`trace`] module).
+    ///
+    /// [`trace`]: crate::trace
+    pub fn with_trace(mut self) -> Self {
+        self.trace = Some(Trace::default());
+        self
+    }
+
     /// Starts the parsing process.
     ///
     /// This function will attempt to build a concrete syntax tree with the
@@ -47,9 +121,20 @@ where
     /// return a [`Parse`] containing a root green node.
     ///
     /// [`Parse`]: crate::Parse
-    pub fn parse(mut self) -> (Vec<Event>, Vec<Message<FileId>>) {
+    pub fn parse(
+        mut self,
+    ) -> (Vec<Event>, Vec<Message<FileId>>, Option<Trace>) {
         crate::grammar::root(&mut self);
-        (self.events, self.messages)
+        (self.events, self.messages, self.trace)
+    }
+}
+
+impl<'source, 'tokens, FileId> Parser<'source, 'tokens, FileId> {
+    /// Records the given step in the trace, if the parser is tracing.
+    pub(crate) fn trace(&mut self, event: impl FnOnce() -> TraceEvent) {
+        if let Some(trace) = &mut self.trace {
+            trace.push(event());
+        }
     }
 }
 
@@ -69,7 +154,7 @@ where
     ) -> Option<&'a SyntaxKind> {
         self.expected_kinds.extend(kinds);
         self.peek()
-            .map_or(None, |kind| kinds.iter().find(|&&it| kind == it))
+            .and_then(|kind| kinds.iter().find(|&&it| kind == it))
     }
 
     /// Peeks the next [`SyntaxKind`] token without consuming it.
@@ -80,17 +165,55 @@ where
     /// Adds the next token to the syntax tree (via the [`GreenNodeBuilder`]).
     pub(crate) fn bump(&mut self) {
         self.expected_kinds.clear();
-        self.source.next_token().unwrap();
-        self.events.push(Event::AddToken)
+        let token = self.source.next_token().unwrap();
+        let (kind, range) = (token.kind, token.range.clone());
+        self.events.push(Event::AddToken);
+        self.trace(|| TraceEvent::Bump { kind, range });
     }
 
     /// Starts a new node, returning a [`Marker`].
     pub(crate) fn start(&mut self) -> Marker {
         let pos = self.events.len();
         self.events.push(Event::Placeholder);
+
+        if self.trace.is_some() {
+            let offset = self.peek_range().map_or_else(
+                || self.source.last_token_range().map_or(0, |it| it.end),
+                |range| range.start,
+            );
+            self.trace(|| TraceEvent::Start {
+                marker: pos,
+                offset,
+            });
+        }
+
+        Marker::new(pos)
+    }
+
+    /// Starts a new node that will be the parent of the node of the completed
+    /// marker at `child` (see [`CompletedMarker::precede`]).
+    ///
+    /// [`CompletedMarker::precede`]: marker::CompletedMarker::precede
+    pub(crate) fn start_preceding(&mut self, child: usize) -> Marker {
+        let pos = self.events.len();
+        self.events.push(Event::Placeholder);
+        self.trace(|| TraceEvent::Precede { marker: pos, child });
         Marker::new(pos)
     }
 
+    /// Consumes the next token if it's of the given kind, or else recovers in
+    /// one of two ways (which are reported differently):
+    ///
+    /// - If the token after the next one is of the given kind, the next token
+    ///   is skipped (as a [`SyntaxKind::Error`] node), since it's probably a
+    ///   stray one (such as the `b` of `let a b = 1`); or
+    /// - Otherwise, the token is missing. If it's the only kind expected and
+    ///   it always has the same text (such as `=`), it's inserted in the tree
+    ///   without any text, so that the rest of the tree is shaped as if it
+    ///   were there.
+    ///
+    /// A stray token is never one that ends the enclosing group, block or
+    /// declaration (see the recovery sets), which are left to parse.
     pub(crate) fn expect(
         &mut self,
         kind: SyntaxKind,
@@ -98,12 +221,99 @@ where
     ) {
         if self.is_at(kind) {
             self.bump();
+        } else if self.is_at_stray_before(kind) {
+            self.skip_stray(kind, context.into());
         } else {
+            // Other kinds checked for here may be what's missing instead
+            let is_only_expected = self
+                .expected_kinds
+                .iter()
+                .all(|&it| it == kind || it == SyntaxKind::Lay_Newline);
+
             self.error(context);
+            if is_only_expected && token_text(kind).is_some() {
+                self.insert_missing(kind);
+            }
         }
     }
 
+    /// Determines if the next token is followed by one of the given kind,
+    /// and can be skipped to get to it.
+    fn is_at_stray_before(&self, kind: SyntaxKind) -> bool {
+        let mut lookahead = self.source.lookahead();
+        match (lookahead.next(), lookahead.next()) {
+            (Some(next), Some(after)) => {
+                after == kind
+                    && !GROUP_RECOVERY_SET.contains(&next)
+                    && !self.closes_open_group(next)
+            }
+            _ => false,
+        }
+    }
+
+    /// Skips the next token, which comes before the expected kind of token,
+    /// and consumes the latter.
+    fn skip_stray(
+        &mut self,
+        expected: SyntaxKind,
+        context: Option<SyntaxKind>,
+    ) {
+        let Token { kind, range, .. } = self.source.peek_token().unwrap();
+        let (given, range) = (*kind, range.clone());
+
+        self.expected_kinds.clear();
+        self.trace(|| TraceEvent::Expected {
+            expected: vec![expected],
+            given: Some(given),
+            range: range.clone(),
+        });
+        self.messages.push(Message::new(
+            ParserMessage::StrayKind {
+                context,
+                given,
+                expected,
+            },
+            Location::new(self.file_id.clone(), range),
+        ));
+
+        let m = self.start();
+        self.bump();
+        m.complete(self, SyntaxKind::Error);
+        self.bump();
+    }
+
+    /// Adds a token of the given kind without any text to the tree, in place
+    /// of a missing one.
+    fn insert_missing(&mut self, kind: SyntaxKind) {
+        self.events.push(Event::MissingToken { kind });
+
+        if self.trace.is_some() {
+            let offset = self.peek_range().map_or_else(
+                || self.source.last_token_range().map_or(0, |it| it.end),
+                |range| range.start,
+            );
+            self.trace(|| TraceEvent::Insert { kind, offset });
+        }
+    }
+
+    /// Reports that the next token isn't one of the expected kinds, skipping
+    /// it (in an [`SyntaxKind::Error`] node) unless it can be recovered from.
     pub(crate) fn error(&mut self, context: impl Into<Option<SyntaxKind>>) {
+        if self.report_error(context) {
+            let m = self.start();
+            self.bump();
+            m.complete(self, SyntaxKind::Error);
+        }
+    }
+
+    /// Reports that the next token isn't one of the expected kinds like
+    /// [`Parser::error`], returning whether it should be skipped (which it
+    /// isn't if it starts a declaration or closes an open group) but leaving
+    /// that to the caller.
+    pub(crate) fn report_error(
+        &mut self,
+        context: impl Into<Option<SyntaxKind>>,
+    ) -> bool {
         let current_token = self.source.peek_token();
 
         let (given, range) =
@@ -113,13 +323,37 @@ where
                 (None, self.source.last_token_range().unwrap())
             };
 
-        let expected = std::mem::take(&mut self.expected_kinds);
+        let expected = self.take_expected_kinds();
         let expected_len = expected.len();
+        self.trace(|| TraceEvent::Expected {
+            expected: expected.clone(),
+            given,
+            range: range.clone(),
+        });
+
+        // A closing delimiter that doesn't close any group is reported on its
+        // own (unless a single token is missing before it), since what's
+        // expected before it doesn't matter much
+        let unmatched = given.filter(|&kind| {
+            expected_len > 1
+                && is_closing_delimiter(kind)
+                && !self.closes_open_group(kind)
+        });
 
-        let message_kind = if expected_len == 1 {
+        let message_kind = if let Some(closing) = unmatched {
+            ParserMessage::UnmatchedDelimiter {
+                context: context.into(),
+                closing,
+            }
+        } else if expected_len == 1 {
             ParserMessage::MissingKind {
                 context: context.into(),
+                given,
                 expected: expected[0],
+                offset: match given {
+                    Some(_) => range.start,
+                    None => self.source.last_consumed_end(),
+                },
             }
         } else {
             ParserMessage::UnexpectedKind {
@@ -134,18 +368,218 @@ where
             Location::new(self.file_id.clone(), range),
         ));
 
-        if expected_len > 1
+        // The closing delimiter of a group is left for the group to consume
+        let closes_group =
+            given.is_some_and(|kind| self.closes_open_group(kind));
+        expected_len > 1
+            && !closes_group
             && !self.is_at_set(&RECOVERY_SET)
             && !self.is_at_end()
-        {
-            let m = self.start();
+    }
+
+    /// Reports the next token if it introduces the syntax of the given
+    /// feature and the feature isn't enabled. The syntax is parsed all the
+    /// same, so that the rest of the tree doesn't depend on the feature.
+    pub(crate) fn gate(&mut self, feature: Feature) {
+        if self.features.is_enabled(feature) {
+            return;
+        }
+
+        if let Some(range) = self.peek_range() {
+            self.messages.push(Message::new(
+                ParserMessage::FeatureGated(feature),
+                Location::new(self.file_id.clone(), range),
+            ));
+        }
+    }
+
+    /// The range of the next token, if any.
+    pub(crate) fn peek_range(&mut self) -> Option<Range<usize>> {
+        self.source.peek_token().map(|token| token.range.clone())
+    }
+
+    /// Consumes the opening delimiter of a group (such as the `(` of
+    /// `(a + b)`), which is closed by [`Parser::expect_closing`].
+    pub(crate) fn open_delimiter(&mut self) {
+        let opening = self
+            .peek()
+            .filter(|&kind| closing_delimiter(kind).is_some());
+        let opening = opening.expect("expected an opening delimiter");
+        let range = self.peek_range().unwrap();
+
+        self.delimiters.push((opening, range));
+        self.bump();
+    }
+
+    /// Consumes the closing delimiter of the innermost group (such as the `)`
+    /// of a `(`).
+    ///
+    /// Anything unexpected before the closing delimiter is skipped (as a
+    /// single [`SyntaxKind::Error`] node) and reported, so that a stray token
+    /// doesn't also unbalance the groups around it. The skipped tokens may
+    /// contain groups of their own, but never go past the end of the
+    /// enclosing block or declaration (nor the closing delimiter of an
+    /// enclosing group). If the group isn't closed at all, the error is
+    /// reported at the opening delimiter instead, which is where the mistake
+    /// usually is, unless it's closed by a delimiter of the wrong kind (such
+    /// as the `]` of `(a + b]`).
+    pub(crate) fn expect_closing(&mut self, context: SyntaxKind) {
+        let (opening, opening_range) =
+            self.delimiters.pop().expect("expected an open group");
+        let closing = closing_delimiter(opening).unwrap();
+
+        if self.is_at(closing) {
             self.bump();
+            return;
+        }
+
+        let expected = self.take_expected_kinds();
+
+        let unexpected = self
+            .source
+            .peek_token()
+            .map(|Token { kind, range, .. }| (*kind, range.clone()));
+
+        if self.trace.is_some() {
+            let given = unexpected.clone().map(|(kind, _)| kind);
+            let range = match &unexpected {
+                Some((_, range)) => range.clone(),
+                None => self.source.last_token_range().unwrap(),
+            };
+            let expected = expected.clone();
+            self.trace(|| TraceEvent::Expected {
+                expected,
+                given,
+                range,
+            });
+        }
+
+        // A closing delimiter of another kind that isn't followed by the one
+        // of the group is taken to close the group by mistake
+        let (skipped, is_closed) = self.tokens_before_closing(opening, closing);
+        let is_mismatched = unexpected.as_ref().is_some_and(|&(kind, _)| {
+            is_closing_delimiter(kind)
+                && !is_closed
+                && !self.closes_open_group(kind)
+        });
+        let skipped = if is_mismatched { 1 } else { skipped };
+
+        if skipped > 0 {
+            let m = self.start();
+            for _ in 0..skipped {
+                self.bump();
+            }
             m.complete(self, SyntaxKind::Error);
         }
+
+        let message = match unexpected {
+            Some((given, range)) if is_mismatched => Message::new(
+                ParserMessage::MismatchedDelimiter {
+                    context: Some(context),
+                    opening,
+                    opening_range,
+                    given,
+                },
+                Location::new(self.file_id.clone(), range),
+            ),
+            Some((given, range)) if is_closed => {
+                self.bump();
+                let message = if is_closing_delimiter(given) {
+                    ParserMessage::UnmatchedDelimiter {
+                        context: Some(context),
+                        closing: given,
+                    }
+                } else {
+                    ParserMessage::UnexpectedKind {
+                        context: Some(context),
+                        given: Some(given),
+                        expected,
+                    }
+                };
+
+                Message::new(
+                    message,
+                    Location::new(self.file_id.clone(), range),
+                )
+            }
+            _ => {
+                let stuck = match self.source.peek_token() {
+                    Some(token) => token.range.clone(),
+                    None => self.source.last_token_range().unwrap(),
+                };
+
+                Message::new(
+                    ParserMessage::UnclosedDelimiter {
+                        context: Some(context),
+                        opening,
+                        closing,
+                        stuck,
+                    },
+                    Location::new(self.file_id.clone(), opening_range),
+                )
+            }
+        };
+
+        self.expected_kinds.clear();
+        self.messages.push(message);
+    }
+
+    /// The number of tokens before the `closing` delimiter of the innermost
+    /// group, and whether it's there at all: it isn't if the enclosing block
+    /// or declaration ends first (or an enclosing group is closed first).
+    fn tokens_before_closing(
+        &self,
+        opening: SyntaxKind,
+        closing: SyntaxKind,
+    ) -> (usize, bool) {
+        let mut depth = 0usize;
+        for (index, kind) in self.source.lookahead().enumerate() {
+            if kind == opening {
+                depth += 1;
+            } else if kind == closing {
+                if depth == 0 {
+                    return (index, true);
+                }
+                depth -= 1;
+            } else if GROUP_RECOVERY_SET.contains(&kind)
+                || self.closes_open_group(kind)
+            {
+                return (index, false);
+            }
+        }
+
+        (self.source.lookahead().count(), false)
+    }
+
+    /// Determines if the given kind closes one of the groups being parsed.
+    fn closes_open_group(&self, kind: SyntaxKind) -> bool {
+        self.delimiters
+            .iter()
+            .any(|&(opening, _)| closing_delimiter(opening) == Some(kind))
+    }
+
+    /// Takes the kinds checked for since the last token was consumed, which
+    /// are every kind that would have been valid at the next token (in the
+    /// order they were checked for, without repetitions).
+    fn take_expected_kinds(&mut self) -> Vec<SyntaxKind> {
+        let mut expected = Vec::new();
+        for kind in std::mem::take(&mut self.expected_kinds) {
+            if !expected.contains(&kind) {
+                expected.push(kind);
+            }
+        }
+
+        // The new lines of indented blocks are inferred rather than written,
+        // so they're not worth suggesting along with other tokens
+        if expected.len() > 1 {
+            expected.retain(|&kind| kind != SyntaxKind::Lay_Newline);
+        }
+
+        expected
     }
 
     fn is_at_set(&mut self, set: &[SyntaxKind]) -> bool {
-        self.peek().map_or(false, |kind| set.contains(&kind))
+        self.peek().is_some_and(|kind| set.contains(&kind))
     }
 
     pub(crate) fn is_at_end(&mut self) -> bool {
@@ -7,6 +7,10 @@ pub enum Event {
         forward_parent: Option<usize>,
     },
     AddToken,
+    /// A token that is missing from the source, added without any text.
+    MissingToken {
+        kind: SyntaxKind,
+    },
     FinishNode,
     Placeholder,
 }
@@ -1,5 +1,6 @@
 use super::event::Event;
 use super::Parser;
+use crate::trace::TraceEvent;
 use drop_bomb::DropBomb;
 use helios_syntax::SyntaxKind;
 
@@ -33,8 +34,18 @@ impl Marker {
 
         parser.events.push(Event::FinishNode);
 
+        let marker = self.pos;
+        parser.trace(|| TraceEvent::Complete { marker, kind });
         CompletedMarker { pos: self.pos }
     }
+
+    /// Discards the marker without starting a node.
+    pub(crate) fn abandon<FileId>(mut self, parser: &mut Parser<FileId>) {
+        self.bomb.defuse();
+
+        let marker = self.pos;
+        parser.trace(|| TraceEvent::Abandon { marker });
+    }
 }
 
 pub(crate) struct CompletedMarker {
@@ -46,7 +57,7 @@ impl CompletedMarker {
     where
         FileId: Clone + Default,
     {
-        let new_m = p.start();
+        let new_m = p.start_preceding(self.pos);
 
         if let Event::StartNode {
             ref mut forward_parent,
@@ -1,13 +1,38 @@
 use super::event::Event;
-use crate::{Message, Parse, lexer::Token};
-use helios_syntax::HeliosLanguage as HeliosLanguage;
-use rowan::{GreenNodeBuilder, Language};
+use crate::lexer::Token;
+use helios_syntax::{HeliosLanguage, SyntaxKind};
+use rowan::{GreenNode, GreenNodeBuilder, Language};
 
+/// Builds the syntax tree from the [`Event`]s produced by the [`Parser`].
+///
+/// Because the [`Parser`] never sees trivia tokens (see [`Source`]), the
+/// [`Sink`] is responsible for inserting them back into the syntax tree. Every
+/// trivia token is bound to a non-trivia token and is placed as follows:
+///
+/// - *Trailing trivia* (the whitespace and comments following a token on the
+///   same line) belongs to the largest node that ends with the token and is
+///   placed at the end of that node;
+/// - *Leading trivia* (any other trivia preceding a token) belongs to the
+///   parent of the largest node that starts with the token and is placed
+///   right before that node. The exception is a doc comment (and anything
+///   after it), which is placed at the start of the node it documents; and
+/// - Any trivia at the end of the input belongs to the root node.
+///
+/// For example, in `--| Doc\nlet a = 1 -- Comment\n`, the doc comment belongs
+/// to the binding, as does the trailing comment, while the final newline
+/// belongs to the root node.
+///
+/// [`Parser`]: crate::parser::Parser
+/// [`Source`]: crate::parser::source::Source
 pub struct Sink<'tokens, 'source> {
     tokens: &'tokens [Token<'source>],
     events: Vec<Event>,
     builder: GreenNodeBuilder<'static>,
     cursor: usize,
+    depth: usize,
+    /// The number of nodes to finish before the trailing trivia of the last
+    /// token is added.
+    pending_finishes: Option<usize>,
 }
 
 impl<'tokens, 'source> Sink<'tokens, 'source> {
@@ -17,10 +42,12 @@ impl<'tokens, 'source> Sink<'tokens, 'source> {
             events,
             builder: GreenNodeBuilder::new(),
             cursor: 0,
+            depth: 0,
+            pending_finishes: None,
         }
     }
 
-    pub fn finish<FileId>(mut self, messages: Vec<Message<FileId>>) -> Parse<FileId> {
+    pub fn finish(mut self) -> GreenNode {
         use std::mem;
 
         for i in 0..self.events.len() {
@@ -52,25 +79,123 @@ impl<'tokens, 'source> Sink<'tokens, 'source> {
                         };
                     }
 
-                    for kind in kinds.into_iter().rev() {
+                    // An empty node (such as a missing expression) is right
+                    // after the last token, before any leading trivia
+                    if kinds.len() == 1 && self.is_finished_after(i) {
                         self.builder
                             .start_node(HeliosLanguage::kind_to_raw(kind));
+                        self.depth += 1;
+                    } else {
+                        self.start_nodes(kinds.into_iter().rev());
                     }
                 }
-                Event::AddToken => self.token(),
-                Event::FinishNode => self.builder.finish_node(),
+                Event::AddToken => {
+                    self.leading_trivia(true);
+                    self.token();
+
+                    let finishes = self.count_finishes_after(i);
+                    if finishes == 0 {
+                        self.trailing_trivia();
+                    } else {
+                        self.pending_finishes = Some(finishes);
+                    }
+                }
+                Event::MissingToken { kind } => self
+                    .builder
+                    .token(HeliosLanguage::kind_to_raw(kind), "".into()),
+                Event::FinishNode => self.finish_node(),
                 Event::Placeholder => {}
             }
+        }
+
+        self.builder.finish()
+    }
+
+    /// Starts the given nodes (from outermost to innermost), which all start
+    /// with the same token.
+    fn start_nodes(&mut self, kinds: impl Iterator<Item = SyntaxKind>) {
+        for (i, kind) in kinds.enumerate() {
+            // The root node has no parent to hold any leading trivia
+            let is_outermost = i == 0 && self.depth > 0;
+
+            if is_outermost {
+                self.leading_trivia(false);
+            }
+
+            self.builder.start_node(HeliosLanguage::kind_to_raw(kind));
+            self.depth += 1;
+
+            if is_outermost {
+                self.leading_trivia(true);
+            }
+        }
+    }
+
+    fn finish_node(&mut self) {
+        match self.pending_finishes {
+            Some(1) => {
+                self.pending_finishes = None;
+                self.trailing_trivia();
+            }
+            Some(n) => self.pending_finishes = Some(n - 1),
+            None => {}
+        }
 
-            self.eat_trivia();
+        // We're about to finish the root node
+        if self.depth == 1 {
+            self.leading_trivia(true);
         }
 
-        Parse::new(self.builder.finish(), messages)
+        self.builder.finish_node();
+        self.depth -= 1;
     }
 
-    fn eat_trivia(&mut self) {
+    /// Determines if the event at the given index is followed by the end of a
+    /// node (without any tokens in between).
+    fn is_finished_after(&self, idx: usize) -> bool {
+        self.events[idx + 1..]
+            .iter()
+            .find(|event| **event != Event::Placeholder)
+            .is_some_and(|event| *event == Event::FinishNode)
+    }
+
+    /// Counts the number of nodes finished right after the event at the given
+    /// index, i.e. the number of nodes (other than the root node) that end
+    /// with the last added token.
+    fn count_finishes_after(&self, idx: usize) -> usize {
+        let finishes = self.events[idx + 1..]
+            .iter()
+            .filter(|event| **event != Event::Placeholder)
+            .take_while(|event| **event == Event::FinishNode)
+            .count();
+
+        finishes.min(self.depth - 1)
+    }
+
+    /// Adds the trivia preceding the next non-trivia token.
+    ///
+    /// If `include_docs` is `false`, this stops at the first doc comment.
+    fn leading_trivia(&mut self, include_docs: bool) {
         while let Some(token) = self.tokens.get(self.cursor) {
-            if !token.kind.is_trivia() {
+            let at_docs = token.kind == SyntaxKind::DocComment;
+            if !token.kind.is_trivia() || (at_docs && !include_docs) {
+                break;
+            }
+
+            self.token();
+        }
+    }
+
+    /// Adds the trivia on the same line as the last added token.
+    fn trailing_trivia(&mut self) {
+        while let Some(token) = self.tokens.get(self.cursor) {
+            let is_trailing = match token.kind {
+                SyntaxKind::Whitespace => !token.text.contains('\n'),
+                SyntaxKind::Comment => true,
+                _ => false,
+            };
+
+            if !is_trailing {
                 break;
             }
 
@@ -85,3 +210,75 @@ impl<'tokens, 'source> Sink<'tokens, 'source> {
         self.cursor += 1;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::check;
+    use expect_test::expect;
+
+    #[test]
+    fn test_trailing_trivia() {
+        check(
+            "let a = 1 -- Comment\n",
+            expect![[r#"
+                Root@0..21
+                  Dec_GlobalBinding@0..20
+                    Kwd_Let@0..3 "let"
+                    Whitespace@3..4 " "
+                    Identifier@4..5 "a"
+                    Whitespace@5..6 " "
+                    Sym_Eq@6..7 "="
+                    Whitespace@7..8 " "
+                    Exp_Literal@8..9
+                      Lit_Integer@8..9 "1"
+                    Whitespace@9..10 " "
+                    Comment@10..20 "-- Comment"
+                  Whitespace@20..21 "\n"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_leading_trivia() {
+        check(
+            "let a =\n  -- Comment\n  1",
+            expect![[r#"
+                Root@0..24
+                  Dec_GlobalBinding@0..24
+                    Kwd_Let@0..3 "let"
+                    Whitespace@3..4 " "
+                    Identifier@4..5 "a"
+                    Whitespace@5..6 " "
+                    Sym_Eq@6..7 "="
+                    Whitespace@7..10 "\n  "
+                    Comment@10..20 "-- Comment"
+                    Whitespace@20..23 "\n  "
+                    Exp_Literal@23..24
+                      Lit_Integer@23..24 "1"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_doc_comment_trivia() {
+        check(
+            "-- Comment\n--| Doc\nlet a = 1",
+            expect![[r#"
+                Root@0..28
+                  Comment@0..10 "-- Comment"
+                  Whitespace@10..11 "\n"
+                  Dec_GlobalBinding@11..28
+                    DocComment@11..18 "--| Doc"
+                    Whitespace@18..19 "\n"
+                    Kwd_Let@19..22 "let"
+                    Whitespace@22..23 " "
+                    Identifier@23..24 "a"
+                    Whitespace@24..25 " "
+                    Sym_Eq@25..26 "="
+                    Whitespace@26..27 " "
+                    Exp_Literal@27..28
+                      Lit_Integer@27..28 "1"
+            "#]],
+        );
+    }
+}
@@ -44,17 +44,36 @@ impl<'source, 'tokens> Source<'source, 'tokens> {
         self.tokens.last().map(|Token { range, .. }| range.clone())
     }
 
+    /// The end of the last token consumed that isn't trivia (or `0` if
+    /// there's none).
+    pub(crate) fn last_consumed_end(&self) -> usize {
+        self.tokens[..self.cursor]
+            .iter()
+            .rev()
+            .find(|token| !token.kind.is_trivia())
+            .map_or(0, |token| token.range.end)
+    }
+
     pub fn peek_kind(&mut self) -> Option<SyntaxKind> {
         self.eat_trivia();
         self.peek_kind_raw()
     }
 
     #[allow(dead_code)]
-    pub fn peek_token(&mut self) -> Option<&Token> {
+    pub fn peek_token(&mut self) -> Option<&Token<'_>> {
         self.eat_trivia();
         self.peek_token_raw()
     }
 
+    /// The kinds of the tokens left, without the trivia (so the first one is
+    /// the next token).
+    pub(crate) fn lookahead(&self) -> impl Iterator<Item = SyntaxKind> + '_ {
+        self.tokens[self.cursor..]
+            .iter()
+            .map(|Token { kind, .. }| *kind)
+            .filter(|kind| !kind.is_trivia())
+    }
+
     fn eat_trivia(&mut self) {
         while self.at_trivia() {
             self.cursor += 1;
@@ -62,14 +81,14 @@ impl<'source, 'tokens> Source<'source, 'tokens> {
     }
 
     fn at_trivia(&self) -> bool {
-        self.peek_kind_raw().map_or(false, SyntaxKind::is_trivia)
+        self.peek_kind_raw().is_some_and(SyntaxKind::is_trivia)
     }
 
     fn peek_kind_raw(&self) -> Option<SyntaxKind> {
         self.peek_token_raw().map(|Token { kind, .. }| *kind)
     }
 
-    fn peek_token_raw(&self) -> Option<&Token> {
+    fn peek_token_raw(&self) -> Option<&Token<'_>> {
         self.tokens.get(self.cursor)
     }
 }
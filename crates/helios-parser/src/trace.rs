@@ -0,0 +1,246 @@
+//! Recording the steps the parser takes, for debugging the grammar.
+//!
+//! Tracing is turned on by [`ParseOptions::trace`], or for every parse by
+//! setting the `HELIOS_PARSER_TRACE` environment variable to `1` (which
+//! prints each trace to the standard error as well). A [`Trace`] lists every
+//! node the parser starts, completes or abandons (by the [`Marker`] it uses
+//! for it), every token it adds to the tree (or inserts, when it's missing)
+//! and every time it doesn't find what it expects, in the order it does so.
+//! Replaying the events one by one shows where the grammar took a wrong turn,
+//! such as which rule claimed a token another one expected.
+//!
+//! [`ParseOptions::trace`]: crate::ParseOptions::trace
+//! [`Marker`]: crate::parser::marker::Marker
+
+use helios_syntax::SyntaxKind;
+use std::fmt::Write;
+use std::ops::Range;
+
+/// The environment variable that turns on tracing for every parse.
+pub const TRACE_VAR: &str = "HELIOS_PARSER_TRACE";
+
+/// Determines if every parse is traced (see [`TRACE_VAR`]).
+pub(crate) fn is_enabled_by_env() -> bool {
+    std::env::var_os(TRACE_VAR).is_some_and(|value| value == "1")
+}
+
+/// A step of the parser, where markers are numbered by the position of their
+/// node in the parser's events (which increases in the order they're
+/// started).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TraceEvent {
+    /// A marker was started before the token at the given offset.
+    Start { marker: usize, offset: usize },
+    /// A marker was started before the node of a completed marker, which
+    /// becomes its first child (such as the left operand of a binary
+    /// expression).
+    Precede { marker: usize, child: usize },
+    /// The node of a marker was completed with the given kind.
+    Complete { marker: usize, kind: SyntaxKind },
+    /// A marker was discarded without making a node.
+    Abandon { marker: usize },
+    /// A token was added to the current node.
+    Bump {
+        kind: SyntaxKind,
+        range: Range<usize>,
+    },
+    /// A missing token was added to the current node, before the token at
+    /// the given offset.
+    Insert { kind: SyntaxKind, offset: usize },
+    /// The next token (or the end of the source) wasn't one of the kinds the
+    /// parser expected.
+    Expected {
+        expected: Vec<SyntaxKind>,
+        given: Option<SyntaxKind>,
+        range: Range<usize>,
+    },
+}
+
+impl TraceEvent {
+    /// The range of the source that the event is about, if any.
+    fn range(&self) -> Option<Range<usize>> {
+        match self {
+            Self::Start { offset, .. } | Self::Insert { offset, .. } => {
+                Some(*offset..*offset)
+            }
+            Self::Bump { range, .. } | Self::Expected { range, .. } => {
+                Some(range.clone())
+            }
+            _ => None,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Self::Start { marker, .. } => format!("start #{}", marker),
+            Self::Precede { marker, child } => {
+                format!("start #{} before #{}", marker, child)
+            }
+            Self::Complete { marker, kind } => {
+                format!("complete #{} as {:?}", marker, kind)
+            }
+            Self::Abandon { marker } => format!("abandon #{}", marker),
+            Self::Bump { kind, .. } => format!("bump {:?}", kind),
+            Self::Insert { kind, .. } => format!("insert {:?}", kind),
+            Self::Expected {
+                expected, given, ..
+            } => {
+                let expected = expected
+                    .iter()
+                    .map(|kind| format!("{:?}", kind))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                match given {
+                    Some(given) => {
+                        format!("expected {} but found {:?}", expected, given)
+                    }
+                    None => format!("expected {} but found the end", expected),
+                }
+            }
+        }
+    }
+}
+
+/// The steps taken by the parser while parsing a source text, in order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Trace {
+    events: Vec<TraceEvent>,
+}
+
+impl Trace {
+    pub fn events(&self) -> &[TraceEvent] {
+        &self.events
+    }
+
+    pub(crate) fn push(&mut self, event: TraceEvent) {
+        self.events.push(event);
+    }
+
+    /// Renders the trace of the given source as a table of the events,
+    /// indented by how many nodes are open, under the lines of the source they
+    /// are about. The ranges of the source are marked in the left column,
+    /// right below the line they're in.
+    pub fn render(&self, source: &str) -> String {
+        // Tabs are shown as spaces, so that the marks line up with the text
+        let lines = source
+            .split('\n')
+            .map(|line| line.trim_end_matches('\r').replace('\t', " "))
+            .collect::<Vec<_>>();
+        let width = lines
+            .iter()
+            .map(|line| line.chars().count() + 1)
+            .max()
+            .unwrap_or_default();
+
+        let mut rendered = String::new();
+        let mut current_line = None;
+        let mut depth = 0usize;
+
+        for (step, event) in self.events.iter().enumerate() {
+            let mut marks = String::new();
+            if let Some(range) = event.range() {
+                let (line, column) = line_col(source, range.start);
+                if current_line != Some(line) {
+                    let text = lines.get(line).map_or("", |line| line.as_str());
+                    writeln!(rendered, "{:<4} | {}", line + 1, text).unwrap();
+                    current_line = Some(line);
+                }
+
+                if !matches!(event, TraceEvent::Start { .. }) {
+                    let end = range.end.min(line_end(source, range.start));
+                    let len = source[range.start..end].chars().count();
+                    marks = " ".repeat(column) + &"^".repeat(len.max(1));
+                }
+            }
+
+            if let TraceEvent::Complete { .. } | TraceEvent::Abandon { .. } =
+                event
+            {
+                depth = depth.saturating_sub(1);
+            }
+
+            writeln!(
+                rendered,
+                "     | {:<width$} {:>4}  {}{}",
+                marks,
+                step,
+                "  ".repeat(depth),
+                event.describe(),
+                width = width
+            )
+            .unwrap();
+
+            if let TraceEvent::Start { .. } | TraceEvent::Precede { .. } = event
+            {
+                depth += 1;
+            }
+        }
+
+        rendered
+    }
+}
+
+/// The line and column (in characters, counting from `0`) of the given
+/// offset of the source.
+fn line_col(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset];
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    (line, before[line_start..].chars().count())
+}
+
+/// The offset of the end of the line containing the given offset (before its
+/// line break).
+fn line_end(source: &str, offset: usize) -> usize {
+    source[offset..]
+        .find('\n')
+        .map_or(source.len(), |index| offset + index)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ParseOptions;
+    use expect_test::expect;
+
+    #[test]
+    fn test_render_trace() {
+        let source = "let a = 1 +\nlet b";
+        let options = ParseOptions {
+            trace: true,
+            ..ParseOptions::default()
+        };
+        let parse = crate::parse_with_options(0usize, source, options);
+
+        let trace = parse.trace().expect("The parse wasn't traced");
+        expect![[r##"
+            1    | let a = 1 +
+                 |                 0  start #0
+                 |                 1    start #1
+                 | ^^^             2      bump Kwd_Let
+                 |     ^           3      bump Identifier
+                 |       ^         4      bump Sym_Eq
+                 |                 5      start #5
+                 |         ^       6        bump Lit_Integer
+                 |                 7      complete #5 as Exp_Literal
+                 |           ^     8      bump Sym_Plus
+                 |                 9      start #9 before #5
+            2    | let b
+                 |                10        start #10
+                 |                11        complete #10 as Exp_Missing
+                 | ^^^            12        expected Lit_Character, Lit_Float, Lit_Integer, Lit_String, Lit_StringStart, Identifier, Kwd_Return, Sym_BackSlash, Sym_LParen, Sym_LBrace, Lay_Begin, Sym_Minus, Sym_Bang but found Kwd_Let
+                 |                13      complete #9 as Exp_Binary
+                 |                14    complete #1 as Dec_GlobalBinding
+                 |                15    start #14
+                 | ^^^            16      bump Kwd_Let
+                 |     ^          17      bump Identifier
+                 |     ^          18      expected Sym_Eq but found the end
+                 |      ^         19      insert Sym_Eq
+                 |                20      start #18
+                 |                21      complete #18 as Exp_Missing
+                 |     ^          22      expected Lit_Character, Lit_Float, Lit_Integer, Lit_String, Lit_StringStart, Identifier, Kwd_Return, Sym_BackSlash, Sym_LParen, Sym_LBrace, Lay_Begin, Sym_Minus, Sym_Bang but found the end
+                 |                23    complete #14 as Dec_GlobalBinding
+                 |                24  complete #0 as Root
+        "##]]
+        .assert_eq(&trace.render(source));
+    }
+}
@@ -0,0 +1,768 @@
+//! Validating the syntax tree produced by the parser.
+//!
+//! The grammar is intentionally more permissive than the language itself so
+//! that the parser can recover from as many errors as possible. This module
+//! walks the finished syntax tree and reports the structural issues that the
+//! grammar permits but the language forbids (such as number literals that are
+//! out of range, invalid escape sequences, assignments to something other
+//! than a mutable variable, `return`s outside of functions or global bindings
+//! that are defined more than once), as well as identifiers that could be
+//! mistaken for others.
+
+use crate::identifier::{self, normalize_identifier};
+use crate::literal::{self, Literal, LiteralError};
+use crate::message::{Message, ValidationMessage};
+use helios_diagnostics::Location;
+use helios_syntax::{SyntaxKind, SyntaxNode, SyntaxToken};
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Validates the syntax tree with the given root node, returning a list of
+/// [`Message`]s describing every issue found.
+pub(crate) fn validate<FileId>(
+    file_id: FileId,
+    root: &SyntaxNode,
+) -> Vec<Message<FileId>>
+where
+    FileId: Clone + Default,
+{
+    let mut validator = Validator {
+        file_id,
+        messages: Vec::new(),
+        globals: HashMap::new(),
+    };
+
+    validator.global_bindings(root);
+    validator.identifiers(root);
+
+    for node in root.descendants() {
+        match node.kind() {
+            SyntaxKind::Exp_Literal => validator.literal(&node),
+            SyntaxKind::Exp_Interpolation => validator.interpolation(&node),
+            SyntaxKind::Exp_Assign => validator.assignment(&node),
+            SyntaxKind::Exp_Return => validator.return_expr(&node),
+            _ => {}
+        }
+    }
+
+    validator.unmutated_globals();
+
+    validator.messages
+}
+
+struct Validator<FileId> {
+    file_id: FileId,
+    messages: Vec<Message<FileId>>,
+    /// The global bindings of the file by (normalized) name, as found by
+    /// [`Validator::global_bindings`].
+    globals: HashMap<String, Global>,
+}
+
+/// The first definition of a global binding.
+#[derive(Clone)]
+struct Global {
+    /// The range of the binding's name.
+    range: Range<usize>,
+    /// The range of the `let` or `var` keyword defining the binding.
+    keyword: Range<usize>,
+    /// Whether the binding is defined with `var` rather than `let`.
+    is_mutable: bool,
+    is_public: bool,
+    /// Whether the binding is assigned to anywhere in the file.
+    is_mutated: bool,
+}
+
+impl<FileId> Validator<FileId>
+where
+    FileId: Clone + Default,
+{
+    fn literal(&mut self, node: &SyntaxNode) {
+        let literal = match Literal::cast(node.clone()) {
+            Some(literal) => literal,
+            None => return,
+        };
+
+        let token = literal.token();
+        let message = match literal.value() {
+            // The lexer has already reported unterminated literals
+            Ok(_) | Err(LiteralError::Unterminated) => return,
+            Err(LiteralError::OutOfRange) => {
+                ValidationMessage::NumberOutOfRange {
+                    kind: token.kind(),
+                    text: token.text().to_string(),
+                }
+            }
+            Err(LiteralError::InvalidDigit { radix }) => {
+                ValidationMessage::InvalidDigit {
+                    text: token.text().to_string(),
+                    radix,
+                }
+            }
+            Err(LiteralError::InvalidEscape(range)) => {
+                return self.invalid_escape(&token, range)
+            }
+            Err(LiteralError::CharacterLength(length)) => {
+                ValidationMessage::InvalidCharacterLength {
+                    text: token.text().to_string(),
+                    length,
+                }
+            }
+        };
+
+        self.push(message, token_range(&token));
+    }
+
+    /// Reports the invalid escape sequences of the fragments of a string with
+    /// interpolations (the first one of each fragment).
+    fn interpolation(&mut self, node: &SyntaxNode) {
+        let fragments = node
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .filter(|token| {
+                matches!(
+                    token.kind(),
+                    SyntaxKind::Lit_StringStart
+                        | SyntaxKind::Lit_StringMiddle
+                        | SyntaxKind::Lit_StringEnd
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let multiline = fragments
+            .first()
+            .is_some_and(|start| start.text().starts_with(r#"""""#));
+
+        for token in fragments {
+            let fragment =
+                literal::string_fragment(token.kind(), token.text(), multiline);
+            if let Err(LiteralError::InvalidEscape(range)) = fragment {
+                self.invalid_escape(&token, range);
+            }
+        }
+    }
+
+    /// Reports the invalid escape sequence at the given range of the given
+    /// token.
+    fn invalid_escape(&mut self, token: &SyntaxToken, range: Range<usize>) {
+        let message = ValidationMessage::InvalidEscape {
+            text: token.text()[range.clone()].to_string(),
+        };
+
+        let start = token_range(token).start;
+        self.push(message, start + range.start..start + range.end);
+    }
+
+    /// Reports assignments to something other than a variable, and to
+    /// variables that can't change (lambda parameters and global bindings
+    /// defined with `let`).
+    fn assignment(&mut self, node: &SyntaxNode) {
+        let operator = match first_token(node) {
+            Some(operator) => operator,
+            None => return,
+        };
+
+        // If the operator came first, the parser has already reported the
+        // missing left-hand side
+        let target = match node.first_child() {
+            Some(target)
+                if target.text_range().end()
+                    <= operator.text_range().start() =>
+            {
+                target
+            }
+            _ => return,
+        };
+
+        if !is_assignable(&target) {
+            let message = ValidationMessage::InvalidAssignmentTarget {
+                target: target.kind(),
+            };
+
+            return self.push(message, node_range(&target));
+        }
+
+        let name = match variable_name(&target) {
+            Some(name) => name,
+            None => return,
+        };
+        let name = normalize_identifier(name.text()).into_owned();
+
+        let message = if let Some(parameter) = parameter(&target, &name) {
+            ValidationMessage::AssignmentToImmutable {
+                name,
+                definition: token_range(&parameter),
+                is_parameter: true,
+            }
+        } else {
+            match self.globals.get_mut(&name) {
+                Some(global) if !global.is_mutable => {
+                    ValidationMessage::AssignmentToImmutable {
+                        name,
+                        definition: global.range.clone(),
+                        is_parameter: false,
+                    }
+                }
+                Some(global) => {
+                    global.is_mutated = true;
+                    return;
+                }
+                // Defined in another module
+                None => return,
+            }
+        };
+
+        self.push(message, node_range(&target));
+    }
+
+    /// Reports a `return` that isn't in the body of a function (there is no
+    /// function to return from).
+    fn return_expr(&mut self, node: &SyntaxNode) {
+        let is_in_function = node
+            .ancestors()
+            .any(|node| node.kind() == SyntaxKind::Exp_Lambda);
+
+        if !is_in_function {
+            let range = first_token(node).map_or_else(
+                || node_range(node),
+                |keyword| token_range(&keyword),
+            );
+
+            self.push(ValidationMessage::ReturnOutsideFunction, range);
+        }
+    }
+
+    /// Records the global bindings of the file, reporting every one with the
+    /// same name as a previous one.
+    fn global_bindings(&mut self, root: &SyntaxNode) {
+        let bindings = root
+            .children()
+            .filter(|node| node.kind() == SyntaxKind::Dec_GlobalBinding);
+
+        for node in bindings {
+            let tokens = node
+                .children_with_tokens()
+                .filter_map(|it| it.into_token())
+                .collect::<Vec<_>>();
+
+            let name = match tokens
+                .iter()
+                .find(|token| token.kind() == SyntaxKind::Identifier)
+            {
+                Some(name) => name,
+                None => continue,
+            };
+
+            let range = token_range(name);
+            let name = normalize_identifier(name.text()).into_owned();
+            match self.globals.get(&name) {
+                Some(first) => {
+                    let message = ValidationMessage::DuplicateDefinition {
+                        name,
+                        first: first.range.clone(),
+                    };
+
+                    self.push(message, range);
+                }
+                None => {
+                    let keyword = match tokens.iter().find(|token| {
+                        matches!(
+                            token.kind(),
+                            SyntaxKind::Kwd_Let | SyntaxKind::Kwd_Var
+                        )
+                    }) {
+                        Some(keyword) => keyword,
+                        None => continue,
+                    };
+                    let is_mutable = keyword.kind() == SyntaxKind::Kwd_Var;
+                    let is_public = node
+                        .children()
+                        .any(|node| node.kind() == SyntaxKind::Visibility);
+
+                    let global = Global {
+                        range,
+                        keyword: token_range(keyword),
+                        is_mutable,
+                        is_public,
+                        is_mutated: false,
+                    };
+                    self.globals.insert(name, global);
+                }
+            }
+        }
+    }
+
+    /// Reports the identifiers that mix letters of scripts which look alike,
+    /// and those that look like a different identifier used before them.
+    ///
+    /// Each identifier is only reported where it is first used.
+    fn identifiers(&mut self, root: &SyntaxNode) {
+        let mut seen = HashMap::new();
+        let mut skeletons = HashMap::new();
+
+        let identifiers = root
+            .descendants_with_tokens()
+            .filter_map(|it| it.into_token())
+            .filter(|token| token.kind() == SyntaxKind::Identifier);
+
+        for token in identifiers {
+            let name = normalize_identifier(token.text()).into_owned();
+            if seen.contains_key(&name) {
+                continue;
+            }
+
+            let range = token_range(&token);
+            seen.insert(name.clone(), range.clone());
+
+            if let Some(scripts) = identifier::mixed_scripts(&name) {
+                let message = ValidationMessage::MixedScriptIdentifier {
+                    name: name.clone(),
+                    scripts,
+                };
+
+                self.push(message, range.clone());
+            }
+
+            match skeletons.get(&identifier::skeleton(&name)) {
+                Some(other) => {
+                    let message = ValidationMessage::ConfusableIdentifier {
+                        name,
+                        other: String::clone(other),
+                        first: Range::clone(&seen[other]),
+                    };
+
+                    self.push(message, range);
+                }
+                None => {
+                    skeletons.insert(identifier::skeleton(&name), name);
+                }
+            }
+        }
+    }
+
+    /// Reports the private global bindings defined with `var` that are never
+    /// assigned to (once every assignment has been seen).
+    ///
+    /// Public ones may be assigned to by the modules importing them, so they
+    /// aren't reported.
+    fn unmutated_globals(&mut self) {
+        let mut unmutated = self
+            .globals
+            .iter()
+            .filter(|(_, global)| {
+                global.is_mutable && !global.is_public && !global.is_mutated
+            })
+            .map(|(name, global)| (name.clone(), global.clone()))
+            .collect::<Vec<_>>();
+        unmutated.sort_by_key(|(_, global)| global.range.start);
+
+        for (name, global) in unmutated {
+            let message = ValidationMessage::NeverMutated {
+                name,
+                keyword: global.keyword,
+            };
+
+            self.push(message, global.range);
+        }
+    }
+
+    fn push(&mut self, message: ValidationMessage, range: Range<usize>) {
+        let location = Location::new(self.file_id.clone(), range);
+        self.messages.push(Message::new(message, location));
+    }
+}
+
+/// Determines if the given expression may be assigned to. Only variables (which
+/// may be surrounded by parentheses) are assignable.
+fn is_assignable(node: &SyntaxNode) -> bool {
+    match node.kind() {
+        SyntaxKind::Exp_VariableRef => true,
+        SyntaxKind::Exp_Paren => node
+            .first_child()
+            .is_some_and(|inner| is_assignable(&inner)),
+        // The parser has already reported this
+        SyntaxKind::Error => true,
+        _ => false,
+    }
+}
+
+/// The name of the variable the given (assignable) expression refers to.
+fn variable_name(node: &SyntaxNode) -> Option<SyntaxToken> {
+    match node.kind() {
+        SyntaxKind::Exp_VariableRef => first_token(node),
+        SyntaxKind::Exp_Paren => variable_name(&node.first_child()?),
+        _ => None,
+    }
+}
+
+/// The parameter with the given name of the innermost lambda enclosing the
+/// given node, if any.
+fn parameter(node: &SyntaxNode, name: &str) -> Option<SyntaxToken> {
+    node.ancestors()
+        .filter(|node| node.kind() == SyntaxKind::Exp_Lambda)
+        .filter_map(|lambda| {
+            lambda
+                .children()
+                .find(|node| node.kind() == SyntaxKind::ParamList)
+        })
+        .find_map(|params| {
+            params
+                .children_with_tokens()
+                .filter_map(|it| it.into_token())
+                .find(|token| {
+                    token.kind() == SyntaxKind::Identifier
+                        && normalize_identifier(token.text()) == name
+                })
+        })
+}
+
+/// The first non-trivia token that is a direct child of the given node.
+fn first_token(node: &SyntaxNode) -> Option<SyntaxToken> {
+    node.children_with_tokens()
+        .filter_map(|it| it.into_token())
+        .find(|token| !token.kind().is_trivia())
+}
+
+fn token_range(token: &SyntaxToken) -> Range<usize> {
+    let range = token.text_range();
+    range.start().into()..range.end().into()
+}
+
+/// The range of the given node, excluding any leading or trailing trivia.
+fn node_range(node: &SyntaxNode) -> Range<usize> {
+    let mut tokens = node
+        .descendants_with_tokens()
+        .filter_map(|it| it.into_token())
+        .filter(|token| !token.kind().is_trivia());
+
+    match tokens.next() {
+        Some(first) => {
+            let last = tokens.last().unwrap_or_else(|| first.clone());
+            token_range(&first).start..token_range(&last).end
+        }
+        None => {
+            let range = node.text_range();
+            range.start().into()..range.end().into()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, Script};
+
+    fn check(input: &str, expected: Vec<(ValidationMessage, Range<usize>)>) {
+        let root = parse(0u8, input).syntax();
+        let expected = expected
+            .into_iter()
+            .map(|(message, range)| {
+                Message::new(message, Location::new(0u8, range))
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(validate(0u8, &root), expected);
+    }
+
+    #[test]
+    fn test_validate_integer_literals() {
+        check("9_223_372_036_854_775_807 + 0x7fff_ffff_ffff_ffff", vec![]);
+        check("-9223372036854775808", vec![]);
+        check(
+            "9223372036854775808",
+            vec![(
+                ValidationMessage::NumberOutOfRange {
+                    kind: SyntaxKind::Lit_Integer,
+                    text: "9223372036854775808".to_string(),
+                },
+                0..19,
+            )],
+        );
+        check(
+            "1 + 0b102",
+            vec![(
+                ValidationMessage::InvalidDigit {
+                    text: "0b102".to_string(),
+                    radix: 2,
+                },
+                4..9,
+            )],
+        );
+    }
+
+    #[test]
+    fn test_validate_float_literals() {
+        check("1.5 + 1_000.25", vec![]);
+
+        let text = format!("1{}.0", "0".repeat(309));
+        check(
+            &text,
+            vec![(
+                ValidationMessage::NumberOutOfRange {
+                    kind: SyntaxKind::Lit_Float,
+                    text: text.clone(),
+                },
+                0..text.len(),
+            )],
+        );
+    }
+
+    #[test]
+    fn test_validate_character_literals() {
+        check("'a' + '\\n' + '\\'' + '\\u{1F600}' + '😀' + '\\\\'", vec![]);
+        check("'\\u{0}' + '\\u{10FFFF}'", vec![]);
+
+        let invalid_escape = |text: &str, range| {
+            (
+                ValidationMessage::InvalidEscape {
+                    text: text.to_string(),
+                },
+                range,
+            )
+        };
+
+        check("'\\q'", vec![invalid_escape("\\q", 1..3)]);
+        check("'\\u{110000}'", vec![invalid_escape("\\u{110000}", 1..11)]);
+        check("'\\u{D800}'", vec![invalid_escape("\\u{D800}", 1..9)]);
+        check("'\\u{}'", vec![invalid_escape("\\u{}", 1..5)]);
+        check(
+            "'\\u{1234567}'",
+            vec![invalid_escape("\\u{1234567}", 1..12)],
+        );
+        check("'\\u{12'", vec![invalid_escape("\\u{12", 1..6)]);
+        check("'\\u1'", vec![invalid_escape("\\u", 1..3)]);
+
+        let invalid_length = |text: &str, length, range| {
+            (
+                ValidationMessage::InvalidCharacterLength {
+                    text: text.to_string(),
+                    length,
+                },
+                range,
+            )
+        };
+
+        check("''", vec![invalid_length("''", 0, 0..2)]);
+        check("x + 'ab'", vec![invalid_length("'ab'", 2, 4..8)]);
+        check("'\\n\\t'", vec![invalid_length("'\\n\\t'", 2, 0..6)]);
+
+        // Already reported by the lexer
+        check("'a", vec![]);
+        check("'\\'", vec![]);
+    }
+
+    #[test]
+    fn test_validate_string_literals() {
+        check(
+            "\"a\\n\\\"b\\\" \\{c}\" + r\"\\q\" + \"\"\"\\t\"\"\"",
+            vec![],
+        );
+
+        let invalid_escape = |text: &str, range| {
+            (
+                ValidationMessage::InvalidEscape {
+                    text: text.to_string(),
+                },
+                range,
+            )
+        };
+
+        check("\"a\\qb\"", vec![invalid_escape("\\q", 2..4)]);
+        check(
+            "\"\"\"\n\\u{D800}\"\"\"",
+            vec![invalid_escape("\\u{D800}", 4..12)],
+        );
+        check(
+            "\"\\q {a} \\{ {b} \\w\"",
+            vec![invalid_escape("\\q", 1..3), invalid_escape("\\w", 15..17)],
+        );
+
+        // Already reported by the lexer
+        check("\"abc", vec![]);
+    }
+
+    #[test]
+    fn test_validate_assignment_targets() {
+        check("a <- 1", vec![]);
+        check("(a) <- 1", vec![]);
+        check(
+            "a + 1 <- 2",
+            vec![(
+                ValidationMessage::InvalidAssignmentTarget {
+                    target: SyntaxKind::Exp_Binary,
+                },
+                0..5,
+            )],
+        );
+        check(
+            "a <- 1 <- 2",
+            vec![(
+                ValidationMessage::InvalidAssignmentTarget {
+                    target: SyntaxKind::Exp_Literal,
+                },
+                5..6,
+            )],
+        );
+    }
+
+    #[test]
+    fn test_validate_assignments_to_immutable_bindings() {
+        let immutable = |name: &str, definition, is_parameter, range| {
+            (
+                ValidationMessage::AssignmentToImmutable {
+                    name: name.to_string(),
+                    definition,
+                    is_parameter,
+                },
+                range,
+            )
+        };
+
+        check("var a = 1\nlet b = a <- 2", vec![]);
+        check(
+            "let f = \\a -> (a) <- 1",
+            vec![immutable("a", 9..10, true, 14..17)],
+        );
+        check(
+            "let a = 1\nlet b = \\x -> a <- x",
+            vec![immutable("a", 4..5, false, 24..25)],
+        );
+        check(
+            "var a = 1\nlet b = \\x a -> a <- x",
+            vec![
+                immutable("a", 21..22, true, 26..27),
+                (
+                    ValidationMessage::NeverMutated {
+                        name: "a".to_string(),
+                        keyword: 0..3,
+                    },
+                    4..5,
+                ),
+            ],
+        );
+
+        // Only the first definition of a name counts
+        check(
+            "let a = 1\nvar a = 2\nlet b = a <- 3",
+            vec![
+                (
+                    ValidationMessage::DuplicateDefinition {
+                        name: "a".to_string(),
+                        first: 4..5,
+                    },
+                    14..15,
+                ),
+                immutable("a", 4..5, false, 28..29),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_validate_unmutated_bindings() {
+        let never_mutated = |name: &str, keyword, range| {
+            let name = name.to_string();
+            (ValidationMessage::NeverMutated { name, keyword }, range)
+        };
+
+        check("var a = 1\nlet f = \\x -> a <- x", vec![]);
+        check("public var a = 1", vec![]);
+        check(
+            "var b = 1\nvar a = 2\nlet c = a + b",
+            vec![
+                never_mutated("b", 0..3, 4..5),
+                never_mutated("a", 10..13, 14..15),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_validate_returns() {
+        check("let f = \\x -> return x", vec![]);
+        check("let f = \\x -> { return; x }", vec![]);
+        check(
+            "let a = return 1",
+            vec![(ValidationMessage::ReturnOutsideFunction, 8..14)],
+        );
+        check(
+            "let a = 1; return",
+            vec![(ValidationMessage::ReturnOutsideFunction, 11..17)],
+        );
+    }
+
+    #[test]
+    fn test_validate_duplicate_definitions() {
+        check("let a = 1\nlet b = a", vec![]);
+        check(
+            "let a = 1\nlet a = 2\nlet a = 3",
+            vec![
+                (
+                    ValidationMessage::DuplicateDefinition {
+                        name: "a".to_string(),
+                        first: 4..5,
+                    },
+                    14..15,
+                ),
+                (
+                    ValidationMessage::DuplicateDefinition {
+                        name: "a".to_string(),
+                        first: 4..5,
+                    },
+                    24..25,
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_validate_normalized_duplicate_definitions() {
+        check(
+            "let \u{e9} = 1\nlet e\u{301} = 2",
+            vec![(
+                ValidationMessage::DuplicateDefinition {
+                    name: "\u{e9}".to_string(),
+                    first: 4..6,
+                },
+                15..18,
+            )],
+        );
+    }
+
+    #[test]
+    fn test_validate_identifiers() {
+        check("let человек = 1\nlet people = человек", vec![]);
+
+        // A Cyrillic `а` in the middle of a Latin name
+        check(
+            "let pass = 1\nlet b = p\u{430}ss",
+            vec![
+                (
+                    ValidationMessage::MixedScriptIdentifier {
+                        name: "p\u{430}ss".to_string(),
+                        scripts: [Script::Latin, Script::Cyrillic],
+                    },
+                    21..26,
+                ),
+                (
+                    ValidationMessage::ConfusableIdentifier {
+                        name: "p\u{430}ss".to_string(),
+                        other: "pass".to_string(),
+                        first: 4..8,
+                    },
+                    21..26,
+                ),
+            ],
+        );
+
+        // Only reported once, where the name is first used
+        check(
+            "let \u{441}\u{43E}\u{440} = 1\nlet cop = \u{441}\u{43E}\u{440}",
+            vec![(
+                ValidationMessage::ConfusableIdentifier {
+                    name: "cop".to_string(),
+                    other: "\u{441}\u{43E}\u{440}".to_string(),
+                    first: 4..10,
+                },
+                19..22,
+            )],
+        );
+    }
+}
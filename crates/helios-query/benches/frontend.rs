@@ -0,0 +1,87 @@
+//! Benchmarks for the front end of the compiler: lexing, parsing, reparsing
+//! after an edit and converting offsets into positions.
+//!
+//! Run them with `cargo bench -p helios-query`. Every benchmark reports the
+//! mean and the fastest time of a number of runs, after an untimed warm-up
+//! run.
+
+use helios_query::{FileId, HeliosDatabase, Input, InputLocation};
+use helios_text::Rope;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const FILE_ID: FileId = FileId(0);
+
+/// A few lines of Helios source, repeated to generate large inputs.
+const CHUNK: &str = "\
+--- Computes something important.
+let value = \\x y -> (x + y) * 2 - -x
+import Foo.Bar
+public type Shape =
+  | Circle
+  | Square
+external function print(message: String) -> Unit
+let answer = 42 -- The answer
+";
+
+/// Generates a source text of at least the given length (in bytes).
+fn generate_source(len: usize) -> String {
+    CHUNK.repeat(len.div_ceil(CHUNK.len()))
+}
+
+/// Runs `f` the given number of times and prints how long it took.
+fn bench<T>(name: &str, runs: u32, mut f: impl FnMut() -> T) {
+    black_box(f());
+
+    let mut total = Duration::ZERO;
+    let mut fastest = Duration::MAX;
+    for _ in 0..runs {
+        let start = Instant::now();
+        black_box(f());
+        let elapsed = start.elapsed();
+
+        total += elapsed;
+        fastest = fastest.min(elapsed);
+    }
+
+    println!(
+        "{:<32} mean {:>12?}   fastest {:>12?}   ({} runs)",
+        name,
+        total / runs,
+        fastest,
+        runs
+    );
+}
+
+fn main() {
+    let source = generate_source(1024 * 1024);
+
+    bench("lex 1MB", 20, || helios_parser::tokenize(FILE_ID, &source));
+    bench("parse 1MB", 10, || helios_parser::parse(FILE_ID, &source));
+
+    // Every edit changes the source (alternating between two digits), so
+    // that the database has to parse the file again
+    let mut db = HeliosDatabase::default();
+    db.set_source(FILE_ID, Rope::from(&source));
+    let edit_offset = source.find("42").unwrap();
+    let mut edited = source.clone().into_bytes();
+    let mut digit = b'4';
+    bench("reparse 1MB after 1-char edit", 10, || {
+        digit = if digit == b'4' { b'5' } else { b'4' };
+        edited[edit_offset] = digit;
+        let text = String::from_utf8(edited.clone()).unwrap();
+        db.set_source(FILE_ID, Rope::from(text));
+        db.parse(FILE_ID)
+    });
+
+    // The database memoizes every query after the warm-up run, so this
+    // measures the cost of a lookup through the database
+    let offsets = (0..source.len()).step_by(97).collect::<Vec<_>>();
+    bench("source_position_at_offset x10k", 20, || {
+        offsets
+            .iter()
+            .take(10_000)
+            .map(|&offset| db.source_position_at_offset(FILE_ID, offset).line)
+            .sum::<usize>()
+    });
+}
@@ -0,0 +1,233 @@
+use crate::cancel::{check_canceled, Cancelable};
+use crate::*;
+use helios_diagnostics::Diagnostic;
+use helios_parser::Parse;
+use helios_text::{LineCol, LineIndex, Rope};
+use salsa::{Durability, ParallelDatabase};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// The owner of the database, through which every change to its inputs goes.
+///
+/// The database is only read through [`Analysis`] snapshots, which can be
+/// sent to other threads. Changing an input while snapshots are alive waits
+/// for them to be dropped, so the reads of a snapshot fail with
+/// [`Cancelled`](crate::Cancelled) as soon as a change is waiting (instead
+/// of blocking the change until they are done).
+#[derive(Debug, Default)]
+pub struct AnalysisHost {
+    db: HeliosDatabase,
+}
+
+impl AnalysisHost {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A snapshot of the current state of the database.
+    pub fn analysis(&self) -> Analysis {
+        Analysis {
+            db: self.db.snapshot(),
+        }
+    }
+
+    /// The [`FileId`] of the file at the given path.
+    pub fn file_id(&self, path: PathBuf) -> FileId {
+        self.db.intern_file_path(path)
+    }
+
+    /// The path of the file with the given [`FileId`].
+    pub fn file_path(&self, file_id: FileId) -> PathBuf {
+        self.db.lookup_intern_file_path(file_id)
+    }
+
+    /// Sets the source text of a file, where the durability tells how often
+    /// it is expected to change (see [`Durability`]).
+    pub fn set_source(
+        &mut self,
+        file_id: FileId,
+        source: Rope,
+        durability: Durability,
+    ) {
+        self.db
+            .set_source_with_durability(file_id, source, durability);
+    }
+
+    /// Sets the experimental features every file is parsed with, if they
+    /// aren't the ones already set (since a change reparses every file).
+    pub fn set_features(&mut self, features: Features) {
+        if self.db.features() != features {
+            self.db
+                .set_features_with_durability(features, Durability::HIGH);
+        }
+    }
+
+    /// The database itself, for reading it on the thread that changes it
+    /// (where reads can't be cancelled).
+    pub fn raw_database(&self) -> &HeliosDatabase {
+        &self.db
+    }
+}
+
+/// A read-only snapshot of the database, taken by [`AnalysisHost::analysis`].
+///
+/// Every read fails with [`Cancelled`](crate::Cancelled) if the host is
+/// waiting to change the database, since its result would be outdated.
+#[derive(Debug)]
+pub struct Analysis {
+    db: salsa::Snapshot<HeliosDatabase>,
+}
+
+impl Analysis {
+    /// Reads the database with the given function, unless the snapshot is
+    /// outdated.
+    pub fn with_db<T>(
+        &self,
+        f: impl FnOnce(&HeliosDatabase) -> T,
+    ) -> Cancelable<T> {
+        check_canceled(&*self.db)?;
+        Ok(f(&self.db))
+    }
+
+    /// The [`FileId`] of the file at the given path (which is never
+    /// cancelled, since interning a path doesn't read any input).
+    pub fn file_id(&self, path: PathBuf) -> FileId {
+        self.db.intern_file_path(path)
+    }
+
+    /// The path of the file with the given [`FileId`].
+    pub fn file_path(&self, file_id: FileId) -> PathBuf {
+        self.db.lookup_intern_file_path(file_id)
+    }
+
+    pub fn source(&self, file_id: FileId) -> Cancelable<Rope> {
+        self.with_db(|db| db.source(file_id))
+    }
+
+    pub fn source_lines(&self, file_id: FileId) -> Cancelable<Arc<LineIndex>> {
+        self.with_db(|db| db.source_lines(file_id))
+    }
+
+    /// The line and column of the given offset in a file.
+    pub fn position_at_offset(
+        &self,
+        file_id: FileId,
+        offset: usize,
+    ) -> Cancelable<LineCol> {
+        self.with_db(|db| db.source_position_at_offset(file_id, offset))
+    }
+
+    pub fn parse(&self, file_id: FileId) -> Cancelable<Parse<FileId>> {
+        self.with_db(|db| db.parse(file_id))
+    }
+
+    pub fn diagnostics(
+        &self,
+        file_id: FileId,
+    ) -> Cancelable<Arc<Vec<Diagnostic<FileId>>>> {
+        self.with_db(|db| db.diagnostics(file_id))
+    }
+
+    pub fn items(&self, file_id: FileId) -> Cancelable<Arc<Vec<Item>>> {
+        self.with_db(|db| db.items(file_id))
+    }
+
+    pub fn references(
+        &self,
+        file_id: FileId,
+    ) -> Cancelable<Arc<Vec<Reference>>> {
+        self.with_db(|db| db.references(file_id))
+    }
+
+    pub fn calls(&self, file_id: FileId) -> Cancelable<Arc<Vec<Call>>> {
+        self.with_db(|db| db.calls(file_id))
+    }
+
+    /// The item named by the identifier at the given offset of a file (see
+    /// [`item_at_offset`](crate::item_at_offset)).
+    pub fn item_at_offset(
+        &self,
+        file_id: FileId,
+        offset: usize,
+    ) -> Cancelable<Option<Item>> {
+        self.with_db(|db| item_at_offset(db, file_id, offset))
+    }
+
+    /// The identifier at the given offset of a file (see
+    /// [`identifier_at_offset`](crate::identifier_at_offset)).
+    pub fn identifier_at_offset(
+        &self,
+        file_id: FileId,
+        offset: usize,
+    ) -> Cancelable<Option<String>> {
+        self.with_db(|db| identifier_at_offset(db, file_id, offset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analysis_snapshots() {
+        let mut host = AnalysisHost::new();
+        let file_id = host.file_id(PathBuf::from("Main.he"));
+        let source = Rope::from("let a = 1");
+        host.set_source(file_id, source, Durability::LOW);
+
+        let analysis = host.analysis();
+        let items = analysis.items(file_id).unwrap();
+        assert_eq!(items[0].name, "a");
+        assert_eq!(analysis.file_path(file_id), PathBuf::from("Main.he"));
+
+        // Snapshots can be read on other threads
+        let thread = std::thread::spawn(move || {
+            let offset = analysis.source(file_id).unwrap().len();
+            analysis.position_at_offset(file_id, offset).unwrap()
+        });
+        assert_eq!(thread.join().unwrap(), LineCol::new(0, 9));
+
+        // The snapshots taken later see the changes
+        let source = Rope::from("let b = 1");
+        host.set_source(file_id, source, Durability::LOW);
+        let items = host.analysis().items(file_id).unwrap();
+        assert_eq!(items[0].name, "b");
+    }
+
+    #[test]
+    fn test_analysis_is_cancelled_by_changes() {
+        let mut host = AnalysisHost::new();
+        let file_id = host.file_id(PathBuf::from("Main.he"));
+        let source = Rope::from("let a = 1");
+        host.set_source(file_id, source, Durability::LOW);
+
+        // The change waits for the snapshot to be dropped, which it is once
+        // its reads are cancelled
+        let analysis = host.analysis();
+        let thread = std::thread::spawn(move || {
+            while analysis.parse(file_id).is_ok() {
+                std::thread::yield_now();
+            }
+        });
+
+        let source = Rope::from("let b = 1");
+        host.set_source(file_id, source, Durability::LOW);
+        thread.join().unwrap();
+        assert_eq!(host.analysis().source(file_id).unwrap(), "let b = 1");
+    }
+
+    #[test]
+    fn test_analysis_with_features() {
+        let mut host = AnalysisHost::new();
+        let file_id = host.file_id(PathBuf::from("Main.he"));
+        let source = Rope::from("let a = f <| 1");
+        host.set_source(file_id, source, Durability::LOW);
+        assert!(host.analysis().parse(file_id).unwrap().has_errors());
+
+        let features = Features::new().with(Feature::BackwardPipelines);
+        host.set_features(features);
+        let analysis = host.analysis();
+        assert_eq!(analysis.with_db(|db| db.features()).unwrap(), features);
+        assert!(!analysis.parse(file_id).unwrap().has_errors());
+    }
+}
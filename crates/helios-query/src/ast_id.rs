@@ -0,0 +1,142 @@
+use crate::{FileId, Input};
+use helios_syntax::{SyntaxNode, SyntaxNodePtr};
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+#[salsa::query_group(AstIdDatabase)]
+pub trait AstIds: Input {
+    /// The ids of the declarations of a file (see [`AstIdMap`]).
+    fn ast_id_map(&self, file_id: FileId) -> Arc<AstIdMap>;
+}
+
+fn ast_id_map(db: &dyn AstIds, file_id: FileId) -> Arc<AstIdMap> {
+    let parse = db.parse(file_id);
+    db.profiler().time(file_id, "ast ids", || {
+        Arc::new(AstIdMap::from_root(&parse.syntax()))
+    })
+}
+
+/// The id of a declaration in a file, given by an [`AstIdMap`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FileAstId(u32);
+
+/// The id of a declaration in any file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AstId {
+    pub file_id: FileId,
+    pub id: FileAstId,
+}
+
+impl AstId {
+    /// The declaration in the latest syntax tree of its file.
+    pub fn to_node(&self, db: &dyn AstIds) -> SyntaxNode {
+        let ptr = db.ast_id_map(self.file_id).get(self.id);
+        ptr.to_node(&db.parse(self.file_id).syntax())
+    }
+}
+
+/// The ids of the declarations of a syntax tree, which stay the same when
+/// the file is edited without adding, removing or reordering declarations.
+///
+/// Unlike ranges (and the [`SyntaxNodePtr`]s made of them), which change
+/// whenever text is inserted before a declaration, the ids only depend on
+/// the order of the declarations: they are numbered breadth-first, so the
+/// top-level declarations come first and adding a member to an extension
+/// only shifts the ids of the members after it. The results of queries that
+/// refer to declarations by their ids aren't invalidated by most edits.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AstIdMap {
+    ptrs: Vec<SyntaxNodePtr>,
+}
+
+impl AstIdMap {
+    pub fn from_root(root: &SyntaxNode) -> Self {
+        let mut ptrs = Vec::new();
+        let mut queue = root.children().collect::<VecDeque<_>>();
+
+        while let Some(node) = queue.pop_front() {
+            if node.kind().is_declaration() {
+                ptrs.push(SyntaxNodePtr::new(&node));
+            }
+            queue.extend(node.children());
+        }
+
+        Self { ptrs }
+    }
+
+    /// The id of the given declaration, if it is a declaration of the tree
+    /// of this map.
+    pub fn ast_id(&self, node: &SyntaxNode) -> Option<FileAstId> {
+        let ptr = SyntaxNodePtr::new(node);
+        let index = self.ptrs.iter().position(|it| *it == ptr)?;
+        Some(FileAstId(index as u32))
+    }
+
+    /// The pointer to the declaration with the given id.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the id isn't an id of this map.
+    pub fn get(&self, id: FileAstId) -> SyntaxNodePtr {
+        self.ptrs[id.0 as usize]
+    }
+
+    /// The ids of every declaration, in order.
+    pub fn ids(&self) -> impl Iterator<Item = FileAstId> {
+        (0..self.ptrs.len() as u32).map(FileAstId)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HeliosDatabase;
+    use helios_syntax::SyntaxKind;
+    use helios_text::Rope;
+
+    const SOURCE: &str = "extend P with Show = { let show = 1 }\nlet a = 1\n";
+
+    fn first_line(node: &SyntaxNode) -> String {
+        node.text().to_string().lines().next().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_ast_ids_are_breadth_first() {
+        let mut db = HeliosDatabase::default();
+        db.set_source(FileId(0), Rope::from(SOURCE));
+
+        let map = db.ast_id_map(FileId(0));
+        let kinds = map.ids().map(|id| map.get(id).kind()).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                SyntaxKind::Dec_Extend,
+                SyntaxKind::Dec_GlobalBinding,
+                SyntaxKind::Dec_GlobalBinding,
+            ]
+        );
+
+        let root = db.parse(FileId(0)).syntax();
+        let binding = root.children().nth(1).unwrap();
+        assert_eq!(map.ast_id(&binding), map.ids().nth(1));
+        assert_eq!(map.ast_id(&root), None);
+    }
+
+    #[test]
+    fn test_ast_ids_are_stable_across_edits() {
+        let mut db = HeliosDatabase::default();
+        db.set_source(FileId(0), Rope::from(SOURCE));
+
+        let id = db.ast_id_map(FileId(0)).ids().nth(1).unwrap();
+        let ast_id = AstId {
+            file_id: FileId(0),
+            id,
+        };
+        assert_eq!(first_line(&ast_id.to_node(&db)), "let a = 1");
+
+        // The binding moves, but it keeps its id
+        let source = "extend P with Show = { let show = 1 + 2 }\nlet a = 2\n";
+        db.set_source(FileId(0), Rope::from(source));
+        assert_eq!(first_line(&ast_id.to_node(&db)), "let a = 2");
+    }
+}
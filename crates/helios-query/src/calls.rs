@@ -0,0 +1,132 @@
+use crate::items::is_local;
+use crate::{FileId, Item, Items};
+use helios_parser::normalize_identifier;
+use helios_syntax::{SyntaxKind, SyntaxNode};
+use std::ops::Range;
+use std::sync::Arc;
+
+#[salsa::query_group(CallsDatabase)]
+pub trait Calls: Items {
+    /// The calls made by the items of a file to the other items of the file,
+    /// in order.
+    fn calls(&self, file_id: FileId) -> Arc<Vec<Call>>;
+}
+
+fn calls(db: &dyn Calls, file_id: FileId) -> Arc<Vec<Call>> {
+    let parse = db.parse(file_id);
+    let items = db.items(file_id);
+    db.profiler().time(file_id, "calls", || {
+        Arc::new(calls_of(&parse.syntax(), &items))
+    })
+}
+
+/// A call from one item to another, where both items are given by their
+/// index in the [`items`] of the file.
+///
+/// [`items`]: crate::Items::items
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Call {
+    pub caller: usize,
+    pub callee: usize,
+    /// The range of the callee's name in the call.
+    pub range: Range<usize>,
+}
+
+/// Finds the calls to the given items in the given syntax tree.
+///
+/// Functions are called with the pipeline operator (`x |> f` calls `f` with
+/// `x`), so every pipeline whose right-hand side refers to an item is a call
+/// to that item. References to the parameters of a lambda are local, even if
+/// an item has the same name.
+pub fn calls_of(root: &SyntaxNode, items: &[Item]) -> Vec<Call> {
+    let mut calls = Vec::new();
+
+    for node in root.children() {
+        let start = usize::from(node.text_range().start());
+        let caller =
+            match items.iter().position(|item| item.range.start == start) {
+                Some(caller) => caller,
+                None => continue,
+            };
+
+        for pipeline in node.descendants().filter(is_pipeline) {
+            let callee = match pipeline.children().nth(1) {
+                Some(callee)
+                    if callee.kind() == SyntaxKind::Exp_VariableRef =>
+                {
+                    callee
+                }
+                _ => continue,
+            };
+
+            let token = match callee.first_token() {
+                Some(token) => token,
+                None => continue,
+            };
+
+            let name = normalize_identifier(token.text());
+            if is_local(&callee, &name) {
+                continue;
+            }
+
+            if let Some(index) = items.iter().position(|item| item.name == name)
+            {
+                let range = token.text_range();
+                calls.push(Call {
+                    caller,
+                    callee: index,
+                    range: range.start().into()..range.end().into(),
+                });
+            }
+        }
+    }
+
+    calls.sort_by_key(|call| call.range.start);
+    calls
+}
+
+fn is_pipeline(node: &SyntaxNode) -> bool {
+    node.kind() == SyntaxKind::Exp_Binary
+        && node
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .any(|token| token.kind() == SyntaxKind::Sym_PipeGt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HeliosDatabase, Input};
+    use helios_text::Rope;
+
+    #[test]
+    fn test_calls() {
+        let mut db = HeliosDatabase::default();
+        let source = "let inc = \\x -> x + 1\n\
+                      let twice = \\x -> x |> inc |> inc\n\
+                      let shadowed = \\inc -> 1 |> inc\n\
+                      let main = 1 |> twice |> missing\n";
+        db.set_source(FileId(0), Rope::from(source));
+
+        assert_eq!(
+            *db.calls(FileId(0)),
+            vec![
+                Call {
+                    caller: 1,
+                    callee: 0,
+                    range: 45..48,
+                },
+                Call {
+                    caller: 1,
+                    callee: 0,
+                    range: 52..55,
+                },
+                Call {
+                    caller: 3,
+                    callee: 1,
+                    range: 104..109,
+                },
+            ]
+        );
+    }
+}
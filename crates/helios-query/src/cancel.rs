@@ -1,7 +1,7 @@
+use crate::{Calls, FileId, InputLocation};
 use std::error::Error;
 use std::fmt::{self, Display};
 
-#[allow(dead_code)]
 pub type Cancelable<T> = Result<T, Cancelled>;
 
 #[derive(Debug)]
@@ -14,3 +14,67 @@ impl Display for Cancelled {
 }
 
 impl Error for Cancelled {}
+
+/// Fails with [`Cancelled`] if an input of the database is waiting to be
+/// changed (in which case the results computed now would be outdated).
+pub fn check_canceled(db: &dyn salsa::Database) -> Cancelable<()> {
+    if db.salsa_runtime().is_current_revision_canceled() {
+        Err(Cancelled)
+    } else {
+        Ok(())
+    }
+}
+
+/// Computes the queries of the given file that most requests need (such as
+/// hovers and completions), so that they are already up to date when the
+/// requests come in after an edit.
+///
+/// This is meant to be run on a snapshot of the database in the background,
+/// and gives up as soon as the database is about to change again (changing it
+/// waits for the snapshots to be dropped).
+pub fn revalidate<DB>(db: &DB, file_id: FileId) -> Cancelable<()>
+where
+    DB: Calls + InputLocation,
+{
+    check_canceled(db)?;
+    db.diagnostics(file_id);
+    check_canceled(db)?;
+    db.source_lines(file_id);
+    check_canceled(db)?;
+    db.references(file_id);
+    check_canceled(db)?;
+    db.calls(file_id);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile::Profile;
+    use crate::{HeliosDatabase, Input, Items};
+    use helios_text::Rope;
+
+    #[test]
+    fn test_revalidate() {
+        let mut db = HeliosDatabase::default();
+        db.set_source(FileId(0), Rope::from("let a = 1\nlet b = a"));
+        assert!(revalidate(&db, FileId(0)).is_ok());
+
+        // The queries requests need are already memoized
+        db.items(FileId(0));
+        db.references(FileId(0));
+        let runs = db.profiler().timings().into_iter();
+        let runs = runs.map(|(_, phase, timing)| (phase, timing.count));
+        assert_eq!(
+            runs.collect::<Vec<_>>(),
+            vec![
+                ("calls", 1),
+                ("diagnostics", 1),
+                ("items", 1),
+                ("lexing", 1),
+                ("parsing", 1),
+                ("references", 1),
+            ]
+        );
+    }
+}
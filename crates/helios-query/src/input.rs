@@ -1,26 +1,38 @@
 #![allow(unused)]
 
 use crate::interner::{BindingData, BindingId, Interner};
+use crate::profile::Profile;
 use helios_diagnostics::Diagnostic;
-use helios_parser::Parse;
+use helios_parser::{Features, Parse, ParseOptions};
+use helios_text::Rope;
 use std::sync::Arc;
 
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct FileId(pub u32);
 
 #[salsa::query_group(InputDatabase)]
-pub trait Input: Interner {
-    /// The source text of a file.
+pub trait Input: Interner + Profile {
+    /// The source text of a file, as a [`Rope`] (so that an edit doesn't copy
+    /// the whole text of a large file).
     #[salsa::input]
-    fn source(&self, file_id: FileId) -> Arc<String>;
+    fn source(&self, file_id: FileId) -> Rope;
+
+    /// The experimental features every file is parsed with, which are none
+    /// until they are set.
+    #[salsa::input]
+    fn features(&self) -> Features;
 
     /// The length of a file's source text.
     fn source_len(&self, file_id: FileId) -> usize;
 
-    /// The parsed syntax tree of the given file.
+    /// The parsed syntax tree of the given file, along with its diagnostics.
     fn parse(&self, file_id: FileId) -> Parse<FileId>;
 
-    /// Diagnostics emitted by the parser for a given file.
+    /// The diagnostics reported for a given file, sorted by location and
+    /// without duplicates or errors caused by a syntax error (see
+    /// [`DiagnosticSink`]).
+    ///
+    /// [`DiagnosticSink`]: helios_diagnostics::DiagnosticSink
     fn diagnostics(&self, file_id: FileId) -> Arc<Vec<Diagnostic<FileId>>>;
 }
 
@@ -30,8 +42,19 @@ fn source_len(db: &dyn Input, file_id: FileId) -> usize {
 }
 
 fn parse(db: &dyn Input, file_id: FileId) -> Parse<FileId> {
-    let source = db.source(file_id);
-    helios_parser::parse(file_id, &source)
+    // The lexer needs the text in one piece
+    let source = db.source(file_id).to_string();
+    let options = ParseOptions {
+        features: db.features(),
+        ..ParseOptions::default()
+    };
+    let (parse, timings) =
+        helios_parser::parse_with_timings(file_id, &source, options);
+
+    let profiler = db.profiler();
+    profiler.record(file_id, "lexing", timings.lexing);
+    profiler.record(file_id, "parsing", timings.parsing);
+    parse
 }
 
 fn diagnostics(
@@ -39,6 +62,7 @@ fn diagnostics(
     file_id: FileId,
 ) -> Arc<Vec<Diagnostic<FileId>>> {
     let parse = db.parse(file_id);
-    let messages = parse.messages();
-    Arc::new(messages.into_iter().map(|message| message.into()).collect())
+    db.profiler().time(file_id, "diagnostics", || {
+        Arc::new(parse.diagnostics().to_vec())
+    })
 }
@@ -1,7 +1,16 @@
+use crate::input::FileId;
+use std::path::PathBuf;
+
 #[salsa::query_group(InternerDatabase)]
 pub trait Interner: salsa::Database {
     #[salsa::interned]
     fn intern_binding(&self, binding: BindingData) -> BindingId;
+
+    /// The [`FileId`] of the file at the given path, which is the same every
+    /// time the same path is given (use `lookup_intern_file_path` to get the
+    /// path of a [`FileId`] back).
+    #[salsa::interned]
+    fn intern_file_path(&self, path: PathBuf) -> FileId;
 }
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
@@ -21,3 +30,13 @@ impl salsa::InternKey for BindingId {
         self.0
     }
 }
+
+impl salsa::InternKey for FileId {
+    fn from_intern_id(id: salsa::InternId) -> Self {
+        Self(id.as_u32())
+    }
+
+    fn as_intern_id(&self) -> salsa::InternId {
+        salsa::InternId::from(self.0)
+    }
+}
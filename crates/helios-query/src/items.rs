@@ -0,0 +1,378 @@
+use crate::{FileId, Input};
+use helios_parser::normalize_identifier;
+use helios_syntax::{SyntaxKind, SyntaxNode};
+use std::ops::Range;
+use std::sync::Arc;
+
+#[salsa::query_group(ItemsDatabase)]
+pub trait Items: Input {
+    /// The items declared at the top level of a file, in order.
+    fn items(&self, file_id: FileId) -> Arc<Vec<Item>>;
+
+    /// The references to the items of a file made in the file, in order.
+    fn references(&self, file_id: FileId) -> Arc<Vec<Reference>>;
+}
+
+fn items(db: &dyn Items, file_id: FileId) -> Arc<Vec<Item>> {
+    let parse = db.parse(file_id);
+    db.profiler()
+        .time(file_id, "items", || Arc::new(items_of(&parse.syntax())))
+}
+
+fn references(db: &dyn Items, file_id: FileId) -> Arc<Vec<Reference>> {
+    let parse = db.parse(file_id);
+    let items = db.items(file_id);
+    db.profiler().time(file_id, "references", || {
+        Arc::new(references_of(&parse.syntax(), &items))
+    })
+}
+
+/// The item named by the identifier at the given offset of a file (either
+/// where the item is declared or where it is referred to), if any.
+pub fn item_at_offset(
+    db: &dyn Items,
+    file_id: FileId,
+    offset: usize,
+) -> Option<Item> {
+    let name = identifier_at_offset(db, file_id, offset)?;
+    db.items(file_id)
+        .iter()
+        .find(|item| item.name == name)
+        .cloned()
+}
+
+/// The identifier at the given offset of a file, in the form identifiers are
+/// compared in (see [`normalize_identifier`]).
+///
+/// Every token of the source is in its syntax tree, so this finds the
+/// identifier even when the source doesn't parse.
+pub fn identifier_at_offset(
+    db: &dyn Items,
+    file_id: FileId,
+    offset: usize,
+) -> Option<String> {
+    let root = db.parse(file_id).syntax();
+    let identifier = root
+        .descendants_with_tokens()
+        .filter_map(|it| it.into_token())
+        .filter(|token| token.kind() == SyntaxKind::Identifier)
+        .find(|token| {
+            let range = token.text_range();
+            usize::from(range.start()) <= offset
+                && offset <= usize::from(range.end())
+        })?;
+
+    Some(normalize_identifier(identifier.text()).into_owned())
+}
+
+/// The kinds of declarations that make up an [`Item`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ItemKind {
+    /// A global binding (such as `let a = 1`).
+    Binding,
+    /// An external function (such as `external function f(a: Int) -> Int`).
+    External,
+    /// An enum type (such as `type Shape = Circle(Float) | Square(Float)`).
+    Type,
+    /// An interface (such as `interface Show = { function show(a) }`), whose
+    /// methods aren't items themselves.
+    Interface,
+}
+
+/// A named declaration at the top level of a source file.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Item {
+    pub kind: ItemKind,
+    /// The name of the item, in the form identifiers are compared in (see
+    /// [`normalize_identifier`]).
+    pub name: String,
+    pub is_public: bool,
+    /// Whether the item is a binding defined with `var`, which may be assigned
+    /// a new value.
+    pub is_mutable: bool,
+    /// The declaration as it is written, without its documentation and (for
+    /// bindings) its value, such as `public let a`.
+    pub signature: String,
+    /// The text of the doc comments (`--|`) directly preceding the
+    /// declaration, without their markers.
+    pub docs: Option<String>,
+    /// The range of the whole declaration (including its documentation).
+    pub range: Range<usize>,
+    /// The range of the item's name in the declaration.
+    pub name_range: Range<usize>,
+}
+
+/// Finds every named declaration at the top level of the given syntax tree.
+///
+/// Declarations missing a name (which the parser has already reported) are
+/// skipped.
+pub fn items_of(root: &SyntaxNode) -> Vec<Item> {
+    root.children().filter_map(|node| item(&node)).collect()
+}
+
+fn item(node: &SyntaxNode) -> Option<Item> {
+    let kind = match node.kind() {
+        SyntaxKind::Dec_GlobalBinding => ItemKind::Binding,
+        SyntaxKind::Dec_External => ItemKind::External,
+        SyntaxKind::Dec_TypeEnum => ItemKind::Type,
+        SyntaxKind::Dec_Interface => ItemKind::Interface,
+        _ => return None,
+    };
+
+    let tokens = node
+        .children_with_tokens()
+        .filter_map(|it| it.into_token())
+        .collect::<Vec<_>>();
+
+    let name = tokens
+        .iter()
+        .find(|token| token.kind() == SyntaxKind::Identifier)?;
+
+    let docs = tokens
+        .iter()
+        .filter(|token| token.kind() == SyntaxKind::DocComment)
+        .map(|token| {
+            let text = token.text().trim_start_matches("--|");
+            text.strip_prefix(' ').unwrap_or(text).trim_end()
+        })
+        .collect::<Vec<_>>();
+
+    let is_public = node
+        .children()
+        .any(|node| node.kind() == SyntaxKind::Visibility);
+    let is_mutable = tokens
+        .iter()
+        .any(|token| token.kind() == SyntaxKind::Kwd_Var);
+
+    let range = node.text_range();
+    let name_range = name.text_range();
+
+    Some(Item {
+        kind,
+        name: normalize_identifier(name.text()).into_owned(),
+        is_public,
+        is_mutable,
+        signature: signature(node, kind),
+        docs: if docs.is_empty() {
+            None
+        } else {
+            Some(docs.join("\n"))
+        },
+        range: range.start().into()..range.end().into(),
+        name_range: name_range.start().into()..name_range.end().into(),
+    })
+}
+
+/// A reference to an item, given by its index in the [`items`] of the file.
+///
+/// [`items`]: crate::Items::items
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Reference {
+    pub item: usize,
+    /// The range of the item's name in the reference.
+    pub range: Range<usize>,
+}
+
+/// Finds the references to the given items in the given syntax tree.
+///
+/// References to the parameters of a lambda are local, even if an item has
+/// the same name.
+pub fn references_of(root: &SyntaxNode, items: &[Item]) -> Vec<Reference> {
+    root.descendants()
+        .filter(|node| node.kind() == SyntaxKind::Exp_VariableRef)
+        .filter_map(|node| {
+            let token = node.first_token()?;
+            let name = normalize_identifier(token.text());
+            if is_local(&node, &name) {
+                return None;
+            }
+
+            let item = items.iter().position(|item| item.name == name)?;
+
+            let range = token.text_range();
+            Some(Reference {
+                item,
+                range: range.start().into()..range.end().into(),
+            })
+        })
+        .collect()
+}
+
+/// Determines if the given name is a parameter of a lambda enclosing the
+/// given node.
+pub(crate) fn is_local(node: &SyntaxNode, name: &str) -> bool {
+    node.ancestors()
+        .filter(|node| node.kind() == SyntaxKind::Exp_Lambda)
+        .filter_map(|lambda| {
+            lambda
+                .children()
+                .find(|node| node.kind() == SyntaxKind::ParamList)
+        })
+        .flat_map(|params| params.children_with_tokens())
+        .filter_map(|it| it.into_token())
+        .any(|token| {
+            token.kind() == SyntaxKind::Identifier
+                && normalize_identifier(token.text()) == name
+        })
+}
+
+/// The text of the given declaration on a single line, leaving out its
+/// comments (and the value of a binding or the methods of an interface).
+fn signature(node: &SyntaxNode, kind: ItemKind) -> String {
+    let mut signature = String::new();
+
+    for token in node
+        .descendants_with_tokens()
+        .filter_map(|it| it.into_token())
+    {
+        match token.kind() {
+            SyntaxKind::Sym_Eq
+                if matches!(kind, ItemKind::Binding | ItemKind::Interface) =>
+            {
+                break
+            }
+            kind if kind.is_comment() => {}
+            SyntaxKind::Whitespace => {
+                if !signature.is_empty() && !signature.ends_with(' ') {
+                    signature.push(' ');
+                }
+            }
+            _ => signature.push_str(token.text()),
+        }
+    }
+
+    signature.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use helios_text::Rope;
+
+    #[test]
+    fn test_items() {
+        let source = "--| Adds one.\n--|\n--|   Indented.\n\
+                      public let f = \\x -> x + 1\n\n\
+                      -- Not documentation\n\
+                      external function g(a: Int) -> Int\n\
+                      type T =\n  | A\n  | B(Int) -- Comment\n\
+                      let = 1\n\
+                      var h = 0\n\
+                      interface Show = { function show(a) -> String }\n";
+        let root = helios_parser::parse(FileId(0), source).syntax();
+
+        assert_eq!(
+            items_of(&root),
+            vec![
+                Item {
+                    kind: ItemKind::Binding,
+                    name: "f".to_string(),
+                    is_public: true,
+                    is_mutable: false,
+                    signature: "public let f".to_string(),
+                    docs: Some("Adds one.\n\n  Indented.".to_string()),
+                    range: 0..60,
+                    name_range: 45..46,
+                },
+                Item {
+                    kind: ItemKind::External,
+                    name: "g".to_string(),
+                    is_public: false,
+                    is_mutable: false,
+                    signature: "external function g(a: Int) -> Int".to_string(),
+                    docs: None,
+                    range: 83..117,
+                    name_range: 101..102,
+                },
+                Item {
+                    kind: ItemKind::Type,
+                    name: "T".to_string(),
+                    is_public: false,
+                    is_mutable: false,
+                    signature: "type T = | A | B(Int)".to_string(),
+                    docs: None,
+                    range: 118..154,
+                    name_range: 123..124,
+                },
+                Item {
+                    kind: ItemKind::Binding,
+                    name: "h".to_string(),
+                    is_public: false,
+                    is_mutable: true,
+                    signature: "var h".to_string(),
+                    docs: None,
+                    range: 163..172,
+                    name_range: 167..168,
+                },
+                Item {
+                    kind: ItemKind::Interface,
+                    name: "Show".to_string(),
+                    is_public: false,
+                    is_mutable: false,
+                    signature: "interface Show".to_string(),
+                    docs: None,
+                    range: 173..220,
+                    name_range: 183..187,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_item_at_offset() {
+        let mut db = crate::HeliosDatabase::default();
+        let source = "--| One.\nlet a = 1\nlet b = a + c\n";
+        db.set_source(FileId(0), Rope::from(source));
+
+        let item = |offset| {
+            item_at_offset(&db, FileId(0), offset).map(|item| item.name)
+        };
+
+        assert_eq!(item(13), Some("a".to_string()));
+        assert_eq!(item(27), Some("a".to_string()));
+        assert_eq!(item(23), Some("b".to_string()));
+        assert_eq!(item(31), None);
+        assert_eq!(item(16), None);
+
+        // `c` isn't an item, but it's still an identifier
+        let identifier = identifier_at_offset(&db, FileId(0), 31);
+        assert_eq!(identifier, Some("c".to_string()));
+    }
+
+    #[test]
+    fn test_references() {
+        let mut db = crate::HeliosDatabase::default();
+        let source = "let a = 1\nlet b = a + a\nlet c = \\a -> a |> b\n";
+        db.set_source(FileId(0), Rope::from(source));
+
+        assert_eq!(
+            *db.references(FileId(0)),
+            vec![
+                Reference {
+                    item: 0,
+                    range: 18..19,
+                },
+                Reference {
+                    item: 0,
+                    range: 22..23,
+                },
+                Reference {
+                    item: 1,
+                    range: 43..44,
+                },
+            ]
+        );
+
+        // The same names, written with different characters
+        let source = "let e\u{301} = 1\nlet b = \u{e9}\n";
+        db.set_source(FileId(0), Rope::from(source));
+        assert_eq!(db.items(FileId(0))[0].name, "\u{e9}");
+        assert_eq!(
+            *db.references(FileId(0)),
+            vec![Reference {
+                item: 0,
+                range: 20..22,
+            }]
+        );
+    }
+}
@@ -1,25 +1,67 @@
+pub mod analysis;
+pub mod ast_id;
+pub mod calls;
 pub mod cancel;
 pub mod input;
 pub mod interner;
+pub mod items;
 pub mod location;
+pub mod profile;
+pub mod stats;
 
+pub use crate::analysis::{Analysis, AnalysisHost};
+pub use crate::ast_id::*;
+pub use crate::calls::*;
+pub use crate::cancel::{revalidate, Cancelable, Cancelled};
 pub use crate::input::*;
 pub use crate::interner::*;
+pub use crate::items::*;
 pub use crate::location::*;
+pub use crate::profile::{Profile, Profiler};
+pub use crate::stats::{memory_stats, MemoryStats, QueryStats};
+pub use helios_parser::{Feature, Features};
+pub use salsa::Durability;
 use std::fmt::{self, Debug};
 
-#[salsa::database(InputLocationDatabase, InputDatabase, InternerDatabase)]
-#[derive(Default)]
+#[salsa::database(
+    AstIdDatabase,
+    CallsDatabase,
+    InputLocationDatabase,
+    InputDatabase,
+    InternerDatabase,
+    ItemsDatabase
+)]
 pub struct HeliosDatabase {
     storage: salsa::Storage<HeliosDatabase>,
+    profiler: Profiler,
+}
+
+impl Default for HeliosDatabase {
+    fn default() -> Self {
+        let mut db = HeliosDatabase {
+            storage: salsa::Storage::default(),
+            profiler: Profiler::default(),
+        };
+
+        // The features rarely change, and a change reparses every file
+        db.set_features_with_durability(Features::new(), Durability::HIGH);
+        db
+    }
 }
 
 impl salsa::Database for HeliosDatabase {}
 
+impl Profile for HeliosDatabase {
+    fn profiler(&self) -> &Profiler {
+        &self.profiler
+    }
+}
+
 impl salsa::ParallelDatabase for HeliosDatabase {
     fn snapshot(&self) -> salsa::Snapshot<Self> {
         salsa::Snapshot::new(HeliosDatabase {
             storage: self.storage.snapshot(),
+            profiler: self.profiler.clone(),
         })
     }
 }
@@ -33,7 +75,8 @@ impl Debug for HeliosDatabase {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::sync::Arc;
+    use helios_text::LineCol;
+    use helios_text::Rope;
 
     const FILE_ID: FileId = FileId(0);
     const SOURCE: &str = "let a = 0\nlet b = 1\n\nlet c = 2\n";
@@ -41,25 +84,27 @@ mod tests {
     #[test]
     fn test_source_location_queries() {
         let mut db = HeliosDatabase::default();
-        db.set_source(FILE_ID, Arc::new(SOURCE.to_string()));
+        db.set_source(FILE_ID, Rope::from(SOURCE));
 
-        assert_eq!(db.source(FILE_ID), Arc::new(SOURCE.to_string()));
+        assert_eq!(db.source(FILE_ID), Rope::from(SOURCE));
         assert_eq!(db.source_len(FILE_ID), 31);
 
-        let indexes = vec![0, 10, 20, 21, 31];
-        assert_eq!(db.source_line_indexes(FILE_ID), Arc::new(indexes));
+        let line_starts = [0, 10, 20, 21, 31];
+        assert_eq!(db.source_lines(FILE_ID).line_starts(), &line_starts);
 
-        assert_eq!(db.source_line_start(FILE_ID, 0), 0);
-        assert_eq!(db.source_line_start(FILE_ID, 1), 10);
-        assert_eq!(db.source_line_start(FILE_ID, 2), 20);
-        assert_eq!(db.source_line_start(FILE_ID, 3), 21);
-        assert_eq!(db.source_line_start(FILE_ID, 4), 31);
+        assert_eq!(db.source_line_start(FILE_ID, 0), Some(0));
+        assert_eq!(db.source_line_start(FILE_ID, 1), Some(10));
+        assert_eq!(db.source_line_start(FILE_ID, 2), Some(20));
+        assert_eq!(db.source_line_start(FILE_ID, 3), Some(21));
+        assert_eq!(db.source_line_start(FILE_ID, 4), Some(31));
+        assert_eq!(db.source_line_start(FILE_ID, 6), None);
 
-        assert_eq!(db.source_line_range(FILE_ID, 0), 0..10);
-        assert_eq!(db.source_line_range(FILE_ID, 1), 10..20);
-        assert_eq!(db.source_line_range(FILE_ID, 2), 20..21);
-        assert_eq!(db.source_line_range(FILE_ID, 3), 21..31);
-        assert_eq!(db.source_line_range(FILE_ID, 4), 31..31);
+        assert_eq!(db.source_line_range(FILE_ID, 0), Some(0..10));
+        assert_eq!(db.source_line_range(FILE_ID, 1), Some(10..20));
+        assert_eq!(db.source_line_range(FILE_ID, 2), Some(20..21));
+        assert_eq!(db.source_line_range(FILE_ID, 3), Some(21..31));
+        assert_eq!(db.source_line_range(FILE_ID, 4), Some(31..31));
+        assert_eq!(db.source_line_range(FILE_ID, 6), None);
 
         assert_eq!(db.source_line_index(FILE_ID, 0), 0);
         assert_eq!(db.source_line_index(FILE_ID, 1), 0);
@@ -90,21 +135,90 @@ mod tests {
         assert_eq!(db.source_column_index(FILE_ID, 3, 26), 5);
         assert_eq!(db.source_column_index(FILE_ID, 3, 30), 9);
         assert_eq!(db.source_column_index(FILE_ID, 4, 31), 0);
+        assert_eq!(db.source_column_index(FILE_ID, 1, 99), 10);
+        assert_eq!(db.source_column_index(FILE_ID, 9, 3), 0);
+
+        assert_eq!(
+            db.source_position_at_offset(FILE_ID, 0),
+            LineCol::new(0, 0)
+        );
+        assert_eq!(
+            db.source_position_at_offset(FILE_ID, 1),
+            LineCol::new(0, 1)
+        );
+        assert_eq!(
+            db.source_position_at_offset(FILE_ID, 5),
+            LineCol::new(0, 5)
+        );
+        assert_eq!(
+            db.source_position_at_offset(FILE_ID, 9),
+            LineCol::new(0, 9)
+        );
+        assert_eq!(
+            db.source_position_at_offset(FILE_ID, 10),
+            LineCol::new(1, 0)
+        );
+        assert_eq!(
+            db.source_position_at_offset(FILE_ID, 11),
+            LineCol::new(1, 1)
+        );
+        assert_eq!(
+            db.source_position_at_offset(FILE_ID, 15),
+            LineCol::new(1, 5)
+        );
+        assert_eq!(
+            db.source_position_at_offset(FILE_ID, 19),
+            LineCol::new(1, 9)
+        );
+        assert_eq!(
+            db.source_position_at_offset(FILE_ID, 20),
+            LineCol::new(2, 0)
+        );
+        assert_eq!(
+            db.source_position_at_offset(FILE_ID, 21),
+            LineCol::new(3, 0)
+        );
+        assert_eq!(
+            db.source_position_at_offset(FILE_ID, 22),
+            LineCol::new(3, 1)
+        );
+        assert_eq!(
+            db.source_position_at_offset(FILE_ID, 26),
+            LineCol::new(3, 5)
+        );
+        assert_eq!(
+            db.source_position_at_offset(FILE_ID, 30),
+            LineCol::new(3, 9)
+        );
+        assert_eq!(
+            db.source_position_at_offset(FILE_ID, 31),
+            LineCol::new(4, 0)
+        );
+
+        assert_eq!(
+            db.source_position_at_offset(FILE_ID, 99),
+            LineCol::new(4, 0)
+        );
+
+        let position = LineCol::new(3, 5);
+        assert_eq!(db.source_offset_at_position(FILE_ID, position), Some(26));
+        let position = LineCol::new(1, 99);
+        assert_eq!(db.source_offset_at_position(FILE_ID, position), Some(19));
+        let position = LineCol::new(9, 0);
+        assert_eq!(db.source_offset_at_position(FILE_ID, position), None);
+    }
+
+    #[test]
+    fn test_intern_file_paths() {
+        let db = HeliosDatabase::default();
+
+        let foo = db.intern_file_path("src/Foo.he".into());
+        let bar = db.intern_file_path("src/Bar.he".into());
+        assert_ne!(foo, bar);
+        assert_eq!(db.intern_file_path("src/Foo.he".into()), foo);
 
-        assert_eq!(db.source_position_at_offset(FILE_ID, 0), (0, 0));
-        assert_eq!(db.source_position_at_offset(FILE_ID, 1), (0, 1));
-        assert_eq!(db.source_position_at_offset(FILE_ID, 5), (0, 5));
-        assert_eq!(db.source_position_at_offset(FILE_ID, 9), (0, 9));
-        assert_eq!(db.source_position_at_offset(FILE_ID, 10), (1, 0));
-        assert_eq!(db.source_position_at_offset(FILE_ID, 11), (1, 1));
-        assert_eq!(db.source_position_at_offset(FILE_ID, 15), (1, 5));
-        assert_eq!(db.source_position_at_offset(FILE_ID, 19), (1, 9));
-        assert_eq!(db.source_position_at_offset(FILE_ID, 20), (2, 0));
-        assert_eq!(db.source_position_at_offset(FILE_ID, 21), (3, 0));
-        assert_eq!(db.source_position_at_offset(FILE_ID, 22), (3, 1));
-        assert_eq!(db.source_position_at_offset(FILE_ID, 26), (3, 5));
-        assert_eq!(db.source_position_at_offset(FILE_ID, 30), (3, 9));
-        assert_eq!(db.source_position_at_offset(FILE_ID, 31), (4, 0));
+        let path = db.lookup_intern_file_path(bar);
+        assert_eq!(path, std::path::PathBuf::from("src/Bar.he"));
     }
 
     /*
@@ -122,7 +236,7 @@ mod tests {
         }
 
         let mut db = HeliosDatabase::default();
-        db.set_source(FILE_ID, Arc::new(SOURCE.to_string()));
+        db.set_source(FILE_ID, Rope::from(SOURCE));
 
         let bindings = db.all_bindings(FILE_ID);
         print_bindings(&mut db, bindings);
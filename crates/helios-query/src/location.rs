@@ -1,22 +1,32 @@
 use crate::{FileId, Input};
+use helios_text::{LineCol, LineIndex};
 use std::{ops::Range, sync::Arc};
 
 #[salsa::query_group(InputLocationDatabase)]
 pub trait InputLocation: Input {
-    /// The indices of each line in a file. The first element in the returned
-    /// vector will always be `0`.
-    fn source_line_indexes(&self, file_id: FileId) -> Arc<Vec<usize>>;
+    /// The [`LineIndex`] of a file, which is only computed again when its
+    /// source changes.
+    fn source_lines(&self, file_id: FileId) -> Arc<LineIndex>;
 
-    fn source_line_start(&self, file_id: FileId, line_index: usize) -> usize;
+    /// The offset of the start of a line, if the line exists (see
+    /// [`LineIndex::line_start`]).
+    fn source_line_start(
+        &self,
+        file_id: FileId,
+        line_index: usize,
+    ) -> Option<usize>;
 
+    /// The range of a line (including its line feed), if the line exists.
     fn source_line_range(
         &self,
         file_id: FileId,
         line_index: usize,
-    ) -> Range<usize>;
+    ) -> Option<Range<usize>>;
 
     fn source_line_index(&self, file_id: FileId, byte_offset: usize) -> usize;
 
+    /// The column (in characters) of an offset in a line, clamped to the end
+    /// of the line (or `0` if the line doesn't exist).
     fn source_column_index(
         &self,
         file_id: FileId,
@@ -24,57 +34,41 @@ pub trait InputLocation: Input {
         byte_offset: usize,
     ) -> usize;
 
+    /// The line and column of an offset, where offsets past the end of the
+    /// source are clamped to its end.
     fn source_position_at_offset(
         &self,
         file_id: FileId,
         byte_offset: usize,
-    ) -> (usize, usize);
+    ) -> LineCol;
 
+    /// The offset of a line and column, if the line exists. A column past the
+    /// end of its line is clamped to the end of the line.
     fn source_offset_at_position(
         &self,
         file_id: FileId,
-        position: (usize, usize),
-    ) -> usize;
+        position: LineCol,
+    ) -> Option<usize>;
 }
 
-fn source_line_indexes(
-    db: &dyn InputLocation,
-    file_id: FileId,
-) -> Arc<Vec<usize>> {
-    let source = db.source(file_id);
-    let indexes = std::iter::once(0)
-        .chain(source.match_indices('\n').map(|(i, _)| i + 1))
-        .collect();
-
-    Arc::new(indexes)
+fn source_lines(db: &dyn InputLocation, file_id: FileId) -> Arc<LineIndex> {
+    Arc::new(LineIndex::from_rope(&db.source(file_id)))
 }
 
 fn source_line_start(
     db: &dyn InputLocation,
     file_id: FileId,
     line_index: usize,
-) -> usize {
-    let line_indexes = db.source_line_indexes(file_id);
-
-    if line_index == line_indexes.len() {
-        db.source_len(file_id)
-    } else {
-        line_indexes
-            .get(line_index)
-            .cloned()
-            .expect("Out of bounds")
-    }
+) -> Option<usize> {
+    db.source_lines(file_id).line_start(line_index)
 }
 
 fn source_line_range(
     db: &dyn InputLocation,
     file_id: FileId,
     line_index: usize,
-) -> Range<usize> {
-    let line_start = db.source_line_start(file_id, line_index);
-    let next_line_start = db.source_line_start(file_id, line_index + 1);
-
-    line_start..next_line_start
+) -> Option<Range<usize>> {
+    db.source_lines(file_id).line_range(line_index)
 }
 
 fn source_line_index(
@@ -82,9 +76,7 @@ fn source_line_index(
     file_id: FileId,
     byte_offset: usize,
 ) -> usize {
-    db.source_line_indexes(file_id)
-        .binary_search(&byte_offset)
-        .unwrap_or_else(|expected| expected.checked_sub(1).unwrap_or(0))
+    db.source_lines(file_id).line(byte_offset)
 }
 
 fn source_column_index(
@@ -93,42 +85,28 @@ fn source_column_index(
     line_index: usize,
     byte_offset: usize,
 ) -> usize {
-    fn column_index(
-        source: &str,
-        line_range: Range<usize>,
-        byte_offset: usize,
-    ) -> usize {
-        use std::cmp::min;
-        let end_index = min(byte_offset, min(line_range.end, source.len()));
-
-        (line_range.start..end_index)
-            .filter(|index| source.is_char_boundary(index + 1))
-            .count()
-    }
-
     let source = db.source(file_id);
+    let end = source.len();
     let line_range = db.source_line_range(file_id, line_index);
-    let column_index = column_index(source.as_ref(), line_range, byte_offset);
 
-    column_index
+    source.column_index(line_range.unwrap_or(end..end), byte_offset)
 }
 
 fn source_position_at_offset(
     db: &dyn InputLocation,
     file_id: FileId,
     byte_offset: usize,
-) -> (usize, usize) {
+) -> LineCol {
     let line_index = db.source_line_index(file_id, byte_offset);
     let column_index = db.source_column_index(file_id, line_index, byte_offset);
 
-    (line_index, column_index)
+    LineCol::new(line_index, column_index)
 }
 
 fn source_offset_at_position(
     db: &dyn InputLocation,
     file_id: FileId,
-    position: (usize, usize),
-) -> usize {
-    let line_indexes = db.source_line_indexes(file_id);
-    line_indexes[position.0] + position.1
+    position: LineCol,
+) -> Option<usize> {
+    db.source(file_id).offset(position)
 }
@@ -0,0 +1,236 @@
+use crate::FileId;
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+thread_local! {
+    /// The time spent in the phases run (or recorded) inside the phase being
+    /// timed on this thread, which is left out of its own time.
+    static NESTED_TIME: Cell<Duration> = const { Cell::new(Duration::ZERO) };
+}
+
+/// A database that measures the time its queries spend on each file.
+pub trait Profile {
+    fn profiler(&self) -> &Profiler;
+}
+
+/// Accumulates the time spent in each phase (such as `lexing` or `items`) of
+/// compiling each file, across threads and snapshots of the database.
+///
+/// The time of a phase leaves out the phases run inside it, such as the
+/// parsing of a file that another query needs first, so that the total of
+/// every phase is the time actually spent. Memoized queries don't take any
+/// time, so the phases of a query are only timed when it is executed.
+#[derive(Clone, Debug, Default)]
+pub struct Profiler {
+    timings: Arc<Mutex<BTreeMap<(FileId, &'static str), Timing>>>,
+}
+
+/// The time spent in a phase of compiling a file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Timing {
+    /// The number of times the phase was run.
+    pub count: usize,
+    pub total: Duration,
+}
+
+impl Profiler {
+    /// Runs the given phase of compiling the given file, adding the time it
+    /// took to the time of the phase.
+    pub fn time<T>(
+        &self,
+        file_id: FileId,
+        phase: &'static str,
+        f: impl FnOnce() -> T,
+    ) -> T {
+        let outer = NESTED_TIME.with(|time| time.replace(Duration::ZERO));
+        let start = Instant::now();
+        let result = f();
+        let elapsed = start.elapsed();
+
+        let nested = NESTED_TIME.with(|time| time.replace(outer));
+        self.record(file_id, phase, elapsed.saturating_sub(nested));
+        result
+    }
+
+    /// Adds the given time to a phase of compiling the given file, such as a
+    /// phase timed by a function that reports its own timings.
+    pub fn record(&self, file_id: FileId, phase: &'static str, time: Duration) {
+        NESTED_TIME.with(|nested| nested.set(nested.get() + time));
+
+        let mut timings = self.timings.lock().unwrap();
+        let timing = timings.entry((file_id, phase)).or_default();
+        timing.count += 1;
+        timing.total += time;
+    }
+
+    /// The time spent in every phase of every file so far, sorted by file.
+    pub fn timings(&self) -> Vec<(FileId, &'static str, Timing)> {
+        let timings = self.timings.lock().unwrap();
+        timings
+            .iter()
+            .map(|(&(file_id, phase), &timing)| (file_id, phase, timing))
+            .collect()
+    }
+
+    /// Forgets every timing recorded so far.
+    pub fn reset(&self) {
+        self.timings.lock().unwrap().clear();
+    }
+
+    /// A report of the timings, where files are named by the given function.
+    pub fn report(
+        &self,
+        file_name: impl Fn(FileId) -> String,
+    ) -> ProfileReport {
+        let rows = self
+            .timings()
+            .into_iter()
+            .map(|(file_id, phase, timing)| (file_name(file_id), phase, timing))
+            .collect();
+
+        ProfileReport { rows }
+    }
+}
+
+/// The timings of a [`Profiler`], displayed as a table of text with a row
+/// for each phase of each file, followed by the total of each phase.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProfileReport {
+    pub rows: Vec<(String, &'static str, Timing)>,
+}
+
+impl ProfileReport {
+    /// The total time spent in each phase, in the order the phases first
+    /// appear in the report.
+    pub fn totals(&self) -> Vec<(&'static str, Timing)> {
+        let mut totals: Vec<(&'static str, Timing)> = Vec::new();
+        for &(_, phase, timing) in &self.rows {
+            match totals.iter_mut().find(|(it, _)| *it == phase) {
+                Some((_, total)) => {
+                    total.count += timing.count;
+                    total.total += timing.total;
+                }
+                None => totals.push((phase, timing)),
+            }
+        }
+
+        totals
+    }
+}
+
+impl Display for ProfileReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let totals = self.totals();
+        let file_width = self
+            .rows
+            .iter()
+            .map(|(file, _, _)| file.chars().count())
+            .chain(Some("Total".len()))
+            .max()
+            .unwrap_or_default();
+        let phase_width = totals
+            .iter()
+            .map(|(phase, _)| phase.len())
+            .chain(Some("Phase".len()))
+            .max()
+            .unwrap_or_default();
+
+        let mut row = |file: &str, phase: &str, count: &dyn Display, time| {
+            writeln!(
+                f,
+                "{:file_width$}  {:phase_width$}  {:>5}  {:>10}",
+                file,
+                phase,
+                count,
+                time,
+                file_width = file_width,
+                phase_width = phase_width
+            )
+        };
+
+        let millis =
+            |time: Duration| format!("{:.3}ms", time.as_secs_f64() * 1e3);
+
+        row("File", "Phase", &"Runs", "Time".to_string())?;
+        for (file, phase, timing) in &self.rows {
+            row(file, phase, &timing.count, millis(timing.total))?;
+        }
+        for (phase, timing) in totals {
+            row("Total", phase, &timing.count, millis(timing.total))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HeliosDatabase, Input, Items};
+    use helios_text::Rope;
+
+    #[test]
+    fn test_profiler_leaves_out_nested_phases() {
+        let profiler = Profiler::default();
+        let file_id = FileId(0);
+
+        profiler.time(file_id, "outer", || {
+            profiler.record(file_id, "inner", Duration::from_secs(3600));
+        });
+
+        let timings = profiler.timings();
+        assert_eq!(timings.len(), 2);
+        assert_eq!(timings[0].1, "inner");
+        assert_eq!(timings[0].2.total, Duration::from_secs(3600));
+        assert_eq!(timings[1].1, "outer");
+        assert!(timings[1].2.total < Duration::from_secs(3600));
+
+        profiler.reset();
+        assert_eq!(profiler.timings(), Vec::new());
+    }
+
+    #[test]
+    fn test_profile_queries() {
+        let mut db = HeliosDatabase::default();
+        db.set_source(FileId(0), Rope::from("let a = 1"));
+        db.set_source(FileId(1), Rope::from("let b = a"));
+        db.references(FileId(0));
+        db.references(FileId(0));
+        db.items(FileId(1));
+
+        let report = db.profiler().report(|file_id| match file_id {
+            FileId(0) => "Main.he".to_string(),
+            _ => "Foo.he".to_string(),
+        });
+
+        let phases = |file: &str| {
+            let rows = report.rows.iter().filter(|(it, _, _)| it == file);
+            rows.map(|(_, phase, timing)| (*phase, timing.count))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            phases("Main.he"),
+            vec![
+                ("items", 1),
+                ("lexing", 1),
+                ("parsing", 1),
+                ("references", 1)
+            ]
+        );
+        assert_eq!(
+            phases("Foo.he"),
+            vec![("items", 1), ("lexing", 1), ("parsing", 1)]
+        );
+
+        let totals = report.totals();
+        assert_eq!(totals[0].0, "items");
+        assert_eq!(totals[0].1.count, 2);
+
+        let table = report.to_string();
+        assert!(table.starts_with("File     Phase        Runs        Time\n"));
+        assert!(table.contains("\nTotal    items           2  "));
+    }
+}
@@ -0,0 +1,193 @@
+use crate::*;
+use salsa::debug::DebugQueryTable;
+use salsa::Query;
+use std::fmt::{self, Display};
+
+/// The approximate size of a node of a green tree (its kind, its length and
+/// the count of references to it), without its children.
+const GREEN_NODE_SIZE: usize = 16;
+
+/// The approximate size of a child of a node of a green tree (a pointer to
+/// the child along with its offset).
+const GREEN_CHILD_SIZE: usize = 16;
+
+/// The approximate size of a token of a green tree, without its text.
+const GREEN_TOKEN_SIZE: usize = 16;
+
+/// The number of entries stored for a query.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QueryStats {
+    pub name: &'static str,
+    /// The number of keys the query was run with.
+    pub entries: usize,
+    /// The number of those entries whose value is still stored.
+    pub memoized: usize,
+}
+
+/// The sizes of the tables of a database, for tracking its memory footprint.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemoryStats {
+    pub queries: Vec<QueryStats>,
+    pub interned_bindings: usize,
+    pub interned_file_paths: usize,
+    /// The number of syntax trees stored (one for every parsed file).
+    pub syntax_trees: usize,
+    pub syntax_nodes: usize,
+    pub syntax_tokens: usize,
+    /// The approximate memory used by the syntax trees stored, in bytes.
+    pub syntax_bytes: usize,
+}
+
+/// Collects the [`MemoryStats`] of the given database.
+pub fn memory_stats(db: &HeliosDatabase) -> MemoryStats {
+    macro_rules! query_stats {
+        ($($query:ty),* $(,)?) => {
+            vec![$({
+                let entries = <$query>::default()
+                    .in_db(db)
+                    .entries::<Vec<_>>();
+                QueryStats {
+                    name: <$query as Query>::QUERY_NAME,
+                    entries: entries.len(),
+                    memoized: entries
+                        .iter()
+                        .filter(|entry| entry.value.is_some())
+                        .count(),
+                }
+            }),*]
+        };
+    }
+
+    let queries = query_stats![
+        SourceQuery,
+        SourceLenQuery,
+        ParseQuery,
+        DiagnosticsQuery,
+        SourceLinesQuery,
+        SourceLineStartQuery,
+        SourceLineRangeQuery,
+        SourceLineIndexQuery,
+        SourceColumnIndexQuery,
+        SourcePositionAtOffsetQuery,
+        SourceOffsetAtPositionQuery,
+        ItemsQuery,
+        ReferencesQuery,
+        CallsQuery,
+        AstIdMapQuery,
+    ];
+
+    let mut stats = MemoryStats {
+        queries,
+        interned_bindings: InternBindingQuery
+            .in_db(db)
+            .entries::<Vec<_>>()
+            .len(),
+        interned_file_paths: InternFilePathQuery
+            .in_db(db)
+            .entries::<Vec<_>>()
+            .len(),
+        syntax_trees: 0,
+        syntax_nodes: 0,
+        syntax_tokens: 0,
+        syntax_bytes: 0,
+    };
+
+    let parses = ParseQuery.in_db(db).entries::<Vec<_>>();
+    for parse in parses.into_iter().filter_map(|entry| entry.value) {
+        stats.syntax_trees += 1;
+        for element in parse.syntax().descendants_with_tokens() {
+            match element {
+                rowan::NodeOrToken::Node(node) => {
+                    let children = node.children_with_tokens().count();
+                    stats.syntax_nodes += 1;
+                    stats.syntax_bytes +=
+                        GREEN_NODE_SIZE + children * GREEN_CHILD_SIZE;
+                }
+                rowan::NodeOrToken::Token(token) => {
+                    stats.syntax_tokens += 1;
+                    stats.syntax_bytes += GREEN_TOKEN_SIZE + token.text().len();
+                }
+            }
+        }
+    }
+
+    stats
+}
+
+impl Display for MemoryStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .queries
+            .iter()
+            .map(|query| query.name.len())
+            .max()
+            .unwrap_or_default();
+
+        writeln!(f, "{:width$}  Entries  Memoized", "Query", width = width)?;
+        for query in &self.queries {
+            writeln!(
+                f,
+                "{:width$}  {:>7}  {:>8}",
+                query.name,
+                query.entries,
+                query.memoized,
+                width = width
+            )?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "Interned bindings: {}", self.interned_bindings)?;
+        writeln!(f, "Interned file paths: {}", self.interned_file_paths)?;
+        writeln!(
+            f,
+            "Syntax trees: {} ({} nodes, {} tokens, about {:.1} KiB)",
+            self.syntax_trees,
+            self.syntax_nodes,
+            self.syntax_tokens,
+            self.syntax_bytes as f64 / 1024.0
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use helios_text::Rope;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_memory_stats() {
+        let mut db = HeliosDatabase::default();
+        let file_id = db.intern_file_path(PathBuf::from("Main.he"));
+        db.set_source(file_id, Rope::from("let a = 1"));
+
+        let stats = memory_stats(&db);
+        assert_eq!(stats.syntax_trees, 0);
+        assert_eq!(stats.interned_file_paths, 1);
+
+        db.items(file_id);
+        let stats = memory_stats(&db);
+        let query = |name| {
+            let query = stats.queries.iter().find(|it| it.name == name);
+            query.map(|it| (it.entries, it.memoized)).unwrap()
+        };
+        assert_eq!(query("source"), (1, 1));
+        assert_eq!(query("parse"), (1, 1));
+        assert_eq!(query("items"), (1, 1));
+        assert_eq!(query("calls"), (0, 0));
+
+        // `Root`, `Dec_GlobalBinding` and `Exp_Literal`, with the tokens
+        // `let`, `a`, `=`, `1` and three spaces (every element but the root
+        // is a child of a node)
+        assert_eq!(stats.syntax_trees, 1);
+        assert_eq!(stats.syntax_nodes, 3);
+        assert_eq!(stats.syntax_tokens, 7);
+        assert_eq!(stats.syntax_bytes, 3 * 16 + 9 * 16 + 7 * 16 + 9);
+
+        let table = stats.to_string();
+        assert!(table.starts_with("Query "));
+        assert!(table.contains("\nparse  "));
+        assert!(table
+            .ends_with("Syntax trees: 1 (3 nodes, 7 tokens, about 0.3 KiB)\n"));
+    }
+}
@@ -2,8 +2,8 @@
 
 use colored::*;
 use helios_diagnostics::files::SimpleFiles;
-use helios_diagnostics::Diagnostic;
-use std::io::{self, Write};
+use helios_diagnostics::SyntaxColors;
+use std::io::{self, IsTerminal, Write};
 
 const LOGO_BANNER: &[&str] = &[
     r"          __   __     __              ",
@@ -49,6 +49,25 @@ fn print_logo_banner() -> io::Result<()> {
     Ok(())
 }
 
+/// Prints the given input again over the line it was typed on, with syntax
+/// highlighting.
+///
+/// This moves the cursor with ANSI escape codes, so it's only done when the
+/// input is typed in a terminal and the output is colorized.
+fn echo_input(
+    stdout: &mut io::Stdout,
+    input: &str,
+    colors: &SyntaxColors,
+) -> io::Result<()> {
+    let colorize = colored::control::SHOULD_COLORIZE.should_colorize();
+    if colorize && io::stdin().is_terminal() {
+        let input = helios_parser::highlight::to_ansi(input, colors);
+        writeln!(stdout, "\x1b[1A\r\x1b[2K{}{}", "> ".blue(), input)?;
+    }
+
+    Ok(())
+}
+
 fn start_main_loop() -> io::Result<()> {
     print_logo_banner()?;
 
@@ -57,6 +76,7 @@ fn start_main_loop() -> io::Result<()> {
 
     let mut input = String::new();
     let mut files = SimpleFiles::new();
+    let theme = helios_parser::highlight::theme();
 
     loop {
         write!(stdout, "{}", "> ".blue())?;
@@ -83,25 +103,24 @@ fn start_main_loop() -> io::Result<()> {
 
             println!()
         } else {
+            echo_input(&mut stdout, input.trim_end(), &theme.syntax)?;
             let file_id = files.add("<repl>", input.to_string());
             let file = files.get(file_id).unwrap();
 
             let parse = helios_parser::parse(file_id, file.source());
             println!("{}", parse.debug_tree().cyan());
 
-            let mut emitted_ranges = Vec::new();
-            for message in parse.messages() {
-                let diagnostic = Diagnostic::from(message);
-
-                if !(emitted_ranges.contains(&diagnostic.location)) {
-                    emitted_ranges.push(diagnostic.location.clone());
-                    helios_diagnostics::emit(&mut stdout, &files, &diagnostic)
-                        .expect("Failed to print diagnostic");
-                }
+            for diagnostic in parse.diagnostics() {
+                helios_diagnostics::emit_with_theme(
+                    &mut stdout,
+                    &files,
+                    diagnostic,
+                    &theme,
+                )
+                .expect("Failed to print diagnostic");
             }
         }
 
-
         input.clear();
     }
 
@@ -0,0 +1,381 @@
+//! Structural search: finding the code that matches a pattern.
+//!
+//! A pattern is Helios code (a single expression or declaration) where some
+//! parts are placeholders, written as `$` followed by a name. For example,
+//! `$a + $a` matches the additions of an expression to itself, such as
+//! `x + x` or `(f x) + (f x)`. A placeholder matches any expression (or any
+//! type, or any name, depending on where it is), but the same placeholder
+//! must match the same code everywhere it appears.
+//!
+//! The code is compared by its syntax tree, so the whitespace and comments
+//! (and the way identifiers are written, see [`normalize_identifier`]) don't
+//! matter. This is meant for prototyping lints and for refactoring tools,
+//! which can find the code to change with a pattern rather than by walking
+//! the syntax tree themselves.
+
+use helios_diagnostics::Severity;
+use helios_parser::normalize_identifier;
+use helios_syntax::{SyntaxElement, SyntaxKind, SyntaxNode, SyntaxToken};
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::ops::Range;
+
+/// The prefix of the identifiers that placeholders are replaced with before
+/// the pattern is parsed (which can't be written by accident).
+const PLACEHOLDER_PREFIX: &str = "__ssr_";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PatternError {
+    /// A `$` isn't followed by the name of a placeholder.
+    MissingPlaceholderName { offset: usize },
+    /// The pattern isn't a single expression or declaration.
+    NotSingle,
+    /// The pattern has a syntax error (with the given title).
+    Syntax(String),
+}
+
+impl Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingPlaceholderName { offset } => write!(
+                f,
+                "Expected the name of a placeholder after the `$` at offset {}",
+                offset
+            ),
+            Self::NotSingle => {
+                write!(f, "Expected a single expression or declaration")
+            }
+            Self::Syntax(title) => write!(f, "Invalid pattern: {}", title),
+        }
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+/// A parsed pattern (see the [module documentation](self)).
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    node: SyntaxNode,
+    /// The names of the placeholders, by the identifiers replacing them.
+    placeholders: BTreeMap<String, String>,
+}
+
+impl Pattern {
+    pub fn parse(text: &str) -> Result<Self, PatternError> {
+        let mut source = String::new();
+        let mut placeholders = BTreeMap::new();
+
+        let mut chars = text.char_indices().peekable();
+        while let Some((offset, c)) = chars.next() {
+            if c != '$' {
+                source.push(c);
+                continue;
+            }
+
+            let mut name = String::new();
+            while let Some(&(_, c)) = chars.peek() {
+                if c.is_alphanumeric() || c == '_' {
+                    name.push(c);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+
+            if name.is_empty() {
+                return Err(PatternError::MissingPlaceholderName { offset });
+            }
+
+            let identifier = format!("{}{}", PLACEHOLDER_PREFIX, name);
+            source.push_str(&identifier);
+            placeholders.insert(identifier, name);
+        }
+
+        let parse = helios_parser::parse(0usize, &source);
+        if let Some(error) = parse
+            .diagnostics()
+            .iter()
+            .find(|it| it.severity >= Severity::Error)
+        {
+            return Err(PatternError::Syntax(error.title.clone()));
+        }
+
+        let mut nodes = parse.syntax().children();
+        match (nodes.next(), nodes.next()) {
+            (Some(node), None) => Ok(Self { node, placeholders }),
+            _ => Err(PatternError::NotSingle),
+        }
+    }
+
+    /// The name of the placeholder that the given element is, if it is one.
+    ///
+    /// Placeholders are either identifiers (such as the name of a binding) or
+    /// nodes made only of one (such as a variable reference).
+    fn placeholder(&self, element: &SyntaxElement) -> Option<&str> {
+        let token = match element {
+            SyntaxElement::Token(token) => token.clone(),
+            SyntaxElement::Node(node) => {
+                let mut children = significant_children(node);
+                match (children.next(), children.next()) {
+                    (Some(SyntaxElement::Token(token)), None) => token,
+                    _ => return None,
+                }
+            }
+        };
+
+        if token.kind() != SyntaxKind::Identifier {
+            return None;
+        }
+
+        self.placeholders
+            .get(token.text().as_str())
+            .map(String::as_str)
+    }
+}
+
+/// A piece of code matching a [`Pattern`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Match {
+    pub node: SyntaxNode,
+    /// The code matched by each placeholder, by the names of the
+    /// placeholders.
+    pub placeholders: BTreeMap<String, SyntaxElement>,
+}
+
+impl Match {
+    /// The range of the matched code, without its leading and trailing
+    /// trivia.
+    pub fn range(&self) -> Range<usize> {
+        significant_range(&SyntaxElement::Node(self.node.clone()))
+    }
+}
+
+/// The range of the given element without its leading and trailing trivia.
+pub fn significant_range(element: &SyntaxElement) -> Range<usize> {
+    let tokens = significant_tokens(element);
+    match (tokens.first(), tokens.last()) {
+        (Some(first), Some(last)) => {
+            first.text_range().start().into()..last.text_range().end().into()
+        }
+        _ => {
+            let range = element.text_range();
+            range.start().into()..range.start().into()
+        }
+    }
+}
+
+/// Finds every node in the given tree that matches the given pattern, in
+/// preorder (so a match may contain other matches).
+pub fn search(pattern: &Pattern, root: &SyntaxNode) -> Vec<Match> {
+    root.descendants()
+        .filter(|node| node.kind() != SyntaxKind::Root)
+        .filter_map(|node| {
+            let mut placeholders = BTreeMap::new();
+            let is_match = matches(
+                pattern,
+                &SyntaxElement::Node(pattern.node.clone()),
+                &SyntaxElement::Node(node.clone()),
+                &mut placeholders,
+            );
+
+            if is_match {
+                Some(Match { node, placeholders })
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Determines if the given code matches the given element of the pattern,
+/// recording the code matched by the placeholders.
+fn matches(
+    pattern: &Pattern,
+    expected: &SyntaxElement,
+    actual: &SyntaxElement,
+    placeholders: &mut BTreeMap<String, SyntaxElement>,
+) -> bool {
+    if let Some(name) = pattern.placeholder(expected) {
+        let is_same_category = match (expected, actual) {
+            (SyntaxElement::Token(_), SyntaxElement::Token(token)) => {
+                token.kind() == SyntaxKind::Identifier
+            }
+            (SyntaxElement::Node(expected), SyntaxElement::Node(actual)) => {
+                let (expected, actual) = (expected.kind(), actual.kind());
+                (expected.is_expression() && actual.is_expression())
+                    || (expected.is_type() && actual.is_type())
+                    || expected == actual
+            }
+            _ => false,
+        };
+
+        if !is_same_category {
+            return false;
+        }
+
+        return match placeholders.get(name) {
+            Some(previous) => is_same_code(previous, actual),
+            None => {
+                placeholders.insert(name.to_string(), actual.clone());
+                true
+            }
+        };
+    }
+
+    match (expected, actual) {
+        (SyntaxElement::Token(expected), SyntaxElement::Token(actual)) => {
+            expected.kind() == actual.kind()
+                && token_text(expected) == token_text(actual)
+        }
+        (SyntaxElement::Node(expected), SyntaxElement::Node(actual)) => {
+            if expected.kind() != actual.kind() {
+                return false;
+            }
+
+            let mut expected = significant_children(expected);
+            let mut actual = significant_children(actual);
+            loop {
+                match (expected.next(), actual.next()) {
+                    (Some(expected), Some(actual)) => {
+                        if !matches(pattern, &expected, &actual, placeholders) {
+                            return false;
+                        }
+                    }
+                    (None, None) => return true,
+                    _ => return false,
+                }
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Determines if the given pieces of code are the same (apart from their
+/// trivia).
+fn is_same_code(a: &SyntaxElement, b: &SyntaxElement) -> bool {
+    let (a, b) = (significant_tokens(a), significant_tokens(b));
+    a.len() == b.len()
+        && a.iter().zip(&b).all(|(a, b)| {
+            a.kind() == b.kind() && token_text(a) == token_text(b)
+        })
+}
+
+/// The text that the given token is compared with, which is normalized for
+/// identifiers.
+fn token_text(token: &SyntaxToken) -> String {
+    if token.kind() == SyntaxKind::Identifier {
+        normalize_identifier(token.text()).into_owned()
+    } else {
+        token.text().to_string()
+    }
+}
+
+fn significant_tokens(element: &SyntaxElement) -> Vec<SyntaxToken> {
+    match element {
+        SyntaxElement::Token(token) => vec![token.clone()],
+        SyntaxElement::Node(node) => node
+            .descendants_with_tokens()
+            .filter_map(|it| it.into_token())
+            .filter(|token| !token.kind().is_trivia())
+            .collect(),
+    }
+}
+
+fn significant_children(
+    node: &SyntaxNode,
+) -> impl Iterator<Item = SyntaxElement> {
+    node.children_with_tokens()
+        .filter(|it| !it.kind().is_trivia())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Searches the given source for the given pattern, returning the text of
+    /// every match along with the text matched by its placeholders.
+    fn check(pattern: &str, source: &str) -> Vec<(String, Vec<String>)> {
+        let pattern = Pattern::parse(pattern).unwrap();
+        let root = helios_parser::parse(0usize, source).syntax();
+
+        let text = |element: &SyntaxElement| {
+            source[significant_range(element)].to_string()
+        };
+
+        search(&pattern, &root)
+            .into_iter()
+            .map(|it| {
+                let placeholders = it
+                    .placeholders
+                    .iter()
+                    .map(|(name, element)| {
+                        format!("{}={}", name, text(element))
+                    })
+                    .collect();
+                (source[it.range()].to_string(), placeholders)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_search_binary_expressions() {
+        assert_eq!(
+            check("$a + $b", "let x = 1 + 2 * 3\nlet y = x - 1"),
+            vec![(
+                "1 + 2 * 3".to_string(),
+                vec!["a=1", "b=2 * 3"]
+                    .into_iter()
+                    .map(String::from)
+                    .collect()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_search_repeated_placeholders() {
+        let matches = check("$a + $a", "let x = (y) + (y)\nlet z = y + w");
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].0, "(y) + (y)");
+
+        // Trivia doesn't matter
+        let matches = check("$a + $a", "let x = (y --| y\n) + (y)");
+        assert_eq!(matches.len(), 1);
+    }
+
+    #[test]
+    fn test_search_declarations() {
+        let matches =
+            check("var $name = $value", "var a = 1\nlet b = 2\nvar c = a");
+        let names = matches
+            .iter()
+            .map(|(_, placeholders)| placeholders[0].as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["name=a", "name=c"]);
+    }
+
+    #[test]
+    fn test_search_nested_matches() {
+        let matches = check("$f |> $g", "let a = 1 |> f |> g");
+        let texts = matches
+            .iter()
+            .map(|(text, _)| text.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(texts, vec!["1 |> f |> g", "1 |> f"]);
+    }
+
+    #[test]
+    fn test_invalid_patterns() {
+        assert_eq!(
+            Pattern::parse("$ + 1").unwrap_err(),
+            PatternError::MissingPlaceholderName { offset: 0 }
+        );
+        assert_eq!(
+            Pattern::parse("$a\n$b").unwrap_err(),
+            PatternError::NotSingle
+        );
+        assert!(matches!(
+            Pattern::parse("$a +").unwrap_err(),
+            PatternError::Syntax(_)
+        ));
+    }
+}
@@ -0,0 +1,161 @@
+//! Comparing syntax trees without their trivia.
+//!
+//! Trees that only differ by their trivia (such as a tree built with
+//! [`make`](crate::make) and the tree of its parsed text, or the tree of a
+//! reparsed file) are structurally equal. [`diff_trees`] finds where two
+//! trees aren't, which is more useful in a failing test than two complete
+//! debug trees.
+
+use crate::{SyntaxElement, SyntaxKind, SyntaxNode};
+use rowan::NodeOrToken;
+use std::fmt::{self, Display};
+
+/// The first difference between two syntax trees (in preorder), found by
+/// [`diff_trees`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TreeDiff {
+    /// The kinds of the nodes from the root to the node whose children
+    /// differ (which is empty if the roots themselves differ).
+    pub path: Vec<SyntaxKind>,
+    /// The element of the first tree, or `None` if it has fewer children.
+    pub expected: Option<SyntaxElement>,
+    /// The element of the second tree, or `None` if it has fewer children.
+    pub actual: Option<SyntaxElement>,
+}
+
+impl Display for TreeDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let describe = |element: &Option<SyntaxElement>| match element {
+            Some(NodeOrToken::Node(node)) => format!("{:?}", node),
+            Some(NodeOrToken::Token(token)) => format!("{:?}", token),
+            None => "nothing".to_string(),
+        };
+
+        if !self.path.is_empty() {
+            let path = self.path.iter().map(|kind| format!("{:?}", kind));
+            write!(f, "In {}: ", path.collect::<Vec<_>>().join(" > "))?;
+        }
+
+        write!(
+            f,
+            "expected {}, found {}",
+            describe(&self.expected),
+            describe(&self.actual)
+        )
+    }
+}
+
+/// Determines if the given trees are the same apart from their trivia.
+pub fn structurally_eq(a: &SyntaxNode, b: &SyntaxNode) -> bool {
+    diff_trees(a, b).is_none()
+}
+
+/// Finds the first difference between the given trees, leaving out their
+/// trivia. Nodes are compared by their kinds and children, and tokens by
+/// their kinds and text.
+pub fn diff_trees(
+    expected: &SyntaxNode,
+    actual: &SyntaxNode,
+) -> Option<TreeDiff> {
+    if expected.kind() != actual.kind() {
+        return Some(TreeDiff {
+            path: Vec::new(),
+            expected: Some(expected.clone().into()),
+            actual: Some(actual.clone().into()),
+        });
+    }
+
+    diff_children(&mut Vec::new(), expected, actual)
+}
+
+fn diff_children(
+    path: &mut Vec<SyntaxKind>,
+    expected: &SyntaxNode,
+    actual: &SyntaxNode,
+) -> Option<TreeDiff> {
+    path.push(expected.kind());
+
+    let significant = |node: &SyntaxNode| {
+        node.children_with_tokens()
+            .filter(|it| !it.kind().is_trivia())
+            .collect::<Vec<_>>()
+    };
+
+    let (expected, actual) = (significant(expected), significant(actual));
+    for index in 0..std::cmp::max(expected.len(), actual.len()) {
+        match (expected.get(index), actual.get(index)) {
+            (Some(NodeOrToken::Node(a)), Some(NodeOrToken::Node(b)))
+                if a.kind() == b.kind() =>
+            {
+                if let Some(diff) = diff_children(path, a, b) {
+                    return Some(diff);
+                }
+            }
+            (Some(NodeOrToken::Token(a)), Some(NodeOrToken::Token(b)))
+                if a.kind() == b.kind() && a.text() == b.text() => {}
+            (a, b) => {
+                return Some(TreeDiff {
+                    path: path.clone(),
+                    expected: a.cloned(),
+                    actual: b.cloned(),
+                })
+            }
+        }
+    }
+
+    path.pop();
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{make, Sym};
+
+    #[test]
+    fn test_diff_trees() {
+        let sum = |rhs| make::binary_expr(make::name_ref("a"), Sym!["+"], rhs);
+        let tree = |rhs| make::root(vec![make::let_binding("b", sum(rhs))]);
+
+        let a = tree(make::integer_literal(1));
+        assert_eq!(diff_trees(&a, &a.clone()), None);
+        assert!(structurally_eq(&a, &tree(make::integer_literal(1))));
+
+        let diff = diff_trees(&a, &tree(make::integer_literal(2))).unwrap();
+        assert_eq!(
+            diff.path,
+            vec![
+                SyntaxKind::Root,
+                SyntaxKind::Dec_GlobalBinding,
+                SyntaxKind::Exp_Binary,
+                SyntaxKind::Exp_Literal,
+            ]
+        );
+        assert_eq!(
+            diff.to_string(),
+            "In Root > Dec_GlobalBinding > Exp_Binary > Exp_Literal: \
+             expected Lit_Integer@12..13 \"1\", found Lit_Integer@12..13 \"2\""
+        );
+
+        let diff =
+            diff_trees(&sum(make::integer_literal(1)), &make::name_ref("a"));
+        assert_eq!(
+            diff.unwrap().to_string(),
+            "expected Exp_Binary@0..5, found Exp_VariableRef@0..1"
+        );
+    }
+
+    #[test]
+    fn test_diff_trees_ignores_trivia() {
+        let a = make::root(vec![make::name_ref("a"), make::name_ref("b")]);
+        let mut editor = crate::SyntaxEditor::new(a.clone());
+        editor.insert_after(a.first_child().unwrap(), make::whitespace("  "));
+        let (b, _) = editor.finish();
+        assert!(structurally_eq(&a, &b));
+
+        let c = make::root(vec![make::name_ref("a")]);
+        let diff = diff_trees(&a, &c).unwrap();
+        assert_eq!(diff.path, vec![SyntaxKind::Root]);
+        assert_eq!(diff.actual, None);
+    }
+}
@@ -0,0 +1,365 @@
+//! Editing syntax trees.
+//!
+//! Syntax trees are immutable, so a [`SyntaxEditor`] queues the changes to
+//! make to a tree and then builds a new tree with all of them at once. Along
+//! with the new tree, it returns the [`TextEdit`]s turning the original
+//! source text into the text of the new tree, which only touch the parts of
+//! the text that were changed (so editors can keep the cursor and the undo
+//! history of the rest of the document).
+//!
+//! # Examples
+//!
+//! ```rust
+//! use helios_syntax::{make, Sym, SyntaxEditor, SyntaxKind};
+//!
+//! let root = make::root(vec![make::var_binding("a", make::name_ref("b"))]);
+//! let binding = root.first_child().unwrap();
+//! let keyword = binding.first_token().unwrap();
+//! let value = binding.last_child().unwrap();
+//!
+//! let mut editor = SyntaxEditor::new(root);
+//! editor.replace(keyword, make::token(SyntaxKind::Kwd_Let));
+//! let one = make::integer_literal(1);
+//! editor.replace(value, make::binary_expr(make::name_ref("b"), Sym!["+"], one));
+//!
+//! let (root, edits) = editor.finish();
+//! assert_eq!(root.to_string(), "let a = b + 1");
+//! assert_eq!(edits.len(), 2);
+//! ```
+
+use crate::{SyntaxElement, SyntaxNode};
+use helios_text::TextEdit;
+use rowan::{GreenNode, GreenToken, NodeOrToken};
+use std::ops::Range;
+
+type GreenElement = NodeOrToken<GreenNode, GreenToken>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Position {
+    Before,
+    After,
+}
+
+#[derive(Clone, Debug)]
+enum Change {
+    Insert {
+        anchor: SyntaxElement,
+        position: Position,
+        new: SyntaxElement,
+    },
+    /// Replaces the element with the given one, or deletes it.
+    Replace {
+        old: SyntaxElement,
+        new: Option<SyntaxElement>,
+    },
+}
+
+impl Change {
+    fn target(&self) -> &SyntaxElement {
+        match self {
+            Self::Insert { anchor, .. } => anchor,
+            Self::Replace { old, .. } => old,
+        }
+    }
+}
+
+/// Queues changes to a syntax tree (see the [module documentation](self)).
+///
+/// The elements changed must be in the tree given to [`SyntaxEditor::new`],
+/// but they can't be its root. The new elements may come from any tree (such
+/// as the trees built by [`make`](crate::make)), and are copied into the new
+/// tree.
+#[derive(Clone, Debug)]
+pub struct SyntaxEditor {
+    root: SyntaxNode,
+    changes: Vec<Change>,
+}
+
+impl SyntaxEditor {
+    /// Constructs a new [`SyntaxEditor`] for the given tree.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the given node isn't the root of its tree.
+    pub fn new(root: SyntaxNode) -> Self {
+        assert!(root.parent().is_none(), "Expected the root of a tree");
+        Self {
+            root,
+            changes: Vec::new(),
+        }
+    }
+
+    /// Replaces the given element with another one.
+    pub fn replace(
+        &mut self,
+        old: impl Into<SyntaxElement>,
+        new: impl Into<SyntaxElement>,
+    ) {
+        let (old, new) = (old.into(), Some(new.into()));
+        self.push(Change::Replace { old, new });
+    }
+
+    /// Deletes the given element.
+    pub fn delete(&mut self, element: impl Into<SyntaxElement>) {
+        let old = element.into();
+        self.push(Change::Replace { old, new: None });
+    }
+
+    /// Inserts an element before the given one. The elements inserted before
+    /// the same element are inserted in order.
+    pub fn insert_before(
+        &mut self,
+        anchor: impl Into<SyntaxElement>,
+        new: impl Into<SyntaxElement>,
+    ) {
+        let (anchor, new) = (anchor.into(), new.into());
+        let position = Position::Before;
+        self.push(Change::Insert {
+            anchor,
+            position,
+            new,
+        });
+    }
+
+    /// Inserts an element after the given one. The elements inserted after
+    /// the same element are inserted in order.
+    pub fn insert_after(
+        &mut self,
+        anchor: impl Into<SyntaxElement>,
+        new: impl Into<SyntaxElement>,
+    ) {
+        let (anchor, new) = (anchor.into(), new.into());
+        let position = Position::After;
+        self.push(Change::Insert {
+            anchor,
+            position,
+            new,
+        });
+    }
+
+    /// Builds the new tree with every change, returning it along with the
+    /// edits turning the text of the original tree into the text of the new
+    /// one (sorted by their ranges).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the changes overlap, such as when an element is changed
+    /// inside another element that is replaced (or deleted).
+    pub fn finish(self) -> (SyntaxNode, Vec<TextEdit>) {
+        let mut edits = Vec::new();
+        let mut applied = 0;
+        let green = self.rebuild(&self.root, &mut edits, &mut applied);
+
+        assert_eq!(applied, self.changes.len(), "The changes must not overlap");
+        (SyntaxNode::new_root(green), edits)
+    }
+
+    fn push(&mut self, change: Change) {
+        let target = change.target();
+        let root = target.parent().and_then(|parent| parent.ancestors().last());
+        assert!(
+            root.as_ref() == Some(&self.root),
+            "Expected an element of the tree being edited (other than its root)"
+        );
+
+        self.changes.push(change);
+    }
+
+    /// Builds the given node with the changes made to its descendants,
+    /// recording the edits (in the order of the text) and the number of
+    /// changes made.
+    fn rebuild(
+        &self,
+        node: &SyntaxNode,
+        edits: &mut Vec<TextEdit>,
+        applied: &mut usize,
+    ) -> GreenNode {
+        let mut children = Vec::new();
+
+        for child in node.children_with_tokens() {
+            let range = text_range(&child);
+
+            for new in self.insertions(&child, Position::Before) {
+                edits.push(TextEdit::insert(range.start, text(new)));
+                children.push(green(new));
+                *applied += 1;
+            }
+
+            match self.replacement(&child) {
+                Some(new) => {
+                    let replacement = new.map(text).unwrap_or_default();
+                    edits.push(TextEdit::new(range.clone(), replacement));
+                    children.extend(new.map(green));
+                    *applied += 1;
+                }
+                None => match &child {
+                    NodeOrToken::Node(node) if self.is_changed_inside(node) => {
+                        let green = self.rebuild(node, edits, applied);
+                        children.push(NodeOrToken::Node(green));
+                    }
+                    child => children.push(green(child)),
+                },
+            }
+
+            for new in self.insertions(&child, Position::After) {
+                edits.push(TextEdit::insert(range.end, text(new)));
+                children.push(green(new));
+                *applied += 1;
+            }
+        }
+
+        GreenNode::new(node.kind().into(), children)
+    }
+
+    fn insertions<'a>(
+        &'a self,
+        element: &'a SyntaxElement,
+        position: Position,
+    ) -> impl Iterator<Item = &'a SyntaxElement> {
+        self.changes.iter().filter_map(move |change| match change {
+            Change::Insert {
+                anchor,
+                position: p,
+                new,
+            } if anchor == element && *p == position => Some(new),
+            _ => None,
+        })
+    }
+
+    fn replacement(
+        &self,
+        element: &SyntaxElement,
+    ) -> Option<Option<&SyntaxElement>> {
+        self.changes.iter().find_map(|change| match change {
+            Change::Replace { old, new } if old == element => {
+                Some(new.as_ref())
+            }
+            _ => None,
+        })
+    }
+
+    /// Determines if any change is made to a descendant of the given node.
+    fn is_changed_inside(&self, node: &SyntaxNode) -> bool {
+        self.changes.iter().any(|change| {
+            change.target().parent().is_some_and(|parent| {
+                parent.ancestors().any(|ancestor| ancestor == *node)
+            })
+        })
+    }
+}
+
+fn text_range(element: &SyntaxElement) -> Range<usize> {
+    let range = element.text_range();
+    range.start().into()..range.end().into()
+}
+
+fn text(element: &SyntaxElement) -> String {
+    match element {
+        NodeOrToken::Node(node) => node.to_string(),
+        NodeOrToken::Token(token) => token.text().to_string(),
+    }
+}
+
+fn green(element: &SyntaxElement) -> GreenElement {
+    match element {
+        NodeOrToken::Node(node) => NodeOrToken::Node(node.green().clone()),
+        NodeOrToken::Token(token) => NodeOrToken::Token(token.green().clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{make, Sym, SyntaxKind};
+    use helios_text::apply_edits;
+
+    /// Makes the changes with the given function to the tree of
+    /// `let a = 1\nvar b = a + c`, checking that the edits give the text of
+    /// the new tree.
+    fn check(
+        edit: impl FnOnce(&mut SyntaxEditor, &SyntaxNode),
+    ) -> (String, Vec<TextEdit>) {
+        let sum = make::binary_expr(
+            make::name_ref("a"),
+            Sym!["+"],
+            make::name_ref("c"),
+        );
+        let root = make::root(vec![
+            make::let_binding("a", make::integer_literal(1)),
+            make::var_binding("b", sum),
+        ]);
+
+        let mut editor = SyntaxEditor::new(root.clone());
+        edit(&mut editor, &root);
+        let (new_root, edits) = editor.finish();
+
+        let text = new_root.to_string();
+        assert_eq!(apply_edits(&root.to_string(), &edits), text);
+        (text, edits)
+    }
+
+    fn sum(root: &SyntaxNode) -> SyntaxNode {
+        root.descendants()
+            .find(|node| node.kind() == SyntaxKind::Exp_Binary)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_replace() {
+        let (text, edits) = check(|editor, root| {
+            let sum = sum(root);
+            editor.replace(sum.last_child().unwrap(), make::integer_literal(2));
+        });
+
+        assert_eq!(text, "let a = 1\nvar b = a + 2");
+        assert_eq!(edits, vec![TextEdit::new(22..23, "2")]);
+    }
+
+    #[test]
+    fn test_insert_and_delete() {
+        let (text, edits) = check(|editor, root| {
+            let first = root.first_child().unwrap();
+            let second = root.last_child().unwrap();
+            editor.insert_after(first, make::whitespace(" "));
+            editor.delete(second.last_child().unwrap());
+            editor.insert_before(second, make::name_ref("y"));
+        });
+
+        assert_eq!(text, "let a = 1 \nyvar b = ");
+        assert_eq!(
+            edits,
+            vec![
+                TextEdit::insert(9, " "),
+                TextEdit::insert(10, "y"),
+                TextEdit::delete(18..23),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_changes_in_order_of_text() {
+        let (text, edits) = check(|editor, root| {
+            let sum = sum(root);
+            editor.replace(sum.last_child().unwrap(), make::name_ref("d"));
+            editor.replace(sum.first_child().unwrap(), make::name_ref("e"));
+            editor.replace(root.first_child().unwrap(), make::name_ref("f"));
+        });
+
+        assert_eq!(text, "f\nvar b = e + d");
+        let ranges = edits
+            .iter()
+            .map(|edit| edit.range.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(ranges, vec![0..9, 18..19, 22..23]);
+    }
+
+    #[test]
+    #[should_panic(expected = "must not overlap")]
+    fn test_overlapping_changes() {
+        check(|editor, root| {
+            let sum = sum(root);
+            editor.delete(sum.first_child().unwrap());
+            editor.delete(sum);
+        });
+    }
+}
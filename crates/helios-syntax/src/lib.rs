@@ -1,13 +1,21 @@
+mod compare;
+mod edit;
 mod lang;
+pub mod make;
+mod ptr;
 mod repr;
 
+pub use compare::{diff_trees, structurally_eq, TreeDiff};
+pub use edit::SyntaxEditor;
 use helios_formatting::FormattedString;
 pub use lang::HeliosLanguage;
+pub use ptr::SyntaxNodePtr;
 use repr::{Article, HumanReadableRepr};
 use std::fmt::{self, Display};
 
 pub type SyntaxNode = rowan::SyntaxNode<HeliosLanguage>;
 pub type SyntaxToken = rowan::SyntaxToken<HeliosLanguage>;
+pub type SyntaxElement = rowan::SyntaxElement<HeliosLanguage>;
 
 /// A convenient way to construct new `SyntaxNode` symbols.
 ///
@@ -27,6 +35,7 @@ macro_rules! Sym {
     ["!"] => ($crate::SyntaxKind::Sym_Bang);
     ["!="]=> ($crate::SyntaxKind::Sym_BangEq);
     ["^"] => ($crate::SyntaxKind::Sym_Caret);
+    [":"] => ($crate::SyntaxKind::Sym_Colon);
     [","] => ($crate::SyntaxKind::Sym_Comma);
     ["$"] => ($crate::SyntaxKind::Sym_Dollar);
     ["."] => ($crate::SyntaxKind::Sym_Dot);
@@ -51,6 +60,10 @@ macro_rules! Sym {
     ["<-"]=> ($crate::SyntaxKind::Sym_LThinArrow);
     ["->"]=> ($crate::SyntaxKind::Sym_RThinArrow);
     ["=>"]=> ($crate::SyntaxKind::Sym_ThickArrow);
+    ["|>"]=> ($crate::SyntaxKind::Sym_PipeGt);
+    ["<|"]=> ($crate::SyntaxKind::Sym_LtPipe);
+    [".."]=> ($crate::SyntaxKind::Sym_DotDot);
+    ["..="]=> ($crate::SyntaxKind::Sym_DotDotEq);
 
     ["{"] => ($crate::SyntaxKind::Sym_LParen);
     ["}"] => ($crate::SyntaxKind::Sym_RParen);
@@ -72,12 +85,15 @@ pub enum SyntaxKind {
     Kwd_Else,
     Kwd_End,
     Kwd_Export,
+    Kwd_Extend,
     Kwd_External,
     Kwd_For,
     Kwd_Forall,
+    Kwd_Function,
     Kwd_If,
     Kwd_Import,
     Kwd_In,
+    Kwd_Interface,
     Kwd_Let,
     Kwd_Loop,
     Kwd_Match,
@@ -85,12 +101,15 @@ pub enum SyntaxKind {
     Kwd_Not,
     Kwd_Of,
     Kwd_Or,
+    Kwd_Public,
     Kwd_Rec,
     Kwd_Ref,
+    Kwd_Return,
     Kwd_Then,
     Kwd_Type,
     Kwd_Unimplemented,
     Kwd_Val,
+    Kwd_Var,
     Kwd_While,
     Kwd_With,
 
@@ -126,6 +145,10 @@ pub enum SyntaxKind {
     Sym_LThinArrow,
     Sym_RThinArrow,
     Sym_ThickArrow,
+    Sym_PipeGt,
+    Sym_LtPipe,
+    Sym_DotDot,
+    Sym_DotDotEq,
 
     Sym_LBrace,
     Sym_RBrace,
@@ -134,20 +157,49 @@ pub enum SyntaxKind {
     Sym_LParen,
     Sym_RParen,
 
+    Lay_Begin,
+    Lay_Newline,
+    Lay_End,
+
     Lit_Character,
     Lit_Float,
     Lit_Integer,
     Lit_String,
+    Lit_StringStart,
+    Lit_StringMiddle,
+    Lit_StringEnd,
 
+    Exp_Assign,
     Exp_Binary,
+    Exp_Block,
+    Exp_Interpolation,
+    Exp_Lambda,
     Exp_Literal,
+    Exp_Missing,
     Exp_Paren,
+    Exp_Range,
+    Exp_Return,
     Exp_UnaryPrefix,
     Exp_UnaryPostfix,
+    Exp_Unit,
     Exp_VariableRef,
     Exp_Unnamed,
 
     Dec_GlobalBinding,
+    Dec_External,
+    Dec_Extend,
+    Dec_Import,
+    Dec_Interface,
+    Dec_TypeEnum,
+
+    Typ_Named,
+
+    EnumVariant,
+    MethodSignature,
+    ModulePath,
+    Param,
+    ParamList,
+    Visibility,
 
     Comment,
     DocComment,
@@ -196,12 +248,18 @@ impl SyntaxKind {
 
     #[inline]
     pub fn is_expression(self) -> bool {
-        self >= SyntaxKind::Exp_Binary && self <= SyntaxKind::Exp_Unnamed
+        self >= SyntaxKind::Exp_Assign && self <= SyntaxKind::Exp_Unnamed
     }
 
     #[inline]
     pub fn is_declaration(self) -> bool {
-        self == SyntaxKind::Dec_GlobalBinding
+        self >= SyntaxKind::Dec_GlobalBinding
+            && self <= SyntaxKind::Dec_TypeEnum
+    }
+
+    #[inline]
+    pub fn is_type(self) -> bool {
+        self == SyntaxKind::Typ_Named
     }
 
     #[inline]
@@ -228,6 +286,9 @@ impl SyntaxKind {
     pub fn article(self) -> Article {
         match self {
             kind if kind.is_keyword() => Article::The,
+            SyntaxKind::Lay_Begin
+            | SyntaxKind::Lay_Newline
+            | SyntaxKind::Lay_End => Article::The,
             SyntaxKind::Sym_Ampersand
             | SyntaxKind::Sym_Asterisk
             | SyntaxKind::Sym_At
@@ -238,8 +299,18 @@ impl SyntaxKind {
             | SyntaxKind::Sym_LBrace
             | SyntaxKind::Sym_LBracket
             | SyntaxKind::Sym_LParen
+            | SyntaxKind::Sym_DotDotEq
             | SyntaxKind::Lit_Integer
+            | SyntaxKind::Lit_StringStart
+            | SyntaxKind::Exp_Assign
+            | SyntaxKind::Exp_Interpolation
             | SyntaxKind::Exp_Unnamed
+            | SyntaxKind::Dec_External
+            | SyntaxKind::Dec_Extend
+            | SyntaxKind::Dec_Import
+            | SyntaxKind::Dec_Interface
+            | SyntaxKind::Dec_TypeEnum
+            | SyntaxKind::EnumVariant
             | SyntaxKind::Identifier
             | SyntaxKind::UnknownChar
             | SyntaxKind::Error => Article::An,
@@ -255,6 +326,9 @@ impl SyntaxKind {
             SyntaxKind::Sym_RBrace => "closing curly",
             SyntaxKind::Sym_RBracket => "closing square",
             SyntaxKind::Sym_RParen => "closing",
+            SyntaxKind::Lit_StringStart => "opening",
+            SyntaxKind::Lit_StringMiddle => "middle",
+            SyntaxKind::Lit_StringEnd => "closing",
             _ => return None,
         };
 
@@ -270,12 +344,15 @@ impl SyntaxKind {
             SyntaxKind::Kwd_Else => "else",
             SyntaxKind::Kwd_End => "end",
             SyntaxKind::Kwd_Export => "export",
+            SyntaxKind::Kwd_Extend => "extend",
             SyntaxKind::Kwd_External => "external",
             SyntaxKind::Kwd_For => "for",
             SyntaxKind::Kwd_Forall => "forall",
+            SyntaxKind::Kwd_Function => "function",
             SyntaxKind::Kwd_If => "if",
             SyntaxKind::Kwd_Import => "import",
             SyntaxKind::Kwd_In => "in",
+            SyntaxKind::Kwd_Interface => "interface",
             SyntaxKind::Kwd_Let => "let",
             SyntaxKind::Kwd_Loop => "loop",
             SyntaxKind::Kwd_Match => "match",
@@ -283,12 +360,15 @@ impl SyntaxKind {
             SyntaxKind::Kwd_Not => "not",
             SyntaxKind::Kwd_Of => "of",
             SyntaxKind::Kwd_Or => "or",
+            SyntaxKind::Kwd_Public => "public",
             SyntaxKind::Kwd_Rec => "rec",
             SyntaxKind::Kwd_Ref => "ref",
+            SyntaxKind::Kwd_Return => "return",
             SyntaxKind::Kwd_Then => "then",
             SyntaxKind::Kwd_Type => "type",
             SyntaxKind::Kwd_Unimplemented => "unimplemented",
             SyntaxKind::Kwd_Val => "val",
+            SyntaxKind::Kwd_Var => "var",
             SyntaxKind::Kwd_While => "while",
             SyntaxKind::Kwd_With => "with",
 
@@ -323,6 +403,10 @@ impl SyntaxKind {
             SyntaxKind::Sym_LThinArrow => "leftwards thin arrow",
             SyntaxKind::Sym_RThinArrow => "rightwards thin arrow",
             SyntaxKind::Sym_ThickArrow => "thick arrow",
+            SyntaxKind::Sym_PipeGt => "pipeline",
+            SyntaxKind::Sym_LtPipe => "backward pipeline",
+            SyntaxKind::Sym_DotDot => "range",
+            SyntaxKind::Sym_DotDotEq => "inclusive range",
             SyntaxKind::Sym_LBrace | SyntaxKind::Sym_RBrace => "brace",
             SyntaxKind::Sym_LBracket | SyntaxKind::Sym_RBracket => "bracket",
             SyntaxKind::Sym_LParen | SyntaxKind::Sym_RParen => "parenthesis",
@@ -330,13 +414,27 @@ impl SyntaxKind {
             SyntaxKind::Lit_Float => "float",
             SyntaxKind::Lit_Integer => "integer",
             SyntaxKind::Lit_String => "string",
+            SyntaxKind::Exp_Assign => "assignment",
             SyntaxKind::Exp_Binary => "binary",
+            SyntaxKind::Exp_Block => "block",
+            SyntaxKind::Exp_Interpolation => "interpolated string",
+            SyntaxKind::Exp_Lambda => "lambda",
             SyntaxKind::Exp_Literal => "literal",
+            SyntaxKind::Exp_Missing => "missing",
             SyntaxKind::Exp_Paren => "parenthesized",
+            SyntaxKind::Exp_Range => "range",
+            SyntaxKind::Exp_Return => "return",
             SyntaxKind::Exp_UnaryPrefix => "unary prefixed",
             SyntaxKind::Exp_UnaryPostfix => "unary postfixed",
+            SyntaxKind::Exp_Unit => "unit",
             SyntaxKind::Exp_VariableRef => "variable reference",
             SyntaxKind::Dec_GlobalBinding => "global binding",
+            SyntaxKind::Dec_External => "external",
+            SyntaxKind::Dec_Extend => "extension",
+            SyntaxKind::Dec_Import => "import",
+            SyntaxKind::Dec_Interface => "interface",
+            SyntaxKind::Dec_TypeEnum => "enum type",
+            SyntaxKind::Typ_Named => "named",
             SyntaxKind::DocComment => "documentation",
             SyntaxKind::ReservedIdentifier => "reserved",
             _ => return None,
@@ -352,9 +450,24 @@ impl SyntaxKind {
             kind if kind.is_literal() => "literal",
             kind if kind.is_expression() => "expression",
             kind if kind.is_declaration() => "declaration",
+            kind if kind.is_type() => "type",
             kind if kind.is_comment() => "comment",
             kind if kind.is_identifier() => "identifier",
+            SyntaxKind::Lit_StringStart
+            | SyntaxKind::Lit_StringMiddle
+            | SyntaxKind::Lit_StringEnd => "string fragment",
+            SyntaxKind::Lay_Begin => "start of an indented block",
+            SyntaxKind::Lay_Newline => {
+                "start of a new line in an indented block"
+            }
+            SyntaxKind::Lay_End => "end of an indented block",
             SyntaxKind::Whitespace => "whitespace",
+            SyntaxKind::EnumVariant => "enum variant",
+            SyntaxKind::MethodSignature => "method signature",
+            SyntaxKind::ModulePath => "module path",
+            SyntaxKind::Param => "parameter",
+            SyntaxKind::ParamList => "parameter list",
+            SyntaxKind::Visibility => "visibility modifier",
             SyntaxKind::UnknownChar => "unknown character",
             SyntaxKind::Error => "error",
             _ => unreachable!("Unreachable kind: {:?}", self),
@@ -396,6 +509,10 @@ impl SyntaxKind {
             SyntaxKind::Sym_LThinArrow => "<-",
             SyntaxKind::Sym_RThinArrow => "->",
             SyntaxKind::Sym_ThickArrow => "=>",
+            SyntaxKind::Sym_PipeGt => "|>",
+            SyntaxKind::Sym_LtPipe => "<|",
+            SyntaxKind::Sym_DotDot => "..",
+            SyntaxKind::Sym_DotDotEq => "..=",
             SyntaxKind::Sym_LBrace => "{",
             SyntaxKind::Sym_RBrace => "}",
             SyntaxKind::Sym_LBracket => "[",
@@ -435,10 +552,13 @@ impl Display for SyntaxKind {
 }
 
 /// An array of all the keywords defined in the Helios grammar.
+#[rustfmt::skip]
 pub const KEYWORDS: &[&str] = &[
-    "alias", "and", "as", "begin", "else", "end", "export", "external", "for",
-    "forall", "if", "import", "in", "let", "loop", "match", "module", "not",
-    "of", "or", "rec", "ref", "then", "type", "val", "while", "with",
+    "alias", "and", "as", "begin", "else", "end", "export", "extend",
+    "external", "for", "forall", "function", "if", "import", "in",
+    "interface", "let", "loop", "match", "module", "not", "of", "or",
+    "public", "rec", "ref", "return", "then", "type", "val", "var", "while",
+    "with",
 ];
 
 /// Create a new symbol variant of [`SyntaxKind`] that corresponds to the given
@@ -512,6 +632,9 @@ pub fn symbol_from_chars(chars: &[char]) -> Option<SyntaxKind> {
         ['<', '-'] => Some(SyntaxKind::Sym_LThinArrow),
         ['-', '>'] => Some(SyntaxKind::Sym_RThinArrow),
         ['=', '>'] => Some(SyntaxKind::Sym_ThickArrow),
+        ['|', '>'] => Some(SyntaxKind::Sym_PipeGt),
+        ['<', '|'] => Some(SyntaxKind::Sym_LtPipe),
+        ['.', '.'] => Some(SyntaxKind::Sym_DotDot),
         _ => None,
     }
 }
@@ -572,6 +695,9 @@ mod tests {
         check!(['<', '-'] => Sym_LThinArrow);
         check!(['-', '>'] => Sym_RThinArrow);
         check!(['=', '>'] => Sym_ThickArrow);
+        check!(['|', '>'] => Sym_PipeGt);
+        check!(['<', '|'] => Sym_LtPipe);
+        check!(['.', '.'] => Sym_DotDot);
     }
 
     #[test]
@@ -611,11 +737,16 @@ mod tests {
         check(Kwd_Match, "the match keyword");
         check(Kwd_With, "the with keyword");
         check(Kwd_Unimplemented, "the unimplemented keyword");
+        check(Kwd_Var, "the var keyword");
+        check(Kwd_Return, "the return keyword");
+        check(Kwd_Interface, "the interface keyword");
 
         check(Sym_Ampersand, "an ampersand symbol (`&`)");
         check(Sym_ForwardSlash, "a forward slash symbol (`/`)");
         check(Sym_Lt, "a less than symbol (`<`)");
         check(Sym_LtEq, "a less than equal symbol (`<=`)");
+        check(Sym_DotDot, "a range symbol (`..`)");
+        check(Sym_DotDotEq, "an inclusive range symbol (`..=`)");
 
         check(Sym_LBrace, "an opening curly brace symbol (`{`)");
         check(Sym_LBracket, "an opening square bracket symbol (`[`)");
@@ -629,15 +760,40 @@ mod tests {
         check(Lit_Integer, "an integer literal (such as `123`)");
         check(Lit_String, "a string literal (such as `\"hello, world!\"`)");
 
+        check(Exp_Assign, "an assignment expression");
         check(Exp_Binary, "a binary expression");
+        check(Exp_Block, "a block expression");
+        check(Exp_Lambda, "a lambda expression");
         check(Exp_Literal, "a literal expression");
         check(Exp_Paren, "a parenthesized expression");
+        check(Exp_Range, "a range expression");
+        check(Exp_Return, "a return expression");
         check(Exp_UnaryPrefix, "a unary prefixed expression");
         check(Exp_UnaryPostfix, "a unary postfixed expression");
+        check(Exp_Missing, "a missing expression");
+        check(Exp_Unit, "a unit expression");
         check(Exp_VariableRef, "a variable reference expression");
         check(Exp_Unnamed, "an expression");
 
         check(Dec_GlobalBinding, "a global binding declaration");
+        check(Dec_External, "an external declaration");
+        check(Dec_Extend, "an extension declaration");
+        check(Dec_Import, "an import declaration");
+        check(Dec_Interface, "an interface declaration");
+        check(Dec_TypeEnum, "an enum type declaration");
+
+        check(Typ_Named, "a named type");
+
+        check(EnumVariant, "an enum variant");
+        check(MethodSignature, "a method signature");
+        check(ModulePath, "a module path");
+        check(Param, "a parameter");
+        check(ParamList, "a parameter list");
+        check(Visibility, "a visibility modifier");
+
+        check(Lay_Begin, "the start of an indented block");
+        check(Lay_Newline, "the start of a new line in an indented block");
+        check(Lay_End, "the end of an indented block");
 
         check(Comment, "a comment");
         check(DocComment, "a documentation comment");
@@ -0,0 +1,238 @@
+//! Constructing syntax trees programmatically.
+//!
+//! Every function of this module builds a new tree (whose root is the node
+//! returned), with single spaces between its tokens, so that code actions and
+//! code generators can produce syntax without formatting source text and
+//! parsing it. The nodes given to these functions are copied into the new
+//! tree, so they may come from any other tree.
+//!
+//! The trees are built as they would be parsed, except that the precedence of
+//! operators isn't known here: an operand that binds more loosely than its
+//! operator must be wrapped with [`paren_expr`].
+//!
+//! # Examples
+//!
+//! ```rust
+//! use helios_syntax::{make, Sym};
+//!
+//! let sum = make::binary_expr(
+//!     make::integer_literal(1),
+//!     Sym!["+"],
+//!     make::name_ref("a"),
+//! );
+//! let binding = make::let_binding("b", sum);
+//! assert_eq!(binding.to_string(), "let b = 1 + a");
+//! ```
+
+use crate::{SyntaxKind, SyntaxNode, SyntaxToken};
+use rowan::{GreenNode, GreenToken, NodeOrToken, SmolStr};
+
+type GreenElement = NodeOrToken<GreenNode, GreenToken>;
+
+/// A reference to a variable, such as `foo`.
+pub fn name_ref(name: &str) -> SyntaxNode {
+    let children = vec![green_token(SyntaxKind::Identifier, name)];
+    node(SyntaxKind::Exp_VariableRef, children)
+}
+
+/// An integer literal, such as `42`.
+pub fn integer_literal(value: u64) -> SyntaxNode {
+    let children =
+        vec![green_token(SyntaxKind::Lit_Integer, &value.to_string())];
+    node(SyntaxKind::Exp_Literal, children)
+}
+
+/// An expression wrapped in parentheses, such as `(a + b)`.
+pub fn paren_expr(expr: SyntaxNode) -> SyntaxNode {
+    let children = vec![
+        green_token(SyntaxKind::Sym_LParen, "("),
+        green(&expr),
+        green_token(SyntaxKind::Sym_RParen, ")"),
+    ];
+    node(SyntaxKind::Exp_Paren, children)
+}
+
+/// A binary expression with the given operator, such as `a + b` or `a and b`.
+///
+/// # Panics
+///
+/// Panics if the operator isn't a symbol or a keyword.
+pub fn binary_expr(
+    lhs: SyntaxNode,
+    operator: SyntaxKind,
+    rhs: SyntaxNode,
+) -> SyntaxNode {
+    let children = vec![
+        green(&lhs),
+        space(),
+        green_token(operator, &token_text(operator)),
+        space(),
+        green(&rhs),
+    ];
+    node(SyntaxKind::Exp_Binary, children)
+}
+
+/// A global binding defined with `let`, such as `let a = 1`.
+pub fn let_binding(name: &str, value: SyntaxNode) -> SyntaxNode {
+    global_binding(SyntaxKind::Kwd_Let, name, value)
+}
+
+/// A global binding defined with `var`, such as `var a = 1`.
+pub fn var_binding(name: &str, value: SyntaxNode) -> SyntaxNode {
+    global_binding(SyntaxKind::Kwd_Var, name, value)
+}
+
+/// The given symbol or keyword, such as `+` or `let`.
+///
+/// # Panics
+///
+/// Panics if the given kind isn't a symbol or a keyword.
+pub fn token(kind: SyntaxKind) -> SyntaxToken {
+    standalone_token(kind, &token_text(kind))
+}
+
+/// Whitespace with the given text (such as a line feed).
+///
+/// # Panics
+///
+/// Panics if the given text isn't only whitespace.
+pub fn whitespace(text: &str) -> SyntaxToken {
+    assert!(
+        text.chars().all(char::is_whitespace),
+        "Expected whitespace, found {:?}",
+        text
+    );
+    standalone_token(SyntaxKind::Whitespace, text)
+}
+
+/// A source file with the given items, each on its own line.
+pub fn root(items: impl IntoIterator<Item = SyntaxNode>) -> SyntaxNode {
+    let mut children = Vec::new();
+    for item in items {
+        if !children.is_empty() {
+            children.push(green_token(SyntaxKind::Whitespace, "\n"));
+        }
+        children.push(green(&item));
+    }
+
+    node(SyntaxKind::Root, children)
+}
+
+fn global_binding(
+    keyword: SyntaxKind,
+    name: &str,
+    value: SyntaxNode,
+) -> SyntaxNode {
+    let children = vec![
+        green_token(keyword, &token_text(keyword)),
+        space(),
+        green_token(SyntaxKind::Identifier, name),
+        space(),
+        green_token(SyntaxKind::Sym_Eq, "="),
+        space(),
+        green(&value),
+    ];
+    node(SyntaxKind::Dec_GlobalBinding, children)
+}
+
+/// The text of the given symbol or keyword.
+fn token_text(kind: SyntaxKind) -> String {
+    if kind.is_keyword() {
+        let name = format!("{:?}", kind);
+        name.trim_start_matches("Kwd_").to_lowercase()
+    } else if let Some(text) = kind.code_repr().filter(|_| kind.is_symbol()) {
+        text
+    } else {
+        panic!("Expected a symbol or a keyword, found {:?}", kind)
+    }
+}
+
+/// A token in a tree of its own (since rowan trees can only have nodes at
+/// their roots).
+fn standalone_token(kind: SyntaxKind, text: &str) -> SyntaxToken {
+    let root = node(SyntaxKind::Root, vec![green_token(kind, text)]);
+    root.first_token().unwrap()
+}
+
+fn node(kind: SyntaxKind, children: Vec<GreenElement>) -> SyntaxNode {
+    SyntaxNode::new_root(GreenNode::new(kind.into(), children))
+}
+
+fn green_token(kind: SyntaxKind, text: &str) -> GreenElement {
+    NodeOrToken::Token(GreenToken::new(kind.into(), SmolStr::new(text)))
+}
+
+fn space() -> GreenElement {
+    green_token(SyntaxKind::Whitespace, " ")
+}
+
+fn green(node: &SyntaxNode) -> GreenElement {
+    NodeOrToken::Node(node.green().clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sym;
+    use expect_test::expect;
+
+    #[test]
+    fn test_binary_expr() {
+        let expr = make_sum();
+        assert_eq!(expr.to_string(), "(1 + a) * a");
+
+        expect![[r#"
+            Exp_Binary@0..11
+              Exp_Paren@0..7
+                Sym_LParen@0..1 "("
+                Exp_Binary@1..6
+                  Exp_Literal@1..2
+                    Lit_Integer@1..2 "1"
+                  Whitespace@2..3 " "
+                  Sym_Plus@3..4 "+"
+                  Whitespace@4..5 " "
+                  Exp_VariableRef@5..6
+                    Identifier@5..6 "a"
+                Sym_RParen@6..7 ")"
+              Whitespace@7..8 " "
+              Sym_Asterisk@8..9 "*"
+              Whitespace@9..10 " "
+              Exp_VariableRef@10..11
+                Identifier@10..11 "a"
+        "#]]
+        .assert_eq(&format!("{:#?}", expr));
+
+        let expr =
+            binary_expr(name_ref("a"), SyntaxKind::Kwd_And, name_ref("b"));
+        assert_eq!(expr.to_string(), "a and b");
+    }
+
+    #[test]
+    fn test_token() {
+        let token = token(SyntaxKind::Kwd_Unimplemented);
+        assert_eq!(token.text(), "unimplemented");
+        assert_eq!(token.parent().kind(), SyntaxKind::Root);
+        assert_eq!(whitespace("\n  ").kind(), SyntaxKind::Whitespace);
+    }
+
+    #[test]
+    fn test_root() {
+        let root = root(vec![
+            let_binding("a", integer_literal(1)),
+            var_binding("b", make_sum()),
+        ]);
+        assert_eq!(root.kind(), SyntaxKind::Root);
+        assert_eq!(root.to_string(), "let a = 1\nvar b = (1 + a) * a");
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected a symbol or a keyword")]
+    fn test_binary_expr_with_invalid_operator() {
+        binary_expr(name_ref("a"), SyntaxKind::Identifier, name_ref("b"));
+    }
+
+    fn make_sum() -> SyntaxNode {
+        let sum = binary_expr(integer_literal(1), Sym!["+"], name_ref("a"));
+        binary_expr(paren_expr(sum), Sym!["*"], name_ref("a"))
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{SyntaxKind, SyntaxNode};
+use rowan::TextRange;
+
+/// A reference to a node of a syntax tree that doesn't keep the tree alive,
+/// made of the kind and the range of the node.
+///
+/// A pointer can be stored in the results of queries (unlike a node), and it
+/// finds the node again in a tree parsed from the same text. It doesn't point
+/// to the same node after an edit that moves or changes the node, so
+/// references that must survive edits should use ids that don't depend on
+/// ranges (such as the ids of an `AstIdMap`), and only resolve them to
+/// pointers in the latest tree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SyntaxNodePtr {
+    kind: SyntaxKind,
+    range: TextRange,
+}
+
+impl SyntaxNodePtr {
+    pub fn new(node: &SyntaxNode) -> Self {
+        Self {
+            kind: node.kind(),
+            range: node.text_range(),
+        }
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+
+    pub fn range(&self) -> TextRange {
+        self.range
+    }
+
+    /// Finds the node in the given tree, if it has a node of the same kind at
+    /// the same range.
+    pub fn try_to_node(&self, root: &SyntaxNode) -> Option<SyntaxNode> {
+        if !root.text_range().contains_range(self.range) {
+            return None;
+        }
+
+        // The innermost element covering the range is inside the node (or is
+        // the node itself)
+        let element = root.covering_element(self.range);
+        let start = match element {
+            rowan::NodeOrToken::Node(node) => node,
+            rowan::NodeOrToken::Token(token) => token.parent(),
+        };
+
+        start.ancestors().find(|node| {
+            node.kind() == self.kind && node.text_range() == self.range
+        })
+    }
+
+    /// Finds the node in the given tree.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tree doesn't have a node of the same kind at the same
+    /// range (such as a tree parsed from a different text).
+    pub fn to_node(&self, root: &SyntaxNode) -> SyntaxNode {
+        self.try_to_node(root).unwrap_or_else(|| {
+            panic!("Expected {:?} at {:?} in the tree", self.kind, self.range)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{make, Sym};
+
+    #[test]
+    fn test_ptr_to_node() {
+        let one = make::integer_literal(1);
+        let sum = make::binary_expr(one, Sym!["+"], make::name_ref("b"));
+        let root = make::root(vec![make::let_binding("a", sum)]);
+
+        for node in root.descendants() {
+            let ptr = SyntaxNodePtr::new(&node);
+            assert_eq!(ptr.kind(), node.kind());
+            assert_eq!(ptr.to_node(&root), node);
+        }
+
+        // The tree of a different text doesn't have the node
+        let literal = root.descendants().last().unwrap();
+        let ptr = SyntaxNodePtr::new(&literal);
+        let other = make::root(vec![make::name_ref("a")]);
+        assert_eq!(ptr.try_to_node(&other), None);
+    }
+}
@@ -0,0 +1,550 @@
+//! Positions in source text, shared by every crate of the compiler.
+//!
+//! Ranges in source text are byte offsets everywhere (from the tokens of the
+//! lexer to the locations of diagnostics), and are only converted to lines
+//! and columns (a [`LineCol`]) with a [`LineIndex`] when they are shown to
+//! the user or sent to an editor.
+
+pub mod rope;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Display};
+use std::ops::Range;
+
+pub use crate::rope::{Rope, RopeSlice};
+pub use text_size::{TextRange, TextSize};
+
+/// A zero-based line and column in a source text, where the column counts
+/// characters (not bytes) from the start of the line.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct LineCol {
+    pub line: usize,
+    pub col: usize,
+}
+
+impl LineCol {
+    /// Constructs a new [`LineCol`] with the given line and column.
+    pub fn new(line: usize, col: usize) -> Self {
+        Self { line, col }
+    }
+}
+
+/// The offsets of the start of every line in a source text, for converting
+/// byte offsets to [`LineCol`]s and back.
+///
+/// A source text with `n` line feeds has `n + 1` lines (the last of which may
+/// be empty). The methods taking a line also accept the line one past the
+/// last one, which starts (and ends) at the end of the source text.
+///
+/// The index also records the characters of every line that are longer than
+/// a byte, so that it can convert offsets to the columns of the LSP (which
+/// count UTF-16 code units) and back without the source text.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct LineIndex {
+    line_starts: Vec<usize>,
+    /// The characters longer than a byte, by line (for the lines that have
+    /// any), in order.
+    wide_chars: BTreeMap<usize, Vec<WideChar>>,
+    /// The lines ending with a carriage return and a line feed, whose
+    /// carriage return belongs to the line ending rather than to the line.
+    crlf_lines: BTreeSet<usize>,
+    len: usize,
+}
+
+/// A character that is more than one byte long in UTF-8.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct WideChar {
+    /// The offset of the character from the start of its line.
+    start: usize,
+    /// The length of the character in UTF-8, which is 2 to 4 bytes.
+    len: usize,
+}
+
+impl WideChar {
+    /// The length of the character in UTF-16 code units (characters of four
+    /// bytes are encoded as surrogate pairs).
+    fn len_utf16(&self) -> usize {
+        if self.len == 4 {
+            2
+        } else {
+            1
+        }
+    }
+}
+
+/// A position that isn't in a source text, given to
+/// [`LineIndex::try_utf16_offset`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PositionError {
+    /// The line is past the line one past the last one.
+    LineOutOfBounds { line: usize, line_count: usize },
+    /// The column is past the end of its line, which has the given length
+    /// (without its line feed).
+    ColumnOutOfBounds { line_col: LineCol, line_len: usize },
+}
+
+impl Display for PositionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LineOutOfBounds { line, line_count } => write!(
+                f,
+                "Line {} is out of bounds (the text has {} lines)",
+                line, line_count
+            ),
+            Self::ColumnOutOfBounds { line_col, line_len } => write!(
+                f,
+                "Column {} is out of bounds (line {} has {} columns)",
+                line_col.col, line_col.line, line_len
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PositionError {}
+
+impl LineIndex {
+    /// Constructs the [`LineIndex`] of the given source text.
+    pub fn new(source: &str) -> Self {
+        Self::from_chunks(Some(source))
+    }
+
+    /// Constructs the [`LineIndex`] of the text of the given [`Rope`],
+    /// without copying the text.
+    pub fn from_rope(rope: &Rope) -> Self {
+        Self::from_chunks(rope.chunks())
+    }
+
+    fn from_chunks<'a>(chunks: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut index = Self {
+            line_starts: vec![0],
+            ..Self::default()
+        };
+
+        // The carriage return may end the chunk before the line feed
+        let mut after_cr = false;
+        for chunk in chunks {
+            for (i, c) in chunk.char_indices() {
+                let offset = index.len + i;
+                if c == '\n' {
+                    if after_cr {
+                        index.crlf_lines.insert(index.line_starts.len() - 1);
+                    }
+                    index.line_starts.push(offset + 1);
+                } else if c.len_utf8() > 1 {
+                    let line = index.line_starts.len() - 1;
+                    let start = offset - index.line_starts[line];
+                    let wide_char = WideChar {
+                        start,
+                        len: c.len_utf8(),
+                    };
+                    index.wide_chars.entry(line).or_default().push(wide_char);
+                }
+
+                after_cr = c == '\r';
+            }
+
+            index.len += chunk.len();
+        }
+
+        index
+    }
+
+    /// The offsets of the start of every line. The first element is always
+    /// `0`.
+    pub fn line_starts(&self) -> &[usize] {
+        &self.line_starts
+    }
+
+    /// The number of lines in the source text.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// The offset of the start of the given line, if it exists.
+    pub fn line_start(&self, line: usize) -> Option<usize> {
+        if line == self.line_starts.len() {
+            Some(self.len)
+        } else {
+            self.line_starts.get(line).copied()
+        }
+    }
+
+    /// The range of the given line (including its line feed), if it exists.
+    pub fn line_range(&self, line: usize) -> Option<Range<usize>> {
+        let start = self.line_start(line)?;
+        let end = self.line_start(line + 1).unwrap_or(self.len);
+
+        Some(start..end)
+    }
+
+    /// The line containing the given offset.
+    pub fn line(&self, offset: usize) -> usize {
+        self.line_starts
+            .binary_search(&offset)
+            .unwrap_or_else(|expected| expected.saturating_sub(1))
+    }
+
+    /// The line and column of the given offset in the given source text (which
+    /// must be the text this index was constructed from).
+    pub fn line_col(&self, source: &str, offset: usize) -> LineCol {
+        let line = self.line(offset);
+        let line_range = self.line_range(line).unwrap_or(self.len..self.len);
+
+        LineCol::new(line, column_index(source, line_range, offset))
+    }
+
+    /// The offset of the given line and column in the given source text (which
+    /// must be the text this index was constructed from), if the line exists.
+    ///
+    /// A column past the end of its line is clamped to the end of the line,
+    /// before its line ending.
+    pub fn offset(&self, source: &str, line_col: LineCol) -> Option<usize> {
+        let line_range = self.line_range(line_col.line)?;
+        let end = line_range.end - self.line_ending_len(line_col.line);
+        let line = &source[line_range.start..end];
+
+        let offset = line
+            .char_indices()
+            .nth(line_col.col)
+            .map_or(end, |(index, _)| line_range.start + index);
+
+        Some(offset)
+    }
+
+    fn line_wide_chars(&self, line: usize) -> impl Iterator<Item = &WideChar> {
+        self.wide_chars.get(&line).into_iter().flatten()
+    }
+
+    /// The length of the line ending of the given line: 2 for a carriage
+    /// return and a line feed, 1 for a line feed and 0 for the last line.
+    pub fn line_ending_len(&self, line: usize) -> usize {
+        if self.crlf_lines.contains(&line) {
+            2
+        } else {
+            usize::from(line + 1 < self.line_starts.len())
+        }
+    }
+
+    /// The length of the given line (without its line ending) in UTF-16 code
+    /// units, if the line exists.
+    pub fn utf16_line_len(&self, line: usize) -> Option<usize> {
+        let range = self.line_range(line)?;
+        let extra_bytes = self
+            .line_wide_chars(line)
+            .map(|c| c.len - c.len_utf16())
+            .sum::<usize>();
+
+        Some(range.len() - self.line_ending_len(line) - extra_bytes)
+    }
+
+    /// The line and column of the given offset, where the column counts
+    /// UTF-16 code units (like the positions of the LSP). An offset in the
+    /// middle of a character (or of a line ending) is in the column of that
+    /// character (or at the end of the line), and an offset past the end of
+    /// the text is at the end of the last line.
+    pub fn utf16_line_col(&self, offset: usize) -> LineCol {
+        let offset = std::cmp::min(offset, self.len);
+        let line = self.line(offset);
+        let byte_col = offset - self.line_starts[line];
+
+        let mut col = byte_col;
+        for c in self.line_wide_chars(line) {
+            if c.start + c.len <= byte_col {
+                col -= c.len - c.len_utf16();
+            } else {
+                col -= byte_col.saturating_sub(c.start);
+                break;
+            }
+        }
+
+        let line_len = self.utf16_line_len(line).unwrap_or_default();
+        LineCol::new(line, std::cmp::min(col, line_len))
+    }
+
+    /// The position in the text closest to the given one, where the column
+    /// counts UTF-16 code units (like the positions of the LSP). A line past
+    /// the last one is clamped to the end of the text, and a column past the
+    /// end of its line to the end of the line.
+    ///
+    /// Editors may send positions computed before their last edits, so they
+    /// are clamped (as the LSP requires for columns) instead of rejected.
+    pub fn clamp_position(&self, position: LineCol) -> LineCol {
+        let last_line = self.line_starts.len() - 1;
+        if position.line > last_line {
+            let line_len = self.utf16_line_len(last_line).unwrap_or_default();
+            return LineCol::new(last_line, line_len);
+        }
+
+        let line_len = self.utf16_line_len(position.line).unwrap_or_default();
+        LineCol::new(position.line, std::cmp::min(position.col, line_len))
+    }
+
+    /// The offset of the given line and column, where the column counts
+    /// UTF-16 code units (like the positions of the LSP). Positions that
+    /// aren't in the text can be clamped first with
+    /// [`LineIndex::clamp_position`].
+    ///
+    /// A column in the middle of a character (between the two halves of a
+    /// surrogate pair) is at the end of that character.
+    pub fn try_utf16_offset(
+        &self,
+        line_col: LineCol,
+    ) -> Result<usize, PositionError> {
+        let LineCol { line, col } = line_col;
+        let start =
+            self.line_start(line)
+                .ok_or(PositionError::LineOutOfBounds {
+                    line,
+                    line_count: self.line_count(),
+                })?;
+
+        let line_len = self.utf16_line_len(line).unwrap_or_default();
+        if col > line_len {
+            return Err(PositionError::ColumnOutOfBounds {
+                line_col,
+                line_len,
+            });
+        }
+
+        let mut byte_col = col;
+        for c in self.line_wide_chars(line) {
+            if c.start < byte_col {
+                byte_col = std::cmp::max(
+                    byte_col + c.len - c.len_utf16(),
+                    c.start + c.len,
+                );
+            } else {
+                break;
+            }
+        }
+
+        Ok(start + byte_col)
+    }
+}
+
+/// A change to a source text, which replaces the text in the given range
+/// (which is empty for insertions) with the given text.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub replacement: String,
+}
+
+impl TextEdit {
+    /// Constructs a new [`TextEdit`] replacing the given range with the given
+    /// text.
+    pub fn new(range: Range<usize>, replacement: impl Into<String>) -> Self {
+        Self {
+            range,
+            replacement: replacement.into(),
+        }
+    }
+
+    /// Constructs a new [`TextEdit`] inserting the given text at the given
+    /// offset.
+    pub fn insert(offset: usize, text: impl Into<String>) -> Self {
+        Self::new(offset..offset, text)
+    }
+
+    /// Constructs a new [`TextEdit`] deleting the given range.
+    pub fn delete(range: Range<usize>) -> Self {
+        Self::new(range, String::new())
+    }
+}
+
+/// Applies the given edits to the given source text, where the edits are
+/// sorted by their ranges and don't overlap (as the edits sent to an editor
+/// must be). The edits inserting text at the same offset are applied in
+/// order.
+///
+/// # Panics
+///
+/// Panics if the edits aren't sorted or overlap.
+pub fn apply_edits(source: &str, edits: &[TextEdit]) -> String {
+    let mut text = String::with_capacity(source.len());
+    let mut offset = 0;
+
+    for edit in edits {
+        assert!(
+            offset <= edit.range.start,
+            "Edits must be sorted and must not overlap"
+        );
+
+        text.push_str(&source[offset..edit.range.start]);
+        text.push_str(&edit.replacement);
+        offset = edit.range.end;
+    }
+
+    text.push_str(&source[offset..]);
+    text
+}
+
+/// The column (in characters) of the given offset in the given line of a
+/// source text. Offsets past the end of the line are clamped to its end.
+pub fn column_index(
+    source: &str,
+    line_range: Range<usize>,
+    offset: usize,
+) -> usize {
+    use std::cmp::min;
+    let end_index = min(offset, min(line_range.end, source.len()));
+
+    (line_range.start..end_index)
+        .filter(|index| source.is_char_boundary(index + 1))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "let a = 0\nlet λ = 1\n\n";
+
+    #[test]
+    fn test_line_index() {
+        let index = LineIndex::new(SOURCE);
+
+        assert_eq!(index.line_starts(), &[0, 10, 21, 22]);
+        assert_eq!(index.line_count(), 4);
+        assert_eq!(index.line_start(3), Some(22));
+        assert_eq!(index.line_start(4), Some(22));
+        assert_eq!(index.line_start(5), None);
+        assert_eq!(index.line_range(1), Some(10..21));
+        assert_eq!(index.line_range(3), Some(22..22));
+
+        assert_eq!(index.line(0), 0);
+        assert_eq!(index.line(9), 0);
+        assert_eq!(index.line(10), 1);
+        assert_eq!(index.line(21), 2);
+        assert_eq!(index.line(22), 3);
+    }
+
+    #[test]
+    fn test_line_col_round_trip() {
+        let index = LineIndex::new(SOURCE);
+
+        // `λ` is two bytes long, but it is a single column
+        assert_eq!(index.line_col(SOURCE, 16), LineCol::new(1, 5));
+        assert_eq!(index.line_col(SOURCE, 20), LineCol::new(1, 9));
+        assert_eq!(index.line_col(SOURCE, 22), LineCol::new(3, 0));
+
+        for offset in (0..=SOURCE.len()).filter(|&i| SOURCE.is_char_boundary(i))
+        {
+            let line_col = index.line_col(SOURCE, offset);
+            assert_eq!(index.offset(SOURCE, line_col), Some(offset));
+        }
+
+        assert_eq!(index.offset(SOURCE, LineCol::new(0, 99)), Some(9));
+        assert_eq!(index.offset(SOURCE, LineCol::new(9, 0)), None);
+    }
+
+    #[test]
+    fn test_offset_past_end_of_line() {
+        // The offset stays before the line ending, on the same line
+        let source = "ab\ncd\n";
+        let index = LineIndex::new(source);
+        assert_eq!(index.offset(source, LineCol::new(0, 10)), Some(2));
+        assert_eq!(index.offset(source, LineCol::new(1, 10)), Some(5));
+        assert_eq!(index.offset(source, LineCol::new(2, 10)), Some(6));
+
+        let source = "ab\r\ncd\r\n";
+        let index = LineIndex::new(source);
+        assert_eq!(index.offset(source, LineCol::new(0, 2)), Some(2));
+        assert_eq!(index.offset(source, LineCol::new(0, 10)), Some(2));
+        assert_eq!(index.offset(source, LineCol::new(1, 10)), Some(6));
+    }
+
+    #[test]
+    fn test_utf16_positions() {
+        let source = "a𝔸λb\nλ\n";
+        let index = LineIndex::new(source);
+
+        // `𝔸` is two UTF-16 code units long, and `λ` is one
+        assert_eq!(index.utf16_line_len(0), Some(5));
+        assert_eq!(index.utf16_line_len(1), Some(1));
+        assert_eq!(index.utf16_line_len(2), Some(0));
+        assert_eq!(index.utf16_line_col(5), LineCol::new(0, 3));
+        assert_eq!(index.utf16_line_col(7), LineCol::new(0, 4));
+        assert_eq!(index.utf16_line_col(3), LineCol::new(0, 1));
+        assert_eq!(index.utf16_line_col(10), LineCol::new(1, 0));
+
+        for offset in (0..=source.len()).filter(|&i| source.is_char_boundary(i))
+        {
+            let line_col = index.utf16_line_col(offset);
+            assert_eq!(index.try_utf16_offset(line_col), Ok(offset));
+        }
+
+        assert_eq!(index.try_utf16_offset(LineCol::new(0, 2)), Ok(5));
+        assert_eq!(
+            index.clamp_position(LineCol::new(0, 9)),
+            LineCol::new(0, 5)
+        );
+        assert_eq!(
+            index.clamp_position(LineCol::new(1, 1)),
+            LineCol::new(1, 1)
+        );
+        assert_eq!(
+            index.clamp_position(LineCol::new(7, 1)),
+            LineCol::new(2, 0)
+        );
+        assert_eq!(
+            index.try_utf16_offset(LineCol::new(1, 2)),
+            Err(PositionError::ColumnOutOfBounds {
+                line_col: LineCol::new(1, 2),
+                line_len: 1
+            })
+        );
+        assert_eq!(
+            index.try_utf16_offset(LineCol::new(4, 0)),
+            Err(PositionError::LineOutOfBounds {
+                line: 4,
+                line_count: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_crlf_line_endings() {
+        let source = "let a\r\n\tb\r\n\r\n";
+        let index = LineIndex::new(source);
+
+        assert_eq!(index.line_starts(), &[0, 7, 11, 13]);
+        assert_eq!(index.line_ending_len(0), 2);
+        assert_eq!(index.line_ending_len(3), 0);
+        assert_eq!(index.utf16_line_len(0), Some(5));
+        assert_eq!(index.utf16_line_len(1), Some(2));
+        assert_eq!(index.utf16_line_len(2), Some(0));
+
+        // The carriage returns aren't columns of their lines
+        assert_eq!(index.utf16_line_col(5), LineCol::new(0, 5));
+        assert_eq!(index.utf16_line_col(6), LineCol::new(0, 5));
+        assert_eq!(
+            index.clamp_position(LineCol::new(1, 9)),
+            LineCol::new(1, 2)
+        );
+        assert_eq!(index.try_utf16_offset(LineCol::new(1, 2)), Ok(9));
+
+        // A carriage return and a line feed split across chunks
+        let rope = Rope::from(format!("{}\r\nb", "a".repeat(1023)));
+        assert_eq!(LineIndex::from_rope(&rope).line_ending_len(0), 2);
+    }
+
+    #[test]
+    fn test_apply_edits() {
+        let edits = vec![
+            TextEdit::new(0..3, "var"),
+            TextEdit::insert(9, " + 1"),
+            TextEdit::insert(9, " + 2"),
+            TextEdit::delete(10..21),
+        ];
+        assert_eq!(apply_edits(SOURCE, &edits), "var a = 0 + 1 + 2\n\n");
+        assert_eq!(apply_edits(SOURCE, &[]), SOURCE);
+    }
+
+    #[test]
+    #[should_panic(expected = "must not overlap")]
+    fn test_apply_overlapping_edits() {
+        let edits = vec![TextEdit::delete(0..5), TextEdit::delete(4..6)];
+        apply_edits(SOURCE, &edits);
+    }
+}
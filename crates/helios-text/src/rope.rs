@@ -0,0 +1,837 @@
+//! A rope, for storing source text that is edited often.
+//!
+//! The text of a [`Rope`] is split into chunks held by the leaves of a
+//! balanced binary tree (an AVL tree, where the heights of the children of
+//! each node differ by one at most), whose nodes know the length and the
+//! number of line feeds of their text. Replacing a range of the text or
+//! finding the start of a line only walks down (and rebuilds) a path of the
+//! tree, so it takes `O(log n)` time whatever the length of the text.
+//!
+//! The nodes are shared between the clones of a rope (which are cheap), so a
+//! rope is never changed in place: editing a rope rebuilds the path to the
+//! edited chunks and shares the rest of the tree with the ropes it was
+//! cloned from.
+
+use crate::LineCol;
+use std::fmt::{self, Debug, Display};
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+use std::sync::Arc;
+
+/// The maximum length of the text of a leaf, in bytes.
+const MAX_LEAF_LEN: usize = 1024;
+
+/// A source text stored as a balanced tree of chunks (see the [module
+/// documentation](self)).
+///
+/// Ropes are compared and hashed by their text, whatever chunks it is split
+/// into.
+#[derive(Clone)]
+pub struct Rope {
+    root: Arc<Node>,
+}
+
+enum Node {
+    Leaf {
+        text: String,
+        newlines: usize,
+    },
+    Branch {
+        left: Arc<Node>,
+        right: Arc<Node>,
+        len: usize,
+        newlines: usize,
+        height: usize,
+    },
+}
+
+impl Node {
+    fn leaf(text: &str) -> Arc<Node> {
+        Arc::new(Node::Leaf {
+            text: text.to_string(),
+            newlines: text.bytes().filter(|&byte| byte == b'\n').count(),
+        })
+    }
+
+    fn branch(left: Arc<Node>, right: Arc<Node>) -> Arc<Node> {
+        Arc::new(Node::Branch {
+            len: left.len() + right.len(),
+            newlines: left.newlines() + right.newlines(),
+            height: 1 + std::cmp::max(left.height(), right.height()),
+            left,
+            right,
+        })
+    }
+
+    /// Builds a balanced tree from the given text.
+    fn build(text: &str) -> Arc<Node> {
+        let mut leaves = Vec::new();
+        let mut rest = text;
+        while rest.len() > MAX_LEAF_LEN {
+            let mut end = MAX_LEAF_LEN;
+            while !rest.is_char_boundary(end) {
+                end -= 1;
+            }
+
+            leaves.push(Node::leaf(&rest[..end]));
+            rest = &rest[end..];
+        }
+
+        leaves.push(Node::leaf(rest));
+        Self::build_from_leaves(&leaves)
+    }
+
+    fn build_from_leaves(leaves: &[Arc<Node>]) -> Arc<Node> {
+        match leaves {
+            [leaf] => Arc::clone(leaf),
+            leaves => {
+                let (left, right) = leaves.split_at(leaves.len() / 2);
+                let left = Self::build_from_leaves(left);
+                Node::branch(left, Self::build_from_leaves(right))
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Node::Leaf { text, .. } => text.len(),
+            Node::Branch { len, .. } => *len,
+        }
+    }
+
+    fn newlines(&self) -> usize {
+        match self {
+            Node::Leaf { newlines, .. } | Node::Branch { newlines, .. } => {
+                *newlines
+            }
+        }
+    }
+
+    fn height(&self) -> usize {
+        match self {
+            Node::Leaf { .. } => 0,
+            Node::Branch { height, .. } => *height,
+        }
+    }
+
+    fn children(&self) -> (&Arc<Node>, &Arc<Node>) {
+        match self {
+            Node::Branch { left, right, .. } => (left, right),
+            Node::Leaf { .. } => unreachable!("Leaves don't have children"),
+        }
+    }
+}
+
+/// Concatenates the given trees, keeping the result balanced.
+fn join(left: Arc<Node>, right: Arc<Node>) -> Arc<Node> {
+    if left.len() == 0 {
+        return right;
+    } else if right.len() == 0 {
+        return left;
+    }
+
+    if let (Node::Leaf { text: a, .. }, Node::Leaf { text: b, .. }) =
+        (&*left, &*right)
+    {
+        if a.len() + b.len() <= MAX_LEAF_LEN {
+            return Node::leaf(&format!("{}{}", a, b));
+        }
+    }
+
+    if left.height() > right.height() + 1 {
+        join_right(&left, right)
+    } else if right.height() > left.height() + 1 {
+        join_left(left, &right)
+    } else {
+        Node::branch(left, right)
+    }
+}
+
+/// Joins the given trees where the left one is the taller, by joining the
+/// right one to the rightmost subtree of about the same height.
+fn join_right(left: &Node, right: Arc<Node>) -> Arc<Node> {
+    let (l, c) = left.children();
+    if c.height() <= right.height() + 1 {
+        let joined = Node::branch(Arc::clone(c), right);
+        if joined.height() <= l.height() + 1 {
+            Node::branch(Arc::clone(l), joined)
+        } else {
+            rotate_left(&Node::branch(Arc::clone(l), rotate_right(&joined)))
+        }
+    } else {
+        let joined = join_right(c, right);
+        let height = joined.height();
+        let node = Node::branch(Arc::clone(l), joined);
+        if height <= l.height() + 1 {
+            node
+        } else {
+            rotate_left(&node)
+        }
+    }
+}
+
+/// Joins the given trees where the right one is the taller (the mirror of
+/// [`join_right`]).
+fn join_left(left: Arc<Node>, right: &Node) -> Arc<Node> {
+    let (c, r) = right.children();
+    if c.height() <= left.height() + 1 {
+        let joined = Node::branch(left, Arc::clone(c));
+        if joined.height() <= r.height() + 1 {
+            Node::branch(joined, Arc::clone(r))
+        } else {
+            rotate_right(&Node::branch(rotate_left(&joined), Arc::clone(r)))
+        }
+    } else {
+        let joined = join_left(left, c);
+        let height = joined.height();
+        let node = Node::branch(joined, Arc::clone(r));
+        if height <= r.height() + 1 {
+            node
+        } else {
+            rotate_right(&node)
+        }
+    }
+}
+
+/// Turns `(a, (b, c))` into `((a, b), c)`.
+fn rotate_left(node: &Node) -> Arc<Node> {
+    let (a, right) = node.children();
+    let (b, c) = right.children();
+    let left = Node::branch(Arc::clone(a), Arc::clone(b));
+    Node::branch(left, Arc::clone(c))
+}
+
+/// Turns `((a, b), c)` into `(a, (b, c))`.
+fn rotate_right(node: &Node) -> Arc<Node> {
+    let (left, c) = node.children();
+    let (a, b) = left.children();
+    let right = Node::branch(Arc::clone(b), Arc::clone(c));
+    Node::branch(Arc::clone(a), right)
+}
+
+/// Splits the given tree at the given offset (which must be at a character
+/// boundary).
+fn split(node: &Arc<Node>, offset: usize) -> (Arc<Node>, Arc<Node>) {
+    match &**node {
+        _ if offset == 0 => (Node::leaf(""), Arc::clone(node)),
+        _ if offset == node.len() => (Arc::clone(node), Node::leaf("")),
+        Node::Leaf { text, .. } => {
+            (Node::leaf(&text[..offset]), Node::leaf(&text[offset..]))
+        }
+        Node::Branch { left, right, .. } => {
+            if offset < left.len() {
+                let (a, b) = split(left, offset);
+                (a, join(b, Arc::clone(right)))
+            } else {
+                let (a, b) = split(right, offset - left.len());
+                (join(Arc::clone(left), a), b)
+            }
+        }
+    }
+}
+
+impl Rope {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The length of the text, in bytes.
+    pub fn len(&self) -> usize {
+        self.root.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of lines of the text, which is one more than the number of
+    /// line feeds (the last line may be empty).
+    pub fn line_count(&self) -> usize {
+        self.root.newlines() + 1
+    }
+
+    /// Replaces the given range of the text with the given text.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds or doesn't start and end at
+    /// character boundaries.
+    pub fn replace(&mut self, range: Range<usize>, text: &str) {
+        assert!(
+            range.start <= range.end && range.end <= self.len(),
+            "The range {:?} is out of bounds",
+            range
+        );
+        assert!(
+            self.is_char_boundary(range.start)
+                && self.is_char_boundary(range.end),
+            "The range {:?} isn't at character boundaries",
+            range
+        );
+
+        let (left, rest) = split(&self.root, range.start);
+        let (_, right) = split(&rest, range.end - range.start);
+        let left = join(left, Node::build(text));
+        self.root = join(left, right);
+    }
+
+    /// Inserts the given text at the given offset (see [`Rope::replace`]).
+    pub fn insert(&mut self, offset: usize, text: &str) {
+        self.replace(offset..offset, text);
+    }
+
+    /// Removes the given range of the text (see [`Rope::replace`]).
+    pub fn remove(&mut self, range: Range<usize>) {
+        self.replace(range, "");
+    }
+
+    /// The chunks of the text, in order.
+    pub fn chunks(&self) -> Chunks<'_> {
+        self.slice(0..self.len()).chunks()
+    }
+
+    /// The given range of the text, without copying it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds or doesn't start and end at
+    /// character boundaries.
+    pub fn slice(&self, range: Range<usize>) -> RopeSlice<'_> {
+        assert!(
+            range.start <= range.end && range.end <= self.len(),
+            "The range {:?} is out of bounds",
+            range
+        );
+        assert!(
+            self.is_char_boundary(range.start)
+                && self.is_char_boundary(range.end),
+            "The range {:?} isn't at character boundaries",
+            range
+        );
+
+        RopeSlice {
+            rope: self,
+            range: (range.start, range.end),
+        }
+    }
+
+    /// The given line (including its line feed), if it exists.
+    pub fn line(&self, line: usize) -> Option<RopeSlice<'_>> {
+        self.line_range(line).map(|range| self.slice(range))
+    }
+
+    /// The offset of the start of the given line, if it exists. The line one
+    /// past the last one starts at the end of the text.
+    pub fn line_start(&self, line: usize) -> Option<usize> {
+        let mut node = &self.root;
+        let mut newlines = line;
+        let mut offset = 0;
+
+        if line == 0 {
+            return Some(0);
+        } else if line == self.line_count() {
+            return Some(self.len());
+        } else if line > self.line_count() {
+            return None;
+        }
+
+        // Finds the line feed ending the previous line
+        loop {
+            match &**node {
+                Node::Leaf { text, .. } => {
+                    let (index, _) =
+                        text.match_indices('\n').nth(newlines - 1)?;
+                    return Some(offset + index + 1);
+                }
+                Node::Branch { left, right, .. } => {
+                    if newlines <= left.newlines() {
+                        node = left;
+                    } else {
+                        newlines -= left.newlines();
+                        offset += left.len();
+                        node = right;
+                    }
+                }
+            }
+        }
+    }
+
+    /// The range of the given line (including its line feed), if it exists.
+    pub fn line_range(&self, line: usize) -> Option<Range<usize>> {
+        let start = self.line_start(line)?;
+        let end = self.line_start(line + 1).unwrap_or_else(|| self.len());
+
+        Some(start..end)
+    }
+
+    /// The line containing the given offset (or the last line, for offsets
+    /// past the end of the text).
+    pub fn line_of_offset(&self, offset: usize) -> usize {
+        let mut node = &self.root;
+        let mut offset = std::cmp::min(offset, self.len());
+        let mut line = 0;
+
+        loop {
+            match &**node {
+                Node::Leaf { text, .. } => {
+                    let before = &text.as_bytes()[..offset];
+                    return line
+                        + before.iter().filter(|&&b| b == b'\n').count();
+                }
+                Node::Branch { left, right, .. } => {
+                    if offset <= left.len() {
+                        node = left;
+                    } else {
+                        line += left.newlines();
+                        offset -= left.len();
+                        node = right;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Determines if the given offset is at the start or the end of a
+    /// character (which the ends of the text are).
+    pub fn is_char_boundary(&self, offset: usize) -> bool {
+        let mut node = &self.root;
+        let mut offset = offset;
+
+        loop {
+            match &**node {
+                Node::Leaf { text, .. } => {
+                    return text.is_char_boundary(offset)
+                }
+                Node::Branch { left, right, .. } => {
+                    if offset <= left.len() {
+                        node = left;
+                    } else {
+                        offset -= left.len();
+                        node = right;
+                    }
+                }
+            }
+        }
+    }
+
+    /// The line and column (in characters) of the given offset. An offset in
+    /// the middle of a character is in the column of that character, and an
+    /// offset past the end of the text is at the end of the last line.
+    pub fn line_col(&self, offset: usize) -> LineCol {
+        let line = self.line_of_offset(offset);
+        let range = self.line_range(line).unwrap_or(self.len()..self.len());
+        LineCol::new(line, self.column_index(range, offset))
+    }
+
+    /// The column (in characters) of the given offset in the line with the
+    /// given range, like [`column_index`](crate::column_index).
+    pub fn column_index(
+        &self,
+        line_range: Range<usize>,
+        offset: usize,
+    ) -> usize {
+        let start = std::cmp::min(line_range.start, self.len());
+        let mut end = offset.min(line_range.end).min(self.len()).max(start);
+        while !self.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        self.slice(start..end).chars().count()
+    }
+
+    /// The offset of the given line and column (in characters), if the line
+    /// exists. A column past the end of its line is clamped to the end of the
+    /// line, before its line ending (like [`Rope::utf16_offset`]).
+    pub fn offset(&self, line_col: LineCol) -> Option<usize> {
+        let range = self.line_range(line_col.line)?;
+        let mut col = 0;
+        let mut offset = range.start;
+
+        let mut chars = self.slice(range).chars().peekable();
+        while let Some(c) = chars.next() {
+            let crlf = c == '\r' && chars.peek() == Some(&'\n');
+            if col >= line_col.col || c == '\n' || crlf {
+                break;
+            }
+
+            col += 1;
+            offset += c.len_utf8();
+        }
+
+        Some(offset)
+    }
+
+    /// The offset of the given line and column, where the column counts
+    /// UTF-16 code units (like the positions of the LSP), if the line exists.
+    /// A column past the end of its line (or in the middle of a character) is
+    /// clamped to the end of the line (or of the character), before its line
+    /// ending (a line feed, or a carriage return and a line feed).
+    pub fn utf16_offset(&self, line: usize, col: usize) -> Option<usize> {
+        let range = self.line_range(line)?;
+        let mut units = 0;
+        let mut offset = range.start;
+
+        let mut chars = self.slice(range).chars().peekable();
+        while let Some(c) = chars.next() {
+            let crlf = c == '\r' && chars.peek() == Some(&'\n');
+            if units >= col || c == '\n' || crlf {
+                break;
+            }
+
+            units += c.len_utf16();
+            offset += c.len_utf8();
+        }
+
+        Some(offset)
+    }
+}
+
+impl Default for Rope {
+    fn default() -> Self {
+        Self {
+            root: Node::leaf(""),
+        }
+    }
+}
+
+impl From<&str> for Rope {
+    fn from(text: &str) -> Self {
+        Self {
+            root: Node::build(text),
+        }
+    }
+}
+
+impl From<String> for Rope {
+    fn from(text: String) -> Self {
+        Self::from(text.as_str())
+    }
+}
+
+impl From<&String> for Rope {
+    fn from(text: &String) -> Self {
+        Self::from(text.as_str())
+    }
+}
+
+impl Display for Rope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.chunks().try_for_each(|chunk| f.write_str(chunk))
+    }
+}
+
+impl Debug for Rope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&self.to_string(), f)
+    }
+}
+
+impl PartialEq for Rope {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.root, &other.root)
+            || (self.len() == other.len()
+                && bytes_eq(self.chunks(), other.chunks()))
+    }
+}
+
+impl Eq for Rope {}
+
+impl PartialEq<str> for Rope {
+    fn eq(&self, other: &str) -> bool {
+        self.len() == other.len() && bytes_eq(self.chunks(), Some(other))
+    }
+}
+
+impl PartialEq<&str> for Rope {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+impl Hash for Rope {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // The text is hashed in blocks of the same size whatever its chunks
+        // are (since hashers don't have to give the same hash for the same
+        // bytes written in different pieces)
+        let mut block = [0; 64];
+        let mut len = 0;
+        for byte in self.chunks().flat_map(str::bytes) {
+            block[len] = byte;
+            len += 1;
+            if len == block.len() {
+                state.write(&block);
+                len = 0;
+            }
+        }
+
+        state.write(&block[..len]);
+        state.write_usize(self.len());
+    }
+}
+
+fn bytes_eq<'a, 'b>(
+    a: impl IntoIterator<Item = &'a str>,
+    b: impl IntoIterator<Item = &'b str>,
+) -> bool {
+    let a = a.into_iter().flat_map(str::bytes);
+    a.eq(b.into_iter().flat_map(str::bytes))
+}
+
+/// A range of the text of a [`Rope`], given by [`Rope::slice`].
+#[derive(Clone, Copy)]
+pub struct RopeSlice<'a> {
+    rope: &'a Rope,
+    range: (usize, usize),
+}
+
+impl<'a> RopeSlice<'a> {
+    /// The length of the slice, in bytes.
+    pub fn len(&self) -> usize {
+        self.range.1 - self.range.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The chunks of the text of the slice, in order.
+    pub fn chunks(&self) -> Chunks<'a> {
+        Chunks {
+            stack: vec![(&*self.rope.root, 0)],
+            range: self.range.0..self.range.1,
+        }
+    }
+
+    pub fn chars(&self) -> impl Iterator<Item = char> + 'a {
+        self.chunks().flat_map(str::chars)
+    }
+}
+
+impl Display for RopeSlice<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.chunks().try_for_each(|chunk| f.write_str(chunk))
+    }
+}
+
+impl Debug for RopeSlice<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&self.to_string(), f)
+    }
+}
+
+impl PartialEq<str> for RopeSlice<'_> {
+    fn eq(&self, other: &str) -> bool {
+        self.len() == other.len() && bytes_eq(self.chunks(), Some(other))
+    }
+}
+
+impl PartialEq<&str> for RopeSlice<'_> {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+/// An iterator over the chunks of the text of a rope (or of a slice of it),
+/// which only visits the nodes holding the text.
+pub struct Chunks<'a> {
+    /// The nodes left to visit, along with the offsets of their text.
+    stack: Vec<(&'a Node, usize)>,
+    range: Range<usize>,
+}
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((node, start)) = self.stack.pop() {
+            let end = start + node.len();
+            if end <= self.range.start || self.range.end <= start {
+                continue;
+            }
+
+            match node {
+                Node::Leaf { text, .. } => {
+                    let from = self.range.start.saturating_sub(start);
+                    let to = std::cmp::min(self.range.end, end) - start;
+                    return Some(&text[from..to]);
+                }
+                Node::Branch { left, right, .. } => {
+                    self.stack.push((right, start + left.len()));
+                    self.stack.push((left, start));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::hash_map::DefaultHasher;
+
+    /// Checks that the tree is balanced and that the cached lengths are
+    /// right, returning the height of the tree.
+    fn check_node(node: &Node) -> usize {
+        match node {
+            Node::Leaf { text, newlines } => {
+                assert_eq!(*newlines, text.matches('\n').count());
+                assert!(text.len() <= MAX_LEAF_LEN);
+                0
+            }
+            Node::Branch {
+                left,
+                right,
+                len,
+                newlines,
+                height,
+            } => {
+                let (l, r) = (check_node(left), check_node(right));
+                assert!(l.max(r) - l.min(r) <= 1, "The tree isn't balanced");
+                assert_eq!(*height, 1 + l.max(r));
+                assert_eq!(*len, left.len() + right.len());
+                assert_eq!(*newlines, left.newlines() + right.newlines());
+                *height
+            }
+        }
+    }
+
+    fn hash(value: &impl Hash) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_rope_edits() {
+        let line = "let λ = 1\n";
+        let mut text = line.repeat(500);
+        let mut rope = Rope::from(&text);
+
+        // Pseudo-random edits, checked against the same edits of a string
+        let mut seed = 42_usize;
+        let mut next = |bound: usize| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+            (seed >> 33) % bound
+        };
+
+        for round in 0..2000 {
+            let mut start = next(text.len() + 1);
+            while !text.is_char_boundary(start) {
+                start -= 1;
+            }
+            let mut end =
+                start + next(std::cmp::min(40, text.len() - start) + 1);
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+
+            let inserted = match round % 4 {
+                0 => "",
+                1 => "x",
+                2 => "\n",
+                _ => line,
+            };
+
+            text.replace_range(start..end, inserted);
+            rope.replace(start..end, inserted);
+        }
+
+        assert_eq!(rope.to_string(), text);
+        assert_eq!(rope.len(), text.len());
+        assert_eq!(rope.line_count(), text.matches('\n').count() + 1);
+
+        let height = check_node(&rope.root);
+        assert!(height <= 2 * (64 - rope.len().leading_zeros() as usize));
+    }
+
+    #[test]
+    fn test_rope_lines() {
+        let text = format!("{}\n\nlet λ = 1\n", "a".repeat(3000));
+        let rope = Rope::from(&text);
+        let index = crate::LineIndex::new(&text);
+        assert_eq!(crate::LineIndex::from_rope(&rope), index);
+
+        assert_eq!(rope.line_count(), index.line_count());
+        for line in 0..=rope.line_count() + 1 {
+            assert_eq!(rope.line_start(line), index.line_start(line));
+            assert_eq!(rope.line_range(line), index.line_range(line));
+        }
+
+        for offset in 0..=text.len() + 1 {
+            assert_eq!(rope.line_of_offset(offset), index.line(offset));
+            if offset <= text.len() {
+                assert_eq!(
+                    rope.line_col(offset),
+                    index.line_col(&text, offset),
+                    "at offset {}",
+                    offset
+                );
+            }
+        }
+
+        assert_eq!(rope.line(2).unwrap(), "let λ = 1\n");
+        assert_eq!(rope.line(3).unwrap(), "");
+        assert!(rope.line(5).is_none());
+
+        let position = LineCol::new(2, 5);
+        assert_eq!(rope.offset(position), index.offset(&text, position));
+        let position = LineCol::new(2, 99);
+        assert_eq!(rope.offset(position), index.offset(&text, position));
+    }
+
+    #[test]
+    fn test_rope_offset_past_end_of_line() {
+        let rope = Rope::from("ab\ncd\n");
+        assert_eq!(rope.offset(LineCol::new(0, 1)), Some(1));
+        assert_eq!(rope.offset(LineCol::new(0, 10)), Some(2));
+        assert_eq!(rope.offset(LineCol::new(1, 10)), Some(5));
+        assert_eq!(rope.offset(LineCol::new(2, 10)), Some(6));
+        assert_eq!(rope.offset(LineCol::new(9, 0)), None);
+
+        let rope = Rope::from("aλ\r\ncd");
+        assert_eq!(rope.offset(LineCol::new(0, 2)), Some(3));
+        assert_eq!(rope.offset(LineCol::new(0, 10)), Some(3));
+        assert_eq!(rope.offset(LineCol::new(1, 10)), Some(7));
+    }
+
+    #[test]
+    fn test_rope_utf16_offsets() {
+        let rope = Rope::from("a𝔸b\nc");
+        assert_eq!(rope.utf16_offset(0, 0), Some(0));
+        assert_eq!(rope.utf16_offset(0, 1), Some(1));
+        assert_eq!(rope.utf16_offset(0, 3), Some(5));
+        assert_eq!(rope.utf16_offset(0, 4), Some(6));
+
+        // Columns past the end of the line are clamped to its end
+        assert_eq!(rope.utf16_offset(0, 9), Some(6));
+        assert_eq!(rope.utf16_offset(1, 1), Some(8));
+        assert_eq!(rope.utf16_offset(2, 0), Some(8));
+        assert_eq!(rope.utf16_offset(3, 0), None);
+
+        // Columns are clamped before carriage returns ending lines
+        let rope = Rope::from("a\r\nb\rc");
+        assert_eq!(rope.utf16_offset(0, 9), Some(1));
+        assert_eq!(rope.utf16_offset(1, 9), Some(6));
+    }
+
+    #[test]
+    fn test_rope_equality() {
+        let text = "let a = 1\n".repeat(300);
+        let a = Rope::from(&text);
+
+        // The same text split into different chunks
+        let mut b = Rope::from(&text[..1500]);
+        b.insert(1500, &text[1500..]);
+
+        assert_eq!(a, b);
+        assert_eq!(hash(&a), hash(&b));
+        assert_eq!(a, text.as_str());
+
+        b.replace(0..3, "var");
+        assert_ne!(a, b);
+        assert_eq!(b.slice(0..9), "var a = 1");
+        assert_eq!(format!("{:?}", Rope::from("a\n")), "\"a\\n\"");
+    }
+}
@@ -0,0 +1,223 @@
+//! A virtual file system for the source files of a program.
+//!
+//! The compiler and the language server never read source files with
+//! [`std::fs`] directly: they go through a [`Vfs`], whose files come from
+//! memory (the documents opened in an editor, or the files of a test) before
+//! they come from the disk. Reading from the disk can also be turned off
+//! entirely (see [`Vfs::in_memory`]), so that nothing depends on a real file
+//! system.
+//!
+//! Every change to a file in memory is recorded, so that the files can be
+//! kept in sync with the inputs of the query database (see
+//! [`Vfs::take_changes`]).
+//!
+//! The files are held as [`Rope`]s, so that editing a large file in memory
+//! (and reading it back) doesn't copy its whole text.
+
+use helios_text::Rope;
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A set of files held in memory, in front of the files on the disk.
+#[derive(Clone, Debug)]
+pub struct Vfs {
+    files: HashMap<PathBuf, Rope>,
+    read_disk: bool,
+    changes: Vec<PathBuf>,
+}
+
+impl Default for Vfs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Vfs {
+    /// Constructs a [`Vfs`] that reads the files that aren't in memory from
+    /// the disk.
+    pub fn new() -> Self {
+        Self {
+            files: HashMap::new(),
+            read_disk: true,
+            changes: Vec::new(),
+        }
+    }
+
+    /// Constructs a [`Vfs`] that only has the files held in memory.
+    pub fn in_memory() -> Self {
+        Self {
+            read_disk: false,
+            ..Self::new()
+        }
+    }
+
+    /// Sets the contents of the file at the given path in memory, which then
+    /// takes precedence over the file on the disk.
+    pub fn set_file(
+        &mut self,
+        path: impl Into<PathBuf>,
+        text: impl Into<Rope>,
+    ) {
+        let path = path.into();
+        self.files.insert(path.clone(), text.into());
+        self.changes.push(path);
+    }
+
+    /// Removes the file at the given path from memory, so that it is read
+    /// from the disk again (if it exists there).
+    pub fn remove_file(&mut self, path: &Path) {
+        if self.files.remove(path).is_some() {
+            self.changes.push(path.to_path_buf());
+        }
+    }
+
+    /// Records that the file at the given path changed on the disk (such as
+    /// when it is reported by a file watcher), unless the file is held in
+    /// memory (whose contents take precedence).
+    pub fn reload_file(&mut self, path: &Path) {
+        if self.read_disk && !self.files.contains_key(path) {
+            self.changes.push(path.to_path_buf());
+        }
+    }
+
+    /// Reads the contents of the file at the given path.
+    pub fn read(&self, path: &Path) -> io::Result<Rope> {
+        match self.files.get(path) {
+            Some(text) => Ok(text.clone()),
+            None if self.read_disk => {
+                Ok(Rope::from(std::fs::read_to_string(path)?))
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No such file: {}", path.display()),
+            )),
+        }
+    }
+
+    /// Determines if the file at the given path is held in memory (such as a
+    /// document opened in an editor), rather than read from the disk.
+    pub fn is_in_memory(&self, path: &Path) -> bool {
+        self.files.contains_key(path)
+    }
+
+    /// Determines if there is a file at the given path.
+    pub fn is_file(&self, path: &Path) -> bool {
+        self.files.contains_key(path) || (self.read_disk && path.is_file())
+    }
+
+    /// Determines if there is a directory at the given path (which is any
+    /// directory containing a file held in memory).
+    pub fn is_dir(&self, path: &Path) -> bool {
+        self.files
+            .keys()
+            .any(|file| file.starts_with(path) && file != path)
+            || (self.read_disk && path.is_dir())
+    }
+
+    /// Finds every file in the given directory and its subdirectories, sorted
+    /// by path.
+    pub fn files_in(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        fn find_files(dir: &Path, paths: &mut Vec<PathBuf>) -> io::Result<()> {
+            for entry in std::fs::read_dir(dir)? {
+                let path = entry?.path();
+
+                if path.is_dir() {
+                    find_files(&path, paths)?;
+                } else {
+                    paths.push(path);
+                }
+            }
+
+            Ok(())
+        }
+
+        let mut paths = Vec::new();
+        if self.read_disk && dir.is_dir() {
+            find_files(dir, &mut paths)?;
+        } else if !self.is_dir(dir) {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No such directory: {}", dir.display()),
+            ));
+        }
+
+        paths.extend(
+            self.files
+                .keys()
+                .filter(|file| file.starts_with(dir) && *file != dir)
+                .cloned(),
+        );
+
+        paths.sort();
+        paths.dedup();
+        Ok(paths)
+    }
+
+    /// Returns the paths of the files that changed in memory since the last
+    /// call, in the order they changed (without duplicates).
+    pub fn take_changes(&mut self) -> Vec<PathBuf> {
+        let mut changes = std::mem::take(&mut self.changes);
+        let mut seen = std::collections::HashSet::new();
+        changes.retain(|path| seen.insert(path.clone()));
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_memory_files() {
+        let mut vfs = Vfs::in_memory();
+        vfs.set_file("src/Main.he", "import Foo");
+        vfs.set_file("src/Foo/Bar.he", "let a = 1");
+        vfs.set_file("src/Main.he", "import Foo.Bar");
+
+        let main = Path::new("src/Main.he");
+        assert_eq!(vfs.read(main).unwrap(), "import Foo.Bar");
+        assert!(vfs.is_file(main));
+        assert!(vfs.is_in_memory(main));
+        assert!(!vfs.is_file(Path::new("src/Missing.he")));
+        assert!(!vfs.is_in_memory(Path::new("src/Missing.he")));
+        assert!(vfs.read(Path::new("src/Missing.he")).is_err());
+
+        assert!(vfs.is_dir(Path::new("src")));
+        assert!(vfs.is_dir(Path::new("src/Foo")));
+        assert!(!vfs.is_dir(main));
+        assert_eq!(
+            vfs.files_in(Path::new("src")).unwrap(),
+            vec![
+                PathBuf::from("src/Foo/Bar.he"),
+                PathBuf::from("src/Main.he")
+            ]
+        );
+        assert!(vfs.files_in(Path::new("lib")).is_err());
+    }
+
+    #[test]
+    fn test_changes() {
+        let mut vfs = Vfs::in_memory();
+        vfs.set_file("A.he", "");
+        vfs.set_file("B.he", "");
+        vfs.set_file("A.he", "let a = 1");
+
+        let changes = vfs.take_changes();
+        assert_eq!(changes, vec![PathBuf::from("A.he"), PathBuf::from("B.he")]);
+        assert!(vfs.take_changes().is_empty());
+
+        vfs.remove_file(Path::new("B.he"));
+        vfs.remove_file(Path::new("C.he"));
+        assert_eq!(vfs.take_changes(), vec![PathBuf::from("B.he")]);
+        assert!(!vfs.is_file(Path::new("B.he")));
+
+        // Files held in memory aren't read from the disk
+        let mut vfs = Vfs::new();
+        vfs.set_file("A.he", "");
+        vfs.take_changes();
+        vfs.reload_file(Path::new("A.he"));
+        vfs.reload_file(Path::new("B.he"));
+        assert_eq!(vfs.take_changes(), vec![PathBuf::from("B.he")]);
+    }
+}
@@ -8,6 +8,17 @@ fn print_usage() {
     println!("{}", include_str!("../usage.txt"));
 }
 
+/// The exit code of an invalid command line.
+const USAGE_ERROR: i32 = 2;
+
+/// Prints the given error message about the command line, followed by the
+/// usage information, and exits.
+fn usage_error(message: impl Into<String>) -> ! {
+    print_error(message);
+    print_usage();
+    std::process::exit(USAGE_ERROR)
+}
+
 /// Prints the current version number of the Helios executable.
 ///
 /// This function will print the version number found in the `Cargo.toml`
@@ -20,33 +31,185 @@ fn print_version() {
     }
 }
 
+/// Runs `helios doc [--format <format>] <path>`.
+fn doc_command(args: &[String]) {
+    let (format, path) = match args {
+        [path] => (Ok(helios_build::DocFormat::Markdown), path),
+        [flag, format, path] if flag == "--format" => (format.parse(), path),
+        _ => usage_error("Expected `doc [--format <format>] <path>`"),
+    };
+
+    match format {
+        Ok(format) => helios_build::doc(path, format),
+        Err(message) => usage_error(message),
+    }
+}
+
+/// Runs `helios highlight [--format <format>] <path>`.
+fn highlight_command(args: &[String]) {
+    let (format, path) = match args {
+        [path] => (Ok(helios_build::HighlightFormat::Html), path),
+        [flag, format, path] | [path, flag, format] if flag == "--format" => {
+            (format.parse(), path)
+        }
+        _ => usage_error("Expected `highlight [--format <format>] <path>`"),
+    };
+
+    match format {
+        Ok(format) => helios_build::highlight(path, format),
+        Err(message) => usage_error(message),
+    }
+}
+
+/// Runs `helios parse [--layout] [--format <format>] <path>`.
+fn parse_command(args: &[String]) {
+    let (layout, args) = match args {
+        [flag, args @ ..] if flag == "--layout" => (true, args),
+        args => (false, args),
+    };
+
+    let (format, path) = match args {
+        [path] => (Ok(helios_build::TreeFormat::Text), path),
+        [flag, format, path] if flag == "--format" => (format.parse(), path),
+        _ => usage_error(
+            "Expected `parse [--layout] [--format <format>] <path>`",
+        ),
+    };
+
+    let mut features = helios_build::session::Features::new();
+    if layout {
+        features.enable(helios_build::session::Feature::IndentationBlocks);
+    }
+
+    let options = helios_build::ParseOptions {
+        features,
+        ..helios_build::ParseOptions::default()
+    };
+    match format {
+        Ok(format) => helios_build::parse(path, format, options),
+        Err(message) => usage_error(message),
+    }
+}
+
+/// Runs `helios build [options] [path]` (or `check`), where the options are
+/// `--profile`, `--message-format=<format>` and the flags of the session
+/// (such as `--feature=<feature>`), in any order.
+fn build_command(command: &str, args: &[String]) {
+    const MESSAGE_FORMAT: &str = "--message-format=";
+
+    let mut options = helios_build::CompilationOptions::new(".");
+    let mut format = helios_build::MessageFormat::Human;
+    let mut paths = Vec::new();
+
+    for arg in args {
+        if arg == "--profile" {
+            options.profile = true;
+        } else if let Some(name) = arg.strip_prefix(MESSAGE_FORMAT) {
+            format =
+                name.parse().unwrap_or_else(|message| usage_error(message));
+        } else if arg.starts_with("--") {
+            match options.session.parse_flag(arg) {
+                Ok(true) => {}
+                Ok(false) => {
+                    usage_error(format!("Unrecognised option `{}`", arg))
+                }
+                Err(message) => usage_error(message),
+            }
+        } else {
+            paths.push(arg);
+        }
+    }
+
+    match &paths[..] {
+        [] => {}
+        [path] => options.path = path.into(),
+        _ => usage_error(format!("Expected `{} [options] [path]`", command)),
+    }
+
+    if command == "build" {
+        log::trace!("Starting build process...");
+        helios_build::build(&options, format)
+    } else {
+        log::trace!("Starting check process...");
+        helios_build::check(&options, format)
+    }
+}
+
+/// Removes the `--color <when>` option from the given arguments (wherever it
+/// is), returning the choice it gives.
+fn color_option(
+    args: &mut Vec<String>,
+) -> Result<helios_build::ColorChoice, String> {
+    let index = match args.iter().position(|arg| arg == "--color") {
+        Some(index) => index,
+        None => return Ok(helios_build::ColorChoice::default()),
+    };
+
+    args.remove(index);
+    if index < args.len() {
+        args.remove(index).parse()
+    } else {
+        Err("Expected `--color <when>`".to_string())
+    }
+}
+
 fn main() {
     env_logger::init();
-    let mut args = std::env::args();
-    args.next(); // Skip path to executable
+    let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+
+    match color_option(&mut args) {
+        Ok(choice) => choice.apply(),
+        Err(message) => usage_error(message),
+    }
+
+    let mut args = args.into_iter();
 
     match (args.next(), args.next()) {
         (Some(arg), param) => match (&*arg, param) {
             ("-h", _) | ("--help", _) => print_usage(),
             ("-V", _) | ("--version", _) => print_version(),
-            ("build", None) => {
-                print_error("Missing argument for subcommand `build`");
-                print_usage();
+            ("build", param) | ("check", param) => {
+                let args = param.into_iter().chain(args).collect::<Vec<_>>();
+                build_command(&arg, &args)
             }
-            ("build", Some(ref file_name)) => {
-                log::trace!("Starting build process...");
-                helios_build::build(file_name)
+            ("doc", param) => {
+                let args = param.into_iter().chain(args).collect::<Vec<_>>();
+                doc_command(&args)
+            }
+            ("highlight", param) => {
+                let args = param.into_iter().chain(args).collect::<Vec<_>>();
+                highlight_command(&args)
+            }
+            ("parse", param) => {
+                let args = param.into_iter().chain(args).collect::<Vec<_>>();
+                parse_command(&args)
+            }
+            ("search", param) => {
+                let args = param.into_iter().chain(args).collect::<Vec<_>>();
+                match &args[..] {
+                    [pattern] => helios_build::search(pattern, "."),
+                    [pattern, path] => helios_build::search(pattern, path),
+                    _ => usage_error("Expected `search <pattern> [path]`"),
+                }
+            }
+            ("tokenize", param) => {
+                let args = param.into_iter().chain(args).collect::<Vec<_>>();
+                match &args[..] {
+                    [path] => helios_build::tokenize(path, false),
+                    [flag, path] if flag == "--json" => {
+                        helios_build::tokenize(path, true)
+                    }
+                    _ => usage_error("Expected `tokenize [--json] <path>`"),
+                }
             }
             ("repl", _) => {
                 log::trace!("Starting REPL...");
                 helios_repl::start()
             }
-            _ => {
-                let message =
-                    format!("Unrecognised option or subcommand `{}`", arg);
-                print_error(message);
-                print_usage()
-            }
+            _ => usage_error(format!(
+                "Unrecognised option or subcommand `{}`",
+                arg
+            )),
         },
         _ => print_usage(),
     }
@@ -0,0 +1,207 @@
+//! End-to-end tests of the `helios` executable, run on the projects in
+//! `tests/fixtures`.
+//!
+//! The output of a command is compared with its snapshot in
+//! `tests/snapshots`. Run the tests with `UPDATE_SNAPSHOTS=1` to write the
+//! current outputs to the snapshots instead (and check the diff before
+//! committing them).
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The output of a run of the executable.
+struct Output {
+    code: Option<i32>,
+    stdout: String,
+    stderr: String,
+}
+
+fn fixtures() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+
+/// Runs the executable in the fixtures directory with the given arguments,
+/// without colors.
+fn helios(args: &[&str]) -> Output {
+    let output = Command::new(env!("CARGO_BIN_EXE_helios"))
+        .args(["--color", "never"])
+        .args(args)
+        .current_dir(fixtures())
+        .env_remove("RUST_LOG")
+        .output()
+        .expect("Failed to run `helios`");
+
+    Output {
+        code: output.status.code(),
+        stdout: String::from_utf8(output.stdout).unwrap(),
+        stderr: String::from_utf8(output.stderr).unwrap(),
+    }
+}
+
+/// Compares the given output with the snapshot of the given name (or writes
+/// it to the snapshot, if `UPDATE_SNAPSHOTS` is set).
+fn assert_snapshot(name: &str, actual: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(name);
+
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!("Missing snapshot `{}` (run with UPDATE_SNAPSHOTS=1)", name)
+    });
+    assert!(
+        expected == actual,
+        "The output doesn't match the snapshot `{}`:\n{}",
+        name,
+        actual
+    );
+}
+
+fn json(text: &str) -> serde_json::Value {
+    serde_json::from_str(text).expect("Invalid JSON")
+}
+
+#[test]
+fn test_check_project() {
+    let output = helios(&["check", "hello"]);
+    assert_eq!(output.code, Some(0));
+    assert_snapshot("check_hello.stdout", &output.stdout);
+    assert_eq!(output.stderr, "");
+}
+
+#[test]
+fn test_check_project_with_errors() {
+    let output = helios(&["check", "broken"]);
+    assert_eq!(output.code, Some(1));
+    assert_snapshot("check_broken.stdout", &output.stdout);
+    assert_snapshot("check_broken.stderr", &output.stderr);
+}
+
+#[test]
+fn test_check_project_with_options() {
+    let args = ["check", "--deny-warnings", "--opt-level=2", "hello"];
+    let output = helios(&args);
+    assert_eq!(output.code, Some(0));
+    assert_snapshot("check_hello.stdout", &output.stdout);
+}
+
+#[test]
+fn test_check_sarif() {
+    let output = helios(&["check", "--message-format=sarif", "broken"]);
+    assert_eq!(output.code, Some(1));
+
+    let log = json(&output.stdout);
+    assert_eq!(log["version"], "2.1.0");
+    let results = log["runs"][0]["results"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+
+    let location = &results[0]["locations"][0]["physicalLocation"];
+    assert_eq!(location["artifactLocation"]["uri"], "broken/src/Main.he");
+    assert_eq!(location["region"]["startLine"], 1);
+    assert_eq!(location["region"]["startColumn"], 8);
+    assert_eq!(results[1]["level"], "error");
+}
+
+#[test]
+fn test_parse() {
+    let output = helios(&["parse", "hello/src/Greeting.he"]);
+    assert_eq!(output.code, Some(0));
+    assert_snapshot("parse_greeting.stdout", &output.stdout);
+}
+
+#[test]
+fn test_parse_json() {
+    let output = helios(&["parse", "--format", "json", "hello/src/Main.he"]);
+    assert_eq!(output.code, Some(0));
+
+    // Every node has a kind, a range and either children or text
+    fn check_node(node: &serde_json::Value) {
+        assert!(node["kind"].is_string());
+        assert_eq!(node["range"].as_array().map(Vec::len), Some(2));
+        match node["children"].as_array() {
+            Some(children) => children.iter().for_each(check_node),
+            None => assert!(node["text"].is_string()),
+        }
+    }
+
+    let tree = json(&output.stdout);
+    assert_eq!(tree["kind"], "Root");
+    assert_eq!(tree["range"], serde_json::json!([0, 36]));
+    check_node(&tree);
+}
+
+#[test]
+fn test_tokenize_json() {
+    let output = helios(&["tokenize", "--json", "hello/src/Main.he"]);
+    assert_eq!(output.code, Some(0));
+
+    let tokens = json(&output.stdout);
+    let kinds = tokens
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|token| token["kind"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(&kinds[..3], &["Kwd_Import", "Identifier", "Kwd_Let"]);
+
+    // The diagnostics of the lexer don't end up in the JSON document
+    let output = helios(&["tokenize", "--json", "Unterminated.he"]);
+    assert_eq!(output.code, Some(0));
+    let tokens = json(&output.stdout);
+    let last = tokens.as_array().unwrap().last().unwrap();
+    assert_eq!(last["kind"], "Lit_String");
+    assert!(
+        output.stderr.contains("Unterminated string"),
+        "{}",
+        output.stderr
+    );
+}
+
+#[test]
+fn test_doc() {
+    let output = helios(&["doc", "hello/src/Greeting.he"]);
+    assert_eq!(output.code, Some(0));
+    assert_snapshot("doc_greeting.stdout", &output.stdout);
+}
+
+#[test]
+fn test_highlight() {
+    let args = ["highlight", "hello/src/Greeting.he", "--format", "html"];
+    let output = helios(&args);
+    assert_eq!(output.code, Some(0));
+    assert_snapshot("highlight_greeting.stdout", &output.stdout);
+}
+
+#[test]
+fn test_missing_file() {
+    let output = helios(&["parse", "hello/src/Missing.he"]);
+    assert_eq!(output.code, Some(1));
+    assert_eq!(output.stdout, "");
+    assert!(!output.stderr.is_empty());
+}
+
+#[test]
+fn test_usage_errors() {
+    let output = helios(&["frob"]);
+    assert_eq!(output.code, Some(2));
+    assert!(output.stdout.starts_with("USAGE: helios"));
+    assert_eq!(
+        output.stderr,
+        "ERROR: Unrecognised option or subcommand `frob`\n\n"
+    );
+
+    let output = helios(&["parse", "--format", "xml", "hello/src/Main.he"]);
+    assert_eq!(output.code, Some(2));
+
+    let output = helios(&["check", "--target=jvm", "hello"]);
+    assert_eq!(output.code, Some(2));
+    assert!(output.stderr.starts_with("ERROR: Unknown target `jvm`"));
+
+    let output = helios(&["--help"]);
+    assert_eq!(output.code, Some(0));
+    assert!(output.stdout.starts_with("USAGE: helios"));
+}
@@ -0,0 +1,18 @@
+//! Feeds arbitrary input to the lexer, checking that it never panics and that
+//! its tokens cover the input exactly (in order and without any gaps).
+
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|source: &str| {
+    let (tokens, _) = helios_parser::tokenize(0u8, source);
+
+    let mut offset = 0;
+    for token in &tokens {
+        assert_eq!(token.range.start, offset, "gap before {:?}", token);
+        assert_eq!(&source[token.range.clone()], token.text);
+        offset = token.range.end;
+    }
+
+    assert_eq!(offset, source.len());
+});
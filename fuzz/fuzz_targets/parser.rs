@@ -0,0 +1,14 @@
+//! Feeds arbitrary input to the parser, checking that it never panics and that
+//! the syntax tree is lossless (its text is exactly the input).
+
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|source: &str| {
+    let parse = helios_parser::parse(0u8, source);
+    let root = parse.syntax();
+
+    assert_eq!(usize::from(root.text_range().start()), 0);
+    assert_eq!(usize::from(root.text_range().end()), source.len());
+    assert_eq!(root.text().to_string(), source);
+});